@@ -0,0 +1,190 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Throughput and latency of the event subset channels, on their own and behind an `EventSender`.
+//!
+//! Run with `cargo bench --bench event_sender`.
+
+#[macro_use]
+extern crate maidsafe_utilities;
+
+use maidsafe_utilities::event_sender::{ChannelSender, EventSender, LatencyHistogram, Timestamped,
+                                       lock_free};
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant};
+
+const PRODUCERS: usize = 4;
+const EVENTS_PER_PRODUCER: usize = 250_000;
+const TOTAL_EVENTS: usize = PRODUCERS * EVENTS_PER_PRODUCER;
+
+fn report(name: &str, elapsed: Duration, send_time: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    println!("{:<40} {:>8.2} M events/s {:>10.3} µs mean send latency",
+             name,
+             TOTAL_EVENTS as f64 / seconds / 1e6,
+             send_time.as_secs_f64() * 1e6 / TOTAL_EVENTS as f64);
+}
+
+// Spawns the producers, each of which sends `EVENTS_PER_PRODUCER` events via `send`, then runs
+// `consume` until it has received them all. Returns the total elapsed time and the total time
+// spent inside `send` across all producers.
+fn run<S, F, C>(make_sender: F, consume: C) -> (Duration, Duration)
+    where S: FnMut(usize) + Send + 'static,
+          F: Fn() -> S,
+          C: FnOnce()
+{
+    let start = Instant::now();
+    let joiners = (0..PRODUCERS)
+                      .map(|_| {
+                          let mut send = make_sender();
+                          thread!("BenchProducer", move || {
+                              let start = Instant::now();
+                              for event in 0..EVENTS_PER_PRODUCER {
+                                  send(event);
+                              }
+                              start.elapsed()
+                          })
+                      })
+                      .collect::<Vec<_>>();
+    consume();
+    let send_time = joiners.into_iter().map(|joiner| unwrap_result!(joiner.join())).sum();
+    (start.elapsed(), send_time)
+}
+
+fn std_channel() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let (elapsed, send_time) = run(move || {
+                                       let tx = tx.clone();
+                                       move |event| unwrap_result!(tx.send(event))
+                                   },
+                                   move || {
+                                       for _ in 0..TOTAL_EVENTS {
+                                           let _ = unwrap_result!(rx.recv());
+                                       }
+                                   });
+    report("std::sync::mpsc", elapsed, send_time);
+}
+
+fn lock_free_channel() {
+    let (tx, rx) = lock_free::channel();
+    let (elapsed, send_time) = run(move || {
+                                       let tx = tx.clone();
+                                       move |event| unwrap_result!(tx.send(event))
+                                   },
+                                   move || {
+                                       let mut received = 0;
+                                       while received < TOTAL_EVENTS {
+                                           match rx.try_recv() {
+                                               Ok(_) => received += 1,
+                                               Err(TryRecvError::Empty) => std::thread::yield_now(),
+                                               Err(TryRecvError::Disconnected) => unreachable!(),
+                                           }
+                                       }
+                                   });
+    report("lock_free::channel", elapsed, send_time);
+}
+
+fn event_sender_over_std() {
+    let (event_tx, event_rx) = std::sync::mpsc::channel();
+    let (category_tx, category_rx) = std::sync::mpsc::channel();
+    let sender = EventSender::new(event_tx, (), category_tx);
+    let (elapsed, send_time) = run(move || {
+                                       let sender = sender.clone();
+                                       move |event| unwrap_result!(sender.send(event))
+                                   },
+                                   move || {
+                                       for _ in 0..TOTAL_EVENTS {
+                                           unwrap_result!(category_rx.recv());
+                                           let _ = unwrap_result!(event_rx.try_recv());
+                                       }
+                                   });
+    report("EventSender over std::sync::mpsc", elapsed, send_time);
+}
+
+fn event_sender_over_lock_free() {
+    let (event_tx, event_rx) = lock_free::channel();
+    let (category_tx, category_rx) = std::sync::mpsc::channel();
+    let sender = EventSender::new(event_tx, (), category_tx);
+    let (elapsed, send_time) = run(move || {
+                                       let sender = sender.clone();
+                                       move |event| unwrap_result!(sender.send(event))
+                                   },
+                                   move || {
+                                       for _ in 0..TOTAL_EVENTS {
+                                           unwrap_result!(category_rx.recv());
+                                           let _ = unwrap_result!(event_rx.try_recv());
+                                       }
+                                   });
+    report("EventSender over lock_free::channel", elapsed, send_time);
+}
+
+const LATENCY_SAMPLES: usize = 100_000;
+
+fn report_latency(name: &str, histogram: &LatencyHistogram) {
+    let stats = histogram.stats();
+    println!("{:<40} {:>8.3} µs p50 {:>10.3} µs p99 {:>10.3} µs max",
+             name,
+             stats.p50.as_secs_f64() * 1e6,
+             stats.p99.as_secs_f64() * 1e6,
+             stats.max.as_secs_f64() * 1e6);
+}
+
+// A single producer sends `LATENCY_SAMPLES` timestamped events, yielding in between so that the
+// queue stays short and the histogram measures the hand-off rather than queueing.
+fn produce_timestamped<F: FnMut(Timestamped<usize>) + Send + 'static>(mut send: F) {
+    let _ = thread!("BenchProducer", move || {
+        for event in 0..LATENCY_SAMPLES {
+            send(Timestamped::new(event));
+            std::thread::yield_now();
+        }
+    });
+}
+
+fn std_channel_latency() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    produce_timestamped(move |event| unwrap_result!(tx.send(event)));
+    let mut histogram = LatencyHistogram::new();
+    for _ in 0..LATENCY_SAMPLES {
+        histogram.record(unwrap_result!(rx.recv()).age());
+    }
+    report_latency("std::sync::mpsc", &histogram);
+}
+
+fn event_sender_latency() {
+    let (event_tx, event_rx) = std::sync::mpsc::channel();
+    let (category_tx, category_rx) = std::sync::mpsc::channel();
+    let sender = EventSender::new(event_tx, (), category_tx);
+    produce_timestamped(move |event| unwrap_result!(sender.send(event)));
+    let mut histogram = LatencyHistogram::new();
+    for _ in 0..LATENCY_SAMPLES {
+        unwrap_result!(category_rx.recv());
+        histogram.record(unwrap_result!(event_rx.try_recv()).age());
+    }
+    report_latency("EventSender over std::sync::mpsc", &histogram);
+}
+
+fn main() {
+    println!("{} producers, {} events each\n", PRODUCERS, EVENTS_PER_PRODUCER);
+    std_channel();
+    lock_free_channel();
+    event_sender_over_std();
+    event_sender_over_lock_free();
+
+    println!("\nSend to receive latency, 1 producer, {} events\n", LATENCY_SAMPLES);
+    std_channel_latency();
+    event_sender_latency();
+}
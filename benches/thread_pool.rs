@@ -0,0 +1,87 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Throughput of the thread pool with a shared queue versus work stealing, for uniform and for
+//! heterogeneous job sizes.
+//!
+//! Run with `cargo bench --bench thread_pool`.
+
+#[macro_use]
+extern crate maidsafe_utilities;
+
+use maidsafe_utilities::thread::Pool;
+use std::time::{Duration, Instant};
+
+const THREADS: usize = 4;
+const JOBS: usize = 100_000;
+
+// Spin rather than sleep, so that the job really occupies its worker.
+fn spin(duration: Duration) {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        std::hint::spin_loop();
+    }
+}
+
+fn run<F: Fn(usize) -> Duration>(name: &str, pool: Pool, job_duration: F) {
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    let start = Instant::now();
+    for job in 0..JOBS {
+        let done_tx = done_tx.clone();
+        let duration = job_duration(job);
+        pool.execute(move || {
+            spin(duration);
+            unwrap_result!(done_tx.send(()));
+        });
+    }
+    drop(done_tx);
+    assert_eq!(done_rx.iter().count(), JOBS);
+    let elapsed = start.elapsed();
+    println!("{:<40} {:>8.3} s {:>10.2} k jobs/s",
+             name,
+             elapsed.as_secs_f64(),
+             JOBS as f64 / elapsed.as_secs_f64() / 1e3);
+}
+
+fn uniform(_job: usize) -> Duration {
+    Duration::from_micros(0)
+}
+
+// One job in a thousand is three orders of magnitude longer than the rest.
+fn heterogeneous(job: usize) -> Duration {
+    if job.is_multiple_of(1000) {
+        Duration::from_millis(10)
+    } else {
+        Duration::from_micros(10)
+    }
+}
+
+fn main() {
+    println!("{} threads, {} jobs\n", THREADS, JOBS);
+    run("Shared queue, empty jobs",
+        unwrap_result!(Pool::new("Bench", THREADS)),
+        uniform);
+    run("Work stealing, empty jobs",
+        unwrap_result!(Pool::with_work_stealing("Bench", THREADS)),
+        uniform);
+    run("Shared queue, heterogeneous jobs",
+        unwrap_result!(Pool::new("Bench", THREADS)),
+        heterogeneous);
+    run("Work stealing, heterogeneous jobs",
+        unwrap_result!(Pool::with_work_stealing("Bench", THREADS)),
+        heterogeneous);
+}
@@ -0,0 +1,239 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! # MaidSafe Utilities Derive
+//!
+//! Custom derives for `maidsafe_utilities`. These are re-exported by that crate and should be used
+//! through it rather than directly.
+
+#![doc(html_logo_url =
+           "https://raw.githubusercontent.com/maidsafe/QA/master/Images/maidsafe_logo.png",
+       html_favicon_url = "http://maidsafe.net/img/favicon.ico",
+       html_root_url = "http://maidsafe.github.io/maidsafe_utilities")]
+
+// For explanation of lint checks, run `rustc -W help` or see
+// https://github.com/maidsafe/QA/blob/master/Documentation/Rust%20Lint%20Checks.md
+#![forbid(bad_style, exceeding_bitshifts, mutable_transmutes, no_mangle_const_items,
+          unknown_crate_types, warnings)]
+#![deny(deprecated, drop_with_repr_extern, improper_ctypes, missing_docs,
+        non_shorthand_field_patterns, overflowing_literals, plugin_as_library,
+        private_no_mangle_fns, private_no_mangle_statics, stable_features, unconditional_recursion,
+        unknown_lints, unsafe_code, unused, unused_allocation, unused_attributes,
+        unused_comparisons, unused_features, unused_parens, while_true)]
+#![warn(trivial_casts, trivial_numeric_casts, unused_extern_crates, unused_import_braces,
+        unused_qualifications, unused_results, variant_size_differences)]
+#![allow(box_pointers, fat_ptr_transmutes, missing_copy_implementations,
+         missing_debug_implementations)]
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+struct Variant {
+    name  : String,
+    subset: Option<String>,
+}
+
+/// Derive `Clone`, `Debug`, the `SubsetCategory` mappings and typed `EventSender` constructors for
+/// an event category enum. See `maidsafe_utilities::event_sender::EventCategory`.
+#[proc_macro_derive(EventCategory, attributes(event_subset))]
+pub fn derive_event_category(input: TokenStream) -> TokenStream {
+    let output = match parse_enum(input) {
+        Ok((name, variants)) => generate(&name, &variants),
+        Err(error) => format!("compile_error!({:?});", error),
+    };
+    output.parse().expect("Generated code should be valid")
+}
+
+fn parse_enum(input: TokenStream) -> Result<(String, Vec<Variant>), String> {
+    let mut tokens = input.into_iter().peekable();
+    let mut name = None;
+    while let Some(token) = tokens.next() {
+        if let TokenTree::Ident(ref ident) = token {
+            match &ident.to_string()[..] {
+                "enum" => {
+                    match tokens.next() {
+                        Some(TokenTree::Ident(ident)) => name = Some(ident.to_string()),
+                        _ => return Err("Expected the name of the enum".to_string()),
+                    }
+                    break;
+                }
+                "struct" | "union" => {
+                    return Err("EventCategory can only be derived for enums".to_string())
+                }
+                _ => (),
+            }
+        }
+    }
+    let name = name.ok_or_else(|| "EventCategory can only be derived for enums".to_string())?;
+
+    let body = match tokens.next() {
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Brace => group.stream(),
+        Some(TokenTree::Punct(ref punct)) if punct.as_char() == '<' => {
+            return Err("EventCategory cannot be derived for generic enums".to_string())
+        }
+        Some(TokenTree::Ident(ref ident)) if ident.to_string() == "where" => {
+            return Err("EventCategory cannot be derived for enums with a `where` clause"
+                           .to_string())
+        }
+        Some(token) => return Err(format!("Expected the body of the enum, found `{}`", token)),
+        None => return Err("Expected the body of the enum".to_string()),
+    };
+
+    let mut variants = Vec::new();
+    let mut subset = None;
+    let mut body = body.into_iter().peekable();
+    while let Some(token) = body.next() {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == '#' => {
+                if let Some(TokenTree::Group(attribute)) = body.next() {
+                    if let Some(parsed) = parse_subset_attribute(attribute.stream())? {
+                        subset = Some(parsed);
+                    }
+                }
+            }
+            TokenTree::Ident(ident) => {
+                let variant_name = ident.to_string();
+                if let Some(&TokenTree::Group(_)) = body.peek() {
+                    return Err(format!("EventCategory variant `{}` must not carry data",
+                                       variant_name));
+                }
+                // Skip any discriminant up to the end of this variant.
+                for token in body.by_ref() {
+                    if let TokenTree::Punct(ref punct) = token {
+                        if punct.as_char() == ',' {
+                            break;
+                        }
+                    }
+                }
+                variants.push(Variant {
+                    name  : variant_name,
+                    subset: subset.take(),
+                });
+            }
+            _ => (),
+        }
+    }
+
+    Ok((name, variants))
+}
+
+fn parse_subset_attribute(attribute: TokenStream) -> Result<Option<String>, String> {
+    let mut tokens = attribute.into_iter();
+    match tokens.next() {
+        Some(TokenTree::Ident(ref ident)) if ident.to_string() == "event_subset" => (),
+        _ => return Ok(None),
+    }
+    match tokens.next() {
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Parenthesis &&
+                                             !group.stream().is_empty() => {
+            Ok(Some(group.stream().to_string()))
+        }
+        _ => Err("Expected `#[event_subset(EventSubsetType)]`".to_string()),
+    }
+}
+
+fn generate(name: &str, variants: &[Variant]) -> String {
+    let clone_arms = variants.iter()
+                             .map(|variant| format!("{0}::{1} => {0}::{1},", name, variant.name))
+                             .collect::<String>();
+    let debug_arms = variants.iter()
+                             .map(|variant| {
+                                 format!("{}::{} => formatter.write_str({:?}),",
+                                         name,
+                                         variant.name,
+                                         variant.name)
+                             })
+                             .collect::<String>();
+
+    let mut output = format!("
+        impl ::std::clone::Clone for {name} {{
+            fn clone(&self) -> {name} {{
+                match *self {{ {clone_arms} }}
+            }}
+        }}
+
+        impl ::std::fmt::Debug for {name} {{
+            fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{
+                match *self {{ {debug_arms} }}
+            }}
+        }}",
+                             name = name,
+                             clone_arms = clone_arms,
+                             debug_arms = debug_arms);
+
+    let mut constructors = String::new();
+    for variant in variants {
+        if let Some(ref subset) = variant.subset {
+            output.push_str(&format!("
+                impl ::maidsafe_utilities::event_sender::SubsetCategory<{name}> for {subset} {{
+                    fn category() -> {name} {{
+                        {name}::{variant}
+                    }}
+                }}",
+                                     name = name,
+                                     subset = subset,
+                                     variant = variant.name));
+            constructors.push_str(&format!("
+                /// Create an `EventSender` for `{subset}` events in the `{variant}` category.
+                #[allow(dead_code)]
+                pub fn {function}_sender(event_tx: ::std::sync::mpsc::Sender<{subset}>,
+                                         event_category_tx: ::std::sync::mpsc::Sender<{name}>)
+                        -> ::maidsafe_utilities::event_sender::EventSender<{name}, {subset}> {{
+                    ::maidsafe_utilities::event_sender::EventSender::new(event_tx,
+                                                                         {name}::{variant},
+                                                                         event_category_tx)
+                }}",
+                                           name = name,
+                                           subset = subset,
+                                           variant = variant.name,
+                                           function = to_snake_case(&variant.name)));
+        }
+    }
+    if !constructors.is_empty() {
+        output.push_str(&format!("impl {} {{ {} }}", name, constructors));
+    }
+
+    output
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+    for (index, character) in name.char_indices() {
+        if character.is_uppercase() {
+            if index != 0 {
+                snake_case.push('_');
+            }
+            snake_case.extend(character.to_lowercase());
+        } else {
+            snake_case.push(character);
+        }
+    }
+    snake_case
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snake_case_constructor_names() {
+        assert_eq!(to_snake_case("Network"), "network");
+        assert_eq!(to_snake_case("UserInterface"), "user_interface");
+        assert_eq!(to_snake_case("ABC"), "a_b_c");
+    }
+}
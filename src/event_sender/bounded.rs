@@ -0,0 +1,364 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelReceiver, ChannelSender};
+
+/// What a `bounded_channel` does with a value sent while it is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for the receiver to make room, as `std::sync::mpsc::SyncSender::send` does.
+    Block,
+    /// Refuse the value: `EventSender::send` fails with `EventSenderError::QueueFull`.
+    Error,
+    /// Discard the value being sent, keeping the queue as it is.
+    DropNewest,
+    /// Discard the oldest queued value to make room, giving ring-buffer semantics.
+    DropOldest,
+}
+
+/// Create a channel holding at most `capacity` values, handling overflow according to `policy`.
+/// Discarded values are counted (see `BoundedSender::dropped`).
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, OverflowPolicy, bounded_channel};
+///
+/// // Only the latest three position updates matter.
+/// let (event_tx, event_rx) = bounded_channel(3, OverflowPolicy::DropOldest);
+/// let (category_tx, _category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Position", category_tx);
+///
+/// for position in 0..10 {
+///     assert!(sender.send(position).is_ok());
+/// }
+/// assert_eq!(event_rx.dropped(), 7);
+/// assert_eq!(event_rx.try_iter().collect::<Vec<_>>(), vec![7, 8, 9]);
+/// # }
+/// ```
+pub fn bounded_channel<T>(capacity: usize, policy: OverflowPolicy)
+                          -> (BoundedSender<T>, BoundedReceiver<T>) {
+    make_channel(capacity, policy, None)
+}
+
+/// As `bounded_channel`, but values discarded by the `DropNewest` and `DropOldest` policies are
+/// sent on `dead_letter_tx` instead of being dropped outright.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn bounded_channel_with_dead_letters<T>(capacity: usize,
+                                            policy: OverflowPolicy,
+                                            dead_letter_tx: ::std::sync::mpsc::Sender<T>)
+                                            -> (BoundedSender<T>, BoundedReceiver<T>) {
+    make_channel(capacity, policy, Some(dead_letter_tx))
+}
+
+fn make_channel<T>(capacity: usize,
+                   policy: OverflowPolicy,
+                   dead_letter_tx: Option<::std::sync::mpsc::Sender<T>>)
+                   -> (BoundedSender<T>, BoundedReceiver<T>) {
+    assert!(capacity > 0, "A bounded channel needs a capacity of at least one");
+    let shared = ::std::sync::Arc::new(Shared {
+        state         : ::std::sync::Mutex::new(State {
+            queue         : ::std::collections::VecDeque::with_capacity(capacity),
+            senders       : 1,
+            receiver_alive: true,
+        }),
+        not_empty     : ::std::sync::Condvar::new(),
+        not_full      : ::std::sync::Condvar::new(),
+        capacity,
+        policy,
+        dropped       : ::std::sync::atomic::AtomicUsize::new(0),
+        dead_letter_tx: dead_letter_tx.map(::std::sync::Mutex::new),
+    });
+    (BoundedSender { shared: shared.clone() }, BoundedReceiver { shared })
+}
+
+struct Shared<T> {
+    state         : ::std::sync::Mutex<State<T>>,
+    not_empty     : ::std::sync::Condvar,
+    not_full      : ::std::sync::Condvar,
+    capacity      : usize,
+    policy        : OverflowPolicy,
+    dropped       : ::std::sync::atomic::AtomicUsize,
+    dead_letter_tx: Option<::std::sync::Mutex<::std::sync::mpsc::Sender<T>>>,
+}
+
+struct State<T> {
+    queue         : ::std::collections::VecDeque<T>,
+    senders       : usize,
+    receiver_alive: bool,
+}
+
+impl<T> Shared<T> {
+    // Count `value` as dropped and hand it to the dead letter channel, if any. Called without the
+    // state lock held.
+    fn discard(&self, value: T) {
+        let _ = self.dropped.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+        if let Some(ref dead_letter_tx) = self.dead_letter_tx {
+            let _ = unwrap_result!(dead_letter_tx.lock()).send(value);
+        }
+    }
+
+    fn dropped(&self) -> usize {
+        self.dropped.load(::std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// The sending half of a `bounded_channel`.
+pub struct BoundedSender<T> {
+    shared: ::std::sync::Arc<Shared<T>>,
+}
+
+impl<T> BoundedSender<T> {
+    /// The number of values discarded so far because the channel was full.
+    pub fn dropped(&self) -> usize {
+        self.shared.dropped()
+    }
+
+    // Push `value` if there is room, applying the overflow policy if not. `block` selects between
+    // waiting and reporting `Full` for the `Block` policy.
+    fn push(&self, value: T, block: bool) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        let mut state = unwrap_result!(self.shared.state.lock());
+        let mut discarded = None;
+        loop {
+            if !state.receiver_alive {
+                return Err(::std::sync::mpsc::TrySendError::Disconnected(value));
+            }
+            if state.queue.len() < self.shared.capacity {
+                break;
+            }
+            match self.shared.policy {
+                OverflowPolicy::Block if block => {
+                    state = unwrap_result!(self.shared.not_full.wait(state));
+                }
+                OverflowPolicy::Block | OverflowPolicy::Error => {
+                    return Err(::std::sync::mpsc::TrySendError::Full(value));
+                }
+                OverflowPolicy::DropNewest => {
+                    drop(state);
+                    self.shared.discard(value);
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    discarded = state.queue.pop_front();
+                    break;
+                }
+            }
+        }
+        state.queue.push_back(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+        if let Some(discarded) = discarded {
+            self.shared.discard(discarded);
+        }
+        Ok(())
+    }
+}
+
+impl<T> ChannelSender<T> for BoundedSender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        // `Full` can only come back for the `Error` policy; treat that as the policy demands of
+        // a plain `send` as well.
+        self.push(value, true).map_err(|error| {
+            match error {
+                ::std::sync::mpsc::TrySendError::Full(value) |
+                ::std::sync::mpsc::TrySendError::Disconnected(value) => {
+                    ::std::sync::mpsc::SendError(value)
+                }
+            }
+        })
+    }
+
+    fn try_send(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.push(value, false)
+    }
+
+    fn send_or_reject(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.push(value, true)
+    }
+
+    fn pending(&self) -> Option<usize> {
+        Some(unwrap_result!(self.shared.state.lock()).queue.len())
+    }
+}
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> BoundedSender<T> {
+        unwrap_result!(self.shared.state.lock()).senders += 1;
+        BoundedSender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.senders -= 1;
+        }
+        self.shared.not_empty.notify_all();
+    }
+}
+
+/// The receiving half of a `bounded_channel`.
+pub struct BoundedReceiver<T> {
+    shared: ::std::sync::Arc<Shared<T>>,
+}
+
+impl<T> BoundedReceiver<T> {
+    /// Block until a value is received. See `std::sync::mpsc::Receiver::recv`.
+    pub fn recv(&self) -> Result<T, ::std::sync::mpsc::RecvError> {
+        let mut state = unwrap_result!(self.shared.state.lock());
+        loop {
+            if let Some(value) = state.queue.pop_front() {
+                self.shared.not_full.notify_one();
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(::std::sync::mpsc::RecvError);
+            }
+            state = unwrap_result!(self.shared.not_empty.wait(state));
+        }
+    }
+
+    /// Receive a value if one is waiting. See `std::sync::mpsc::Receiver::try_recv`.
+    pub fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        let mut state = unwrap_result!(self.shared.state.lock());
+        match state.queue.pop_front() {
+            Some(value) => {
+                self.shared.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.senders == 0 => Err(::std::sync::mpsc::TryRecvError::Disconnected),
+            None => Err(::std::sync::mpsc::TryRecvError::Empty),
+        }
+    }
+
+    /// An iterator over the values currently waiting, which does not block.
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        ::std::iter::from_fn(move || self.try_recv().ok())
+    }
+
+    /// The number of values discarded so far because the channel was full.
+    pub fn dropped(&self) -> usize {
+        self.shared.dropped()
+    }
+}
+
+impl<T> ChannelReceiver<T> for BoundedReceiver<T> {
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        BoundedReceiver::try_recv(self)
+    }
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        let queued = match self.shared.state.lock() {
+            Ok(mut state) => {
+                state.receiver_alive = false;
+                state.queue.drain(..).collect::<Vec<_>>()
+            }
+            Err(_) => Vec::new(),
+        };
+        self.shared.not_full.notify_all();
+        drop(queued);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::{EventSender, EventSenderError};
+
+    type Sender<T> = EventSender<(), T, BoundedSender<T>>;
+
+    fn sender<T: ::std::fmt::Debug>(event_tx: BoundedSender<T>)
+                                    -> (Sender<T>, ::std::sync::mpsc::Receiver<()>) {
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        (EventSender::new(event_tx, (), category_tx), category_rx)
+    }
+
+    #[test]
+    fn error_policy() {
+        let (event_tx, event_rx) = bounded_channel(2, OverflowPolicy::Error);
+        let (sender, _category_rx) = sender(event_tx);
+        assert!(sender.send(1).is_ok());
+        assert!(sender.send(2).is_ok());
+        match sender.send(3) {
+            Err(EventSenderError::QueueFull(3)) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert_eq!(event_rx.try_recv(), Ok(1));
+        assert!(sender.send(3).is_ok());
+        assert_eq!(event_rx.dropped(), 0);
+
+        drop(event_rx);
+        match sender.send(4) {
+            Err(EventSenderError::EventSendError(_)) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn block_policy() {
+        let (event_tx, event_rx) = bounded_channel(1, OverflowPolicy::Block);
+        let (sender, _category_rx) = sender(event_tx);
+        assert!(sender.send(1).is_ok());
+        match sender.try_send(2) {
+            Err(EventSenderError::QueueFull(2)) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let blocked = sender.clone();
+        let joiner = ::std::thread::spawn(move || blocked.send(2).is_ok());
+        ::std::thread::sleep(::std::time::Duration::from_millis(20));
+        assert_eq!(event_rx.recv(), Ok(1));
+        assert!(unwrap_result!(joiner.join()));
+        assert_eq!(event_rx.recv(), Ok(2));
+
+        drop(sender);
+        assert_eq!(event_rx.recv(), Err(::std::sync::mpsc::RecvError));
+    }
+
+    #[test]
+    fn drop_policies_with_dead_letters() {
+        let (dead_letter_tx, dead_letter_rx) = ::std::sync::mpsc::channel();
+        let (event_tx, event_rx) =
+            bounded_channel_with_dead_letters(2, OverflowPolicy::DropNewest, dead_letter_tx);
+        for value in 0..5 {
+            assert!(event_tx.send(value).is_ok());
+            assert!(event_tx.try_send(value + 10).is_ok());
+        }
+        assert_eq!(event_tx.dropped(), 8);
+        assert_eq!(event_rx.try_iter().collect::<Vec<_>>(), vec![0, 10]);
+        assert_eq!(dead_letter_rx.try_iter().collect::<Vec<_>>(),
+                   vec![1, 11, 2, 12, 3, 13, 4, 14]);
+
+        let (event_tx, event_rx) = bounded_channel(2, OverflowPolicy::DropOldest);
+        for value in 0..5 {
+            assert!(event_tx.send(value).is_ok());
+        }
+        assert_eq!(event_tx.pending(), Some(2));
+        assert_eq!(event_rx.try_iter().collect::<Vec<_>>(), vec![3, 4]);
+        assert_eq!(event_rx.dropped(), 3);
+    }
+}
@@ -0,0 +1,288 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelReceiver, Flow};
+
+type Entry = ::std::sync::Arc<::std::sync::Mutex<Box<dyn Subscriber>>>;
+type CategoryRx<Category> = ::std::sync::mpsc::Receiver<Category>;
+type Table<Category> = Vec<(Category, u64, Entry)>;
+
+trait Subscriber: Send {
+    fn dispatch(&mut self) -> Flow;
+    fn drain(&mut self) -> Box<dyn std::any::Any + Send>;
+}
+
+struct Typed<EventSubset, Rx, F> {
+    event_rx: Rx,
+    handler : F,
+    _event  : ::std::marker::PhantomData<fn() -> EventSubset>,
+}
+
+impl<EventSubset, Rx, F> Subscriber for Typed<EventSubset, Rx, F>
+    where EventSubset: Send + 'static,
+          Rx: ChannelReceiver<EventSubset> + Send,
+          F: FnMut(EventSubset) -> Flow + Send
+{
+    fn dispatch(&mut self) -> Flow {
+        match self.event_rx.try_recv() {
+            Ok(event) => (self.handler)(event),
+            // The event was already consumed or the sender has gone; nothing to do.
+            Err(_) => Flow::Continue,
+        }
+    }
+
+    fn drain(&mut self) -> Box<dyn std::any::Any + Send> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.event_rx.try_recv() {
+            events.push(event);
+        }
+        Box::new(events)
+    }
+}
+
+// Left in place of a removed subscriber, in case the bus is just about to dispatch to it.
+struct Unsubscribed;
+
+impl Subscriber for Unsubscribed {
+    fn dispatch(&mut self) -> Flow {
+        Flow::Continue
+    }
+
+    fn drain(&mut self) -> Box<dyn std::any::Any + Send> {
+        Box::new(())
+    }
+}
+
+/// Returned by `EventBus::subscribe`, and needed to unsubscribe again.
+pub struct Subscription<Category, EventSubset> {
+    category: Category,
+    id      : u64,
+    _event  : ::std::marker::PhantomData<fn() -> EventSubset>,
+}
+
+impl<Category, EventSubset> Subscription<Category, EventSubset> {
+    /// The category this subscription receives.
+    pub fn category(&self) -> &Category {
+        &self.category
+    }
+}
+
+impl<Category: ::std::fmt::Debug, EventSubset> ::std::fmt::Debug
+    for Subscription<Category, EventSubset> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "Subscription({:?}, {})", self.category, self.id)
+    }
+}
+
+/// A listener loop whose category→receiver registrations can change while it runs, so that
+/// dynamically loaded components can join and leave the event bus.
+///
+/// Unlike `EventLoop`, registration goes through a cloneable `EventBus` handle which can be used
+/// from any thread, including from within a handler. A category may have several subscribers;
+/// each notification for it is offered to all of them and each pulls from its own receiver.
+/// `unsubscribe` hands back whatever events were still queued for the subscriber, so nothing is
+/// lost when a component leaves. It must not be called from the handler of the subscription
+/// being removed, as that handler is running at the time.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventBus, EventSender, Flow};
+///
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let bus = EventBus::new(category_rx);
+/// let joiner = bus.spawn("EventBus");
+///
+/// // A plugin joins...
+/// let (plugin_tx, plugin_rx) = std::sync::mpsc::channel();
+/// let (seen_tx, seen_rx) = std::sync::mpsc::channel();
+/// let subscription = bus.subscribe("Plugin", plugin_rx, move |event: u32| {
+///     unwrap_result!(seen_tx.send(event));
+///     Flow::Continue
+/// });
+/// let sender = EventSender::new(plugin_tx, "Plugin", category_tx);
+/// assert!(sender.send(1).is_ok());
+/// assert_eq!(unwrap_result!(seen_rx.recv()), 1);
+///
+/// // ...and leaves, taking back any events it has not handled yet.
+/// let _unhandled: Vec<u32> = bus.unsubscribe(subscription);
+/// assert!(sender.send(2).is_err());
+///
+/// drop(sender);
+/// drop(joiner);
+/// # }
+/// ```
+pub struct EventBus<Category> {
+    category_rx: ::std::sync::Arc<::std::sync::Mutex<Option<CategoryRx<Category>>>>,
+    table      : ::std::sync::Arc<::std::sync::Mutex<Table<Category>>>,
+    next_id    : ::std::sync::Arc<::std::sync::atomic::AtomicUsize>,
+}
+
+impl<Category: PartialEq + Clone + Send + 'static> EventBus<Category> {
+    /// Create a new bus listening on the given category receiver, with no subscribers.
+    pub fn new(category_rx: ::std::sync::mpsc::Receiver<Category>) -> EventBus<Category> {
+        EventBus {
+            category_rx: ::std::sync::Arc::new(::std::sync::Mutex::new(Some(category_rx))),
+            table      : ::std::sync::Arc::new(::std::sync::Mutex::new(Vec::new())),
+            next_id    : ::std::sync::Arc::new(::std::sync::atomic::AtomicUsize::new(0)),
+        }
+    }
+
+    /// Call `handler` with each event received on `event_rx` for notifications of `category`,
+    /// starting with the next notification dispatched.
+    pub fn subscribe<EventSubset, Rx, F>(&self, category: Category, event_rx: Rx, handler: F)
+                                         -> Subscription<Category, EventSubset>
+        where EventSubset: Send + 'static,
+              Rx: ChannelReceiver<EventSubset> + Send + 'static,
+              F: FnMut(EventSubset) -> Flow + Send + 'static
+    {
+        let id = self.next_id.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) as u64;
+        let subscriber: Box<dyn Subscriber> = Box::new(Typed {
+            event_rx,
+            handler,
+            _event: ::std::marker::PhantomData,
+        });
+        let entry = ::std::sync::Arc::new(::std::sync::Mutex::new(subscriber));
+        unwrap_result!(self.table.lock())
+            .push((category.clone(), id, entry));
+        Subscription {
+            category,
+            id,
+            _event: ::std::marker::PhantomData,
+        }
+    }
+
+    /// Remove `subscription`, returning the events which were still waiting in its receiver. The
+    /// receiver is dropped, so its senders will fail from now on. If the bus has already stopped
+    /// and been dropped the subscription is gone and no events are returned.
+    pub fn unsubscribe<EventSubset: 'static>(&self,
+                                             subscription: Subscription<Category, EventSubset>)
+                                             -> Vec<EventSubset> {
+        let entry = {
+            let mut table = unwrap_result!(self.table.lock());
+            let position = table.iter().position(|entry| entry.1 == subscription.id);
+            position.map(|position| table.remove(position).2)
+        };
+        let drained = match entry {
+            Some(entry) => {
+                let mut subscriber = unwrap_result!(entry.lock());
+                let drained = subscriber.drain();
+                *subscriber = Box::new(Unsubscribed);
+                drained
+            }
+            None => return Vec::new(),
+        };
+        // The subscription's type parameter guarantees what the subscriber drained.
+        let events = drained.downcast::<Vec<EventSubset>>().map_err(|_| "Subscriber type mismatch");
+        *unwrap_result!(events)
+    }
+
+    /// The number of subscribers for `category`.
+    pub fn subscribers(&self, category: &Category) -> usize {
+        unwrap_result!(self.table.lock()).iter().filter(|entry| entry.0 == *category).count()
+    }
+
+    /// Run the bus on the current thread until a handler returns `Flow::Stop` or all the senders
+    /// have been dropped. Only one clone of the bus can run; on the others this returns at once.
+    pub fn run(&self) {
+        let category_rx = match unwrap_result!(self.category_rx.lock()).take() {
+            Some(category_rx) => category_rx,
+            None => return,
+        };
+        for category in category_rx.iter() {
+            let subscribers: Vec<_> = unwrap_result!(self.table.lock())
+                .iter()
+                .filter(|entry| entry.0 == category)
+                .map(|entry| entry.2.clone())
+                .collect();
+            for subscriber in subscribers {
+                if let Flow::Stop = unwrap_result!(subscriber.lock()).dispatch() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Run the bus on a new thread named `thread_name`.
+    pub fn spawn<T: Into<String>>(&self, thread_name: T) -> ::thread::RaiiThreadJoiner {
+        let bus = self.clone();
+        ::thread::RaiiThreadJoiner::new(thread!(thread_name.into(), move || bus.run()))
+    }
+}
+
+impl<Category> Clone for EventBus<Category> {
+    fn clone(&self) -> EventBus<Category> {
+        EventBus {
+            category_rx: self.category_rx.clone(),
+            table      : self.table.clone(),
+            next_id    : self.next_id.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::EventSender;
+
+    #[test]
+    fn subscribe_and_unsubscribe_at_runtime() {
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let bus = EventBus::new(category_rx);
+
+        let (first_tx, first_rx) = ::std::sync::mpsc::channel();
+        let (second_tx, second_rx) = ::std::sync::mpsc::channel();
+        let (seen_tx, seen_rx) = ::std::sync::mpsc::channel();
+        let first_seen_tx = seen_tx.clone();
+        let first = bus.subscribe(1u8, first_rx, move |event: &'static str| {
+            unwrap_result!(first_seen_tx.send(format!("first {}", event)));
+            Flow::Continue
+        });
+        let second = bus.subscribe(1u8, second_rx, move |event: u32| {
+            unwrap_result!(seen_tx.send(format!("second {}", event)));
+            Flow::Continue
+        });
+        assert_eq!(bus.subscribers(&1), 2);
+        assert_eq!(format!("{:?}", second), "Subscription(1, 1)");
+
+        let first_sender = EventSender::new(first_tx, 1u8, category_tx.clone());
+        let second_sender = EventSender::new(second_tx, 1u8, category_tx);
+
+        // Nothing runs the bus yet, so these stay queued.
+        assert!(first_sender.send("a").is_ok());
+        assert!(first_sender.send("b").is_ok());
+        assert_eq!(bus.unsubscribe(first), vec!["a", "b"]);
+        assert!(first_sender.send("c").is_err());
+        assert_eq!(bus.subscribers(&1), 1);
+
+        let joiner = bus.spawn("EventBusTest");
+        assert!(second_sender.send(7).is_ok());
+        let timeout = ::std::time::Duration::from_secs(5);
+        // The notifications for "a" and "b" find nothing in the second receiver.
+        assert_eq!(unwrap_result!(seen_rx.recv_timeout(timeout)), "second 7");
+        assert_eq!(*second.category(), 1);
+        assert!(bus.unsubscribe(second).is_empty());
+
+        drop(first_sender);
+        drop(second_sender);
+        drop(joiner);
+        assert!(seen_rx.recv().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+
+/// An event stamped with the time at which it was sent, so that the receiver can measure how long
+/// it spent queued and discard stale notifications.
+///
+/// Use an `EventSender<Category, Timestamped<EventSubset>>` and `send_timestamped` to have every
+/// event stamped automatically.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, Timestamped};
+/// use std::time::Duration;
+///
+/// let (event_tx, event_rx) = std::sync::mpsc::channel::<Timestamped<&'static str>>();
+/// let (category_tx, _category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+///
+/// assert!(sender.send_timestamped("Connected").is_ok());
+///
+/// let received = event_rx.recv().unwrap();
+/// if received.age() < Duration::from_secs(5) {
+///     assert_eq!(received.event, "Connected");
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Timestamped<EventSubset> {
+    /// When the event was sent.
+    pub when: ::std::time::Instant,
+    /// The event itself.
+    pub event: EventSubset,
+}
+
+impl<EventSubset> Timestamped<EventSubset> {
+    /// Stamp `event` with the current time.
+    pub fn new(event: EventSubset) -> Timestamped<EventSubset> {
+        Timestamped {
+            when: ::std::time::Instant::now(),
+            event,
+        }
+    }
+
+    /// How long ago the event was sent.
+    pub fn age(&self) -> ::std::time::Duration {
+        self.when.elapsed()
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<Timestamped<EventSubset>>,
+     CategoryTx : ChannelSender<Category>>
+    EventSender<Category, Timestamped<EventSubset>, EventTx, CategoryTx> {
+    /// Stamp `event` with the current time and fire it to the observer.
+    pub fn send_timestamped(&self, event: EventSubset)
+                            -> Result<(), EventSenderError<Category, Timestamped<EventSubset>>> {
+        self.send(Timestamped::new(event))
+    }
+}
+
+/// An event stamped with a per-sender sequence number, so that the receiver can detect lost or
+/// reordered events using a `SequenceChecker`.
+///
+/// Use an `EventSender<Category, Sequenced<EventSubset>>` and `send_sequenced` to have every event
+/// numbered automatically. A sender and all its clones share one sequence starting at 0.
+#[derive(Clone, Debug)]
+pub struct Sequenced<EventSubset> {
+    /// Position of the event in its sender's sequence.
+    pub sequence: u64,
+    /// The event itself.
+    pub event: EventSubset,
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<Sequenced<EventSubset>>,
+     CategoryTx : ChannelSender<Category>>
+    EventSender<Category, Sequenced<EventSubset>, EventTx, CategoryTx> {
+    /// Stamp `event` with the next sequence number and fire it to the observer. No lock is held
+    /// while sending, so events sent concurrently from several clones can reach the channel out
+    /// of order, which a `SequenceChecker` reports as a gap followed by the late events filling
+    /// it. A number consumed by a failed send is not reused, and shows up as a gap.
+    pub fn send_sequenced(&self, event: EventSubset)
+                          -> Result<(), EventSenderError<Category, Sequenced<EventSubset>>> {
+        let sequence = self.inner.next_sequence.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+        self.send(Sequenced { sequence, event })
+    }
+}
+
+/// Result of checking a received sequence number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceStatus {
+    /// The sequence number was the expected one.
+    InOrder,
+    /// One or more events before this one were never received.
+    Gap {
+        /// The sequence number which was expected.
+        expected: u64,
+        /// The sequence number actually received.
+        received: u64,
+    },
+    /// The event arrived after a later one, or more than once.
+    Late {
+        /// The sequence number which was expected.
+        expected: u64,
+        /// The sequence number actually received.
+        received: u64,
+    },
+}
+
+/// Receiver-side checker reporting gaps or reordering in the events of one `Sequenced` sender.
+#[derive(Clone, Debug, Default)]
+pub struct SequenceChecker {
+    expected: u64,
+    missed  : u64,
+    late    : u64,
+}
+
+impl SequenceChecker {
+    /// Create a checker expecting the sequence to start at 0.
+    pub fn new() -> SequenceChecker {
+        SequenceChecker::default()
+    }
+
+    /// Check the sequence number of the next received event.
+    pub fn check(&mut self, sequence: u64) -> SequenceStatus {
+        let expected = self.expected;
+        if sequence == expected {
+            self.expected += 1;
+            SequenceStatus::InOrder
+        } else if sequence > expected {
+            self.missed += sequence - expected;
+            self.expected = sequence + 1;
+            SequenceStatus::Gap {
+                expected,
+                received: sequence,
+            }
+        } else {
+            // It was counted as missed when the gap was detected, but it has turned up after all.
+            self.missed = self.missed.saturating_sub(1);
+            self.late += 1;
+            SequenceStatus::Late {
+                expected,
+                received: sequence,
+            }
+        }
+    }
+
+    /// Check the sequence number of a received event, handing back the event itself.
+    pub fn check_event<EventSubset>(&mut self,
+                                    sequenced: Sequenced<EventSubset>)
+                                    -> (SequenceStatus, EventSubset) {
+        (self.check(sequenced.sequence), sequenced.event)
+    }
+
+    /// The number of events currently believed lost. Late events are assumed to be ones previously
+    /// counted as missed, so duplicates can make this an underestimate.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// The number of events which arrived late or more than once.
+    pub fn late(&self) -> u64 {
+        self.late
+    }
+}
+
+/// A category notification carrying the tag of the sender which produced it, so the listener (and
+/// its logs) can tell which of many cloned senders fired a given event.
+///
+/// Use an `EventSender<Tagged<Category>, EventSubset>` created via `new_tagged`.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, Tagged};
+///
+/// let (event_tx, _event_rx) = std::sync::mpsc::channel::<u32>();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let routing_sender = EventSender::new_tagged("routing", event_tx.clone(), "Network",
+///                                              category_tx.clone());
+/// let crust_sender = EventSender::new_tagged("crust", event_tx, "Network", category_tx);
+///
+/// assert!(crust_sender.send(1).is_ok());
+/// assert!(routing_sender.send(2).is_ok());
+///
+/// let tags = category_rx.try_iter().map(|tagged: Tagged<_>| tagged.tag).collect::<Vec<_>>();
+/// assert_eq!(tags, vec!["crust", "routing"]);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Tagged<Category> {
+    /// Tag identifying the sender, typically the name of the module owning it.
+    pub tag: &'static str,
+    /// The category itself.
+    pub category: Category,
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Tagged<Category>>>
+    EventSender<Tagged<Category>, EventSubset, EventTx, CategoryTx> {
+    /// Create a new instance of `EventSender` whose category notifications carry `tag`.
+    pub fn new_tagged(tag              : &'static str,
+                      event_tx         : EventTx,
+                      event_category   : Category,
+                      event_category_tx: CategoryTx)
+                      -> EventSender<Tagged<Category>, EventSubset, EventTx, CategoryTx> {
+        EventSender::new(event_tx,
+                         Tagged {
+                             tag,
+                             category: event_category,
+                         },
+                         event_category_tx)
+    }
+
+    /// The tag this sender was created with.
+    pub fn tag(&self) -> &'static str {
+        self.inner.event_category.tag
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timestamped_events_record_queueing_delay() {
+        const DELAY_MS: u64 = 50;
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+
+        let before = ::std::time::Instant::now();
+        assert!(sender.send_timestamped(1).is_ok());
+        ::std::thread::sleep(::std::time::Duration::from_millis(DELAY_MS));
+
+        let received = unwrap_result!(event_rx.try_recv());
+        assert_eq!(received.event, 1);
+        assert!(received.when >= before);
+        assert!(received.age() >= ::std::time::Duration::from_millis(DELAY_MS));
+    }
+
+    #[test]
+    fn sequence_gaps_and_reordering() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+        let sender_clone = sender.clone();
+
+        assert!(sender.send_sequenced('a').is_ok());
+        assert!(sender_clone.send_sequenced('b').is_ok());
+
+        let mut checker = SequenceChecker::new();
+        for expected_event in &['a', 'b'] {
+            let (status, event) = checker.check_event(unwrap_result!(event_rx.try_recv()));
+            assert_eq!(status, SequenceStatus::InOrder);
+            assert_eq!(event, *expected_event);
+        }
+
+        assert_eq!(checker.check(5),
+                   SequenceStatus::Gap {
+                       expected: 2,
+                       received: 5,
+                   });
+        assert_eq!(checker.missed(), 3);
+        assert_eq!(checker.check(3),
+                   SequenceStatus::Late {
+                       expected: 6,
+                       received: 3,
+                   });
+        assert_eq!((checker.missed(), checker.late()), (2, 1));
+        assert_eq!(checker.check(6), SequenceStatus::InOrder);
+    }
+
+    #[test]
+    fn tags_travel_with_category() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new_tagged("routing", event_tx, "Network", category_tx);
+        let sender_clone = sender.clone();
+        assert_eq!(sender_clone.tag(), "routing");
+
+        assert!(sender_clone.send(1).is_ok());
+        assert_eq!(unwrap_result!(category_rx.try_recv()),
+                   Tagged {
+                       tag: "routing",
+                       category: "Network",
+                   });
+        assert_eq!(unwrap_result!(event_rx.try_recv()), 1);
+    }
+}
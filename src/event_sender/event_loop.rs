@@ -0,0 +1,177 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelReceiver, FairScheduler, Flow, Quota};
+
+type Handler = Box<dyn FnMut() -> Flow + Send>;
+
+/// A ready-made listener loop which replaces the hand-written `match` over the category receiver.
+///
+/// Register a closure per category, each owning that category's event subset receiver, then
+/// `run()` the loop on the current thread or `spawn()` it on a named one. The loop pulls exactly
+/// one event per category notification and passes it to the closure, skipping notifications whose
+/// event has already gone (so closures never see the `try_recv` error cases). It returns once a
+/// closure returns `Flow::Stop`, a shutdown category registered via `register_shutdown` arrives,
+/// or all the senders have been dropped. Categories are scheduled fairly, as by `FairScheduler`.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventLoop, EventSender, Flow};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum EventCategory {
+///     Network,
+///     Terminate,
+/// }
+///
+/// let (network_event_tx, network_event_rx) = std::sync::mpsc::channel();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let network_sender = EventSender::new(network_event_tx,
+///                                       EventCategory::Network,
+///                                       category_tx.clone());
+///
+/// let mut event_loop = EventLoop::new(category_rx);
+/// event_loop.register(EventCategory::Network, network_event_rx, |bytes: usize| {
+///     assert_eq!(bytes, 1024);
+///     Flow::Continue
+/// });
+/// event_loop.register_shutdown(EventCategory::Terminate);
+/// let _raii_joiner = event_loop.spawn("EventListenerThread");
+///
+/// assert!(network_sender.send(1024).is_ok());
+/// assert!(category_tx.send(EventCategory::Terminate).is_ok());
+/// # }
+/// ```
+pub struct EventLoop<Category> {
+    category_rx: ::std::sync::mpsc::Receiver<Category>,
+    handlers   : Vec<(Category, Quota, Handler)>,
+}
+
+impl<Category: PartialEq + Send + 'static> EventLoop<Category> {
+    /// Create a new event loop listening on the given category receiver.
+    pub fn new(category_rx: ::std::sync::mpsc::Receiver<Category>) -> EventLoop<Category> {
+        EventLoop {
+            category_rx,
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Register `handler` to be called with each event received on `event_rx` for `category`.
+    pub fn register<EventSubset, Rx, F>(&mut self, category: Category, event_rx: Rx, handler: F)
+        where Rx: ChannelReceiver<EventSubset> + Send + 'static,
+              F: FnMut(EventSubset) -> Flow + Send + 'static
+    {
+        self.register_with_quota(category, Quota::unlimited(), event_rx, handler)
+    }
+
+    /// As `register`, but limiting each scheduling round for `category` to `quota`.
+    pub fn register_with_quota<EventSubset, Rx, F>(&mut self,
+                                                   category: Category,
+                                                   quota: Quota,
+                                                   event_rx: Rx,
+                                                   mut handler: F)
+        where Rx: ChannelReceiver<EventSubset> + Send + 'static,
+              F: FnMut(EventSubset) -> Flow + Send + 'static
+    {
+        self.add_handler(category, quota, Box::new(move || {
+            match event_rx.try_recv() {
+                Ok(event) => handler(event),
+                // The event was already consumed or the sender has gone; nothing to do.
+                Err(_) => Flow::Continue,
+            }
+        }))
+    }
+
+    /// Stop the loop when a notification for `category` arrives. Such a category needs no event
+    /// subset: send it directly on the category channel.
+    pub fn register_shutdown(&mut self, category: Category) {
+        self.add_handler(category, Quota::unlimited(), Box::new(|| Flow::Stop))
+    }
+
+    /// Run the loop on the current thread until it is stopped.
+    pub fn run(self) {
+        let mut scheduler = FairScheduler::new(self.category_rx);
+        for (category, quota, handler) in self.handlers {
+            scheduler.register(category, quota, handler);
+        }
+        scheduler.run();
+    }
+
+    /// Run the loop on a new thread named `thread_name`.
+    pub fn spawn<T: Into<String>>(self, thread_name: T) -> ::thread::RaiiThreadJoiner {
+        ::thread::RaiiThreadJoiner::new(thread!(thread_name.into(), move || self.run()))
+    }
+
+    fn add_handler(&mut self, category: Category, quota: Quota, handler: Handler) {
+        self.handlers.retain(|(existing, _, _)| *existing != category);
+        self.handlers.push((category, quota, handler));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::EventSender;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum EventCategory {
+        Network,
+        UserInterface,
+        Terminate,
+    }
+
+    #[test]
+    fn dispatch_per_category() {
+        let (network_event_tx, network_event_rx) = ::std::sync::mpsc::channel();
+        let (ui_event_tx, ui_event_rx) = ::event_sender::instrumented_channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let (results_tx, results_rx) = ::std::sync::mpsc::channel();
+
+        let network_sender = EventSender::new(network_event_tx,
+                                              EventCategory::Network,
+                                              category_tx.clone());
+        let ui_sender = EventSender::new(ui_event_tx,
+                                         EventCategory::UserInterface,
+                                         category_tx.clone());
+
+        let mut event_loop = EventLoop::new(category_rx);
+        let network_results_tx = results_tx.clone();
+        event_loop.register(EventCategory::Network, network_event_rx, move |token: u32| {
+            unwrap_result!(network_results_tx.send(format!("Network {}", token)));
+            Flow::Continue
+        });
+        event_loop.register(EventCategory::UserInterface, ui_event_rx, move |name: String| {
+            unwrap_result!(results_tx.send(name));
+            Flow::Continue
+        });
+        event_loop.register_shutdown(EventCategory::Terminate);
+
+        {
+            let _raii_joiner = event_loop.spawn("EventLoopTest");
+            assert!(network_sender.send(9876).is_ok());
+            assert!(ui_sender.send("NewDirectory".to_string()).is_ok());
+            unwrap_result!(category_tx.send(EventCategory::Terminate));
+        }
+
+        assert_eq!(results_rx.iter().collect::<Vec<_>>(),
+                   vec!["Network 9876".to_string(), "NewDirectory".to_string()]);
+        // The loop has exited so its receivers are gone.
+        assert!(network_sender.send(1).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+
+/// The event subset of a sender supporting `flush()`: either a regular event or a flush marker.
+///
+/// The marker acknowledges the flush when it is dropped, so a listener needs no special handling
+/// beyond ignoring it. As the channel is FIFO, by the time the listener gets to the marker it has
+/// received everything sent before the flush. Dropping the receiver (and with it any queued
+/// markers) also releases any waiting `flush()` calls, which then fail.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, Flushable};
+/// use std::sync::{Arc, Mutex};
+/// use std::time::Duration;
+///
+/// let (event_tx, event_rx) = std::sync::mpsc::channel();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Disk", category_tx);
+///
+/// let written = Arc::new(Mutex::new(Vec::new()));
+/// let written_clone = written.clone();
+/// let _listener = thread!("DiskWriter", move || {
+///     for _ in category_rx.iter() {
+///         match event_rx.try_recv() {
+///             Ok(Flushable::Event(block)) => unwrap_result!(written_clone.lock()).push(block),
+///             Ok(Flushable::Flush(_)) | Err(_) => (),
+///         }
+///     }
+/// });
+///
+/// for block in 0..10 {
+///     assert!(sender.send(Flushable::Event(block)).is_ok());
+/// }
+/// assert!(sender.flush(Duration::from_secs(5)).is_ok());
+/// assert_eq!(unwrap_result!(written.lock()).len(), 10);
+/// # }
+/// ```
+#[derive(Debug)]
+pub enum Flushable<EventSubset> {
+    /// A regular event.
+    Event(EventSubset),
+    /// A flush marker, acknowledged when dropped.
+    Flush(FlushMarker),
+}
+
+/// The marker sent by `flush()`. See `Flushable`.
+pub struct FlushMarker {
+    ack_tx: ::std::sync::mpsc::Sender<()>,
+}
+
+impl Drop for FlushMarker {
+    fn drop(&mut self) {
+        let _ = self.ack_tx.send(());
+    }
+}
+
+impl ::std::fmt::Debug for FlushMarker {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "FlushMarker")
+    }
+}
+
+/// Errors that can be returned by `EventSender::flush`
+#[derive(Debug)]
+pub enum FlushError<Category, EventSubset> {
+    /// The flush marker could not be sent
+    Send(EventSenderError<Category, Flushable<EventSubset>>),
+    /// The listener did not reach the flush marker before the deadline
+    Timeout,
+}
+
+impl<Category, EventSubset> ::std::fmt::Display for FlushError<Category, EventSubset> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            FlushError::Send(ref error) => write!(formatter, "Error sending flush marker: {}", error),
+            FlushError::Timeout => write!(formatter, "Timed out waiting for the flush"),
+        }
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + 'static,
+     EventSubset: ::std::fmt::Debug + 'static> ::std::error::Error
+    for FlushError<Category, EventSubset> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            FlushError::Send(ref error) => Some(error),
+            FlushError::Timeout => None,
+        }
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<Flushable<EventSubset>>,
+     CategoryTx : ChannelSender<Category>>
+    EventSender<Category, Flushable<EventSubset>, EventTx, CategoryTx> {
+    /// Block until the listener has received every event sent through this sender (or any of its
+    /// clones) before the call, or until `timeout` has elapsed. Events sent concurrently by other
+    /// threads may or may not be covered.
+    ///
+    /// If the receiver has been dropped by the time the marker is acknowledged, this returns
+    /// `FlushError::Send`, as the marker may have been dropped along with the receiver rather than
+    /// reached by the listener. This is checked by sending a second marker, which the listener
+    /// ignores like the first.
+    pub fn flush(&self, timeout: ::std::time::Duration)
+                 -> Result<(), FlushError<Category, EventSubset>> {
+        let (ack_tx, ack_rx) = ::std::sync::mpsc::channel();
+        self.send(Flushable::Flush(FlushMarker { ack_tx })).map_err(|error| {
+            // Dropping the marker inside the error acknowledges it; nobody is listening for that.
+            FlushError::Send(error)
+        })?;
+        // The marker always acknowledges before dropping `ack_tx`, so this can only time out.
+        ack_rx.recv_timeout(timeout).map_err(|_| FlushError::Timeout)?;
+        // Nobody waits for this marker's acknowledgement.
+        let (probe_tx, _) = ::std::sync::mpsc::channel();
+        match self.send(Flushable::Flush(FlushMarker { ack_tx: probe_tx })) {
+            Err(error @ EventSenderError::EventSendError(_)) |
+            Err(error @ EventSenderError::CategorySendError(_)) => Err(FlushError::Send(error)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flush_waits_for_the_marker() {
+        let timeout = ::std::time::Duration::from_millis(50);
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, (), category_tx);
+
+        assert!(sender.send(Flushable::Event(1)).is_ok());
+        match sender.flush(timeout) {
+            Err(FlushError::Timeout) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // The timed-out marker is still queued behind the event, followed by the next one.
+        let flusher = sender.clone();
+        let joiner = ::std::thread::spawn(move || flusher.flush(::std::time::Duration::from_secs(5)));
+        let received: Vec<_> = (0..3).map(|_| unwrap_result!(event_rx.recv())).collect();
+        match received[..] {
+            [Flushable::Event(1), Flushable::Flush(_), Flushable::Flush(_)] => (),
+            ref events => panic!("Unexpected events {:?}", events),
+        }
+        drop(received);
+        assert!(unwrap_result!(joiner.join()).is_ok());
+        match unwrap_result!(event_rx.recv()) {
+            Flushable::Flush(_) => (),
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        drop(event_rx);
+        match sender.flush(timeout) {
+            Err(FlushError::Send(EventSenderError::EventSendError(_))) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn flush_fails_if_the_receiver_drops_the_marker() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel::<Flushable<u8>>();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, (), category_tx);
+
+        let timeout = ::std::time::Duration::from_secs(5);
+        let joiner = ::std::thread::spawn(move || sender.flush(timeout));
+        // Whether or not the marker has been queued yet, the flush must fail.
+        ::std::thread::sleep(::std::time::Duration::from_millis(50));
+        drop(event_rx);
+        match unwrap_result!(joiner.join()) {
+            Err(FlushError::Send(EventSenderError::EventSendError(_))) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
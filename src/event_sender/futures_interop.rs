@@ -0,0 +1,109 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Lets an `EventSender` feed events into a `futures` based reactor. Only available with the
+//! `futures` feature enabled.
+//!
+//! Any `futures::sync::mpsc::UnboundedSender` can be used as either of an `EventSender`'s
+//! channels. With both of them being such, as created by `unbounded_event_sender`, synchronous
+//! code can keep using `EventSender::send` while the observer consumes the events and category
+//! notifications as `Stream`s:
+//!
+//! ```
+//! # extern crate futures;
+//! # extern crate maidsafe_utilities;
+//! # fn main() {
+//! use futures::{Future, Stream};
+//! use maidsafe_utilities::event_sender::futures_interop::unbounded_event_sender;
+//!
+//! let (sender, event_rx, category_rx) = unbounded_event_sender::<_, u32>("Network");
+//!
+//! assert!(sender.send(7).is_ok());
+//! drop(sender);
+//!
+//! let categories = category_rx.collect().wait().unwrap();
+//! let events = event_rx.collect().wait().unwrap();
+//! assert_eq!((categories, events), (vec!["Network"], vec![7]));
+//! # }
+//! ```
+
+use futures::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use super::{ChannelSender, EventSender};
+
+/// An `EventSender` whose events and category notifications both go over unbounded `futures`
+/// channels.
+pub type FuturesEventSender<Category, EventSubset> =
+    EventSender<Category, EventSubset, UnboundedSender<EventSubset>, UnboundedSender<Category>>;
+
+/// Create an `EventSender` for `event_category` over a pair of new unbounded `futures` channels,
+/// returning it along with the `Stream`s of events and of category notifications.
+pub fn unbounded_event_sender<Category, EventSubset>
+    (event_category: Category)
+     -> (FuturesEventSender<Category, EventSubset>,
+         UnboundedReceiver<EventSubset>,
+         UnboundedReceiver<Category>)
+    where Category: ::std::fmt::Debug + Clone,
+          EventSubset: ::std::fmt::Debug
+{
+    let (event_tx, event_rx) = unbounded();
+    let (category_tx, category_rx) = unbounded();
+    (EventSender::new(event_tx, event_category, category_tx), event_rx, category_rx)
+}
+
+impl<T> ChannelSender<T> for UnboundedSender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        self.unbounded_send(value)
+            .map_err(|error| ::std::sync::mpsc::SendError(error.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use event_sender::EventSender;
+    use futures::{Future, Stream};
+    use super::*;
+
+    #[test]
+    fn feed_async_reactor_from_sync_code() {
+        let (event_tx, event_rx) = ::futures::sync::mpsc::unbounded();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+
+        let joiner = thread!("LegacySyncModule", move || {
+            for event in 0..3 {
+                assert!(sender.send(event).is_ok());
+            }
+        });
+        let _raii_joiner = ::thread::RaiiThreadJoiner::new(joiner);
+
+        let received = unwrap_result!(event_rx.take(3).collect().wait());
+        assert_eq!(received, vec![0, 1, 2]);
+        assert_eq!(category_rx.iter().count(), 3);
+    }
+
+    #[test]
+    fn streams_close_with_the_sender() {
+        let (sender, event_rx, category_rx) = unbounded_event_sender("Network");
+        let clone = sender.clone();
+        assert!(sender.send("Connected").is_ok());
+        assert!(clone.send("Disconnected").is_ok());
+        drop((sender, clone));
+
+        assert_eq!(unwrap_result!(event_rx.collect().wait()), vec!["Connected", "Disconnected"]);
+        assert_eq!(unwrap_result!(category_rx.collect().wait()), vec!["Network", "Network"]);
+    }
+}
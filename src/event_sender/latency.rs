@@ -0,0 +1,278 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::Timestamped;
+
+// Each power of two is split into this many sub-buckets, each at most a quarter as wide as its
+// lower bound. Percentiles are reported as a bucket's upper bound, so may be up to ~25% too high.
+const SUB_BUCKET_BITS: u32 = 2;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (SUB_BUCKETS * (64 - SUB_BUCKET_BITS as u64 + 1)) as usize;
+
+/// A summary of the latencies recorded by a `LatencyHistogram`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// The number of samples.
+    pub count: u64,
+    /// The smallest sample.
+    pub min: ::std::time::Duration,
+    /// The largest sample.
+    pub max: ::std::time::Duration,
+    /// The mean of all samples.
+    pub mean: ::std::time::Duration,
+    /// The median, to within the histogram's precision.
+    pub p50: ::std::time::Duration,
+    /// The 99th percentile, to within the histogram's precision.
+    pub p99: ::std::time::Duration,
+}
+
+/// A fixed-size, log-scaled histogram of latencies. Recording is O(1) and the histogram never
+/// grows, so it can be left running in production.
+#[derive(Clone)]
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count  : u64,
+    sum_ns : u128,
+    min_ns : u64,
+    max_ns : u64,
+}
+
+impl LatencyHistogram {
+    /// Create an empty histogram.
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram {
+            buckets: vec![0; BUCKETS],
+            count  : 0,
+            sum_ns : 0,
+            min_ns : u64::MAX,
+            max_ns : 0,
+        }
+    }
+
+    /// Record one sample.
+    pub fn record(&mut self, latency: ::std::time::Duration) {
+        let nanos = ::std::convert::TryFrom::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+        self.buckets[bucket_index(nanos)] += 1;
+        self.count += 1;
+        self.sum_ns += u128::from(nanos);
+        self.min_ns = self.min_ns.min(nanos);
+        self.max_ns = self.max_ns.max(nanos);
+    }
+
+    /// Summarise the samples recorded so far.
+    pub fn stats(&self) -> LatencyStats {
+        if self.count == 0 {
+            return LatencyStats::default();
+        }
+        LatencyStats {
+            count: self.count,
+            min  : ::std::time::Duration::from_nanos(self.min_ns),
+            max  : ::std::time::Duration::from_nanos(self.max_ns),
+            mean : ::std::time::Duration::from_nanos((self.sum_ns / u128::from(self.count)) as u64),
+            p50  : self.percentile(50),
+            p99  : self.percentile(99),
+        }
+    }
+
+    // The upper bound of the bucket holding the sample at `percent`, capped at the largest sample.
+    fn percentile(&self, percent: u64) -> ::std::time::Duration {
+        let rank = (self.count * percent).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += *count;
+            if seen >= rank {
+                let upper = bucket_upper_bound(index).min(self.max_ns);
+                return ::std::time::Duration::from_nanos(upper.max(self.min_ns));
+            }
+        }
+        ::std::time::Duration::from_nanos(self.max_ns)
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> LatencyHistogram {
+        LatencyHistogram::new()
+    }
+}
+
+impl ::std::fmt::Debug for LatencyHistogram {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "LatencyHistogram({:?})", self.stats())
+    }
+}
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS {
+        return nanos as usize;
+    }
+    let exponent = 63 - nanos.leading_zeros();
+    let sub_bucket = (nanos >> (exponent - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+    (SUB_BUCKETS * u64::from(exponent - SUB_BUCKET_BITS + 1) + sub_bucket) as usize
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let lower = (SUB_BUCKETS + index % SUB_BUCKETS) << shift;
+    lower + ((1u64 << shift) - 1)
+}
+
+/// Opt-in per-category measurement of send→receive latency.
+///
+/// Send `Timestamped` events (see `EventSender::send_timestamped`) and have the listener pass each
+/// received one to `record_timestamped` along with its category. The tracker can be shared with a
+/// reporting thread via an `Arc`.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, LatencyTracker};
+///
+/// let (event_tx, event_rx) = std::sync::mpsc::channel();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+/// let tracker = LatencyTracker::new();
+///
+/// for n in 0..100 {
+///     assert!(sender.send_timestamped(n).is_ok());
+/// }
+/// for category in category_rx.try_iter() {
+///     let event = unwrap_result!(event_rx.try_recv());
+///     tracker.record_timestamped(&category, &event);
+/// }
+///
+/// let stats = unwrap_option!(tracker.stats(&"Network"), "No samples recorded");
+/// assert_eq!(stats.count, 100);
+/// assert!(stats.p50 <= stats.p99 && stats.p99 <= stats.max);
+/// # }
+/// ```
+pub struct LatencyTracker<Category> {
+    histograms: ::std::sync::Mutex<Vec<(Category, LatencyHistogram)>>,
+}
+
+impl<Category: PartialEq + Clone> LatencyTracker<Category> {
+    /// Create a tracker with no samples.
+    pub fn new() -> LatencyTracker<Category> {
+        LatencyTracker { histograms: ::std::sync::Mutex::new(Vec::new()) }
+    }
+
+    /// Record a latency sample for `category`.
+    pub fn record(&self, category: &Category, latency: ::std::time::Duration) {
+        let mut histograms = unwrap_result!(self.histograms.lock());
+        if let Some(&mut (_, ref mut histogram)) =
+               histograms.iter_mut().find(|entry| entry.0 == *category) {
+            return histogram.record(latency);
+        }
+        let mut histogram = LatencyHistogram::new();
+        histogram.record(latency);
+        histograms.push((category.clone(), histogram));
+    }
+
+    /// Record how long `event` spent between being sent and now.
+    pub fn record_timestamped<EventSubset>(&self,
+                                           category: &Category,
+                                           event: &Timestamped<EventSubset>) {
+        self.record(category, event.age())
+    }
+
+    /// The latency summary for `category`, or `None` if nothing has been recorded for it.
+    pub fn stats(&self, category: &Category) -> Option<LatencyStats> {
+        unwrap_result!(self.histograms.lock())
+            .iter()
+            .find(|entry| entry.0 == *category)
+            .map(|entry| entry.1.stats())
+    }
+
+    /// The latency summaries of every category recorded so far.
+    pub fn all_stats(&self) -> Vec<(Category, LatencyStats)> {
+        unwrap_result!(self.histograms.lock())
+            .iter()
+            .map(|entry| (entry.0.clone(), entry.1.stats()))
+            .collect()
+    }
+
+    /// Discard all samples.
+    pub fn reset(&self) {
+        unwrap_result!(self.histograms.lock()).clear();
+    }
+}
+
+impl<Category: PartialEq + Clone> Default for LatencyTracker<Category> {
+    fn default() -> LatencyTracker<Category> {
+        LatencyTracker::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buckets_cover_every_value() {
+        for nanos in (0..10_000).chain(vec![u64::MAX / 2, u64::MAX - 1, u64::MAX]) {
+            let index = bucket_index(nanos);
+            assert!(index < BUCKETS);
+            assert!(nanos <= bucket_upper_bound(index));
+            if index > 0 {
+                assert!(nanos > bucket_upper_bound(index - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn percentiles() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.stats(), LatencyStats::default());
+
+        for micros in 1..1001 {
+            histogram.record(::std::time::Duration::from_micros(micros));
+        }
+        let stats = histogram.stats();
+        assert_eq!(stats.count, 1000);
+        assert_eq!(stats.min, ::std::time::Duration::from_micros(1));
+        assert_eq!(stats.max, ::std::time::Duration::from_micros(1000));
+        assert_eq!(stats.mean, ::std::time::Duration::from_nanos(500_500));
+
+        // Each bucket is at most ~25% wide, and percentiles report its upper bound.
+        let p50 = stats.p50.as_nanos() as f64;
+        let p99 = stats.p99.as_nanos() as f64;
+        assert!((500_000.0..=625_000.0).contains(&p50), "p50 = {}", p50);
+        assert!((990_000.0..=1_000_000.0).contains(&p99), "p99 = {}", p99);
+    }
+
+    #[test]
+    fn per_category() {
+        let tracker = LatencyTracker::new();
+        tracker.record(&1, ::std::time::Duration::from_millis(1));
+        tracker.record(&2, ::std::time::Duration::from_millis(5));
+        tracker.record(&1, ::std::time::Duration::from_millis(3));
+
+        assert_eq!(unwrap_option!(tracker.stats(&1), "").count, 2);
+        assert_eq!(unwrap_option!(tracker.stats(&2), "").max, ::std::time::Duration::from_millis(5));
+        assert_eq!(tracker.stats(&3), None);
+        assert_eq!(tracker.all_stats().len(), 2);
+
+        tracker.reset();
+        assert!(tracker.all_stats().is_empty());
+    }
+}
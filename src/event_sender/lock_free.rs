@@ -0,0 +1,299 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! A lock-free multi-producer single-consumer channel, usable as the event subset transport of an
+//! `EventSender` on high-throughput paths.
+
+// The queue is built from raw, atomically linked nodes.
+#![allow(unsafe_code)]
+
+use super::{ChannelReceiver, ChannelSender};
+
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::mpsc::{SendError, TryRecvError};
+
+struct Node<T> {
+    next : AtomicPtr<Node<T>>,
+    value: Option<T>,
+}
+
+impl<T> Node<T> {
+    fn new(value: Option<T>) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            value,
+        }))
+    }
+}
+
+enum Pop<T> {
+    Data(T),
+    Empty,
+    // A producer has swapped itself in as head but not yet linked its node.
+    Inconsistent,
+}
+
+// Dmitry Vyukov's intrusive MPSC queue: producers only ever touch `head` (with a single atomic
+// swap), the consumer only ever touches `tail`. `tail` always points at a stub node whose value
+// has already been taken.
+struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: UnsafeCell<*mut Node<T>>,
+}
+
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    fn new() -> Queue<T> {
+        let stub = Node::new(None);
+        Queue {
+            head: AtomicPtr::new(stub),
+            tail: UnsafeCell::new(stub),
+        }
+    }
+
+    fn push(&self, value: T) {
+        let node = Node::new(Some(value));
+        let previous = self.head.swap(node, Ordering::AcqRel);
+        // `previous` can't have been freed: the consumer never frees the node at `head`.
+        unsafe { (*previous).next.store(node, Ordering::Release) };
+    }
+
+    // Must only ever be called from the single consumer.
+    unsafe fn pop(&self) -> Pop<T> {
+        let tail = *self.tail.get();
+        let next = (*tail).next.load(Ordering::Acquire);
+        if !next.is_null() {
+            *self.tail.get() = next;
+            let value = (*next).value.take();
+            drop(Box::from_raw(tail));
+            return match value {
+                Some(value) => Pop::Data(value),
+                None => unreachable!("Only the stub node has no value"),
+            };
+        }
+        if self.head.load(Ordering::Acquire) == tail {
+            Pop::Empty
+        } else {
+            Pop::Inconsistent
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        let mut node = *self.tail.get_mut();
+        while !node.is_null() {
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+struct Shared<T> {
+    queue         : Queue<T>,
+    senders       : AtomicUsize,
+    receiver_alive: AtomicBool,
+}
+
+/// Create a lock-free multi-producer single-consumer channel.
+///
+/// Sending is a single atomic swap plus a store, and never blocks or allocates more than the node
+/// holding the value. The receiver is non-blocking only (`try_recv`), which is all an event subset
+/// channel needs: the observer is woken by the category channel and then pulls the event.
+///
+/// Since Rust 1.67 `std::sync::mpsc` is itself built on a lock-free queue, so whether this is
+/// worth using depends on how contended the channel is. Compare the two with
+/// `cargo bench --bench event_sender` (several producers and one consumer) on the target
+/// hardware before choosing one over the other.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, lock_free};
+///
+/// let (event_tx, event_rx) = lock_free::channel();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+///
+/// assert!(sender.send(1).is_ok());
+/// assert_eq!(category_rx.recv(), Ok("Network"));
+/// assert_eq!(event_rx.try_recv(), Ok(1));
+/// # }
+/// ```
+pub fn channel<T>() -> (LockFreeSender<T>, LockFreeReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue         : Queue::new(),
+        senders       : AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+    });
+    (LockFreeSender { shared: shared.clone() },
+     LockFreeReceiver {
+        shared,
+        _not_sync: PhantomData,
+    })
+}
+
+/// The sending half of a lock-free channel. It can be cloned to give multiple producers.
+pub struct LockFreeSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> ChannelSender<T> for LockFreeSender<T> {
+    fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if !self.shared.receiver_alive.load(Ordering::Acquire) {
+            return Err(SendError(value));
+        }
+        self.shared.queue.push(value);
+        Ok(())
+    }
+}
+
+impl<T> Clone for LockFreeSender<T> {
+    fn clone(&self) -> LockFreeSender<T> {
+        let _ = self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        LockFreeSender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for LockFreeSender<T> {
+    fn drop(&mut self) {
+        let _ = self.shared.senders.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The receiving half of a lock-free channel. There can only be one consumer, so this is neither
+/// `Clone` nor `Sync`.
+pub struct LockFreeReceiver<T> {
+    shared   : Arc<Shared<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> LockFreeReceiver<T> {
+    /// Receive a value if one is waiting. Returns `TryRecvError::Disconnected` once all senders
+    /// have gone and the channel has been drained.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut backoff = ::thread::Backoff::new();
+        loop {
+            // Safe as `LockFreeReceiver` is the only consumer and is `!Sync`.
+            match unsafe { self.shared.queue.pop() } {
+                Pop::Data(value) => return Ok(value),
+                Pop::Inconsistent => backoff.spin(),
+                Pop::Empty => {
+                    if self.shared.senders.load(Ordering::Acquire) != 0 {
+                        return Err(TryRecvError::Empty);
+                    }
+                    // All senders have gone, but one may have pushed just before leaving.
+                    return match unsafe { self.shared.queue.pop() } {
+                        Pop::Data(value) => Ok(value),
+                        _ => Err(TryRecvError::Disconnected),
+                    };
+                }
+            }
+        }
+    }
+
+    /// Receive every value currently waiting, without blocking.
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+}
+
+impl<T> ChannelReceiver<T> for LockFreeReceiver<T> {
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        LockFreeReceiver::try_recv(self)
+    }
+}
+
+impl<T> Drop for LockFreeReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+/// Iterator over the values currently waiting in a `LockFreeReceiver`. See
+/// `LockFreeReceiver::try_iter`.
+pub struct TryIter<'a, T: 'a> {
+    receiver: &'a LockFreeReceiver<T>,
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multiple_producers() {
+        const PRODUCERS: usize = 4;
+        const EVENTS_PER_PRODUCER: usize = 10_000;
+
+        let (tx, rx) = channel();
+        let joiners = (0..PRODUCERS)
+                          .map(|producer| {
+                              let tx = tx.clone();
+                              ::thread::RaiiThreadJoiner::new(thread!("LockFreeProducer", move || {
+                                  for index in 0..EVENTS_PER_PRODUCER {
+                                      assert!(tx.send((producer, index)).is_ok());
+                                  }
+                              }))
+                          })
+                          .collect::<Vec<_>>();
+        drop(tx);
+
+        let mut next_index = [0; PRODUCERS];
+        loop {
+            match rx.try_recv() {
+                Ok((producer, index)) => {
+                    // Each producer's values must arrive in the order they were sent.
+                    assert_eq!(index, next_index[producer]);
+                    next_index[producer] += 1;
+                }
+                Err(TryRecvError::Empty) => ::std::thread::yield_now(),
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        drop(joiners);
+
+        assert!(next_index.iter().all(|&count| count == EVENTS_PER_PRODUCER));
+    }
+
+    #[test]
+    fn send_fails_once_receiver_is_gone() {
+        let (tx, rx) = channel();
+        assert!(tx.send(1).is_ok());
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        // Values still queued when the receiver is dropped must be freed with the queue.
+        assert!(tx.send(2).is_ok());
+        drop(rx);
+        assert_eq!(unwrap_option!(tx.send(3).err(), "Send should have failed.").0, 3);
+    }
+}
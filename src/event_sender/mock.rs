@@ -0,0 +1,197 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::EventSender;
+
+/// Errors that can be returned by `MockEventSender::expect_next`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExpectError {
+    /// No further event was sent before the deadline
+    Timeout,
+    /// The next event did not satisfy the matcher. Holds its `Debug` representation.
+    Mismatch(String),
+}
+
+impl ::std::fmt::Display for ExpectError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            ExpectError::Timeout => write!(formatter, "Timed out waiting for the next event"),
+            ExpectError::Mismatch(ref event) => write!(formatter, "Unexpected event: {}", event),
+        }
+    }
+}
+
+impl ::std::error::Error for ExpectError {}
+
+/// Test-support stand-in for a listener: hands out a regular `EventSender` and records everything
+/// sent through it, so a module taking an `EventSender` can be unit-tested without spinning up a
+/// listener thread.
+///
+/// Events are recorded in the order they were sent and are inspected either wholesale via
+/// `events()` or one at a time via `expect_next()`, which keeps its own cursor into the record.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, MockEventSender};
+/// use std::time::Duration;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum NetworkEvent {
+///     Connected(u32),
+///     Disconnected(u32),
+/// }
+///
+/// fn connect(peer: u32, sender: EventSender<&'static str, NetworkEvent>)
+///            -> std::thread::JoinHandle<()> {
+///     std::thread::spawn(move || {
+///         assert!(sender.send(NetworkEvent::Connected(peer)).is_ok());
+///         assert!(sender.send(NetworkEvent::Disconnected(peer)).is_ok());
+///     })
+/// }
+///
+/// let mock = MockEventSender::new("Network");
+/// let joiner = connect(7, mock.sender());
+///
+/// let timeout = Duration::from_secs(5);
+/// assert!(mock.expect_next(|event| *event == NetworkEvent::Connected(7), timeout).is_ok());
+/// assert!(mock.expect_next(|event| *event == NetworkEvent::Disconnected(7), timeout).is_ok());
+///
+/// assert!(joiner.join().is_ok());
+/// assert_eq!(mock.categories(), vec!["Network", "Network"]);
+/// # }
+/// ```
+pub struct MockEventSender<Category, EventSubset> {
+    sender: EventSender<Category, EventSubset>,
+    state : ::std::sync::Mutex<State<Category, EventSubset>>,
+}
+
+struct State<Category, EventSubset> {
+    event_rx   : ::std::sync::mpsc::Receiver<EventSubset>,
+    category_rx: ::std::sync::mpsc::Receiver<Category>,
+    events     : Vec<EventSubset>,
+    categories : Vec<Category>,
+    cursor     : usize,
+}
+
+impl<Category, EventSubset> State<Category, EventSubset> {
+    fn drain(&mut self) {
+        self.events.extend(self.event_rx.try_iter());
+        self.categories.extend(self.category_rx.try_iter());
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug> MockEventSender<Category, EventSubset> {
+    /// Create a new instance whose senders fire events under `event_category`.
+    pub fn new(event_category: Category) -> MockEventSender<Category, EventSubset> {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        MockEventSender {
+            sender: EventSender::new(event_tx, event_category, category_tx),
+            state : ::std::sync::Mutex::new(State {
+                event_rx,
+                category_rx,
+                events: Vec::new(),
+                categories: Vec::new(),
+                cursor: 0,
+            }),
+        }
+    }
+
+    /// A sender to hand to the code under test. All such senders feed this mock.
+    pub fn sender(&self) -> EventSender<Category, EventSubset> {
+        self.sender.clone()
+    }
+
+    /// Every event sent so far, in the order they were sent.
+    pub fn events(&self) -> Vec<EventSubset>
+        where EventSubset: Clone
+    {
+        let mut state = unwrap_result!(self.state.lock());
+        state.drain();
+        state.events.clone()
+    }
+
+    /// Every category notification sent so far, in the order they were sent.
+    pub fn categories(&self) -> Vec<Category> {
+        let mut state = unwrap_result!(self.state.lock());
+        state.drain();
+        state.categories.clone()
+    }
+
+    /// Wait up to `timeout` for the event following the one inspected by the previous call (or
+    /// the first event, on the first call) and check it with `matcher`. The cursor advances even
+    /// if the event does not match. A `timeout` too long to be represented as an `Instant` means
+    /// waiting as long as it takes.
+    pub fn expect_next<F>(&self, matcher: F, timeout: ::std::time::Duration)
+                          -> Result<(), ExpectError>
+        where F: FnOnce(&EventSubset) -> bool
+    {
+        let deadline = ::std::time::Instant::now().checked_add(timeout);
+        let mut state = unwrap_result!(self.state.lock());
+        state.drain();
+        while state.events.len() <= state.cursor {
+            let remaining = deadline.map_or(timeout, |deadline| {
+                deadline.saturating_duration_since(::std::time::Instant::now())
+            });
+            match state.event_rx.recv_timeout(remaining) {
+                Ok(event) => state.events.push(event),
+                Err(_) => return Err(ExpectError::Timeout),
+            }
+        }
+
+        let cursor = state.cursor;
+        state.cursor += 1;
+        if matcher(&state.events[cursor]) {
+            Ok(())
+        } else {
+            Err(ExpectError::Mismatch(format!("{:?}", state.events[cursor])))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expect_next() {
+        let timeout = ::std::time::Duration::from_millis(50);
+        let mock = MockEventSender::new(0u8);
+        let sender = mock.sender();
+
+        assert_eq!(mock.expect_next(|_| true, timeout), Err(ExpectError::Timeout));
+
+        assert!(sender.send("first").is_ok());
+        assert!(sender.send("second").is_ok());
+        assert!(sender.send("third").is_ok());
+
+        assert_eq!(mock.expect_next(|event| *event == "first", timeout), Ok(()));
+        assert_eq!(mock.expect_next(|event| *event == "third", timeout),
+                   Err(ExpectError::Mismatch("\"second\"".to_owned())));
+        assert_eq!(mock.expect_next(|event| *event == "third", timeout), Ok(()));
+        assert_eq!(mock.expect_next(|_| true, timeout), Err(ExpectError::Timeout));
+        assert!(sender.send("fourth").is_ok());
+        assert_eq!(mock.expect_next(|event| *event == "fourth", ::std::time::Duration::MAX),
+                   Ok(()));
+
+        assert_eq!(mock.events(), vec!["first", "second", "third", "fourth"]);
+        assert_eq!(mock.categories(), vec![0, 0, 0, 0]);
+    }
+}
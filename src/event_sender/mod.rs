@@ -0,0 +1,924 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Derive the boilerplate needed to hook an event category enum into the event system.
+///
+/// Alongside `Clone` and `Debug`, each variant annotated with `#[event_subset(Type)]` gets an impl
+/// of `SubsetCategory` mapping `Type` to that variant, and a typed constructor helper on the enum,
+/// named after the variant in snake case with a `_sender` suffix. Variants may have doc comments
+/// and discriminants, but must not carry data, and the enum must not be generic.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// use maidsafe_utilities::event_sender::{EventCategory, EventSender};
+///
+/// #[derive(EventCategory, PartialEq)]
+/// enum Category {
+///     #[event_subset(NetworkEvent)]
+///     Network,
+///     #[event_subset(UiEvent)]
+///     UserInterface,
+///     Terminate,
+/// }
+///
+/// #[derive(Debug)]
+/// enum NetworkEvent {
+///     Connected,
+/// }
+///
+/// #[derive(Debug)]
+/// enum UiEvent {
+///     CreateDirectory,
+/// }
+///
+/// # fn main() {
+/// let (network_event_tx, _network_event_rx) = std::sync::mpsc::channel();
+/// let (ui_event_tx, _ui_event_rx) = std::sync::mpsc::channel();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+///
+/// let network_sender = Category::network_sender(network_event_tx, category_tx.clone());
+/// let ui_sender = EventSender::for_subset(ui_event_tx, category_tx);
+///
+/// assert!(network_sender.send(NetworkEvent::Connected).is_ok());
+/// assert!(ui_sender.send(UiEvent::CreateDirectory).is_ok());
+///
+/// assert!(unwrap_result!(category_rx.recv()) == Category::Network);
+/// assert!(unwrap_result!(category_rx.recv()) == Category::UserInterface);
+/// assert_eq!(format!("{:?}", Category::Terminate.clone()), "Terminate");
+/// # }
+/// ```
+///
+/// Doc comments and discriminants are passed over:
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// use maidsafe_utilities::event_sender::EventCategory;
+///
+/// # #[derive(Debug)]
+/// # enum NetworkEvent {}
+/// #[derive(EventCategory)]
+/// enum Category {
+///     /// Events from the network.
+///     #[event_subset(NetworkEvent)]
+///     Network = 1,
+///     /// Shut the event loop down.
+///     Terminate = 2,
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Category::Network as u8, 1);
+/// assert_eq!(format!("{:?}", Category::Terminate.clone()), "Terminate");
+/// # let _ = Category::network_sender;
+/// # }
+/// ```
+///
+/// Whereas these each fail to compile:
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// struct Category;
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// enum Category {
+///     Network(u8),
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// enum Category {
+///     Network { id: u8 },
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// enum Category {
+///     #[event_subset()]
+///     Network,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// # #[derive(Debug)]
+/// # enum NetworkEvent {}
+/// #[derive(EventCategory)]
+/// enum Category {
+///     #[event_subset = "NetworkEvent"]
+///     Network,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// enum Category<const N: usize> {
+///     Network,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// #[derive(EventCategory)]
+/// enum Category where u8: Copy {
+///     Network,
+/// }
+/// # fn main() {}
+/// ```
+pub use maidsafe_utilities_derive::EventCategory;
+pub use self::bounded::{BoundedReceiver, BoundedSender, OverflowPolicy, bounded_channel,
+                        bounded_channel_with_dead_letters};
+pub use self::bus::{EventBus, Subscription};
+pub use self::event_loop::EventLoop;
+pub use self::envelope::{SequenceChecker, SequenceStatus, Sequenced, Tagged, Timestamped};
+pub use self::flush::{FlushError, FlushMarker, Flushable};
+pub use self::latency::{LatencyHistogram, LatencyStats, LatencyTracker};
+pub use self::mock::{ExpectError, MockEventSender};
+pub use self::priority::{PriorityReceiver, PrioritySender, priority_channel};
+pub use self::rate_limit::RateLimitedSender;
+pub use self::receiver::{EventReceiver, EventReceiverError};
+pub use self::retry::{RetryError, RetryPolicy};
+pub use self::scheduler::{FairScheduler, Flow, Quota};
+pub use self::sharded::{ShardedSender, sharded_channel, sharded_channel_by_key};
+pub use self::task::{Task, TaskSender};
+pub use self::transport::{ChannelReceiver, ChannelSender, InstrumentedReceiver,
+                          InstrumentedSender, instrumented_channel};
+pub use self::ttl::{TtlReceiver, TtlSender, ttl_channel};
+pub use timer::TimerHandle;
+
+mod bounded;
+mod bus;
+mod envelope;
+mod event_loop;
+mod flush;
+#[cfg(feature = "futures")]
+pub mod futures_interop;
+mod latency;
+pub mod lock_free;
+mod mock;
+mod nested;
+mod priority;
+mod rate_limit;
+mod receiver;
+mod retry;
+mod scheduler;
+#[cfg(all(feature = "shared-memory", target_os = "linux"))]
+pub mod shared_memory;
+mod sharded;
+pub mod signals;
+mod task;
+mod transport;
+mod ttl;
+
+/// Errors that can be returned by EventSender
+#[derive(Debug)]
+pub enum EventSenderError<Category, EventSubset> {
+    /// Error sending the event subset
+    EventSendError(::std::sync::mpsc::SendError<EventSubset>),
+    /// Error sending the event category
+    CategorySendError(::std::sync::mpsc::SendError<Category>),
+    /// The event was dropped as sending it would have exceeded the sender's rate limit
+    RateLimited(EventSubset),
+    /// The sender has been closed via `EventSender::close()`
+    Closed(EventSubset),
+    /// The event subset channel is bounded and currently full
+    QueueFull(EventSubset),
+}
+
+impl<Category, EventSubset> ::std::fmt::Display for EventSenderError<Category, EventSubset> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            EventSenderError::EventSendError(ref error) => {
+                write!(formatter, "Error sending the event subset: {}", error)
+            }
+            EventSenderError::CategorySendError(ref error) => {
+                write!(formatter, "Error sending the event category: {}", error)
+            }
+            EventSenderError::RateLimited(_) => {
+                write!(formatter, "Event dropped as the rate limit was exceeded")
+            }
+            EventSenderError::Closed(_) => write!(formatter, "Event sender has been closed"),
+            EventSenderError::QueueFull(_) => write!(formatter, "Event queue is full"),
+        }
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + 'static,
+     EventSubset: ::std::fmt::Debug + 'static> ::std::error::Error
+    for EventSenderError<Category, EventSubset> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            EventSenderError::EventSendError(ref error) => Some(error),
+            EventSenderError::CategorySendError(ref error) => Some(error),
+            EventSenderError::RateLimited(_) |
+            EventSenderError::Closed(_) |
+            EventSenderError::QueueFull(_) => None,
+        }
+    }
+}
+
+// Only the event subset conversion is provided: a second impl for `SendError<Category>` would
+// conflict with this one whenever `Category` and `EventSubset` are the same type.
+impl<Category, EventSubset> From<::std::sync::mpsc::SendError<EventSubset>>
+    for EventSenderError<Category, EventSubset> {
+    fn from(error: ::std::sync::mpsc::SendError<EventSubset>) -> EventSenderError<Category, EventSubset> {
+        EventSenderError::EventSendError(error)
+    }
+}
+
+/// Maps an event subset type to the category it belongs to. Normally implemented via
+/// `#[derive(EventCategory)]`.
+pub trait SubsetCategory<Category> {
+    /// The category under which events of this subset are sent.
+    fn category() -> Category;
+}
+
+/// This structure is coded to achieve event-subsetting. Receivers in Rust are blocking. One cannot
+/// listen to multiple receivers at the same time except by using `try_recv` which again is bad for
+/// the same reasons spin-lock based on some sleep is bad (wasting cycles, 50% efficienct on an
+/// average etc.). Consider a module that listens to signals from various other modules. Different
+/// modules want to talk to this one. So one solution is make a common event set and all senders
+/// (registered in all the interested modules) send events from the same set. This is bad for
+/// maintenance. Wrong modules might use events not expected to originate from them since it is just
+/// one huge event-set. Thus there is a need of event-subsetting and distribute this module-wise so
+/// we prevent modules from using wrong events, completely by design and code-mechanics. Also we
+/// don't want to spawn threads listening to different receivers (which could force to share
+/// ownership and is anyway silly otherwise too). This is what `EventSender` helps to salvage. A
+/// simple mechanism that does what a `skip-list` in linked list does. It brings forth a concept of
+/// an Umbrella event-category and an event subset. The creator of `EventSender` hard-codes the
+/// category for different observers. Each category only links to a particular event-subset and
+/// type information of this is put into `EventSender` to during it's construction. Thus when
+/// distributed, the modules cannot cheat (do the wrong thing) by trying to fire an event they are
+/// not permitted to. Also a single thread listens to many receivers. All problems solved.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # use maidsafe_utilities::event_sender::EventCategory;
+/// # fn main() {
+///     #[derive(Debug, Clone)]
+///     enum EventCategory {
+///         Network,
+///         UserInterface,
+///     }
+///
+///     #[derive(Debug)]
+///     enum NetworkEvent {
+///         Connected,
+///         Disconnected,
+///     }
+///
+///     #[derive(Debug)]
+///     enum UiEvent {
+///         CreateDirectory,
+///         Terminate,
+///     }
+///
+///     let (ui_event_tx, ui_event_rx) = std::sync::mpsc::channel();
+///     let (catergory_tx, catergory_rx) = std::sync::mpsc::channel();
+///     let (network_event_tx, network_event_rx) = std::sync::mpsc::channel();
+///
+///     let ui_event_sender = maidsafe_utilities::event_sender
+///                                             ::EventSender::<EventCategory, UiEvent>
+///                                             ::new(ui_event_tx,
+///                                                   EventCategory::UserInterface,
+///                                                   catergory_tx.clone());
+///
+///     let nw_event_sender = maidsafe_utilities::event_sender
+///                                             ::EventSender::<EventCategory, NetworkEvent>
+///                                             ::new(network_event_tx,
+///                                                   EventCategory::Network,
+///                                                   catergory_tx);
+///
+///     let joiner = thread!("EventListenerThread", move || {
+///         for it in catergory_rx.iter() {
+///             match it {
+///                 EventCategory::Network => {
+///                     if let Ok(network_event) = network_event_rx.try_recv() {
+///                         match network_event {
+///                             NetworkEvent::Connected    => { /* Do Something */ },
+///                             NetworkEvent::Disconnected => { /* Do Something */ },
+///                         }
+///                     }
+///                 },
+///                 EventCategory::UserInterface => {
+///                     if let Ok(ui_event) = ui_event_rx.try_recv() {
+///                         match ui_event {
+///                             UiEvent::Terminate       => break,
+///                             UiEvent::CreateDirectory => { /* Do Something */ },
+///                         }
+///                     }
+///                 }
+///             }
+///         }
+///     });
+///
+///     let _raii_joiner = maidsafe_utilities::thread::RaiiThreadJoiner::new(joiner);
+///
+///     assert!(nw_event_sender.send(NetworkEvent::Connected).is_ok());
+///     assert!(ui_event_sender.send(UiEvent::CreateDirectory).is_ok());
+///     assert!(ui_event_sender.send(UiEvent::Terminate).is_ok());
+/// # }
+pub struct EventSender<Category,
+                       EventSubset,
+                       EventTx    = ::std::sync::mpsc::Sender<EventSubset>,
+                       CategoryTx = ::std::sync::mpsc::Sender<Category>> {
+    inner: ::std::sync::Arc<Senders<Category, EventSubset, EventTx, CategoryTx>>,
+}
+
+struct Senders<Category, EventSubset, EventTx, CategoryTx> {
+    event_tx         : EventTx,
+    event_category   : Category,
+    event_category_tx: CategoryTx,
+    closed           : ::std::sync::atomic::AtomicBool,
+    next_sequence    : ::std::sync::atomic::AtomicU64,
+    on_last_drop     : ::std::sync::Mutex<Option<Box<dyn FnOnce() + Send>>>,
+    _event_subset    : ::std::marker::PhantomData<fn(EventSubset)>,
+}
+
+impl<Category, EventSubset, EventTx, CategoryTx> Drop
+    for Senders<Category, EventSubset, EventTx, CategoryTx> {
+    fn drop(&mut self) {
+        let on_last_drop = match self.on_last_drop.get_mut() {
+            Ok(on_last_drop) => on_last_drop.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+        if let Some(on_last_drop) = on_last_drop {
+            on_last_drop();
+        }
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Create a new instance of `EventSender`. Category type, category value and EventSubset type
+    /// are baked into `EventSender` to disallow user code from misusing it.
+    ///
+    /// The two channels default to `std::sync::mpsc::Sender`, but any `ChannelSender` can be used.
+    pub fn new(event_tx         : EventTx,
+               event_category   : Category,
+               event_category_tx: CategoryTx) -> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+        EventSender {
+            inner: ::std::sync::Arc::new(Senders {
+                event_tx         : event_tx,
+                event_category   : event_category,
+                event_category_tx: event_category_tx,
+                closed           : ::std::sync::atomic::AtomicBool::new(false),
+                next_sequence    : ::std::sync::atomic::AtomicU64::new(0),
+                on_last_drop     : ::std::sync::Mutex::new(None),
+                _event_subset    : ::std::marker::PhantomData,
+            }),
+        }
+    }
+
+    /// Create a new instance of `EventSender` for the category `EventSubset` is mapped to by its
+    /// `SubsetCategory` impl.
+    pub fn for_subset(event_tx: EventTx, event_category_tx: CategoryTx)
+                      -> EventSender<Category, EventSubset, EventTx, CategoryTx>
+        where EventSubset: SubsetCategory<Category>
+    {
+        EventSender::new(event_tx, EventSubset::category(), event_category_tx)
+    }
+
+    /// Fire an allowed event/signal to the observer.
+    pub fn send(&self, event: EventSubset) -> Result<(), EventSenderError<Category, EventSubset>> {
+        if self.is_closed() {
+            return Err(EventSenderError::Closed(event))
+        }
+        match self.inner.event_tx.send_or_reject(event) {
+            Ok(()) => (),
+            Err(::std::sync::mpsc::TrySendError::Full(event)) => {
+                return Err(EventSenderError::QueueFull(event))
+            }
+            Err(::std::sync::mpsc::TrySendError::Disconnected(event)) => {
+                return Err(EventSenderError::EventSendError(::std::sync::mpsc::SendError(event)))
+            }
+        }
+        self.notify()
+    }
+
+    /// Fire an allowed event/signal to the observer without blocking. If the event subset channel
+    /// is bounded and full the event is handed back in `EventSenderError::QueueFull`.
+    pub fn try_send(&self, event: EventSubset) -> Result<(), EventSenderError<Category, EventSubset>> {
+        if self.is_closed() {
+            return Err(EventSenderError::Closed(event))
+        }
+        match self.inner.event_tx.try_send(event) {
+            Ok(()) => (),
+            Err(::std::sync::mpsc::TrySendError::Full(event)) => {
+                return Err(EventSenderError::QueueFull(event))
+            }
+            Err(::std::sync::mpsc::TrySendError::Disconnected(event)) => {
+                return Err(EventSenderError::EventSendError(::std::sync::mpsc::SendError(event)))
+            }
+        }
+        self.notify()
+    }
+
+    fn notify(&self) -> Result<(), EventSenderError<Category, EventSubset>> {
+        if let Err(error) = self.inner.event_category_tx.send(self.inner.event_category.clone()) {
+            return Err(EventSenderError::CategorySendError(error))
+        }
+
+        Ok(())
+    }
+
+    /// Close this sender, along with all its clones and weak handles: from now on `send` fails
+    /// immediately with `EventSenderError::Closed`. The channels themselves are left open, so a
+    /// listener shared with other categories is unaffected.
+    pub fn close(&self) {
+        self.inner.closed.store(true, ::std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Whether `close()` has been called on this sender or any of its clones.
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(::std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// The number of events sent via the event subset channel but not yet received, if the
+    /// transport keeps track of it (see `instrumented_channel`). This can be used to shed load:
+    ///
+    /// ```
+    /// # fn main() {
+    /// use maidsafe_utilities::event_sender::{EventSender, instrumented_channel};
+    ///
+    /// const MAX_BACKLOG: usize = 100;
+    ///
+    /// let (event_tx, _event_rx) = instrumented_channel();
+    /// let (category_tx, _category_rx) = std::sync::mpsc::channel();
+    /// let sender = EventSender::new(event_tx, "Network", category_tx);
+    ///
+    /// for packet in 0..1000 {
+    ///     if sender.pending().map_or(false, |pending| pending >= MAX_BACKLOG) {
+    ///         continue; // Receiver is falling behind: drop the packet.
+    ///     }
+    ///     assert!(sender.send(packet).is_ok());
+    /// }
+    /// assert_eq!(sender.pending(), Some(MAX_BACKLOG));
+    /// # }
+    /// ```
+    pub fn pending(&self) -> Option<usize> {
+        self.inner.event_tx.pending()
+    }
+
+    /// The number of producers sharing this sender's channels: this sender, its clones and any
+    /// delayed sends still pending.
+    pub fn producers(&self) -> usize {
+        ::std::sync::Arc::strong_count(&self.inner)
+    }
+
+    /// Send `category` on the category channel once the last producer (see `producers()`) has
+    /// gone, so that a listener shared with other categories can tell that no more events of this
+    /// one will arrive. Replaces any category previously registered via this method.
+    ///
+    /// ```
+    /// # fn main() {
+    /// use maidsafe_utilities::event_sender::EventSender;
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// enum Category {
+    ///     Network,
+    ///     NetworkGone,
+    /// }
+    ///
+    /// let (event_tx, _event_rx) = std::sync::mpsc::channel::<u32>();
+    /// let (category_tx, category_rx) = std::sync::mpsc::channel();
+    /// let sender = EventSender::new(event_tx, Category::Network, category_tx.clone());
+    /// sender.notify_when_dropped(Category::NetworkGone);
+    ///
+    /// let clone = sender.clone();
+    /// assert_eq!(sender.producers(), 2);
+    /// drop(sender);
+    /// assert!(clone.send(1).is_ok());
+    /// drop(clone);
+    ///
+    /// assert_eq!(category_rx.try_iter().collect::<Vec<_>>(),
+    ///            vec![Category::Network, Category::NetworkGone]);
+    /// # }
+    /// ```
+    pub fn notify_when_dropped(&self, category: Category)
+        where Category  : Send + 'static,
+              CategoryTx: Clone + Send + 'static
+    {
+        let category_tx = self.inner.event_category_tx.clone();
+        let on_last_drop = move || {
+            if let Err(error) = category_tx.send(category) {
+                debug!("Last-drop notification could not be sent: {:?}", error.0);
+            }
+        };
+        *unwrap_result!(self.inner.on_last_drop.lock()) = Some(Box::new(on_last_drop));
+    }
+
+    /// Create a `WeakEventSender` which can fire the same events as this sender but which does
+    /// not by itself keep the underlying channels alive.
+    pub fn downgrade(&self) -> WeakEventSender<Category, EventSubset, EventTx, CategoryTx> {
+        WeakEventSender {
+            inner: ::std::sync::Arc::downgrade(&self.inner),
+        }
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone + Send + Sync + 'static,
+     EventSubset: ::std::fmt::Debug + Send + 'static,
+     EventTx    : ChannelSender<EventSubset> + Send + Sync + 'static,
+     CategoryTx : ChannelSender<Category> + Send + Sync + 'static>
+    EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Fire an allowed event/signal to the observer once `delay` has elapsed.
+    ///
+    /// All delayed sends share a single timer thread rather than each spawning their own. The
+    /// returned handle can be used to cancel the send; dropping it leaves the send scheduled. A
+    /// pending delayed send keeps the channels alive, as it holds a clone of this sender. With a
+    /// `delay` too long to be represented as an `Instant`, the event is never sent.
+    pub fn send_after(&self, event: EventSubset, delay: ::std::time::Duration) -> TimerHandle {
+        let sender = self.clone();
+        ::timer::schedule(delay, move || {
+            if let Err(error) = sender.send(event) {
+                debug!("Delayed event could not be sent: {}", error);
+            }
+        })
+    }
+
+    /// Create a `ProducerCount` through which the receiving side can watch how many producers
+    /// (see `producers()`) remain for this sender's channels.
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate maidsafe_utilities;
+    /// # fn main() {
+    /// use maidsafe_utilities::event_sender::EventSender;
+    ///
+    /// let (event_tx, _event_rx) = std::sync::mpsc::channel::<u32>();
+    /// let (category_tx, category_rx) = std::sync::mpsc::channel();
+    /// let sender = EventSender::new(event_tx, "Network", category_tx);
+    /// let producers = sender.producer_count();
+    ///
+    /// let _raii_joiner = unwrap_result!(maidsafe_utilities::thread::named("Producer", move || {
+    ///     for event in 0..3 {
+    ///         assert!(sender.send(event).is_ok());
+    ///     }
+    /// }));
+    ///
+    /// let timeout = std::time::Duration::from_millis(10);
+    /// while producers.get() > 0 {
+    ///     let _ = category_rx.recv_timeout(timeout);
+    /// }
+    /// # }
+    /// ```
+    pub fn producer_count(&self) -> ProducerCount {
+        let senders = ::std::sync::Arc::downgrade(&self.inner);
+        ProducerCount { senders }
+    }
+}
+
+impl<Category, EventSubset, EventTx, CategoryTx> Clone
+    for EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    fn clone(&self) -> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+        EventSender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// The receiving side's view of how many producers remain for an `EventSender`'s channels,
+/// obtained via `EventSender::producer_count()`. Unlike a `WeakEventSender`, it only needs to know
+/// which sender it came from, not the sender's types, so a listener can keep one per category.
+/// Once the count reaches zero no more events of that category can arrive, so the listener loop
+/// can exit rather than waiting forever.
+#[derive(Clone)]
+pub struct ProducerCount {
+    senders: ::std::sync::Weak<dyn Counted + Send + Sync>,
+}
+
+impl ProducerCount {
+    /// The number of producers still alive (see `EventSender::producers`).
+    pub fn get(&self) -> usize {
+        self.senders.strong_count()
+    }
+}
+
+impl ::std::fmt::Debug for ProducerCount {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.debug_struct("ProducerCount").field("producers", &self.get()).finish()
+    }
+}
+
+// Lets a `ProducerCount` refer to the `Senders` of any `EventSender`, whatever its types.
+trait Counted {}
+
+impl<Category, EventSubset, EventTx, CategoryTx> Counted
+    for Senders<Category, EventSubset, EventTx, CategoryTx> {}
+
+/// A non-owning version of `EventSender`, obtained via `EventSender::downgrade()`.
+///
+/// Once every `EventSender` it was created from has been dropped the channels are closed (so a
+/// listener iterating over the category receiver will exit) and `send` fails, handing the event
+/// back inside `EventSenderError::EventSendError`. This allows caches and background tasks to hold
+/// on to a sender without preventing the listener from being torn down.
+pub struct WeakEventSender<Category,
+                           EventSubset,
+                           EventTx    = ::std::sync::mpsc::Sender<EventSubset>,
+                           CategoryTx = ::std::sync::mpsc::Sender<Category>> {
+    inner: ::std::sync::Weak<Senders<Category, EventSubset, EventTx, CategoryTx>>,
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>> WeakEventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Attempt to get back a strong `EventSender`. Returns `None` if all the strong senders have
+    /// already been dropped.
+    pub fn upgrade(&self) -> Option<EventSender<Category, EventSubset, EventTx, CategoryTx>> {
+        self.inner.upgrade().map(|inner| EventSender { inner })
+    }
+
+    /// The number of producers still alive (see `EventSender::producers`). Once this reaches zero
+    /// no more events can be sent, so a listener holding only weak senders can exit.
+    pub fn producers(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    /// Fire an allowed event/signal to the observer if any strong sender is still alive.
+    pub fn send(&self, event: EventSubset) -> Result<(), EventSenderError<Category, EventSubset>> {
+        match self.upgrade() {
+            Some(sender) => sender.send(event),
+            None => Err(EventSenderError::EventSendError(::std::sync::mpsc::SendError(event))),
+        }
+    }
+}
+
+impl<Category, EventSubset, EventTx, CategoryTx> Clone
+    for WeakEventSender<Category, EventSubset, EventTx, CategoryTx> {
+    fn clone(&self) -> WeakEventSender<Category, EventSubset, EventTx, CategoryTx> {
+        WeakEventSender {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn marshall_multiple_events() {
+        const TOKEN: u32 = 9876;
+        const DIR_NAME: &'static str = "NewDirectory";
+
+        #[derive(Clone, Debug)]
+        enum EventCategory {
+            Network,
+            UserInterface,
+        }
+
+        #[derive(Debug)]
+        enum NetworkEvent {
+            Connected(u32),
+            Disconnected,
+        }
+
+        #[derive(Debug)]
+        enum UiEvent {
+            CreateDirectory(String),
+            Terminate,
+        }
+
+        let (ui_event_tx, ui_event_rx) = ::std::sync::mpsc::channel();
+        let (catergory_tx, catergory_rx) = ::std::sync::mpsc::channel();
+        let (network_event_tx, network_event_rx) = ::std::sync::mpsc::channel();
+
+        type UiEventSender = EventSender<EventCategory, UiEvent>;
+        type NetworkEventSender = EventSender<EventCategory, NetworkEvent>;
+
+        let ui_event_sender = UiEventSender::new(ui_event_tx,
+                                                 EventCategory::UserInterface,
+                                                 catergory_tx.clone());
+
+        let nw_event_sender = NetworkEventSender::new(network_event_tx,
+                                                      EventCategory::Network,
+                                                      catergory_tx);
+
+        let joiner = thread!("EventListenerThread", move || {
+            for it in catergory_rx.iter() {
+                match it {
+                    EventCategory::Network => {
+                        if let Ok(network_event) = network_event_rx.try_recv() {
+                            match network_event {
+                                NetworkEvent::Connected(token) => assert_eq!(token, TOKEN),
+                                _ => panic!("Shouldn't have received this event: {:?}", network_event),
+                            }
+                        }
+                    },
+                    EventCategory::UserInterface => {
+                        if let Ok(ui_event) = ui_event_rx.try_recv() {
+                            match ui_event {
+                                UiEvent::CreateDirectory(name) => assert_eq!(name, DIR_NAME),
+                                UiEvent::Terminate => break,
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let _raii_joiner = ::thread::RaiiThreadJoiner::new(joiner);
+
+        assert!(nw_event_sender.send(NetworkEvent::Connected(TOKEN)).is_ok());
+        assert!(ui_event_sender.send(UiEvent::CreateDirectory(DIR_NAME.to_string())).is_ok());
+        assert!(ui_event_sender.send(UiEvent::Terminate).is_ok());
+
+        ::std::thread::sleep(::std::time::Duration::from_millis(500));
+
+        assert!(ui_event_sender.send(UiEvent::Terminate).is_err());
+        assert!(nw_event_sender.send(NetworkEvent::Disconnected).is_err());
+
+        match unwrap_option!(ui_event_sender.send(UiEvent::CreateDirectory(DIR_NAME.to_string()))
+                                            .err(),
+                             "Return should have evaluated to an error.") {
+            EventSenderError::EventSendError(send_err) => {
+                match send_err.0 {
+                    UiEvent::CreateDirectory(dir_name) => assert_eq!(dir_name, DIR_NAME),
+                    _ => panic!("Expected a different event !"),
+                }
+            },
+            _ => panic!("Expected a different error !"),
+        }
+    }
+
+    #[test]
+    fn weak_event_sender() {
+        #[derive(Clone, Debug, PartialEq)]
+        enum EventCategory {
+            Network,
+        }
+
+        #[derive(Debug, PartialEq)]
+        enum NetworkEvent {
+            Connected,
+            Disconnected,
+        }
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+
+        let sender = EventSender::<EventCategory, NetworkEvent>::new(event_tx,
+                                                                      EventCategory::Network,
+                                                                      category_tx);
+        let weak_sender = sender.downgrade();
+
+        assert!(weak_sender.send(NetworkEvent::Connected).is_ok());
+        assert_eq!(unwrap_result!(category_rx.recv()), EventCategory::Network);
+        assert_eq!(unwrap_result!(event_rx.recv()), NetworkEvent::Connected);
+
+        drop(sender);
+
+        // The weak sender must not keep the channel alive
+        assert!(category_rx.recv().is_err());
+        assert!(weak_sender.upgrade().is_none());
+
+        match unwrap_option!(weak_sender.send(NetworkEvent::Disconnected).err(),
+                             "Sending via a dangling weak sender should fail.") {
+            EventSenderError::EventSendError(send_err) => {
+                assert_eq!(send_err.0, NetworkEvent::Disconnected)
+            }
+            _ => panic!("Expected a different error !"),
+        }
+    }
+
+    #[test]
+    fn producer_count() {
+        let (event_tx, _event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+        sender.notify_when_dropped("NetworkGone");
+        let weak_sender = sender.downgrade();
+        let producers = sender.producer_count();
+        assert_eq!(weak_sender.producers(), 1);
+
+        let handle = sender.send_after(0u8, ::std::time::Duration::from_secs(60));
+        assert_eq!(sender.producers(), 2);
+        drop(sender);
+        assert_eq!(weak_sender.producers(), 1);
+        assert_eq!(producers.get(), 1);
+        assert!(category_rx.try_recv().is_err());
+
+        // Cancelling the delayed send drops the last producer.
+        assert!(handle.cancel());
+        assert_eq!(unwrap_result!(category_rx.recv_timeout(::std::time::Duration::from_secs(5))),
+                   "NetworkGone");
+        assert_eq!(weak_sender.producers(), 0);
+        assert_eq!(producers.get(), 0);
+        assert!(category_rx.recv().is_err());
+    }
+
+    #[test]
+    fn event_sender_error_is_std_error() {
+        fn send_boxed(sender: &EventSender<u8, String>)
+                      -> Result<(), Box<dyn std::error::Error>> {
+            sender.send("Event".to_string())?;
+            Ok(())
+        }
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, 0, category_tx);
+        drop(event_rx);
+
+        let error = unwrap_option!(send_boxed(&sender).err(), "Sending should have failed.");
+        assert_eq!(error.to_string(), "Error sending the event subset: sending on a closed channel");
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn delayed_send() {
+        const DELAY_MS: u64 = 100;
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Timeout", category_tx);
+
+        let start = ::std::time::Instant::now();
+        let _ = sender.send_after(1, ::std::time::Duration::from_millis(DELAY_MS));
+        let cancelled = sender.send_after(2, ::std::time::Duration::from_millis(DELAY_MS / 2));
+        assert!(cancelled.cancel());
+        drop(sender);
+
+        assert_eq!(unwrap_result!(category_rx.recv()), "Timeout");
+        assert!(start.elapsed() >= ::std::time::Duration::from_millis(DELAY_MS));
+        assert_eq!(unwrap_result!(event_rx.recv()), 1);
+
+        // Both the cancelled and the fired send have released their sender by now.
+        assert!(category_rx.recv().is_err());
+    }
+
+    #[test]
+    fn closed_sender_fails_fast() {
+        let (network_event_tx, network_event_rx) = ::std::sync::mpsc::channel();
+        let (ui_event_tx, _ui_event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+
+        let network_sender = EventSender::new(network_event_tx, "Network", category_tx.clone());
+        let ui_sender = EventSender::new(ui_event_tx, "UserInterface", category_tx);
+        let network_sender_clone = network_sender.clone();
+
+        network_sender.close();
+        assert!(network_sender_clone.is_closed());
+        match network_sender_clone.send(1) {
+            Err(EventSenderError::Closed(event)) => assert_eq!(event, 1),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert!(network_event_rx.try_recv().is_err());
+
+        // Other categories sharing the listener keep working
+        assert!(ui_sender.send(2).is_ok());
+        assert_eq!(unwrap_result!(category_rx.try_recv()), "UserInterface");
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+
+/// An `EventSender` can itself serve as the category transport of another `EventSender`, which
+/// lets categories nest. The inner sender's category is then the outer sender's event: sending an
+/// inner event pushes the event onto its own channel, the inner category onto the outer sender's
+/// event channel and finally the outer category onto the top-level category channel.
+///
+/// A top-level listener can thus hand all events of, say, its `Network` category to a second-level
+/// demultiplexer owned by the networking component, without one enum having to list every
+/// category of every layer.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::EventSender;
+/// use std::sync::mpsc;
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum Category {
+///     Network,
+///     UserInterface,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum NetworkCategory {
+///     Connection,
+///     Routing,
+/// }
+///
+/// let (category_tx, category_rx) = mpsc::channel();
+/// let (network_category_tx, network_category_rx) = mpsc::channel();
+/// let network_sender = EventSender::new(network_category_tx, Category::Network, category_tx);
+///
+/// let (connection_tx, connection_rx) = mpsc::channel();
+/// let (routing_tx, routing_rx) = mpsc::channel();
+/// let connection_sender = network_sender.nest(connection_tx, NetworkCategory::Connection);
+/// let routing_sender = network_sender.nest(routing_tx, NetworkCategory::Routing);
+///
+/// assert!(connection_sender.send("peer connected").is_ok());
+/// assert!(routing_sender.send(42u64).is_ok());
+///
+/// // The top-level listener only knows about its own categories...
+/// for category in category_rx.try_iter() {
+///     assert_eq!(category, Category::Network);
+///     // ...and forwards to the network component, which demultiplexes its own.
+///     match unwrap_result!(network_category_rx.try_recv()) {
+///         NetworkCategory::Connection => {
+///             assert_eq!(unwrap_result!(connection_rx.try_recv()), "peer connected")
+///         }
+///         NetworkCategory::Routing => assert_eq!(unwrap_result!(routing_rx.try_recv()), 42),
+///     }
+/// }
+/// # }
+/// ```
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug + Clone,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>> ChannelSender<EventSubset>
+    for EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    fn send(&self, value: EventSubset) -> Result<(), ::std::sync::mpsc::SendError<EventSubset>> {
+        // The sub-category has already been queued if only the notification fails, but the
+        // caller still needs to hear about the failure, so keep a copy to hand back.
+        let copy = value.clone();
+        EventSender::send(self, value).map_err(|error| {
+            match error {
+                EventSenderError::EventSendError(error) => error,
+                EventSenderError::RateLimited(value) |
+                EventSenderError::Closed(value) |
+                EventSenderError::QueueFull(value) => ::std::sync::mpsc::SendError(value),
+                EventSenderError::CategorySendError(_) => ::std::sync::mpsc::SendError(copy),
+            }
+        })
+    }
+
+    fn try_send(&self, value: EventSubset)
+                -> Result<(), ::std::sync::mpsc::TrySendError<EventSubset>> {
+        let copy = value.clone();
+        EventSender::try_send(self, value).map_err(|error| to_try_send_error(error, copy))
+    }
+
+    fn send_or_reject(&self, value: EventSubset)
+                      -> Result<(), ::std::sync::mpsc::TrySendError<EventSubset>> {
+        let copy = value.clone();
+        EventSender::send(self, value).map_err(|error| to_try_send_error(error, copy))
+    }
+
+    fn pending(&self) -> Option<usize> {
+        EventSender::pending(self)
+    }
+}
+
+fn to_try_send_error<Category, EventSubset>(error: EventSenderError<Category, EventSubset>,
+                                            copy: EventSubset)
+                                            -> ::std::sync::mpsc::TrySendError<EventSubset> {
+    match error {
+        EventSenderError::QueueFull(value) => ::std::sync::mpsc::TrySendError::Full(value),
+        EventSenderError::EventSendError(::std::sync::mpsc::SendError(value)) |
+        EventSenderError::RateLimited(value) |
+        EventSenderError::Closed(value) => ::std::sync::mpsc::TrySendError::Disconnected(value),
+        EventSenderError::CategorySendError(_) => ::std::sync::mpsc::TrySendError::Disconnected(copy),
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug + Clone,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Create a second-level `EventSender` which fires `SubEvent`s under `sub_category`, using
+    /// this sender to announce them. See the `ChannelSender` impl above.
+    pub fn nest<SubEvent, SubEventTx>(&self, event_tx: SubEventTx, sub_category: EventSubset)
+        -> EventSender<EventSubset, SubEvent, SubEventTx,
+                       EventSender<Category, EventSubset, EventTx, CategoryTx>>
+        where SubEvent  : ::std::fmt::Debug,
+              SubEventTx: ChannelSender<SubEvent>
+    {
+        EventSender::new(event_tx, sub_category, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn closing_the_outer_sender_stops_the_inner_one() {
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let (sub_category_tx, sub_category_rx) = ::std::sync::mpsc::channel();
+        let outer = EventSender::new(sub_category_tx, "Network", category_tx);
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let inner = outer.nest(event_tx, "Routing");
+
+        assert!(inner.send(1).is_ok());
+        assert_eq!(category_rx.try_iter().collect::<Vec<_>>(), vec!["Network"]);
+        assert_eq!(sub_category_rx.try_iter().collect::<Vec<_>>(), vec!["Routing"]);
+
+        outer.close();
+        match inner.send(2) {
+            Err(EventSenderError::CategorySendError(::std::sync::mpsc::SendError("Routing"))) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        // The inner event itself was queued before the notification failed.
+        assert_eq!(event_rx.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(category_rx.try_iter().count(), 0);
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelReceiver, ChannelSender};
+
+/// Create a channel with two internal lanes: values for which `is_urgent` returns `true` are
+/// received before any normal ones already queued, while order is preserved within each lane.
+///
+/// This lets e.g. a `Disconnect` overtake a backlog of routine data events without needing a
+/// separate category for every urgent message type.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, priority_channel};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum NetworkEvent {
+///     Data(u32),
+///     Disconnect,
+/// }
+///
+/// let (event_tx, event_rx) = priority_channel(|event| *event == NetworkEvent::Disconnect);
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+///
+/// for packet in 0..100 {
+///     assert!(sender.send(NetworkEvent::Data(packet)).is_ok());
+/// }
+/// assert!(sender.send(NetworkEvent::Disconnect).is_ok());
+///
+/// let _ = category_rx.recv();
+/// assert_eq!(event_rx.try_recv(), Ok(NetworkEvent::Disconnect));
+/// assert_eq!(event_rx.try_recv(), Ok(NetworkEvent::Data(0)));
+/// # }
+/// ```
+pub fn priority_channel<T, F>(is_urgent: F) -> (PrioritySender<T>, PriorityReceiver<T>)
+    where F: Fn(&T) -> bool + Send + Sync + 'static
+{
+    let shared = ::std::sync::Arc::new(Shared {
+        state    : ::std::sync::Mutex::new(State {
+            urgent        : ::std::collections::VecDeque::new(),
+            normal        : ::std::collections::VecDeque::new(),
+            senders       : 1,
+            receiver_alive: true,
+        }),
+        condition: ::std::sync::Condvar::new(),
+        is_urgent: Box::new(is_urgent),
+    });
+    (PrioritySender { shared: shared.clone() }, PriorityReceiver { shared })
+}
+
+struct Shared<T> {
+    state    : ::std::sync::Mutex<State<T>>,
+    condition: ::std::sync::Condvar,
+    is_urgent: Box<dyn Fn(&T) -> bool + Send + Sync>,
+}
+
+struct State<T> {
+    urgent        : ::std::collections::VecDeque<T>,
+    normal        : ::std::collections::VecDeque<T>,
+    senders       : usize,
+    receiver_alive: bool,
+}
+
+impl<T> State<T> {
+    fn pop(&mut self) -> Option<T> {
+        self.urgent.pop_front().or_else(|| self.normal.pop_front())
+    }
+
+    fn len(&self) -> usize {
+        self.urgent.len() + self.normal.len()
+    }
+}
+
+/// The sending half of a `priority_channel`.
+pub struct PrioritySender<T> {
+    shared: ::std::sync::Arc<Shared<T>>,
+}
+
+impl<T> ChannelSender<T> for PrioritySender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        let urgent = (self.shared.is_urgent)(&value);
+        let mut state = unwrap_result!(self.shared.state.lock());
+        if !state.receiver_alive {
+            return Err(::std::sync::mpsc::SendError(value));
+        }
+        if urgent {
+            state.urgent.push_back(value);
+        } else {
+            state.normal.push_back(value);
+        }
+        self.shared.condition.notify_one();
+        Ok(())
+    }
+
+    fn pending(&self) -> Option<usize> {
+        Some(unwrap_result!(self.shared.state.lock()).len())
+    }
+}
+
+impl<T> Clone for PrioritySender<T> {
+    fn clone(&self) -> PrioritySender<T> {
+        unwrap_result!(self.shared.state.lock()).senders += 1;
+        PrioritySender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for PrioritySender<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.senders -= 1;
+        }
+        self.shared.condition.notify_all();
+    }
+}
+
+/// The receiving half of a `priority_channel`.
+pub struct PriorityReceiver<T> {
+    shared: ::std::sync::Arc<Shared<T>>,
+}
+
+impl<T> PriorityReceiver<T> {
+    /// Block until a value is received, urgent ones first. See
+    /// `std::sync::mpsc::Receiver::recv`.
+    pub fn recv(&self) -> Result<T, ::std::sync::mpsc::RecvError> {
+        let mut state = unwrap_result!(self.shared.state.lock());
+        loop {
+            if let Some(value) = state.pop() {
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(::std::sync::mpsc::RecvError);
+            }
+            state = unwrap_result!(self.shared.condition.wait(state));
+        }
+    }
+
+    /// Receive a value if one is waiting, urgent ones first. See
+    /// `std::sync::mpsc::Receiver::try_recv`.
+    pub fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        let mut state = unwrap_result!(self.shared.state.lock());
+        match state.pop() {
+            Some(value) => Ok(value),
+            None if state.senders == 0 => Err(::std::sync::mpsc::TryRecvError::Disconnected),
+            None => Err(::std::sync::mpsc::TryRecvError::Empty),
+        }
+    }
+
+    /// Block for at most `timeout` waiting for a value, urgent ones first. See
+    /// `std::sync::mpsc::Receiver::recv_timeout`. A `timeout` too long to be represented as an
+    /// `Instant` means blocking as `recv` does.
+    pub fn recv_timeout(&self, timeout: ::std::time::Duration)
+                        -> Result<T, ::std::sync::mpsc::RecvTimeoutError> {
+        let deadline = match ::std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => {
+                return self.recv().map_err(|_| ::std::sync::mpsc::RecvTimeoutError::Disconnected)
+            }
+        };
+        let mut state = unwrap_result!(self.shared.state.lock());
+        loop {
+            if let Some(value) = state.pop() {
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(::std::sync::mpsc::RecvTimeoutError::Disconnected);
+            }
+            let remaining = deadline.saturating_duration_since(::std::time::Instant::now());
+            if remaining == ::std::time::Duration::from_secs(0) {
+                return Err(::std::sync::mpsc::RecvTimeoutError::Timeout);
+            }
+            state = unwrap_result!(self.shared.condition.wait_timeout(state, remaining)).0;
+        }
+    }
+
+    /// The number of values waiting to be received, in both lanes.
+    pub fn len(&self) -> usize {
+        unwrap_result!(self.shared.state.lock()).len()
+    }
+
+    /// Whether there are no values waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> ChannelReceiver<T> for PriorityReceiver<T> {
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        PriorityReceiver::try_recv(self)
+    }
+}
+
+impl<T> Drop for PriorityReceiver<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.receiver_alive = false;
+            state.urgent.clear();
+            state.normal.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn urgent_lane_overtakes() {
+        let (tx, rx) = priority_channel(|value: &u32| *value >= 100);
+        for value in &[1, 2, 100, 3, 101] {
+            assert!(tx.send(*value).is_ok());
+        }
+        assert_eq!(tx.pending(), Some(5));
+        assert_eq!(rx.recv(), Ok(100));
+        assert_eq!(rx.recv(), Ok(101));
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.len(), 2);
+
+        let timeout = ::std::time::Duration::from_millis(20);
+        assert_eq!(rx.recv_timeout(timeout), Ok(2));
+        assert_eq!(rx.recv_timeout(timeout), Ok(3));
+        assert_eq!(rx.recv_timeout(timeout), Err(::std::sync::mpsc::RecvTimeoutError::Timeout));
+        assert_eq!(rx.try_recv(), Err(::std::sync::mpsc::TryRecvError::Empty));
+
+        let clone = tx.clone();
+        drop(tx);
+        let joiner = ::std::thread::spawn(move || {
+            ::std::thread::sleep(::std::time::Duration::from_millis(20));
+            assert!(clone.send(4).is_ok());
+        });
+        assert_eq!(rx.recv_timeout(::std::time::Duration::MAX), Ok(4));
+        unwrap_result!(joiner.join());
+        assert_eq!(rx.recv(), Err(::std::sync::mpsc::RecvError));
+        assert_eq!(rx.recv_timeout(::std::time::Duration::MAX),
+                   Err(::std::sync::mpsc::RecvTimeoutError::Disconnected));
+        assert!(rx.is_empty());
+    }
+
+    #[test]
+    fn send_fails_once_receiver_has_gone() {
+        let (tx, rx) = priority_channel(|_: &u8| false);
+        drop(rx);
+        assert_eq!(tx.send(1), Err(::std::sync::mpsc::SendError(1)));
+    }
+}
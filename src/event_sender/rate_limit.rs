@@ -0,0 +1,127 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+use token_bucket::TokenBucket;
+
+/// An `EventSender` wrapper enforcing a token-bucket rate limit on outgoing events, obtained via
+/// `EventSender::rate_limited()`.
+///
+/// Up to `burst` events can be sent back-to-back, after which events are admitted at
+/// `max_per_sec`. Clones share the same bucket, so the limit applies to all of them together.
+/// `send` blocks until the event is admitted while `try_send` rejects it with
+/// `EventSenderError::RateLimited`.
+pub struct RateLimitedSender<Category,
+                             EventSubset,
+                             EventTx    = ::std::sync::mpsc::Sender<EventSubset>,
+                             CategoryTx = ::std::sync::mpsc::Sender<Category>> {
+    sender: EventSender<Category, EventSubset, EventTx, CategoryTx>,
+    bucket: ::std::sync::Arc<::std::sync::Mutex<TokenBucket>>,
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>>
+    RateLimitedSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Fire an allowed event/signal to the observer, blocking until the rate limit admits it.
+    pub fn send(&self, event: EventSubset) -> Result<(), EventSenderError<Category, EventSubset>> {
+        loop {
+            let wait = match unwrap_result!(self.bucket.lock()).take() {
+                Ok(()) => return self.sender.send(event),
+                Err(wait) => wait,
+            };
+            ::std::thread::sleep(wait);
+        }
+    }
+
+    /// Fire an allowed event/signal to the observer, or drop it if sending would exceed the rate
+    /// limit.
+    pub fn try_send(&self, event: EventSubset) -> Result<(), EventSenderError<Category, EventSubset>> {
+        match unwrap_result!(self.bucket.lock()).take() {
+            Ok(()) => self.sender.send(event),
+            Err(_) => Err(EventSenderError::RateLimited(event)),
+        }
+    }
+}
+
+impl<Category, EventSubset, EventTx, CategoryTx> Clone
+    for RateLimitedSender<Category, EventSubset, EventTx, CategoryTx> {
+    fn clone(&self) -> RateLimitedSender<Category, EventSubset, EventTx, CategoryTx> {
+        RateLimitedSender {
+            sender: self.sender.clone(),
+            bucket: self.bucket.clone(),
+        }
+    }
+}
+
+impl<Category, EventSubset, EventTx, CategoryTx> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Wrap a clone of this sender in a token-bucket rate limit admitting on average `max_per_sec`
+    /// events per second and at most `burst` in one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_per_sec` is zero.
+    pub fn rate_limited(&self,
+                        max_per_sec: u32,
+                        burst: u32)
+                        -> RateLimitedSender<Category, EventSubset, EventTx, CategoryTx> {
+        RateLimitedSender {
+            sender: self.clone(),
+            bucket: ::std::sync::Arc::new(::std::sync::Mutex::new(TokenBucket::new(max_per_sec,
+                                                                                   burst))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use event_sender::{EventSender, EventSenderError};
+
+    #[test]
+    fn burst_then_limit() {
+        const MAX_PER_SEC: u32 = 10;
+        const BURST: u32 = 3;
+
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx)
+                         .rate_limited(MAX_PER_SEC, BURST);
+
+        for event in 0..BURST {
+            assert!(sender.try_send(event).is_ok());
+        }
+        match sender.try_send(BURST) {
+            Err(EventSenderError::RateLimited(event)) => assert_eq!(event, BURST),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        let start = ::std::time::Instant::now();
+        assert!(sender.send(BURST).is_ok());
+        assert!(start.elapsed() >= ::std::time::Duration::from_millis(80));
+
+        assert_eq!(event_rx.try_iter().collect::<Vec<_>>(), (0..BURST + 1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn zero_rate_is_rejected() {
+        let (event_tx, _event_rx) = ::std::sync::mpsc::channel::<u32>();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let _ = EventSender::new(event_tx, "Network", category_tx).rate_limited(0, 1);
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Errors that can be returned by `EventReceiver`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventReceiverError {
+    /// No category notification arrived before the deadline
+    Timeout,
+    /// All senders have been dropped and every notification has been received
+    Disconnected,
+}
+
+impl ::std::fmt::Display for EventReceiverError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            EventReceiverError::Timeout => write!(formatter, "Timed out waiting for an event"),
+            EventReceiverError::Disconnected => write!(formatter, "All event senders have gone"),
+        }
+    }
+}
+
+impl ::std::error::Error for EventReceiverError {}
+
+/// The observer side of the category channel shared by a set of `EventSender`s.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventReceiver, EventReceiverError, EventSender};
+/// use std::time::Duration;
+///
+/// let (event_tx, event_rx) = std::sync::mpsc::channel::<u32>();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+/// let receiver = EventReceiver::new(category_rx);
+///
+/// let _joiner = std::thread::spawn(move || {
+///     std::thread::sleep(Duration::from_millis(50));
+///     assert!(sender.send(1).is_ok());
+/// });
+///
+/// let mut housekeeping_runs = 0;
+/// loop {
+///     match receiver.recv_timeout(Duration::from_millis(10)) {
+///         Ok("Network") => assert_eq!(event_rx.try_recv(), Ok(1)),
+///         Ok(_) => unreachable!(),
+///         Err(EventReceiverError::Timeout) => housekeeping_runs += 1,
+///         Err(EventReceiverError::Disconnected) => break,
+///     }
+/// }
+/// assert!(housekeeping_runs > 0);
+/// # }
+/// ```
+///
+/// A noisy category can be paused with `pause()` while a critical one is dealt with: its
+/// notifications are then buffered (and its events left in their channel) until `resume()`, so
+/// nothing is dropped and no producer is blocked. Once every sender has gone, there is nothing
+/// left to make way for, so notifications still buffered for paused categories are delivered
+/// before `Disconnected` is reported.
+pub struct EventReceiver<Category> {
+    category_rx: ::std::sync::mpsc::Receiver<Category>,
+    state      : ::std::cell::RefCell<PauseState<Category>>,
+}
+
+struct PauseState<Category> {
+    paused  : Vec<Category>,
+    // Notifications of paused categories, in arrival order.
+    buffered: ::std::collections::VecDeque<Category>,
+    // Notifications of resumed categories, to be delivered before any new ones.
+    resumed : ::std::collections::VecDeque<Category>,
+}
+
+impl<Category: PartialEq> EventReceiver<Category> {
+    /// Create a new instance listening on the given category receiver.
+    pub fn new(category_rx: ::std::sync::mpsc::Receiver<Category>) -> EventReceiver<Category> {
+        EventReceiver {
+            category_rx,
+            state: ::std::cell::RefCell::new(PauseState {
+                paused: Vec::new(),
+                buffered: ::std::collections::VecDeque::new(),
+                resumed: ::std::collections::VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Block until the next category notification arrives.
+    pub fn recv(&self) -> Result<Category, EventReceiverError> {
+        if let Some(category) = self.state.borrow_mut().resumed.pop_front() {
+            return Ok(category);
+        }
+        loop {
+            let category = match self.category_rx.recv() {
+                Ok(category) => category,
+                Err(_) => return self.drain_buffered(),
+            };
+            if let Some(category) = self.filter(category) {
+                return Ok(category);
+            }
+        }
+    }
+
+    /// Block until the next category notification arrives or `timeout` has elapsed, whichever
+    /// comes first. A `timeout` too long to be represented as an `Instant` means blocking as
+    /// `recv` does.
+    pub fn recv_timeout(&self, timeout: ::std::time::Duration)
+                        -> Result<Category, EventReceiverError> {
+        if let Some(category) = self.state.borrow_mut().resumed.pop_front() {
+            return Ok(category);
+        }
+        let deadline = match ::std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return self.recv(),
+        };
+        loop {
+            let remaining = deadline.saturating_duration_since(::std::time::Instant::now());
+            let category = match self.category_rx.recv_timeout(remaining) {
+                Ok(category) => category,
+                Err(::std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    return Err(EventReceiverError::Timeout)
+                }
+                Err(::std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                    return self.drain_buffered()
+                }
+            };
+            if let Some(category) = self.filter(category) {
+                return Ok(category);
+            }
+        }
+    }
+
+    /// Stop delivering notifications for `category` until `resume()` is called for it. Pausing
+    /// an already paused category has no effect.
+    pub fn pause(&self, category: Category) {
+        let mut state = self.state.borrow_mut();
+        if !state.paused.contains(&category) {
+            state.paused.push(category);
+        }
+    }
+
+    /// Resume delivering notifications for `category`. Those which arrived while it was paused
+    /// are delivered first, in the order they arrived.
+    pub fn resume(&self, category: &Category) {
+        let mut state = self.state.borrow_mut();
+        state.paused.retain(|paused| paused != category);
+        let (resumed, still_buffered): (Vec<_>, Vec<_>) =
+            state.buffered.drain(..).partition(|buffered| buffered == category);
+        state.buffered.extend(still_buffered);
+        state.resumed.extend(resumed);
+    }
+
+    /// Whether `category` is currently paused.
+    pub fn is_paused(&self, category: &Category) -> bool {
+        self.state.borrow().paused.contains(category)
+    }
+
+    /// The number of notifications for `category` held back since it was paused.
+    pub fn buffered(&self, category: &Category) -> usize {
+        self.state.borrow().buffered.iter().filter(|buffered| *buffered == category).count()
+    }
+
+    // Called once the senders have gone: deliver the oldest buffered notification, if any.
+    fn drain_buffered(&self) -> Result<Category, EventReceiverError> {
+        self.state.borrow_mut().buffered.pop_front().ok_or(EventReceiverError::Disconnected)
+    }
+
+    // Buffer `category` if it is paused, otherwise hand it back for delivery.
+    fn filter(&self, category: Category) -> Option<Category> {
+        let mut state = self.state.borrow_mut();
+        if state.paused.contains(&category) {
+            state.buffered.push_back(category);
+            None
+        } else {
+            Some(category)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::EventSender;
+
+    #[test]
+    fn recv_timeout() {
+        const TIMEOUT_MS: u64 = 50;
+        let timeout = ::std::time::Duration::from_millis(TIMEOUT_MS);
+
+        let (event_tx, _event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+        let receiver = EventReceiver::new(category_rx);
+
+        let start = ::std::time::Instant::now();
+        assert_eq!(receiver.recv_timeout(timeout), Err(EventReceiverError::Timeout));
+        assert!(start.elapsed() >= timeout);
+
+        assert!(sender.send(()).is_ok());
+        assert_eq!(receiver.recv_timeout(timeout), Ok("Network"));
+        assert!(sender.send(()).is_ok());
+        assert_eq!(receiver.recv_timeout(::std::time::Duration::MAX), Ok("Network"));
+
+        drop(sender);
+        assert_eq!(receiver.recv_timeout(timeout), Err(EventReceiverError::Disconnected));
+        assert_eq!(receiver.recv(), Err(EventReceiverError::Disconnected));
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let timeout = ::std::time::Duration::from_millis(50);
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let receiver = EventReceiver::new(category_rx);
+
+        receiver.pause("Noisy");
+        assert!(receiver.is_paused(&"Noisy"));
+        for category in &["Noisy", "Critical", "Noisy", "Other", "Critical"] {
+            unwrap_result!(category_tx.send(*category));
+        }
+
+        assert_eq!(receiver.recv(), Ok("Critical"));
+        assert_eq!(receiver.recv(), Ok("Other"));
+        assert_eq!(receiver.recv_timeout(timeout), Ok("Critical"));
+        assert_eq!(receiver.recv_timeout(timeout), Err(EventReceiverError::Timeout));
+        assert_eq!(receiver.buffered(&"Noisy"), 2);
+
+        unwrap_result!(category_tx.send("Other"));
+        receiver.resume(&"Noisy");
+        assert!(!receiver.is_paused(&"Noisy"));
+        assert_eq!(receiver.recv(), Ok("Noisy"));
+        assert_eq!(receiver.recv_timeout(timeout), Ok("Noisy"));
+        assert_eq!(receiver.recv(), Ok("Other"));
+        assert_eq!(receiver.buffered(&"Noisy"), 0);
+    }
+
+    #[test]
+    fn buffered_notifications_are_drained_on_disconnection() {
+        let timeout = ::std::time::Duration::from_millis(50);
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let receiver = EventReceiver::new(category_rx);
+
+        receiver.pause("Noisy");
+        for category in &["Noisy", "Critical", "Noisy"] {
+            unwrap_result!(category_tx.send(*category));
+        }
+        drop(category_tx);
+
+        assert_eq!(receiver.recv(), Ok("Critical"));
+        assert_eq!(receiver.recv_timeout(timeout), Ok("Noisy"));
+        assert_eq!(receiver.recv(), Ok("Noisy"));
+        assert_eq!(receiver.recv_timeout(timeout), Err(EventReceiverError::Disconnected));
+        assert_eq!(receiver.recv(), Err(EventReceiverError::Disconnected));
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+
+/// How `EventSender::send_with_retry` backs off while a bounded queue is full.
+///
+/// The first retry happens after `initial_delay`, and each subsequent delay is doubled up to
+/// `max_delay`. At most `max_attempts` sends are made in total.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Delay before the first retry.
+    pub initial_delay: ::std::time::Duration,
+    /// Cap on the delay between retries.
+    pub max_delay: ::std::time::Duration,
+    /// Maximum number of send attempts, including the first one.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            initial_delay: ::std::time::Duration::from_millis(1),
+            max_delay    : ::std::time::Duration::from_millis(100),
+            max_attempts : 10,
+        }
+    }
+}
+
+/// Error returned by `EventSender::send_with_retry`: the final send error along with how many
+/// attempts were made.
+#[derive(Debug)]
+pub struct RetryError<Category, EventSubset> {
+    /// Number of send attempts made.
+    pub attempts: u32,
+    /// The error returned by the final attempt.
+    pub error: EventSenderError<Category, EventSubset>,
+}
+
+impl<Category, EventSubset> ::std::fmt::Display for RetryError<Category, EventSubset> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "{} (after {} attempts)", self.error, self.attempts)
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + 'static,
+     EventSubset: ::std::fmt::Debug + 'static> ::std::error::Error
+    for RetryError<Category, EventSubset> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<Category   : ::std::fmt::Debug + Clone,
+     EventSubset: ::std::fmt::Debug,
+     EventTx    : ChannelSender<EventSubset>,
+     CategoryTx : ChannelSender<Category>> EventSender<Category, EventSubset, EventTx, CategoryTx> {
+    /// Fire an allowed event/signal to the observer, retrying with exponential backoff for as long
+    /// as the bounded event subset channel is full. Any other error fails immediately.
+    pub fn send_with_retry(&self,
+                           mut event: EventSubset,
+                           policy: RetryPolicy)
+                           -> Result<(), RetryError<Category, EventSubset>> {
+        let mut delay = policy.initial_delay;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.try_send(event) {
+                Ok(()) => return Ok(()),
+                Err(EventSenderError::QueueFull(rejected)) if attempts < policy.max_attempts => {
+                    event = rejected;
+                }
+                Err(error) => return Err(RetryError { attempts, error }),
+            }
+            ::std::thread::sleep(delay);
+            delay = ::std::cmp::min(delay * 2, policy.max_delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_delay: ::std::time::Duration::from_millis(5),
+            max_delay    : ::std::time::Duration::from_millis(20),
+            max_attempts : 4,
+        }
+    }
+
+    #[test]
+    fn retries_until_queue_has_room() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::sync_channel(1);
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::<_, u32, _>::new(event_tx, "Network", category_tx);
+
+        assert!(sender.send_with_retry(1, policy()).is_ok());
+        let _joiner = ::thread::RaiiThreadJoiner::new(thread!("RetryTestReceiver", move || {
+            ::std::thread::sleep(::std::time::Duration::from_millis(10));
+            assert_eq!(unwrap_result!(event_rx.recv()), 1);
+            assert_eq!(unwrap_result!(event_rx.recv()), 2);
+        }));
+        assert!(sender.send_with_retry(2, policy()).is_ok());
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::sync_channel(0);
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::<_, u32, _>::new(event_tx, "Network", category_tx);
+
+        match sender.send_with_retry(1, policy()) {
+            Err(RetryError { attempts, error: EventSenderError::QueueFull(event) }) => {
+                assert_eq!((attempts, event), (policy().max_attempts, 1))
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        drop(event_rx);
+        match sender.send_with_retry(2, policy()) {
+            Err(RetryError { attempts: 1, error: EventSenderError::EventSendError(_) }) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
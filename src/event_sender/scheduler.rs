@@ -0,0 +1,300 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Outcome of handling a single event, telling the listener whether to keep going.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// Carry on processing events.
+    Continue,
+    /// Stop processing and return from the listener loop.
+    Stop,
+}
+
+/// Limits on how much work a single category may do in one scheduling round. A category's turn
+/// ends as soon as either limit is reached, or when it has no more pending events.
+///
+/// A turn always handles at least one pending event, however tight the limits, so that a zero
+/// limit cannot leave the scheduler spinning without making progress.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Quota {
+    max_events  : Option<usize>,
+    max_duration: Option<::std::time::Duration>,
+}
+
+impl Quota {
+    /// A quota which places no limit on the category.
+    pub fn unlimited() -> Quota {
+        Quota::default()
+    }
+
+    /// A quota allowing at most `max_events` events per round.
+    pub fn events(max_events: usize) -> Quota {
+        Quota::default().max_events(max_events)
+    }
+
+    /// A quota allowing the category to run for at most `max_duration` per round.
+    pub fn duration(max_duration: ::std::time::Duration) -> Quota {
+        Quota::default().max_duration(max_duration)
+    }
+
+    /// Set the maximum number of events per round. Zero is treated as one.
+    pub fn max_events(mut self, max_events: usize) -> Quota {
+        self.max_events = Some(max_events.max(1));
+        self
+    }
+
+    /// Set the maximum processing time per round. With a zero duration, one event is handled per
+    /// round.
+    pub fn max_duration(mut self, max_duration: ::std::time::Duration) -> Quota {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    fn is_exhausted(&self, handled: usize, started: ::std::time::Instant) -> bool {
+        handled > 0 &&
+        (self.max_events.is_some_and(|max_events| handled >= max_events) ||
+         self.max_duration.is_some_and(|max_duration| started.elapsed() >= max_duration))
+    }
+}
+
+struct Entry<Category> {
+    category: Category,
+    quota   : Quota,
+    pending : usize,
+    handler : Box<dyn FnMut() -> Flow>,
+}
+
+/// An observer-side scheduler which shares a single listener thread fairly between event
+/// categories.
+///
+/// The plain listener loop handles events strictly in the order their category notifications
+/// arrive, so a chatty category can starve every other one. `FairScheduler` instead counts the
+/// pending notifications per category and processes them in rounds, giving each registered
+/// category a turn bounded by its `Quota`.
+///
+/// Each handler is invoked once per pending notification of its category and is expected to pull
+/// (via `try_recv`) and process one event from that category's subset receiver. Notifications for
+/// unregistered categories are discarded.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, FairScheduler, Flow, Quota};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum EventCategory {
+///     Network,
+///     UserInterface,
+/// }
+///
+/// let (network_event_tx, network_event_rx) = std::sync::mpsc::channel::<u32>();
+/// let (ui_event_tx, ui_event_rx) = std::sync::mpsc::channel::<&'static str>();
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+///
+/// let network_sender = EventSender::new(network_event_tx,
+///                                       EventCategory::Network,
+///                                       category_tx.clone());
+/// let ui_sender = EventSender::new(ui_event_tx, EventCategory::UserInterface, category_tx);
+///
+/// for packet in 0..1000 {
+///     assert!(network_sender.send(packet).is_ok());
+/// }
+/// assert!(ui_sender.send("Terminate").is_ok());
+///
+/// let mut scheduler = FairScheduler::new(category_rx);
+/// scheduler.register(EventCategory::Network, Quota::events(10), move || {
+///     let _packet = network_event_rx.try_recv();
+///     Flow::Continue
+/// });
+/// scheduler.register(EventCategory::UserInterface, Quota::unlimited(), move || {
+///     match ui_event_rx.try_recv() {
+///         Ok("Terminate") => Flow::Stop,
+///         _ => Flow::Continue,
+///     }
+/// });
+///
+/// // Returns after the first round rather than after all 1000 network events.
+/// scheduler.run();
+/// # }
+/// ```
+pub struct FairScheduler<Category> {
+    category_rx: ::std::sync::mpsc::Receiver<Category>,
+    entries    : Vec<Entry<Category>>,
+}
+
+impl<Category: PartialEq> FairScheduler<Category> {
+    /// Create a new scheduler listening on the given category receiver.
+    pub fn new(category_rx: ::std::sync::mpsc::Receiver<Category>) -> FairScheduler<Category> {
+        FairScheduler {
+            category_rx,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register the handler for `category`, replacing any previously registered one. Categories
+    /// are visited in registration order within each round.
+    pub fn register<F>(&mut self, category: Category, quota: Quota, handler: F)
+        where F: FnMut() -> Flow + 'static
+    {
+        self.entries.retain(|entry| entry.category != category);
+        self.entries.push(Entry {
+            category,
+            quota,
+            pending: 0,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Process events until a handler returns `Flow::Stop`, or until all senders have been
+    /// dropped and every pending event has been handled.
+    pub fn run(&mut self) {
+        loop {
+            if self.entries.iter().all(|entry| entry.pending == 0) {
+                match self.category_rx.recv() {
+                    Ok(category) => self.note(category),
+                    Err(_) => return,
+                }
+            }
+            while let Ok(category) = self.category_rx.try_recv() {
+                self.note(category);
+            }
+            if self.run_round() == Flow::Stop {
+                return;
+            }
+        }
+    }
+
+    fn note(&mut self, category: Category) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.category == category) {
+            entry.pending += 1;
+        }
+    }
+
+    fn run_round(&mut self) -> Flow {
+        for entry in &mut self.entries {
+            let started = ::std::time::Instant::now();
+            let mut handled = 0;
+            while entry.pending > 0 && !entry.quota.is_exhausted(handled, started) {
+                entry.pending -= 1;
+                handled += 1;
+                if (entry.handler)() == Flow::Stop {
+                    return Flow::Stop;
+                }
+            }
+        }
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::EventSender;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum EventCategory {
+        Network,
+        UserInterface,
+    }
+
+    #[test]
+    fn chatty_category_does_not_starve_others() {
+        const QUOTA: usize = 5;
+
+        let (network_event_tx, network_event_rx) = ::std::sync::mpsc::channel();
+        let (ui_event_tx, ui_event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+
+        let network_sender = EventSender::new(network_event_tx,
+                                              EventCategory::Network,
+                                              category_tx.clone());
+        let ui_sender = EventSender::new(ui_event_tx, EventCategory::UserInterface, category_tx);
+
+        for packet in 0..100u32 {
+            assert!(network_sender.send(packet).is_ok());
+        }
+        assert!(ui_sender.send(()).is_ok());
+
+        let handled_packets = Rc::new(RefCell::new(Vec::new()));
+        let handled_packets_clone = handled_packets.clone();
+
+        let mut scheduler = FairScheduler::new(category_rx);
+        scheduler.register(EventCategory::Network, Quota::events(QUOTA), move || {
+            handled_packets_clone.borrow_mut().push(unwrap_result!(network_event_rx.try_recv()));
+            Flow::Continue
+        });
+        scheduler.register(EventCategory::UserInterface, Quota::unlimited(), move || {
+            unwrap_result!(ui_event_rx.try_recv());
+            Flow::Stop
+        });
+        scheduler.run();
+
+        assert_eq!(*handled_packets.borrow(), (0..QUOTA as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn run_exits_once_senders_are_dropped() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+
+        let sender = EventSender::new(event_tx, EventCategory::Network, category_tx);
+        for packet in 0..10u32 {
+            assert!(sender.send(packet).is_ok());
+        }
+        drop(sender);
+
+        let handled = Rc::new(RefCell::new(0));
+        let handled_clone = handled.clone();
+
+        let mut scheduler = FairScheduler::new(category_rx);
+        scheduler.register(EventCategory::Network, Quota::events(3), move || {
+            let _ = unwrap_result!(event_rx.try_recv());
+            *handled_clone.borrow_mut() += 1;
+            Flow::Continue
+        });
+        scheduler.run();
+
+        assert_eq!(*handled.borrow(), 10);
+    }
+
+    #[test]
+    fn zero_quotas_still_make_progress() {
+        let zero = ::std::time::Duration::from_secs(0);
+        for quota in &[Quota::events(0), Quota::duration(zero)] {
+            let (event_tx, event_rx) = ::std::sync::mpsc::channel();
+            let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+            let sender = EventSender::new(event_tx, EventCategory::Network, category_tx);
+            for packet in 0..3u32 {
+                assert!(sender.send(packet).is_ok());
+            }
+            drop(sender);
+
+            let mut scheduler = FairScheduler::new(category_rx);
+            scheduler.register(EventCategory::Network, *quota, move || {
+                let _ = unwrap_result!(event_rx.try_recv());
+                Flow::Continue
+            });
+            // Would spin forever if a turn could handle nothing.
+            scheduler.run();
+        }
+        assert_eq!(Quota::events(0).max_events, Some(1));
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::ChannelSender;
+
+/// Create `shards` event subset channels fed round-robin by a single `ShardedSender`. Each
+/// receiver is intended to be drained by its own worker thread.
+///
+/// # Panics
+///
+/// Panics if `shards` is zero.
+pub fn sharded_channel<T>(shards: usize)
+                          -> (ShardedSender<T>, Vec<::std::sync::mpsc::Receiver<T>>) {
+    let (txs, rxs) = (0..shards).map(|_| ::std::sync::mpsc::channel()).unzip();
+    (ShardedSender::new(txs), rxs)
+}
+
+/// Create `shards` event subset channels fed by a single `ShardedSender`, with every value for
+/// which `key` returns the same key going to the same receiver. This preserves ordering per key
+/// (e.g. per peer) while still spreading the load.
+///
+/// # Panics
+///
+/// Panics if `shards` is zero.
+pub fn sharded_channel_by_key<T, K, F>(shards: usize, key: F)
+                                       -> (ShardedSender<T>, Vec<::std::sync::mpsc::Receiver<T>>)
+    where K: ::std::hash::Hash,
+          F: Fn(&T) -> K + Send + Sync + 'static
+{
+    let (txs, rxs) = (0..shards).map(|_| ::std::sync::mpsc::channel()).unzip();
+    (ShardedSender::by_key(txs, key), rxs)
+}
+
+/// A transport which spreads the values sent through it across several underlying senders, either
+/// round-robin or by hashing a key derived from each value.
+///
+/// Used as the event subset transport of an `EventSender`, this turns the consumers of the shards
+/// into a worker pool behind the usual API: the category notification still goes to the
+/// dispatcher (once per event), while the heavy lifting is done by whichever worker owns the
+/// shard the event landed in.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, sharded_channel};
+///
+/// let (event_tx, event_rxs) = sharded_channel::<u64>(4);
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Hash", category_tx);
+///
+/// let workers: Vec<_> = event_rxs.into_iter()
+///     .map(|event_rx| std::thread::spawn(move || event_rx.iter().map(|n| n * n).sum::<u64>()))
+///     .collect();
+///
+/// for n in 0..100 {
+///     assert!(sender.send(n).is_ok());
+/// }
+/// drop(sender);
+///
+/// let total: u64 = workers.into_iter().map(|worker| unwrap_result!(worker.join())).sum();
+/// assert_eq!(total, (0..100).map(|n| n * n).sum());
+/// assert_eq!(category_rx.iter().count(), 100);
+/// # }
+/// ```
+pub struct ShardedSender<T, Tx = ::std::sync::mpsc::Sender<T>> {
+    shards : Vec<Tx>,
+    routing: Routing<T>,
+}
+
+enum Routing<T> {
+    RoundRobin(::std::sync::atomic::AtomicUsize),
+    ByKey(Box<dyn Fn(&T) -> u64 + Send + Sync>),
+}
+
+impl<T, Tx: ChannelSender<T>> ShardedSender<T, Tx> {
+    /// Spread values over `shards` round-robin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new(shards: Vec<Tx>) -> ShardedSender<T, Tx> {
+        assert!(!shards.is_empty(), "ShardedSender needs at least one shard");
+        ShardedSender {
+            shards,
+            routing: Routing::RoundRobin(::std::sync::atomic::AtomicUsize::new(0)),
+        }
+    }
+
+    /// Spread values over `shards` by the hash of `key(&value)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn by_key<K, F>(shards: Vec<Tx>, key: F) -> ShardedSender<T, Tx>
+        where K: ::std::hash::Hash,
+              F: Fn(&T) -> K + Send + Sync + 'static
+    {
+        assert!(!shards.is_empty(), "ShardedSender needs at least one shard");
+        ShardedSender {
+            shards,
+            routing: Routing::ByKey(Box::new(move |value| {
+                use std::hash::Hasher;
+                let mut hasher = ::std::collections::hash_map::DefaultHasher::new();
+                key(value).hash(&mut hasher);
+                hasher.finish()
+            })),
+        }
+    }
+
+    /// The number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    fn shard_for(&self, value: &T) -> &Tx {
+        let index = match self.routing {
+            Routing::RoundRobin(ref next) => {
+                next.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) % self.shards.len()
+            }
+            Routing::ByKey(ref hash) => (hash(value) % self.shards.len() as u64) as usize,
+        };
+        &self.shards[index]
+    }
+}
+
+impl<T, Tx: ChannelSender<T>> ChannelSender<T> for ShardedSender<T, Tx> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        self.shard_for(&value).send(value)
+    }
+
+    fn try_send(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.shard_for(&value).try_send(value)
+    }
+
+    fn send_or_reject(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.shard_for(&value).send_or_reject(value)
+    }
+
+    /// The total across all shards, if every shard keeps track of it.
+    fn pending(&self) -> Option<usize> {
+        self.shards.iter().map(ChannelSender::pending).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::{EventSender, instrumented_channel};
+
+    #[test]
+    fn round_robin() {
+        let (event_tx, event_rxs) = sharded_channel(3);
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, (), category_tx);
+
+        for n in 0..7 {
+            assert!(sender.send(n).is_ok());
+        }
+
+        let shards: Vec<Vec<i32>> = event_rxs.iter().map(|rx| rx.try_iter().collect()).collect();
+        assert_eq!(shards, vec![vec![0, 3, 6], vec![1, 4], vec![2, 5]]);
+        assert_eq!(category_rx.try_iter().count(), 7);
+    }
+
+    #[test]
+    fn by_key() {
+        let (event_tx, event_rxs) = sharded_channel_by_key(4, |&(peer, _): &(u8, u32)| peer);
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, (), category_tx);
+
+        for n in 0..100 {
+            assert!(sender.send((n as u8 % 5, n)).is_ok());
+        }
+
+        for rx in &event_rxs {
+            let events: Vec<_> = rx.try_iter().collect();
+            for window in events.windows(2) {
+                assert!(window[0].1 < window[1].1);
+            }
+            for peer in 0..5 {
+                let count = events.iter().filter(|event| event.0 == peer).count();
+                assert!(count == 0 || count == 20);
+            }
+        }
+    }
+
+    #[test]
+    fn pending_is_summed() {
+        let (tx_0, _rx_0) = instrumented_channel();
+        let (tx_1, _rx_1) = instrumented_channel();
+        let sender = ShardedSender::new(vec![tx_0, tx_1]);
+        for n in 0..5 {
+            assert!(ChannelSender::send(&sender, n).is_ok());
+        }
+        assert_eq!(sender.pending(), Some(5));
+
+        let (tx, _rx) = sharded_channel::<u8>(2);
+        assert_eq!(tx.pending(), None);
+    }
+}
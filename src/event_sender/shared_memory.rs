@@ -0,0 +1,676 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Experimental cross-process transport: a ring buffer in POSIX shared memory, with a named
+//! semaphore to wake the receiver.
+//!
+//! The receiving process calls `create` to set up a named queue and the sending processes call
+//! `open` with the same name. As both halves implement the transport traits, two processes on the
+//! same host (e.g. a vault and its local UI) can exchange events through the usual `EventSender`
+//! API without going via TCP. The category channel has to cross the process boundary too, so it
+//! is normally a second shared-memory queue.
+//!
+//! Values are copied into the ring in the form given by their `Wire` impl. Limitations, for now:
+//! the lock guarding the ring spins, so a process dying while holding it will wedge the queue; a
+//! sender blocked on a full queue polls for room; and neither side can detect that the other has
+//! gone, so `recv` on a queue with no live senders blocks for ever. Only available on Linux with
+//! the `shared-memory` feature enabled.
+//!
+//! #Examples
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate maidsafe_utilities;
+//! # fn main() {
+//! use maidsafe_utilities::event_sender::EventSender;
+//! use maidsafe_utilities::event_sender::shared_memory;
+//!
+//! let events = format!("/example-events-{}", std::process::id());
+//! let categories = format!("/example-categories-{}", std::process::id());
+//!
+//! // In the UI process:
+//! let event_rx = unwrap_result!(shared_memory::create::<String>(&events, 64 * 1024));
+//! let category_rx = unwrap_result!(shared_memory::create::<String>(&categories, 4 * 1024));
+//!
+//! // In the vault process:
+//! let sender = EventSender::new(unwrap_result!(shared_memory::open(&events)),
+//!                               "Vault".to_owned(),
+//!                               unwrap_result!(shared_memory::open(&categories)));
+//! assert!(sender.send("Stored chunk".to_owned()).is_ok());
+//!
+//! // Back in the UI process:
+//! assert_eq!(unwrap_result!(category_rx.recv()), "Vault");
+//! assert_eq!(unwrap_result!(event_rx.try_recv()), "Stored chunk");
+//! # }
+//! ```
+
+// Mapping shared memory and using POSIX semaphores requires FFI.
+#![allow(unsafe_code)]
+
+use super::{ChannelReceiver, ChannelSender};
+
+use std::convert::TryFrom;
+use std::ffi::CString;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use std::time::{Duration, Instant};
+
+const MAGIC: u64 = 0x4d53_5f53_484d_5131; // "MS_SHMQ1"
+const LENGTH_PREFIX: u64 = 4;
+const FULL_QUEUE_POLL_INTERVAL_MS: u64 = 1;
+
+#[cfg(target_env = "gnu")]
+extern "C" {
+    // In glibc since 2.30, but not yet exposed by the `libc` crate.
+    fn sem_clockwait(semaphore: *mut ::libc::sem_t,
+                     clock: ::libc::clockid_t,
+                     deadline: *const ::libc::timespec)
+                     -> ::libc::c_int;
+}
+
+// The clock `wait_until` measures its deadline on. Only glibc can wait against the monotonic
+// clock; elsewhere a jump in the wall clock shortens or stretches the wait.
+#[cfg(target_env = "gnu")]
+const WAIT_CLOCK: ::libc::clockid_t = ::libc::CLOCK_MONOTONIC;
+#[cfg(not(target_env = "gnu"))]
+const WAIT_CLOCK: ::libc::clockid_t = ::libc::CLOCK_REALTIME;
+
+// The time `WAIT_CLOCK` will read once `duration` has elapsed, saturating at the latest it can
+// represent.
+fn wait_clock_after(duration: Duration) -> ::libc::timespec {
+    let mut now: ::libc::timespec = unsafe { ::std::mem::zeroed() };
+    let _ = unsafe { ::libc::clock_gettime(WAIT_CLOCK, &mut now) };
+    let nanos = now.tv_nsec as u64 + u64::from(duration.subsec_nanos());
+    let secs = duration.as_secs().saturating_add(nanos / 1_000_000_000);
+    match ::libc::time_t::try_from(secs).ok().and_then(|secs| now.tv_sec.checked_add(secs)) {
+        Some(tv_sec) => {
+            ::libc::timespec {
+                tv_sec,
+                tv_nsec: (nanos % 1_000_000_000) as _,
+            }
+        }
+        None => {
+            ::libc::timespec {
+                tv_sec : ::libc::time_t::MAX,
+                tv_nsec: 999_999_999,
+            }
+        }
+    }
+}
+
+// Wait on `semaphore` until `deadline`, as measured on `WAIT_CLOCK`.
+unsafe fn wait_until(semaphore: *mut ::libc::sem_t, deadline: &::libc::timespec) -> ::libc::c_int {
+    #[cfg(target_env = "gnu")]
+    {
+        sem_clockwait(semaphore, WAIT_CLOCK, deadline)
+    }
+    #[cfg(not(target_env = "gnu"))]
+    {
+        ::libc::sem_timedwait(semaphore, deadline)
+    }
+}
+
+/// Conversion of values to and from the bytes stored in a shared-memory queue.
+pub trait Wire: Sized {
+    /// Encode `self`.
+    fn to_wire(&self) -> Vec<u8>;
+    /// Decode a value encoded by `to_wire`, or return `None` if `bytes` are malformed.
+    fn from_wire(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Wire for Vec<u8> {
+    fn to_wire(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Option<Vec<u8>> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Wire for String {
+    fn to_wire(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_wire(bytes: &[u8]) -> Option<String> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+// Lives at the start of the shared region, followed by `capacity` bytes of ring. `head` and
+// `tail` are byte counters which only ever increase; their difference is the space in use.
+#[repr(C)]
+struct Header {
+    magic   : AtomicU64,
+    capacity: AtomicU64,
+    head    : AtomicU64,
+    tail    : AtomicU64,
+    lock    : AtomicU32,
+}
+
+// One process's mapping of a queue.
+struct Mapping {
+    name     : CString,
+    semaphore: *mut ::libc::sem_t,
+    base     : *mut u8,
+    length   : usize,
+    owner    : bool,
+}
+
+// The mapping is only accessed through atomics or under the ring lock.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+fn names(name: &str) -> io::Result<(CString, CString)> {
+    if !name.starts_with('/') || name[1..].contains('/') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "Queue names must start with '/' and contain no other '/'"));
+    }
+    let to_c = |name: String| {
+        CString::new(name).map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+    };
+    Ok((to_c(name.to_owned())?, to_c(format!("{}.sem", name))?))
+}
+
+fn check(result: ::libc::c_int) -> io::Result<::libc::c_int> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+impl Mapping {
+    fn create(name: &str, capacity: usize) -> io::Result<Mapping> {
+        let (shm_name, sem_name) = names(name)?;
+        let length = ::std::mem::size_of::<Header>() + capacity;
+        let fd = check(unsafe {
+            ::libc::shm_open(shm_name.as_ptr(), ::libc::O_CREAT | ::libc::O_EXCL | ::libc::O_RDWR,
+                             0o600)
+        })?;
+        let mapping = unsafe {
+            if ::libc::ftruncate(fd, length as ::libc::off_t) == -1 {
+                let error = io::Error::last_os_error();
+                let _ = ::libc::close(fd);
+                let _ = ::libc::shm_unlink(shm_name.as_ptr());
+                return Err(error);
+            }
+            Mapping::map(fd, shm_name.clone(), length)
+        };
+        let mut mapping = mapping.inspect_err(|_| {
+            let _ = unsafe { ::libc::shm_unlink(shm_name.as_ptr()) };
+        })?;
+        mapping.owner = true;
+
+        let _ = unsafe { ::libc::sem_unlink(sem_name.as_ptr()) };
+        let semaphore = unsafe {
+            ::libc::sem_open(sem_name.as_ptr(), ::libc::O_CREAT | ::libc::O_EXCL, 0o600, 0)
+        };
+        if semaphore == ::libc::SEM_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        mapping.semaphore = semaphore;
+
+        let header = mapping.header();
+        header.capacity.store(capacity as u64, Ordering::Relaxed);
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(0, Ordering::Relaxed);
+        header.lock.store(0, Ordering::Relaxed);
+        header.magic.store(MAGIC, Ordering::Release);
+        Ok(mapping)
+    }
+
+    fn open(name: &str) -> io::Result<Mapping> {
+        let (shm_name, sem_name) = names(name)?;
+        let fd = check(unsafe { ::libc::shm_open(shm_name.as_ptr(), ::libc::O_RDWR, 0) })?;
+        let mut stat: ::libc::stat = unsafe { ::std::mem::zeroed() };
+        if unsafe { ::libc::fstat(fd, &mut stat) } == -1 {
+            let error = io::Error::last_os_error();
+            let _ = unsafe { ::libc::close(fd) };
+            return Err(error);
+        }
+        let length = stat.st_size as usize;
+        if length < ::std::mem::size_of::<Header>() {
+            let _ = unsafe { ::libc::close(fd) };
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Queue is not initialised"));
+        }
+        let mut mapping = unsafe { Mapping::map(fd, shm_name, length)? };
+        let header = mapping.header();
+        if header.magic.load(Ordering::Acquire) != MAGIC ||
+           header.capacity.load(Ordering::Relaxed) as usize + ::std::mem::size_of::<Header>() !=
+           length {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Queue is not initialised"));
+        }
+
+        let semaphore = unsafe { ::libc::sem_open(sem_name.as_ptr(), 0) };
+        if semaphore == ::libc::SEM_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        mapping.semaphore = semaphore;
+        Ok(mapping)
+    }
+
+    // Maps and closes `fd`.
+    unsafe fn map(fd: ::libc::c_int, name: CString, length: usize) -> io::Result<Mapping> {
+        let base = ::libc::mmap(::std::ptr::null_mut(),
+                                length,
+                                ::libc::PROT_READ | ::libc::PROT_WRITE,
+                                ::libc::MAP_SHARED,
+                                fd,
+                                0);
+        let _ = ::libc::close(fd);
+        if base == ::libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            name,
+            semaphore: ::libc::SEM_FAILED,
+            base: base.cast(),
+            length,
+            owner: false,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.base.cast::<Header>() }
+    }
+
+    fn capacity(&self) -> u64 {
+        (self.length - ::std::mem::size_of::<Header>()) as u64
+    }
+
+    fn ring(&self) -> *mut u8 {
+        unsafe { self.base.add(::std::mem::size_of::<Header>()) }
+    }
+
+    fn lock(&self) -> RingGuard<'_> {
+        let lock = &self.header().lock;
+        let mut backoff = ::thread::Backoff::new();
+        while lock.compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            backoff.snooze();
+        }
+        RingGuard { mapping: self }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            if self.semaphore != ::libc::SEM_FAILED {
+                let _ = ::libc::sem_close(self.semaphore);
+            }
+            let _ = ::libc::munmap(self.base.cast(), self.length);
+            if self.owner {
+                let _ = ::libc::shm_unlink(self.name.as_ptr());
+                if let Ok(name) = self.name.clone().into_string() {
+                    if let Ok((_, sem_name)) = names(&name) {
+                        let _ = ::libc::sem_unlink(sem_name.as_ptr());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Exclusive access to the ring, shared between all processes mapping it.
+struct RingGuard<'a> {
+    mapping: &'a Mapping,
+}
+
+impl<'a> RingGuard<'a> {
+    fn copy_in(&self, position: u64, bytes: &[u8]) {
+        let capacity = self.mapping.capacity();
+        let offset = (position % capacity) as usize;
+        let first = bytes.len().min(capacity as usize - offset);
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.mapping.ring().add(offset), first);
+            ::std::ptr::copy_nonoverlapping(bytes[first..].as_ptr(),
+                                            self.mapping.ring(),
+                                            bytes.len() - first);
+        }
+    }
+
+    fn copy_out(&self, position: u64, bytes: &mut [u8]) {
+        let capacity = self.mapping.capacity();
+        let offset = (position % capacity) as usize;
+        let first = bytes.len().min(capacity as usize - offset);
+        unsafe {
+            ::std::ptr::copy_nonoverlapping(self.mapping.ring().add(offset),
+                                            bytes.as_mut_ptr(),
+                                            first);
+            let length = bytes.len() - first;
+            ::std::ptr::copy_nonoverlapping(self.mapping.ring(),
+                                            bytes[first..].as_mut_ptr(),
+                                            length);
+        }
+    }
+
+    // Append a record, or return `false` if there is not enough room.
+    fn push(&self, bytes: &[u8]) -> bool {
+        let header = self.mapping.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Relaxed);
+        let needed = LENGTH_PREFIX + bytes.len() as u64;
+        match self.mapping.capacity().checked_sub(tail.wrapping_sub(head)) {
+            Some(room) if room >= needed => (),
+            // A corrupt ring is emptied by the receiver's next `pop`.
+            _ => return false,
+        }
+        self.copy_in(tail, &(bytes.len() as u32).to_le_bytes());
+        self.copy_in(tail + LENGTH_PREFIX, bytes);
+        header.tail.store(tail + needed, Ordering::Relaxed);
+        true
+    }
+
+    // Take the oldest record, if any. As the ring is writable by any process which can open it,
+    // its counters and length prefixes are checked before being trusted; if they are
+    // inconsistent the ring's contents are discarded and an error returned.
+    fn pop(&self) -> io::Result<Option<Vec<u8>>> {
+        let header = self.mapping.header();
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Relaxed);
+        if head == tail {
+            return Ok(None);
+        }
+        let used = tail.wrapping_sub(head);
+        if used < LENGTH_PREFIX || used > self.mapping.capacity() {
+            return Err(self.discard());
+        }
+        let mut length = [0u8; LENGTH_PREFIX as usize];
+        self.copy_out(head, &mut length);
+        let length = u64::from(u32::from_le_bytes(length));
+        if length > used - LENGTH_PREFIX {
+            return Err(self.discard());
+        }
+        let mut bytes = vec![0u8; length as usize];
+        self.copy_out(head + LENGTH_PREFIX, &mut bytes);
+        header.head.store(head + LENGTH_PREFIX + length, Ordering::Relaxed);
+        Ok(Some(bytes))
+    }
+
+    // Empty a corrupt ring, returning the error to report.
+    fn discard(&self) -> io::Error {
+        let header = self.mapping.header();
+        header.head.store(header.tail.load(Ordering::Relaxed), Ordering::Relaxed);
+        io::Error::new(io::ErrorKind::InvalidData,
+                       "Shared-memory queue is corrupt; its contents were discarded")
+    }
+
+    fn len(&self) -> u64 {
+        let header = self.mapping.header();
+        header.tail.load(Ordering::Relaxed).wrapping_sub(header.head.load(Ordering::Relaxed))
+    }
+}
+
+impl<'a> Drop for RingGuard<'a> {
+    fn drop(&mut self) {
+        self.mapping.header().lock.store(0, Ordering::Release);
+    }
+}
+
+/// Create the queue `name` (which must start with `/`) with room for `capacity` bytes of
+/// messages, each of which also takes 4 bytes of framing. The queue is removed when the returned
+/// receiver is dropped. Fails if a queue of that name already exists.
+pub fn create<T: Wire>(name: &str, capacity: usize) -> io::Result<ShmReceiver<T>> {
+    if capacity as u64 <= LENGTH_PREFIX || capacity > u32::MAX as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Invalid queue capacity"));
+    }
+    Ok(ShmReceiver {
+        mapping: Mapping::create(name, capacity)?,
+        _value : PhantomData,
+    })
+}
+
+/// Open the queue `name`, previously set up by `create` in this or another process, for sending.
+pub fn open<T: Wire>(name: &str) -> io::Result<ShmSender<T>> {
+    Ok(ShmSender {
+        mapping: ::std::sync::Arc::new(Mapping::open(name)?),
+        _value : PhantomData,
+    })
+}
+
+/// The sending half of a shared-memory queue, obtained via `open`.
+pub struct ShmSender<T> {
+    mapping: ::std::sync::Arc<Mapping>,
+    _value : PhantomData<fn(T)>,
+}
+
+impl<T: Wire> ShmSender<T> {
+    // Returns the value back if there is no room, or if it can never fit.
+    fn push(&self, value: T) -> Result<(), (T, bool)> {
+        let bytes = value.to_wire();
+        if LENGTH_PREFIX + bytes.len() as u64 > self.mapping.capacity() {
+            return Err((value, false));
+        }
+        if !self.mapping.lock().push(&bytes) {
+            return Err((value, true));
+        }
+        let _ = unsafe { ::libc::sem_post(self.mapping.semaphore) };
+        Ok(())
+    }
+}
+
+impl<T: Wire> ChannelSender<T> for ShmSender<T> {
+    /// Sends `value`, polling for room while the queue is full. Fails if `value` is too large to
+    /// ever fit.
+    fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut value = value;
+        loop {
+            match self.push(value) {
+                Ok(()) => return Ok(()),
+                Err((rejected, true)) => {
+                    value = rejected;
+                    ::std::thread::sleep(Duration::from_millis(FULL_QUEUE_POLL_INTERVAL_MS));
+                }
+                Err((rejected, false)) => return Err(SendError(rejected)),
+            }
+        }
+    }
+
+    /// As `TrySendError` has no variant for it, a value too large to ever fit in the queue is
+    /// returned as `TrySendError::Disconnected`, even though the receiver may still be alive.
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.push(value).map_err(|(value, fits)| {
+            if fits {
+                TrySendError::Full(value)
+            } else {
+                TrySendError::Disconnected(value)
+            }
+        })
+    }
+
+    /// The number of bytes queued, including framing.
+    fn pending(&self) -> Option<usize> {
+        Some(self.mapping.lock().len() as usize)
+    }
+}
+
+impl<T> Clone for ShmSender<T> {
+    fn clone(&self) -> ShmSender<T> {
+        ShmSender {
+            mapping: self.mapping.clone(),
+            _value : PhantomData,
+        }
+    }
+}
+
+/// The receiving half of a shared-memory queue, obtained via `create`.
+pub struct ShmReceiver<T> {
+    mapping: Mapping,
+    _value : PhantomData<fn() -> T>,
+}
+
+impl<T: Wire> ShmReceiver<T> {
+    /// Block until a value is received. Values which fail to decode are skipped. An error is only
+    /// returned if waiting on the semaphore fails, or if the queue is found to be corrupt, in
+    /// which case everything queued is discarded (so a later call can succeed).
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            if unsafe { ::libc::sem_wait(self.mapping.semaphore) } == -1 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(RecvError);
+            }
+            if let Some(value) = self.pop().map_err(|_| RecvError)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Receive a value if one is waiting. A corrupt queue is reported as `Disconnected`, as for
+    /// `recv`.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        while unsafe { ::libc::sem_trywait(self.mapping.semaphore) } == 0 {
+            if let Some(value) = self.pop().map_err(|_| TryRecvError::Disconnected)? {
+                return Ok(value);
+            }
+        }
+        Err(TryRecvError::Empty)
+    }
+
+    /// Block for at most `timeout` waiting for a value. A corrupt queue is reported as
+    /// `Disconnected`, as for `recv`. A `timeout` too long to be represented is cut short to the
+    /// latest deadline the platform can represent.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let remaining = deadline.map_or(timeout, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            let until = wait_clock_after(remaining);
+            if unsafe { wait_until(self.mapping.semaphore, &until) } == -1 {
+                match io::Error::last_os_error().raw_os_error() {
+                    Some(::libc::EINTR) => continue,
+                    Some(::libc::ETIMEDOUT) => return Err(RecvTimeoutError::Timeout),
+                    _ => return Err(RecvTimeoutError::Disconnected),
+                }
+            }
+            if let Some(value) = self.pop().map_err(|_| RecvTimeoutError::Disconnected)? {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn pop(&self) -> io::Result<Option<T>> {
+        let bytes = match self.mapping.lock().pop() {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return Ok(None),
+            Err(error) => {
+                warn!("{}", error);
+                return Err(error);
+            }
+        };
+        let value = T::from_wire(&bytes);
+        if value.is_none() {
+            debug!("Discarding a message which could not be decoded");
+        }
+        Ok(value)
+    }
+}
+
+impl<T: Wire> ChannelReceiver<T> for ShmReceiver<T> {
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        ShmReceiver::try_recv(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unique_name(tag: &str) -> String {
+        format!("/maidsafe-utilities-test-{}-{}", ::std::process::id(), tag)
+    }
+
+    #[test]
+    fn wraps_around_the_ring() {
+        let name = unique_name("wrap");
+        let receiver = unwrap_result!(create::<Vec<u8>>(&name, 32));
+        let sender = unwrap_result!(open::<Vec<u8>>(&name));
+        assert!(create::<Vec<u8>>(&name, 32).is_err());
+
+        for round in 0..20u8 {
+            let message = vec![round; 10];
+            assert!(sender.send(message.clone()).is_ok());
+            assert!(sender.send(message.clone()).is_ok());
+            assert_eq!(sender.pending(), Some(28));
+            match sender.try_send(vec![0; 1]) {
+                Err(TrySendError::Full(_)) => (),
+                result => panic!("Unexpected result {:?}", result),
+            }
+            assert_eq!(receiver.try_recv(), Ok(message.clone()));
+            assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(message));
+        }
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(20)),
+                   Err(RecvTimeoutError::Timeout));
+        assert_eq!(sender.send(vec![0; 29]), Err(SendError(vec![0; 29])));
+
+        drop(receiver);
+        assert!(open::<Vec<u8>>(&name).is_err());
+    }
+
+    #[test]
+    fn corrupt_length_is_rejected() {
+        let name = unique_name("corrupt");
+        let receiver = unwrap_result!(create::<Vec<u8>>(&name, 32));
+        let sender = unwrap_result!(open::<Vec<u8>>(&name));
+        assert!(sender.send(vec![1; 4]).is_ok());
+        // Claim the record holds far more than the ring does.
+        receiver.mapping.lock().copy_in(0, &u32::MAX.to_le_bytes());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(sender.pending(), Some(0));
+
+        // The queue is usable again afterwards.
+        assert!(sender.send(vec![2; 4]).is_ok());
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(vec![2; 4]));
+    }
+
+    #[test]
+    fn blocked_sender_resumes() {
+        let name = unique_name("block");
+        let receiver = unwrap_result!(create::<String>(&name, 16));
+        let sender = unwrap_result!(open::<String>(&name));
+        assert!(sender.send("0123456789".to_owned()).is_ok());
+
+        let joiner = ::std::thread::spawn(move || sender.send("abcdefghij".to_owned()).is_ok());
+        assert_eq!(unwrap_result!(receiver.recv()), "0123456789");
+        assert_eq!(unwrap_result!(receiver.recv()), "abcdefghij");
+        assert!(unwrap_result!(joiner.join()));
+        assert!(open::<String>("no-leading-slash").is_err());
+    }
+
+    #[test]
+    fn huge_timeouts_saturate() {
+        let name = unique_name("huge");
+        let receiver = unwrap_result!(create::<String>(&name, 16));
+        let sender = unwrap_result!(open::<String>(&name));
+        let joiner = ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            sender.send("Late".to_owned()).is_ok()
+        });
+        assert_eq!(receiver.recv_timeout(Duration::MAX), Ok("Late".to_owned()));
+        assert!(unwrap_result!(joiner.join()));
+
+        let until = wait_clock_after(Duration::MAX);
+        assert_eq!(until.tv_sec, ::libc::time_t::MAX);
+    }
+}
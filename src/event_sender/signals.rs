@@ -0,0 +1,369 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Forwarding of OS termination signals as events.
+//!
+//! `install` registers handlers for Ctrl-C and `SIGTERM` on Unix, or for console control events
+//! on Windows, which forward each one to an `EventSender` as an event of its subset type. This
+//! saves every binary writing its own signal-to-channel glue. Forwarding stops when the returned
+//! `Forwarding` guard is dropped, and once nothing is being forwarded to, the previous handlers
+//! are restored so that the signals terminate the process again.
+//!
+//! #Examples
+//!
+//! ```no_run
+//! # #[macro_use]
+//! # extern crate maidsafe_utilities;
+//! # fn main() {
+//! use maidsafe_utilities::event_sender::EventSender;
+//! use maidsafe_utilities::event_sender::signals::{self, Signal};
+//!
+//! #[derive(Debug)]
+//! enum ControlEvent {
+//!     Shutdown(Signal),
+//! }
+//!
+//! impl From<Signal> for ControlEvent {
+//!     fn from(signal: Signal) -> ControlEvent {
+//!         ControlEvent::Shutdown(signal)
+//!     }
+//! }
+//!
+//! let (control_tx, control_rx) = std::sync::mpsc::channel::<ControlEvent>();
+//! let (category_tx, category_rx) = std::sync::mpsc::channel();
+//! let _forwarding =
+//!     unwrap_result!(signals::install(EventSender::new(control_tx, "Control", category_tx)));
+//!
+//! // Blocks until Ctrl-C is pressed.
+//! assert_eq!(unwrap_result!(category_rx.recv()), "Control");
+//! let ControlEvent::Shutdown(signal) = unwrap_result!(control_rx.try_recv());
+//! println!("Shutting down after {:?}", signal);
+//! # }
+//! ```
+
+// Installing OS signal handlers requires FFI.
+#![allow(unsafe_code)]
+
+use super::{ChannelSender, EventSender};
+
+/// A termination request from the OS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// `SIGINT` on Unix, or `CTRL_C_EVENT`/`CTRL_BREAK_EVENT` on Windows.
+    Interrupt,
+    /// `SIGTERM` on Unix, or `CTRL_CLOSE_EVENT`/`CTRL_LOGOFF_EVENT`/`CTRL_SHUTDOWN_EVENT` on
+    /// Windows.
+    Terminate,
+}
+
+// Returns whether the signal could be sent; a forwarder which fails is removed.
+type Forwarder = ::std::sync::Arc<dyn Fn(Signal) -> bool + Send + Sync>;
+
+#[derive(Default)]
+struct Forwarders {
+    next_id: u64,
+    by_id  : Vec<(u64, Forwarder)>,
+}
+
+fn forwarders() -> &'static ::std::sync::Mutex<Forwarders> {
+    static FORWARDERS: ::std::sync::OnceLock<::std::sync::Mutex<Forwarders>> =
+        ::std::sync::OnceLock::new();
+    FORWARDERS.get_or_init(|| ::std::sync::Mutex::new(Forwarders::default()))
+}
+
+fn dispatch(signal: Signal) {
+    // The forwarders run outside the lock, so that one blocked on a full channel holds up neither
+    // the others nor `install` and dropping a guard.
+    let current = unwrap_result!(forwarders().lock()).by_id.clone();
+    let failed = current.iter()
+                        .filter(|&(_, forwarder)| !forwarder(signal))
+                        .map(|&(id, _)| id)
+                        .collect::<Vec<_>>();
+    remove(&failed);
+    // Removed forwarders are only dropped here, outside the lock, as dropping a sender can run
+    // arbitrary code.
+    drop(current);
+}
+
+fn remove(ids: &[u64]) {
+    if ids.is_empty() {
+        return;
+    }
+    let removed = {
+        let mut forwarders = unwrap_result!(forwarders().lock());
+        let (removed, kept) = ::std::mem::take(&mut forwarders.by_id)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(id, _)| ids.contains(&id));
+        forwarders.by_id = kept;
+        if !removed.is_empty() && forwarders.by_id.is_empty() {
+            if let Err(error) = platform::uninstall() {
+                warn!("Could not restore the previous signal handlers: {}", error);
+            }
+        }
+        removed
+    };
+    drop(removed);
+}
+
+/// Keeps signals being forwarded to the sender passed to `install`. Dropping it stops the
+/// forwarding and drops the sender, so its observer can see the channel disconnect.
+pub struct Forwarding {
+    id: u64,
+}
+
+impl Drop for Forwarding {
+    fn drop(&mut self) {
+        remove(&[self.id]);
+    }
+}
+
+/// Forward every subsequent Ctrl-C/`SIGTERM` (or Windows console control event) to `sender`, as
+/// an event converted from `Signal`.
+///
+/// Forwarding continues until the returned guard is dropped, or until sending to `sender` fails
+/// because its observer has gone, whichever comes first.
+///
+/// This can be called several times to forward to several senders. The OS handlers are installed
+/// while anything is being forwarded to, replacing the default behaviour of terminating the
+/// process, and the previous handlers are restored once forwarding to the last sender stops. On
+/// platforms other than Unix and Windows this does nothing.
+pub fn install<Category, EventSubset, EventTx, CategoryTx>(sender: EventSender<Category,
+                                                                               EventSubset,
+                                                                               EventTx,
+                                                                               CategoryTx>)
+                                                           -> ::std::io::Result<Forwarding>
+    where Category: ::std::fmt::Debug + Clone + Send + Sync + 'static,
+          EventSubset: From<Signal> + ::std::fmt::Debug + Send + 'static,
+          EventTx: ChannelSender<EventSubset> + Send + Sync + 'static,
+          CategoryTx: ChannelSender<Category> + Send + Sync + 'static
+{
+    let forwarder: Forwarder = ::std::sync::Arc::new(move |signal| {
+        match sender.send(EventSubset::from(signal)) {
+            Ok(()) => true,
+            Err(error) => {
+                debug!("Could not forward {:?}, so no longer forwarding: {}", signal, error);
+                false
+            }
+        }
+    });
+    let mut forwarders = unwrap_result!(forwarders().lock());
+    if forwarders.by_id.is_empty() {
+        platform::install()?;
+    }
+    let id = forwarders.next_id;
+    forwarders.next_id += 1;
+    forwarders.by_id.push((id, forwarder));
+    Ok(Forwarding { id })
+}
+
+#[cfg(unix)]
+mod platform {
+    use super::{Signal, dispatch};
+
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+    // Runs in signal context, so only does the async-signal-safe thing of writing to a pipe which
+    // is drained by an ordinary thread.
+    extern "C" fn on_signal(signal_number: ::libc::c_int) {
+        let byte = signal_number as u8;
+        let _ = unsafe {
+            ::libc::write(WRITE_FD.load(Ordering::Relaxed), ::std::ptr::addr_of!(byte).cast(), 1)
+        };
+    }
+
+    // The handlers replaced by ours, to be restored by `uninstall`.
+    static PREVIOUS: ::std::sync::Mutex<Vec<(::libc::c_int, ::libc::sigaction)>> =
+        ::std::sync::Mutex::new(Vec::new());
+
+    pub fn install() -> ::std::io::Result<()> {
+        // The pipe and its reader outlive the handlers, so are only set up once.
+        static READER: ::std::sync::OnceLock<Result<(), String>> = ::std::sync::OnceLock::new();
+        READER.get_or_init(|| start_reader().map_err(|error| error.to_string()))
+              .clone()
+              .map_err(::std::io::Error::other)?;
+
+        let handler: extern "C" fn(::libc::c_int) = on_signal;
+        let mut previous = unwrap_result!(PREVIOUS.lock());
+        for &signal_number in &[::libc::SIGINT, ::libc::SIGTERM] {
+            unsafe {
+                let mut action: ::libc::sigaction = ::std::mem::zeroed();
+                action.sa_sigaction = handler as ::libc::sighandler_t;
+                action.sa_flags = ::libc::SA_RESTART;
+                let _ = ::libc::sigemptyset(&mut action.sa_mask);
+                let mut replaced: ::libc::sigaction = ::std::mem::zeroed();
+                if ::libc::sigaction(signal_number, &action, &mut replaced) != 0 {
+                    let error = ::std::io::Error::last_os_error();
+                    let _ = restore(&mut previous);
+                    return Err(error);
+                }
+                previous.push((signal_number, replaced));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn uninstall() -> ::std::io::Result<()> {
+        restore(&mut unwrap_result!(PREVIOUS.lock()))
+    }
+
+    fn restore(previous: &mut Vec<(::libc::c_int, ::libc::sigaction)>) -> ::std::io::Result<()> {
+        let mut result = Ok(());
+        for (signal_number, action) in previous.drain(..).rev() {
+            if unsafe { ::libc::sigaction(signal_number, &action, ::std::ptr::null_mut()) } != 0 {
+                result = Err(::std::io::Error::last_os_error());
+            }
+        }
+        result
+    }
+
+    fn start_reader() -> ::std::io::Result<()> {
+        let mut fds = [0; 2];
+        if unsafe { ::libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(::std::io::Error::last_os_error());
+        }
+        for &fd in &fds {
+            unsafe {
+                let _ = ::libc::fcntl(fd, ::libc::F_SETFD, ::libc::FD_CLOEXEC);
+            }
+        }
+        // The handler must never block, even if the reader thread falls behind.
+        unsafe {
+            let flags = ::libc::fcntl(fds[1], ::libc::F_GETFL);
+            let _ = ::libc::fcntl(fds[1], ::libc::F_SETFL, flags | ::libc::O_NONBLOCK);
+        }
+        WRITE_FD.store(fds[1], Ordering::Relaxed);
+
+        let mut pipe = unsafe { ::std::fs::File::from_raw_fd(fds[0]) };
+        // Shared by the whole process, so not named after whichever thread happened to start it.
+        let spawned = ::thread::Builder::new("SignalForwarder")
+                          .inherit_name(false)
+                          .log_panics()
+                          .spawn(move || {
+                              let mut byte = [0u8];
+                              while let Ok(1) = pipe.read(&mut byte) {
+                                  match byte[0] as ::libc::c_int {
+                                      ::libc::SIGINT => dispatch(Signal::Interrupt),
+                                      ::libc::SIGTERM => dispatch(Signal::Terminate),
+                                      _ => (),
+                                  }
+                              }
+                          });
+        match spawned {
+            Ok(raii_joiner) => raii_joiner.detach(),
+            Err(error) => {
+                // The read end was closed along with the unspawned closure owning it, and no
+                // handler has been installed yet.
+                WRITE_FD.store(-1, Ordering::Relaxed);
+                let _ = unsafe { ::libc::close(fds[1]) };
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{Signal, dispatch};
+
+    // Called by Windows on a thread of its own, so it can dispatch directly.
+    unsafe extern "system" fn on_console_event(event: ::winapi::DWORD) -> ::winapi::BOOL {
+        match event {
+            ::winapi::CTRL_C_EVENT | ::winapi::CTRL_BREAK_EVENT => dispatch(Signal::Interrupt),
+            ::winapi::CTRL_CLOSE_EVENT |
+            ::winapi::CTRL_LOGOFF_EVENT |
+            ::winapi::CTRL_SHUTDOWN_EVENT => dispatch(Signal::Terminate),
+            _ => return ::winapi::FALSE,
+        }
+        ::winapi::TRUE
+    }
+
+    pub fn install() -> ::std::io::Result<()> {
+        set_handler(::winapi::TRUE)
+    }
+
+    pub fn uninstall() -> ::std::io::Result<()> {
+        set_handler(::winapi::FALSE)
+    }
+
+    fn set_handler(add: ::winapi::BOOL) -> ::std::io::Result<()> {
+        if unsafe { ::kernel32::SetConsoleCtrlHandler(Some(on_console_event), add) } ==
+           ::winapi::FALSE {
+            return Err(::std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    pub fn install() -> ::std::io::Result<()> {
+        Ok(())
+    }
+
+    pub fn uninstall() -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct ShutdownEvent(Signal);
+
+    impl From<Signal> for ShutdownEvent {
+        fn from(signal: Signal) -> ShutdownEvent {
+            ShutdownEvent(signal)
+        }
+    }
+
+    fn sigterm_handler() -> ::libc::sighandler_t {
+        unsafe {
+            let mut action: ::libc::sigaction = ::std::mem::zeroed();
+            assert_eq!(::libc::sigaction(::libc::SIGTERM, ::std::ptr::null(), &mut action), 0);
+            action.sa_sigaction
+        }
+    }
+
+    #[test]
+    fn sigterm_is_forwarded() {
+        assert_eq!(sigterm_handler(), ::libc::SIG_DFL);
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel::<ShutdownEvent>();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let forwarding = unwrap_result!(install(EventSender::new(event_tx, "Control",
+                                                                  category_tx)));
+
+        assert_eq!(unsafe { ::libc::raise(::libc::SIGTERM) }, 0);
+
+        let timeout = ::std::time::Duration::from_secs(5);
+        assert_eq!(unwrap_result!(category_rx.recv_timeout(timeout)), "Control");
+        assert_eq!(unwrap_result!(event_rx.try_recv()), ShutdownEvent(Signal::Terminate));
+
+        // Dropping the guard releases the sender and, as it was the last, restores the default
+        // handler.
+        drop(forwarding);
+        assert_eq!(category_rx.recv_timeout(timeout),
+                   Err(::std::sync::mpsc::RecvTimeoutError::Disconnected));
+        assert_eq!(sigterm_handler(), ::libc::SIG_DFL);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelSender, EventSender, EventSenderError};
+
+/// A closure to be run by whichever thread receives it, usually an event loop thread.
+///
+/// The closure is boxed so that tasks of different origin can share one event subset channel.
+/// `Debug` is implemented so that `Task` satisfies `EventSender`'s bounds, but it can only say that
+/// this is a task.
+pub struct Task(Box<dyn FnOnce() + Send>);
+
+impl Task {
+    /// Wrap `task` for sending.
+    pub fn new<F: FnOnce() + Send + 'static>(task: F) -> Task {
+        Task(Box::new(task))
+    }
+
+    /// Run the task on the current thread, consuming it.
+    pub fn run(self) {
+        (self.0)()
+    }
+}
+
+impl ::std::fmt::Debug for Task {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "Task")
+    }
+}
+
+/// An `EventSender` whose events are closures for the listener to run, giving other threads a
+/// way to "run this on the event loop thread".
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{Task, TaskSender};
+/// use std::sync::mpsc;
+///
+/// let (task_tx, task_rx) = mpsc::channel::<Task>();
+/// let (category_tx, category_rx) = mpsc::channel();
+/// let sender = TaskSender::new(task_tx, "Task", category_tx);
+///
+/// let listener = thread!("EventLoop", move || {
+///     let mut state = Vec::new();
+///     for _ in category_rx.iter() {
+///         if let Ok(task) = task_rx.try_recv() {
+///             task.run();
+///         }
+///         state.push(std::thread::current().name().map(str::to_owned));
+///     }
+///     state
+/// });
+///
+/// let (result_tx, result_rx) = mpsc::channel();
+/// assert!(sender.send_task(move || {
+///     let name = std::thread::current().name().map(str::to_owned);
+///     assert!(result_tx.send(name).is_ok());
+/// }).is_ok());
+/// assert_eq!(unwrap_result!(result_rx.recv()), Some("EventLoop".to_owned()));
+///
+/// drop(sender);
+/// assert_eq!(unwrap_result!(listener.join()).len(), 1);
+/// # }
+/// ```
+pub type TaskSender<Category,
+                    EventTx    = ::std::sync::mpsc::Sender<Task>,
+                    CategoryTx = ::std::sync::mpsc::Sender<Category>>
+    = EventSender<Category, Task, EventTx, CategoryTx>;
+
+impl<Category  : ::std::fmt::Debug + Clone,
+     EventTx   : ChannelSender<Task>,
+     CategoryTx: ChannelSender<Category>> EventSender<Category, Task, EventTx, CategoryTx> {
+    /// Send `task` to be run by the listener. Equivalent to `send(Task::new(task))`.
+    pub fn send_task<F>(&self, task: F) -> Result<(), EventSenderError<Category, Task>>
+        where F: FnOnce() + Send + 'static
+    {
+        self.send(Task::new(task))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tasks_run_in_order_on_the_listener() {
+        let (task_tx, task_rx) = ::std::sync::mpsc::channel();
+        let (category_tx, category_rx) = ::std::sync::mpsc::channel();
+        let sender: TaskSender<u8> = TaskSender::new(task_tx, 1, category_tx);
+
+        let log = ::std::sync::Arc::new(::std::sync::Mutex::new(Vec::new()));
+        for n in 0..3 {
+            let log = log.clone();
+            assert!(sender.send_task(move || unwrap_result!(log.lock()).push(n)).is_ok());
+        }
+        assert!(unwrap_result!(log.lock()).is_empty());
+
+        for category in category_rx.try_iter() {
+            assert_eq!(category, 1);
+            unwrap_result!(task_rx.try_recv()).run();
+        }
+        assert_eq!(*unwrap_result!(log.lock()), vec![0, 1, 2]);
+        assert_eq!(format!("{:?}", Task::new(|| ())), "Task");
+    }
+}
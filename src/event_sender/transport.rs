@@ -0,0 +1,242 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// The sending half of a transport which can carry values of type `T` to an observer.
+///
+/// `EventSender` is generic over this trait for both its event subset channel and its category
+/// channel, so a custom queue can be plugged in without forking this module. It is implemented for
+/// `std::sync::mpsc::Sender` and `std::sync::mpsc::SyncSender`.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{ChannelSender, EventSender};
+/// use std::sync::{Arc, Mutex};
+/// use std::sync::mpsc::SendError;
+///
+/// // A transport which just queues up the values for later inspection.
+/// #[derive(Clone, Default)]
+/// struct VecSender<T>(Arc<Mutex<Vec<T>>>);
+///
+/// impl<T> ChannelSender<T> for VecSender<T> {
+///     fn send(&self, value: T) -> Result<(), SendError<T>> {
+///         match self.0.lock() {
+///             Ok(mut queue) => Ok(queue.push(value)),
+///             Err(_) => Err(SendError(value)),
+///         }
+///     }
+/// }
+///
+/// let events = VecSender::<u32>::default();
+/// let categories = VecSender::<&'static str>::default();
+/// let sender = EventSender::new(events.clone(), "Network", categories.clone());
+///
+/// assert!(sender.send(7).is_ok());
+/// assert_eq!(*events.0.lock().unwrap(), vec![7]);
+/// assert_eq!(*categories.0.lock().unwrap(), vec!["Network"]);
+/// # }
+/// ```
+pub trait ChannelSender<T> {
+    /// Send `value`, blocking if the transport is bounded and currently full. On failure the value
+    /// is handed back inside the error.
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>>;
+
+    /// Attempt to send `value` without blocking. The default implementation defers to `send`, so
+    /// only bounded transports need to override it.
+    fn try_send(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.send(value).map_err(|error| ::std::sync::mpsc::TrySendError::Disconnected(error.0))
+    }
+
+    /// Send `value` as `send` does, except that a bounded transport whose policy is to reject
+    /// values when full (see `OverflowPolicy::Error`) reports that as `TrySendError::Full` rather
+    /// than blocking. `EventSender::send` goes through this method. The default implementation
+    /// defers to `send`.
+    fn send_or_reject(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        self.send(value).map_err(|error| ::std::sync::mpsc::TrySendError::Disconnected(error.0))
+    }
+
+    /// The number of values sent but not yet received, if the transport keeps track of it. The
+    /// default implementation returns `None`.
+    fn pending(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl<T> ChannelSender<T> for ::std::sync::mpsc::Sender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        ::std::sync::mpsc::Sender::send(self, value)
+    }
+}
+
+impl<T> ChannelSender<T> for ::std::sync::mpsc::SyncSender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        ::std::sync::mpsc::SyncSender::send(self, value)
+    }
+
+    fn try_send(&self, value: T) -> Result<(), ::std::sync::mpsc::TrySendError<T>> {
+        ::std::sync::mpsc::SyncSender::try_send(self, value)
+    }
+}
+
+/// The receiving half of a transport, as used by `EventLoop` to pull events without blocking. It
+/// is implemented for `std::sync::mpsc::Receiver` and the receivers provided by this module.
+pub trait ChannelReceiver<T> {
+    /// Receive a value if one is waiting, without blocking.
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError>;
+}
+
+impl<T> ChannelReceiver<T> for ::std::sync::mpsc::Receiver<T> {
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        ::std::sync::mpsc::Receiver::try_recv(self)
+    }
+}
+
+/// Create a channel which keeps count of the values waiting in it, so that `ChannelSender::pending`
+/// (and hence `EventSender::pending`) can report the queue depth.
+pub fn instrumented_channel<T>() -> (InstrumentedSender<T>, InstrumentedReceiver<T>) {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    let pending = ::std::sync::Arc::new(::std::sync::atomic::AtomicUsize::new(0));
+    (InstrumentedSender {
+        tx,
+        pending: pending.clone(),
+    },
+     InstrumentedReceiver { rx, pending })
+}
+
+/// The sending half of an `instrumented_channel`.
+pub struct InstrumentedSender<T> {
+    tx     : ::std::sync::mpsc::Sender<T>,
+    pending: ::std::sync::Arc<::std::sync::atomic::AtomicUsize>,
+}
+
+impl<T> ChannelSender<T> for InstrumentedSender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        // Count before sending so that the receiver can never observe a negative depth.
+        let _ = self.pending.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+        self.tx.send(value).inspect_err(|_| {
+            let _ = self.pending.fetch_sub(1, ::std::sync::atomic::Ordering::SeqCst);
+        })
+    }
+
+    fn pending(&self) -> Option<usize> {
+        Some(self.pending.load(::std::sync::atomic::Ordering::SeqCst))
+    }
+}
+
+impl<T> Clone for InstrumentedSender<T> {
+    fn clone(&self) -> InstrumentedSender<T> {
+        InstrumentedSender {
+            tx     : self.tx.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+/// The receiving half of an `instrumented_channel`.
+pub struct InstrumentedReceiver<T> {
+    rx     : ::std::sync::mpsc::Receiver<T>,
+    pending: ::std::sync::Arc<::std::sync::atomic::AtomicUsize>,
+}
+
+impl<T> ChannelReceiver<T> for InstrumentedReceiver<T> {
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        InstrumentedReceiver::try_recv(self)
+    }
+}
+
+impl<T> InstrumentedReceiver<T> {
+    /// Block until a value is received. See `std::sync::mpsc::Receiver::recv`.
+    pub fn recv(&self) -> Result<T, ::std::sync::mpsc::RecvError> {
+        self.rx.recv().map(|value| self.received(value))
+    }
+
+    /// Receive a value if one is waiting. See `std::sync::mpsc::Receiver::try_recv`.
+    pub fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        self.rx.try_recv().map(|value| self.received(value))
+    }
+
+    /// Block for at most `timeout` waiting for a value. See
+    /// `std::sync::mpsc::Receiver::recv_timeout`.
+    pub fn recv_timeout(&self, timeout: ::std::time::Duration)
+                        -> Result<T, ::std::sync::mpsc::RecvTimeoutError> {
+        self.rx.recv_timeout(timeout).map(|value| self.received(value))
+    }
+
+    /// The number of values waiting to be received.
+    pub fn len(&self) -> usize {
+        self.pending.load(::std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Whether there are no values waiting to be received.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn received(&self, value: T) -> T {
+        let _ = self.pending.fetch_sub(1, ::std::sync::atomic::Ordering::SeqCst);
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event_sender::EventSender;
+
+    #[test]
+    fn sync_sender_transport() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::sync_channel(1);
+        let (category_tx, category_rx) = ::std::sync::mpsc::sync_channel(2);
+
+        let sender = EventSender::<&'static str, u32, _, _>::new(event_tx, "Network", category_tx);
+        assert!(sender.send(1).is_ok());
+        assert_eq!(unwrap_result!(category_rx.try_recv()), "Network");
+        assert_eq!(unwrap_result!(event_rx.try_recv()), 1);
+
+        let (event_tx, _event_rx) = ::std::sync::mpsc::sync_channel(1);
+        assert!(ChannelSender::try_send(&event_tx, 1).is_ok());
+        match ChannelSender::try_send(&event_tx, 2) {
+            Err(::std::sync::mpsc::TrySendError::Full(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn instrumented_channel_depth() {
+        let (event_tx, event_rx) = instrumented_channel();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Network", category_tx);
+
+        assert_eq!(sender.pending(), Some(0));
+        for event in 0..3 {
+            assert!(sender.send(event).is_ok());
+        }
+        assert_eq!(sender.pending(), Some(3));
+        assert_eq!(event_rx.len(), 3);
+
+        assert_eq!(unwrap_result!(event_rx.try_recv()), 0);
+        assert_eq!(sender.pending(), Some(2));
+
+        drop(event_rx);
+        assert!(sender.send(3).is_err());
+        assert_eq!(sender.pending(), Some(2));
+
+        let (std_tx, _std_rx) = ::std::sync::mpsc::channel::<u8>();
+        assert_eq!(ChannelSender::pending(&std_tx), None);
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{ChannelReceiver, ChannelSender};
+
+/// Create a channel whose values expire `ttl` after being sent. Expired values are silently
+/// discarded by the receiver (and counted), so that a consumer recovering from a stall does not
+/// waste time on notifications which are no longer meaningful. Values sent with a `ttl` too long
+/// to be represented as an `Instant` never expire.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::{EventSender, ttl_channel};
+/// use std::time::Duration;
+///
+/// let (event_tx, event_rx) = ttl_channel(Duration::from_millis(10));
+/// let (category_tx, category_rx) = std::sync::mpsc::channel();
+/// let sender = EventSender::new(event_tx, "Network", category_tx);
+///
+/// assert!(sender.send("PeerLost").is_ok());
+/// std::thread::sleep(Duration::from_millis(20));
+/// assert!(sender.send("PeerFound").is_ok());
+///
+/// for _ in category_rx.try_iter() {
+///     if let Ok(event) = event_rx.try_recv() {
+///         assert_eq!(event, "PeerFound");
+///     }
+/// }
+/// assert_eq!(event_rx.expired(), 1);
+/// # }
+/// ```
+pub fn ttl_channel<T>(ttl: ::std::time::Duration) -> (TtlSender<T>, TtlReceiver<T>) {
+    let (tx, rx) = ::std::sync::mpsc::channel();
+    (TtlSender { tx, ttl },
+     TtlReceiver {
+        rx,
+        expired: ::std::sync::atomic::AtomicUsize::new(0),
+    })
+}
+
+struct Expiring<T> {
+    // `None` if the TTL is too long to be represented, in which case the value never expires.
+    deadline: Option<::std::time::Instant>,
+    value   : T,
+}
+
+/// The sending half of a `ttl_channel`.
+pub struct TtlSender<T> {
+    tx : ::std::sync::mpsc::Sender<Expiring<T>>,
+    ttl: ::std::time::Duration,
+}
+
+impl<T> TtlSender<T> {
+    /// A sender feeding the same receiver but whose values expire after `ttl` instead.
+    pub fn with_ttl(&self, ttl: ::std::time::Duration) -> TtlSender<T> {
+        TtlSender {
+            tx: self.tx.clone(),
+            ttl,
+        }
+    }
+
+    /// The time-to-live given to values sent by this sender.
+    pub fn ttl(&self) -> ::std::time::Duration {
+        self.ttl
+    }
+}
+
+impl<T> ChannelSender<T> for TtlSender<T> {
+    fn send(&self, value: T) -> Result<(), ::std::sync::mpsc::SendError<T>> {
+        let expiring = Expiring {
+            deadline: ::std::time::Instant::now().checked_add(self.ttl),
+            value,
+        };
+        self.tx.send(expiring).map_err(|error| ::std::sync::mpsc::SendError((error.0).value))
+    }
+}
+
+impl<T> Clone for TtlSender<T> {
+    fn clone(&self) -> TtlSender<T> {
+        self.with_ttl(self.ttl)
+    }
+}
+
+/// The receiving half of a `ttl_channel`.
+pub struct TtlReceiver<T> {
+    rx     : ::std::sync::mpsc::Receiver<Expiring<T>>,
+    expired: ::std::sync::atomic::AtomicUsize,
+}
+
+impl<T> TtlReceiver<T> {
+    /// Block until an unexpired value is received. See `std::sync::mpsc::Receiver::recv`.
+    pub fn recv(&self) -> Result<T, ::std::sync::mpsc::RecvError> {
+        loop {
+            if let Some(value) = self.unexpired(self.rx.recv()?) {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Receive an unexpired value if one is waiting, discarding any expired ones ahead of it. See
+    /// `std::sync::mpsc::Receiver::try_recv`.
+    pub fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        loop {
+            if let Some(value) = self.unexpired(self.rx.try_recv()?) {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// The number of values discarded so far because they had expired.
+    pub fn expired(&self) -> usize {
+        self.expired.load(::std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn unexpired(&self, expiring: Expiring<T>) -> Option<T> {
+        if expiring.deadline.is_some_and(|deadline| deadline < ::std::time::Instant::now()) {
+            let _ = self.expired.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+            None
+        } else {
+            Some(expiring.value)
+        }
+    }
+}
+
+impl<T> ChannelReceiver<T> for TtlReceiver<T> {
+    fn try_recv(&self) -> Result<T, ::std::sync::mpsc::TryRecvError> {
+        TtlReceiver::try_recv(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn per_sender_ttl() {
+        let (short_tx, rx) = ttl_channel(::std::time::Duration::from_millis(10));
+        let long_tx = short_tx.with_ttl(::std::time::Duration::from_secs(60));
+        assert_eq!(long_tx.ttl(), ::std::time::Duration::from_secs(60));
+
+        for n in 0..3 {
+            assert!(short_tx.send(n).is_ok());
+            assert!(long_tx.send(n + 10).is_ok());
+        }
+        ::std::thread::sleep(::std::time::Duration::from_millis(20));
+        assert!(short_tx.send(3).is_ok());
+
+        assert_eq!(rx.try_recv(), Ok(10));
+        assert_eq!(rx.recv(), Ok(11));
+        assert_eq!(rx.expired(), 2);
+        assert_eq!(rx.try_recv(), Ok(12));
+        assert_eq!(rx.try_recv(), Ok(3));
+        assert_eq!(rx.try_recv(), Err(::std::sync::mpsc::TryRecvError::Empty));
+        assert_eq!(rx.expired(), 3);
+
+        drop(rx);
+        assert_eq!(short_tx.send(4), Err(::std::sync::mpsc::SendError(4)));
+    }
+
+    #[test]
+    fn unrepresentable_ttl_never_expires() {
+        let (tx, rx) = ttl_channel(::std::time::Duration::from_secs(u64::MAX));
+        assert!(tx.send(0).is_ok());
+        assert_eq!(rx.try_recv(), Ok(0));
+        assert_eq!(rx.expired(), 0);
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Replace the contents of `path` so that a crash at any point leaves either the old or the new
+/// contents on disk, never a partial write.
+pub fn write_atomically(path: &::std::path::Path, contents: &[u8]) -> ::std::io::Result<()> {
+    use std::io::Write;
+
+    let temp_path = with_suffix(path, ".tmp");
+
+    {
+        let mut file = ::std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    ::std::fs::rename(&temp_path, path)?;
+
+    // Make the rename itself durable.
+    #[cfg(unix)]
+    {
+        if let Some(parent) = path.parent() {
+            let parent = if parent.as_os_str().is_empty() {
+                ::std::path::Path::new(".")
+            } else {
+                parent
+            };
+            ::std::fs::File::open(parent)?.sync_all()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Append `suffix` to the file name of `path`, e.g. `data.bin` becomes `data.bin.tmp`.
+pub fn with_suffix(path: &::std::path::Path, suffix: &str) -> ::std::path::PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    ::std::path::PathBuf::from(path)
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    /// A path in the temp dir unique to this test process, with any file left at it removed.
+    pub fn temp_path(name: &str) -> ::std::path::PathBuf {
+        let path = ::std::env::temp_dir().join(format!("maidsafe_utilities_{}_{}",
+                                                       name,
+                                                       ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        path
+    }
+}
@@ -38,18 +38,34 @@
 #![allow(box_pointers, fat_ptr_transmutes, missing_copy_implementations,
          missing_debug_implementations)]
 
-extern crate env_logger;
+#[cfg(feature = "futures")]
+extern crate futures;
+#[cfg(windows)]
+extern crate kernel32;
+#[cfg(unix)]
+extern crate libc;
 #[macro_use]
 extern crate log as logger;
+extern crate maidsafe_utilities_derive;
+extern crate regex;
 extern crate time;
+#[cfg(windows)]
+extern crate winapi;
 
 #[macro_use]
 mod unwrap;
+mod file_utils;
 
 /// Utilities related to threading.
 #[macro_use]
 pub mod thread;
+mod timer;
+mod token_bucket;
 /// Utilities related to event-subsetting.
 pub mod event_sender;
-/// Allows initialising the env_logger with a standard message format.
+/// Allows initialising logging with per-module levels and a standard message format.
 pub mod log;
+/// Versioned on-disk data with ordered migration steps.
+pub mod migrations;
+/// Persistent, strictly increasing counter.
+pub mod persistent_counter;
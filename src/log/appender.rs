@@ -0,0 +1,189 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use std::io::Write;
+use super::Record;
+
+/// A destination for log records, such as stderr or a file. Appenders are added to the logger via
+/// `log::Builder::appender`.
+pub trait Appender: Send + Sync {
+    /// Write `record`, which the logger has already formatted as `line` (without a trailing
+    /// newline).
+    fn append(&self, record: &Record, line: &str) -> ::std::io::Result<()>;
+
+    /// Flush anything buffered. The default does nothing.
+    fn flush(&self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<A: Appender + ?Sized> Appender for Box<A> {
+    fn append(&self, record: &Record, line: &str) -> ::std::io::Result<()> {
+        (**self).append(record, line)
+    }
+
+    fn flush(&self) -> ::std::io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// When `StderrAppender` colours records by level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colour {
+    /// Colour records if stderr is a terminal and the `NO_COLOR` environment variable is unset
+    /// or empty.
+    #[default]
+    Auto,
+    /// Always colour records, e.g. when piping through a pager which understands colours.
+    Always,
+    /// Never colour records.
+    Never,
+}
+
+/// Writes each record to stderr. This is the logger's default appender.
+///
+/// A failed write, e.g. once whatever stderr was piped to has exited, is returned as an error
+/// rather than panicking as `eprintln!` would. As a consequence the test harness does not capture
+/// records, which appear alongside its own output.
+///
+/// Records can be coloured by level: errors red, warnings yellow, info green, debug cyan and
+/// trace grey. By default they are only coloured if stderr is a terminal and `NO_COLOR` is not
+/// set; see `Colour`.
+#[derive(Clone, Copy, Debug)]
+pub struct StderrAppender {
+    coloured: bool,
+}
+
+impl StderrAppender {
+    /// Write records to stderr, colouring them as for `Colour::Auto`.
+    pub fn new() -> StderrAppender {
+        StderrAppender::with_colour(Colour::Auto)
+    }
+
+    /// Write records to stderr, colouring them as `colour` says.
+    pub fn with_colour(colour: Colour) -> StderrAppender {
+        let is_terminal = ::std::io::IsTerminal::is_terminal(&::std::io::stderr());
+        StderrAppender { coloured: use_colour(colour, is_terminal, no_color()) }
+    }
+}
+
+impl Default for StderrAppender {
+    fn default() -> StderrAppender {
+        StderrAppender::new()
+    }
+}
+
+impl Appender for StderrAppender {
+    fn append(&self, record: &Record, line: &str) -> ::std::io::Result<()> {
+        write_line(&mut ::std::io::stderr().lock(), self.coloured, record.level, line)
+    }
+}
+
+/// Writes warnings and errors to stderr and other records to stdout, for service managers and
+/// container runtimes which treat the two streams differently, e.g. systemd's journal or a log
+/// collector alerting on stderr. As for `StderrAppender`, a failed write is returned as an error.
+///
+/// Records are coloured as for `StderrAppender`, each stream being checked separately for
+/// `Colour::Auto`.
+#[derive(Clone, Copy, Debug)]
+pub struct ConsoleAppender {
+    coloured_stdout: bool,
+    coloured_stderr: bool,
+}
+
+impl ConsoleAppender {
+    /// Split records between stdout and stderr, colouring them as for `Colour::Auto`.
+    pub fn new() -> ConsoleAppender {
+        ConsoleAppender::with_colour(Colour::Auto)
+    }
+
+    /// Split records between stdout and stderr, colouring them as `colour` says.
+    pub fn with_colour(colour: Colour) -> ConsoleAppender {
+        let stdout_is_terminal = ::std::io::IsTerminal::is_terminal(&::std::io::stdout());
+        ConsoleAppender {
+            coloured_stdout: use_colour(colour, stdout_is_terminal, no_color()),
+            coloured_stderr: StderrAppender::with_colour(colour).coloured,
+        }
+    }
+}
+
+impl Default for ConsoleAppender {
+    fn default() -> ConsoleAppender {
+        ConsoleAppender::new()
+    }
+}
+
+impl Appender for ConsoleAppender {
+    fn append(&self, record: &Record, line: &str) -> ::std::io::Result<()> {
+        if record.level <= LogLevel::Warn {
+            write_line(&mut ::std::io::stderr().lock(), self.coloured_stderr, record.level, line)
+        } else {
+            write_line(&mut ::std::io::stdout().lock(), self.coloured_stdout, record.level, line)
+        }
+    }
+}
+
+fn write_line(out: &mut dyn Write, coloured: bool, level: LogLevel, line: &str)
+              -> ::std::io::Result<()> {
+    if coloured {
+        writeln!(out, "\x1b[{}m{}\x1b[0m", colour_code(level), line)
+    } else {
+        writeln!(out, "{}", line)
+    }
+}
+
+fn no_color() -> bool {
+    ::std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+fn use_colour(colour: Colour, is_terminal: bool, no_color: bool) -> bool {
+    match colour {
+        Colour::Auto => is_terminal && !no_color,
+        Colour::Always => true,
+        Colour::Never => false,
+    }
+}
+
+// The ANSI SGR parameter for records at `level`.
+fn colour_code(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Error => "31",
+        LogLevel::Warn => "33",
+        LogLevel::Info => "32",
+        LogLevel::Debug => "36",
+        LogLevel::Trace => "90",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn colour_detection() {
+        assert!(use_colour(Colour::Auto, true, false));
+        assert!(!use_colour(Colour::Auto, false, false));
+        assert!(!use_colour(Colour::Auto, true, true));
+        assert!(use_colour(Colour::Always, false, true));
+        assert!(!use_colour(Colour::Never, true, false));
+        assert!(!StderrAppender::with_colour(Colour::Never).coloured);
+        assert!(StderrAppender::with_colour(Colour::Always).coloured);
+        let console = ConsoleAppender::with_colour(Colour::Never);
+        assert!(!console.coloured_stdout && !console.coloured_stderr);
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use event_sender::{BoundedReceiver, BoundedSender, ChannelSender, OverflowPolicy, bounded_channel};
+use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::ThreadId;
+use super::{Appender, Record};
+use thread::{Builder, RaiiThreadJoiner};
+
+/// Hands records to a dedicated `"LogWriter"` thread which writes them to another appender, so
+/// that logging never blocks on slow disk or console I/O.
+///
+/// At most `capacity` records wait for the writer; once that many are queued, further records
+/// are handled according to the overflow policy. `OverflowPolicy::Block` makes the logging thread
+/// wait, `Error` reports the record as not written, and `DropNewest` and `DropOldest` discard
+/// records (counted by `dropped`). Call `log::flush` before exiting to make sure every queued
+/// record has been written.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::event_sender::OverflowPolicy;
+/// use maidsafe_utilities::log::{self, AsyncAppender, Builder, FileAppender};
+///
+/// let path = std::env::temp_dir().join("async.log");
+/// let file = unwrap_result!(FileAppender::new(&path, 1024 * 1024, 1));
+/// let appender = unwrap_result!(AsyncAppender::new(file, 10_000, OverflowPolicy::DropNewest));
+/// unwrap_result!(Builder::new().appender(appender).init());
+/// // ...
+/// log::flush();
+/// # }
+/// ```
+pub struct AsyncAppender {
+    record_tx  : Option<BoundedSender<(Record, String)>>,
+    inner      : Arc<dyn Appender>,
+    // Records accepted onto the queue so far.
+    sent       : AtomicU64,
+    progress   : Arc<Progress>,
+    writer_id  : ThreadId,
+    raii_joiner: Option<RaiiThreadJoiner>,
+}
+
+// Records taken off the queue by the writer so far.
+#[derive(Default)]
+struct Progress {
+    written  : Mutex<u64>,
+    condition: Condvar,
+}
+
+impl AsyncAppender {
+    /// Spawn a writer thread which writes to `inner`, queueing up to `capacity` records for it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new<A: Appender + 'static>(inner: A,
+                                      capacity: usize,
+                                      policy: OverflowPolicy)
+                                      -> io::Result<AsyncAppender> {
+        let (record_tx, record_rx) = bounded_channel(capacity, policy);
+        let inner: Arc<dyn Appender> = Arc::new(inner);
+        let progress = Arc::new(Progress::default());
+        let inner_clone = inner.clone();
+        let progress_clone = progress.clone();
+        let raii_joiner = Builder::new("LogWriter")
+                              .inherit_name(false)
+                              .spawn(move || {
+                                  write_all(&record_rx, &*inner_clone, &progress_clone)
+                              })?;
+        Ok(AsyncAppender {
+            record_tx: Some(record_tx),
+            inner,
+            sent: AtomicU64::new(0),
+            progress,
+            writer_id: raii_joiner.thread().id(),
+            raii_joiner: Some(raii_joiner),
+        })
+    }
+
+    /// The number of records discarded so far because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.record_tx.as_ref().map_or(0, BoundedSender::dropped)
+    }
+
+    fn record_tx(&self) -> &BoundedSender<(Record, String)> {
+        unwrap_option!(self.record_tx.as_ref(),
+                       "Programming error: please report this as a bug.")
+    }
+}
+
+impl Appender for AsyncAppender {
+    fn append(&self, record: &Record, line: &str) -> io::Result<()> {
+        // The writer cannot queue records for itself without risking waiting on itself.
+        if ::std::thread::current().id() == self.writer_id {
+            return self.inner.append(record, line);
+        }
+        match self.record_tx().send_or_reject((record.clone(), line.to_owned())) {
+            Ok(()) => {
+                let _ = self.sent.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            Err(::std::sync::mpsc::TrySendError::Full(_)) => {
+                Err(io::Error::new(io::ErrorKind::WouldBlock, "Log queue is full"))
+            }
+            Err(::std::sync::mpsc::TrySendError::Disconnected(_)) => {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "Log writer thread has stopped"))
+            }
+        }
+    }
+
+    /// Wait for the writer to write every record queued so far, then flush the inner appender.
+    fn flush(&self) -> io::Result<()> {
+        let target = self.sent.load(Ordering::SeqCst);
+        let mut written = unwrap_result!(self.progress.written.lock());
+        while *written + (self.dropped() as u64) < target {
+            if self.raii_joiner.as_ref().is_none_or(RaiiThreadJoiner::is_finished) {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe,
+                                          "Log writer thread has stopped"));
+            }
+            written = unwrap_result!(self.progress
+                                         .condition
+                                         .wait_timeout(written,
+                                                       ::std::time::Duration::from_millis(100)))
+                          .0;
+        }
+        drop(written);
+        self.inner.flush()
+    }
+}
+
+impl Drop for AsyncAppender {
+    fn drop(&mut self) {
+        // Closing the channel lets the writer drain it and exit, then the joiner joins it.
+        self.record_tx = None;
+        self.raii_joiner = None;
+    }
+}
+
+fn write_all(record_rx: &BoundedReceiver<(Record, String)>,
+             inner: &dyn Appender,
+             progress: &Progress) {
+    while let Ok((record, line)) = record_rx.recv() {
+        if let Err(error) = inner.append(&record, &line) {
+            eprintln!("Failed to write log record: {}", error);
+        }
+        *unwrap_result!(progress.written.lock()) += 1;
+        progress.condition.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Records each line, taking a while over it.
+    struct SlowAppender(Arc<Mutex<Vec<String>>>);
+
+    impl Appender for SlowAppender {
+        fn append(&self, _record: &Record, line: &str) -> io::Result<()> {
+            ::std::thread::sleep(::std::time::Duration::from_millis(1));
+            unwrap_result!(self.0.lock()).push(line.to_owned());
+            Ok(())
+        }
+    }
+
+
+    #[test]
+    fn flush_waits_for_queued_records() {
+        let record = Record::for_test(::logger::LogLevel::Info, "");
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let appender = unwrap_result!(AsyncAppender::new(SlowAppender(lines.clone()),
+                                                         100,
+                                                         OverflowPolicy::Block));
+        for index in 0..20 {
+            unwrap_result!(appender.append(&record, &index.to_string()));
+        }
+        unwrap_result!(appender.flush());
+        let expected = (0..20).map(|index: u32| index.to_string()).collect::<Vec<_>>();
+        assert_eq!(*unwrap_result!(lines.lock()), expected);
+    }
+
+    #[test]
+    fn overflow_drops_rather_than_blocking() {
+        let record = Record::for_test(::logger::LogLevel::Info, "");
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let appender = unwrap_result!(AsyncAppender::new(SlowAppender(lines.clone()),
+                                                         2,
+                                                         OverflowPolicy::DropNewest));
+        for index in 0..50 {
+            unwrap_result!(appender.append(&record, &index.to_string()));
+        }
+        unwrap_result!(appender.flush());
+        assert!(appender.dropped() > 0);
+        assert_eq!(unwrap_result!(lines.lock()).len() + appender.dropped(), 50);
+        drop(appender);
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::SetLoggerError;
+use super::{Appender, Colour, ConfigError, ConsoleAppender, DEFAULT_ENV_VAR, Filter, Formatter,
+            StderrAppender, TextFormatter, TimeFormat};
+use super::logger::Logger;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Error initialising the logger.
+#[derive(Debug)]
+pub enum LogInitError {
+    /// A logger other than this module's has already been installed for the `log` crate, e.g. by
+    /// `env_logger`, so records would not reach this one.
+    OtherLogger(SetLoggerError),
+    /// The configuration file given to `init_logging_from_file` could not be read or watched, or
+    /// is invalid.
+    Config(ConfigError),
+}
+
+impl ::std::fmt::Display for LogInitError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            LogInitError::OtherLogger(ref error) => {
+                write!(formatter, "Another logger is installed: {}", error)
+            }
+            LogInitError::Config(ref error) => write!(formatter, "{}", error),
+        }
+    }
+}
+
+impl ::std::error::Error for LogInitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            LogInitError::OtherLogger(ref error) => Some(error),
+            LogInitError::Config(ref error) => Some(error),
+        }
+    }
+}
+
+impl From<SetLoggerError> for LogInitError {
+    fn from(error: SetLoggerError) -> LogInitError {
+        LogInitError::OtherLogger(error)
+    }
+}
+
+impl From<ConfigError> for LogInitError {
+    fn from(error: ConfigError) -> LogInitError {
+        LogInitError::Config(error)
+    }
+}
+
+/// Configuration for the logger, for when the defaults used by `init_logging` are not enough.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, FileAppender, Filter, StderrAppender};
+///
+/// let path = std::env::temp_dir().join("node.log");
+/// let builder = Builder::new()
+///     .filter(Filter::parse("info,routing=debug"))
+///     .appender(StderrAppender::new())
+///     .appender(unwrap_result!(FileAppender::new(&path, 1024 * 1024, 3)));
+/// unwrap_result!(builder.init());
+/// # }
+/// ```
+pub struct Builder {
+    filter          : Option<Filter>,
+    show_thread_name: bool,
+    time_format     : TimeFormat,
+    colour          : Colour,
+    split_console   : bool,
+    formatter       : Option<Box<dyn Formatter>>,
+    appenders       : Vec<Box<dyn Appender>>,
+    repeat_window   : Option<Duration>,
+    log_panics      : bool,
+    crash_buffer    : Option<(usize, PathBuf)>,
+}
+
+impl Builder {
+    /// Start configuring the logger. Unless overridden, the filter is read from `RUST_LOG`,
+    /// records are formatted by a `TextFormatter` showing thread names and are written to stderr.
+    pub fn new() -> Builder {
+        Builder {
+            filter          : None,
+            show_thread_name: true,
+            time_format     : TimeFormat::new(),
+            colour          : Colour::Auto,
+            split_console   : false,
+            formatter       : None,
+            appenders       : Vec::new(),
+            repeat_window   : None,
+            log_panics      : false,
+            crash_buffer    : None,
+        }
+    }
+
+    /// Log the records `filter` enables, rather than those enabled by `RUST_LOG`.
+    pub fn filter(mut self, filter: Filter) -> Builder {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Whether to include the logging thread's name in each record. This has no effect if a
+    /// `formatter` is set.
+    pub fn show_thread_name(mut self, show_thread_name: bool) -> Builder {
+        self.show_thread_name = show_thread_name;
+        self
+    }
+
+    /// How to show the time of each record, e.g. in UTC to line up logs from nodes in different
+    /// timezones. This has no effect if a `formatter` is set; give a `TextFormatter` or
+    /// `PatternFormatter` its own via `with_time_format`.
+    pub fn time_format(mut self, time_format: TimeFormat) -> Builder {
+        self.time_format = time_format;
+        self
+    }
+
+    /// When the default stderr appender, used if no appenders are added, colours records. An
+    /// added `StderrAppender` is instead coloured as given to `StderrAppender::with_colour`.
+    pub fn colour(mut self, colour: Colour) -> Builder {
+        self.colour = colour;
+        self
+    }
+
+    /// Format records with `formatter`, e.g. a `JsonFormatter`, a `PatternFormatter` or a closure,
+    /// rather than a `TextFormatter`.
+    pub fn formatter<F: Formatter + 'static>(mut self, formatter: F) -> Builder {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Whether the default appender, used if no appenders are added, is a `ConsoleAppender`
+    /// writing warnings and errors to stderr and other records to stdout, rather than a
+    /// `StderrAppender`. It is coloured as for `colour`.
+    pub fn split_console(mut self, split_console: bool) -> Builder {
+        self.split_console = split_console;
+        self
+    }
+
+    /// Write records to `appender`, in addition to any others added. If none are added, records
+    /// are written to stderr. Wrap an appender in a `FilteredAppender` to give it its own level
+    /// or format.
+    pub fn appender<A: Appender + 'static>(mut self, appender: A) -> Builder {
+        self.appenders.push(Box::new(appender));
+        self
+    }
+
+    /// Collapse bursts of identical records, e.g. from a flapping connection: a record with the
+    /// same level, target and message as the last one written, arriving within `window` of it, is
+    /// held back. The next different record, or the first identical one after `window`, is
+    /// preceded by a single "Last message repeated N times" record. `log::flush` also writes any
+    /// such summary due.
+    pub fn collapse_repeats(mut self, window: Duration) -> Builder {
+        self.repeat_window = Some(window);
+        self
+    }
+
+    /// Whether to also log every panic in the process, with the thread's name and a backtrace,
+    /// as for `thread::log_all_panics`. Off by default.
+    pub fn log_panics(mut self, log_panics: bool) -> Builder {
+        self.log_panics = log_panics;
+        self
+    }
+
+    /// Keep the last `capacity` records in memory, at every level whatever the filter, and append
+    /// them to the file at `path` when a panic escapes a thread spawned via `thread_logged!` or
+    /// `thread::Builder::log_panics`, on any panic if panics abort, or when
+    /// `log::dump_crash_buffer` is called, e.g. on a fatal error, giving context for a crash
+    /// without writing trace records all the time. Panics which are caught don't dump it. The
+    /// records are only formatted when dumped. As the panic itself is logged first, this implies
+    /// `log_panics(true)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics on `init` if `capacity` is zero.
+    pub fn crash_buffer<P: AsRef<Path>>(mut self, capacity: usize, path: P) -> Builder {
+        self.crash_buffer = Some((capacity, path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Install the logger. As for `init_logging`, only the first logger initialised in the
+    /// process takes effect: if one already has been via this module, this does nothing and
+    /// returns `Ok`.
+    pub fn init(self) -> Result<(), LogInitError> {
+        install(self).map(|_| ())
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder::new()
+    }
+}
+
+// Install the logger `builder` describes, returning whether this call did so.
+pub fn install(builder: Builder) -> Result<bool, LogInitError> {
+    let log_panics = builder.log_panics || builder.crash_buffer.is_some();
+    let installed = super::install(build(builder))?;
+    if installed && log_panics {
+        ::thread::log_all_panics();
+    }
+    Ok(installed)
+}
+
+// The logger `builder` describes, with the defaults filled in.
+pub fn build(mut builder: Builder) -> Logger {
+    if builder.appenders.is_empty() {
+        if builder.split_console {
+            builder.appenders.push(Box::new(ConsoleAppender::with_colour(builder.colour)));
+        } else {
+            builder.appenders.push(Box::new(StderrAppender::with_colour(builder.colour)));
+        }
+    }
+    let filter = builder.filter.unwrap_or_else(|| Filter::from_env(DEFAULT_ENV_VAR));
+    let (show_thread_name, time_format) = (builder.show_thread_name, builder.time_format);
+    let formatter = builder.formatter.unwrap_or_else(|| {
+        Box::new(TextFormatter::new(show_thread_name).with_time_format(time_format))
+    });
+    let mut logger = Logger::new(filter, formatter, builder.appenders);
+    if let Some(window) = builder.repeat_window {
+        logger.collapse_repeats(window);
+    }
+    if let Some((capacity, path)) = builder.crash_buffer {
+        logger.crash_buffer(capacity, path);
+    }
+    logger
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn repeated_init_is_ok() {
+        unwrap_result!(::log::init_logging());
+        unwrap_result!(::log::init_logging());
+        unwrap_result!(Builder::new().show_thread_name(false).init());
+        assert!(::log::LOGGER.get().is_some());
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::Record;
+
+// The number of `Capture`s alive on any thread, while which every level must reach the logger.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static BUFFERS: RefCell<Vec<Rc<RefCell<Vec<Record>>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A guard buffering the records logged on the current thread until it is dropped, so that tests
+/// can check that error paths log what they should. Returned by `log::capture`.
+///
+/// #Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate log;
+/// extern crate maidsafe_utilities;
+///
+/// use log::LogLevel;
+///
+/// fn connect(address: &str) -> Result<(), ()> {
+///     warn!("Failed to connect to {}", address);
+///     Err(())
+/// }
+///
+/// fn main() {
+///     let capture = maidsafe_utilities::log::capture();
+///     assert!(connect("10.0.0.1:5483").is_err());
+///     capture.assert_logged(LogLevel::Warn, "Failed to connect to 10.0.0.1");
+///     capture.assert_not_logged(LogLevel::Error, "");
+/// }
+/// ```
+pub struct Capture {
+    buffer: Rc<RefCell<Vec<Record>>>,
+}
+
+/// Start capturing the records logged on the current thread, at every level whatever the
+/// logger's filter, for as long as the returned guard lives. Records the filter enables are still
+/// written to the appenders as usual. Records logged on other threads, including those spawned by
+/// the test, are not captured.
+///
+/// If no logger has been initialised yet, this initialises one as `init_logging` does.
+///
+/// # Panics
+///
+/// Panics if a logger not initialised via this module is installed, as records would not reach
+/// the capture.
+pub fn capture() -> Capture {
+    let _ = super::init_logging();
+    let logger = unwrap_option!(super::LOGGER.get(),
+                                "log::capture needs the logger to be initialised via \
+                                 maidsafe_utilities::log");
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    BUFFERS.with(|buffers| buffers.borrow_mut().push(buffer.clone()));
+    let _ = ACTIVE.fetch_add(1, Ordering::SeqCst);
+    logger.refresh_max_level();
+    Capture { buffer }
+}
+
+impl Capture {
+    /// The records captured so far, oldest first.
+    pub fn records(&self) -> Vec<Record> {
+        self.buffer.borrow().clone()
+    }
+
+    /// Whether a record at `level` whose message contains `substring` has been captured.
+    pub fn logged(&self, level: LogLevel, substring: &str) -> bool {
+        self.buffer
+            .borrow()
+            .iter()
+            .any(|record| record.level == level && record.message.contains(substring))
+    }
+
+    /// Panic, listing the captured records, unless a record at `level` whose message contains
+    /// `substring` has been captured.
+    pub fn assert_logged(&self, level: LogLevel, substring: &str) {
+        if !self.logged(level, substring) {
+            panic!("No {} record containing {:?} was logged; captured:\n{}",
+                   level,
+                   substring,
+                   self.listing());
+        }
+    }
+
+    /// Panic, listing the captured records, if a record at `level` whose message contains
+    /// `substring` has been captured. An empty `substring` matches every record at `level`.
+    pub fn assert_not_logged(&self, level: LogLevel, substring: &str) {
+        if self.logged(level, substring) {
+            panic!("A {} record containing {:?} was logged; captured:\n{}",
+                   level,
+                   substring,
+                   self.listing());
+        }
+    }
+
+    fn listing(&self) -> String {
+        let lines = self.buffer
+                        .borrow()
+                        .iter()
+                        .map(|record| format!("  {} [{}] {}", record.level, record.target,
+                                              record.message))
+                        .collect::<Vec<_>>();
+        if lines.is_empty() {
+            "  (nothing)".to_owned()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        BUFFERS.with(|buffers| {
+            buffers.borrow_mut().retain(|buffer| !Rc::ptr_eq(buffer, &self.buffer))
+        });
+        let _ = ACTIVE.fetch_sub(1, Ordering::SeqCst);
+        if let Some(logger) = super::LOGGER.get() {
+            logger.refresh_max_level();
+        }
+    }
+}
+
+// Whether any thread is capturing, in which case every level must reach the logger.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::SeqCst) > 0
+}
+
+// Whether the current thread is capturing.
+pub fn is_capturing() -> bool {
+    is_active() && BUFFERS.with(|buffers| !buffers.borrow().is_empty())
+}
+
+// Add `record` to each of the current thread's captures.
+pub fn record(record: &Record) {
+    BUFFERS.with(|buffers| {
+        for buffer in buffers.borrow().iter() {
+            buffer.borrow_mut().push(record.clone());
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn captures_this_threads_records() {
+        let capture = capture();
+        trace!("Below any default filter");
+        warn!("Warned about {}", 42);
+        unwrap_result!(thread!("Elsewhere", || warn!("From another thread")).join());
+        {
+            let inner = super::capture();
+            error!("Nested");
+            inner.assert_logged(LogLevel::Error, "Nested");
+            inner.assert_not_logged(LogLevel::Warn, "");
+        }
+        capture.assert_logged(LogLevel::Trace, "Below any");
+        capture.assert_logged(LogLevel::Warn, "about 42");
+        capture.assert_logged(LogLevel::Error, "Nested");
+        capture.assert_not_logged(LogLevel::Warn, "another thread");
+        assert_eq!(capture.records().len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "No WARN record containing \"Missing\" was logged")]
+    fn assert_logged_fails() {
+        let capture = capture();
+        info!("Present");
+        capture.assert_logged(LogLevel::Warn, "Missing");
+    }
+}
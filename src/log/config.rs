@@ -0,0 +1,620 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevelFilter;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use super::{Appender, Builder, Colour, ConsoleAppender, FileAppender, Filter, FilteredAppender,
+            Formatter, JsonFormatter, PatternFormatter, StderrAppender, TcpAppender, TextFormatter,
+            TimeFormat, TimePrecision, TimeZone, UdpAppender};
+use super::logger::Logger;
+use thread::{RaiiThreadJoiner, named_cancellable};
+
+// How often `init_logging_from_file` checks its file for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+// The keys each part of the file may hold. `[levels]` may hold any key.
+const ROOT_KEYS: &[&str] = &["level", "show_thread_name", "format", "pattern", "time_zone",
+                             "time_precision", "iso8601", "log_panics", "collapse_repeats_ms",
+                             "colour", "split_console"];
+const CRASH_BUFFER_KEYS: &[&str] = &["capacity", "path"];
+// Those of every appender, followed by those of each kind.
+const APPENDER_KEYS: &[&str] = &["kind", "level", "format", "pattern", "show_thread_name",
+                                 "include", "exclude"];
+const APPENDER_KIND_KEYS: &[(&str, &[&str])] =
+    &[("stderr", &["colour"]),
+      ("console", &["colour"]),
+      ("file", &["path", "max_size", "keep", "gzip", "max_total_size"]),
+      ("tcp", &["address"]),
+      ("udp", &["address"])];
+
+/// Errors from reading a log configuration file.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Error reading the file, or opening a file appender it configures.
+    Io(io::Error),
+    /// The file is not in the supported subset of TOML.
+    Syntax {
+        /// The one-based number of the offending line.
+        line: usize,
+        /// What is wrong with it.
+        reason: String,
+    },
+    /// The file is valid TOML but not a valid configuration, e.g. an unknown level.
+    Invalid(String),
+}
+
+impl ::std::fmt::Display for ConfigError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            ConfigError::Io(ref error) => write!(formatter, "I/O error: {}", error),
+            ConfigError::Syntax { line, ref reason } => {
+                write!(formatter, "Log configuration syntax error on line {}: {}", line, reason)
+            }
+            ConfigError::Invalid(ref reason) => {
+                write!(formatter, "Invalid log configuration: {}", reason)
+            }
+        }
+    }
+}
+
+impl ::std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            ConfigError::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> ConfigError {
+        ConfigError::Io(error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Integer(u64),
+    Boolean(bool),
+}
+
+type Table = BTreeMap<String, Value>;
+
+// A parsed file: the top-level keys, the `[name]` tables and the `[[name]]` arrays of tables.
+#[derive(Debug, Default)]
+struct Document {
+    root  : Table,
+    tables: BTreeMap<String, Table>,
+    arrays: BTreeMap<String, Vec<Table>>,
+}
+
+// The logger's settings, as read from a file.
+pub struct Config {
+    document: Document,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        Config::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let config = Config { document: parse_document(text)? };
+        // Reject bad levels and misspelt keys now rather than on reloading.
+        let _ = config.filter()?;
+        config.check_keys()?;
+        Ok(config)
+    }
+
+    fn check_keys(&self) -> Result<(), ConfigError> {
+        let document = &self.document;
+        check_keys(&document.root, ROOT_KEYS, "")?;
+        for name in document.tables.keys() {
+            match &name[..] {
+                "levels" => (),
+                "crash_buffer" => {
+                    check_keys(&document.tables[name], CRASH_BUFFER_KEYS, " in [crash_buffer]")?
+                }
+                _ => return Err(invalid(format!("Unknown table [{}]", name))),
+            }
+        }
+        for (name, tables) in &document.arrays {
+            if name != "appenders" {
+                return Err(invalid(format!("Unknown array of tables [[{}]]", name)));
+            }
+            for appender in tables {
+                let kind = string(appender, "kind")?
+                               .ok_or_else(|| invalid("Appender without a kind".to_owned()))?;
+                let kind_keys = match APPENDER_KIND_KEYS.iter().find(|&&(known, _)| known == kind) {
+                    Some(&(_, keys)) => keys,
+                    None => return Err(invalid(format!("Unknown appender kind \"{}\"", kind))),
+                };
+                let keys = APPENDER_KEYS.iter().chain(kind_keys).cloned().collect::<Vec<_>>();
+                check_keys(appender, &keys, &format!(" for a \"{}\" appender", kind))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn filter(&self) -> Result<Filter, ConfigError> {
+        let mut directives = Vec::new();
+        if let Some(level) = string(&self.document.root, "level")? {
+            directives.push(parse_level(&level)?.to_string());
+        }
+        if let Some(levels) = self.document.tables.get("levels") {
+            for (module, level) in levels {
+                match *level {
+                    Value::String(ref level) => {
+                        directives.push(format!("{}={}", module, parse_level(level)?))
+                    }
+                    _ => return Err(invalid(format!("Level for \"{}\" must be a string", module))),
+                }
+            }
+        }
+        Ok(Filter::parse(&directives.join(",")))
+    }
+
+    pub fn builder(&self) -> Result<Builder, ConfigError> {
+        let root = &self.document.root;
+        let mut builder = Builder::new().filter(self.filter()?);
+        if let Some(show_thread_name) = boolean(root, "show_thread_name")? {
+            builder = builder.show_thread_name(show_thread_name);
+        }
+        builder = builder.colour(colour(root)?);
+        if let Some(split_console) = boolean(root, "split_console")? {
+            builder = builder.split_console(split_console);
+        }
+        let time_format = time_format(root)?;
+        builder = builder.time_format(time_format);
+        if let Some(log_panics) = boolean(root, "log_panics")? {
+            builder = builder.log_panics(log_panics);
+        }
+        if let Some(window) = integer(root, "collapse_repeats_ms")? {
+            builder = builder.collapse_repeats(Duration::from_millis(window));
+        }
+        if let Some(crash_buffer) = self.document.tables.get("crash_buffer") {
+            let capacity = integer(crash_buffer, "capacity")?.unwrap_or(10_000);
+            if capacity == 0 {
+                return Err(invalid("A crash buffer needs a capacity of at least one".to_owned()));
+            }
+            builder = builder.crash_buffer(capacity as usize,
+                                           required_string(crash_buffer, "path")?);
+        }
+        if let Some(formatter) = formatter(root, time_format)? {
+            builder = builder.formatter(formatter);
+        }
+        for appender in self.document.arrays.get("appenders").into_iter().flatten() {
+            let kind = string(appender, "kind")?
+                           .ok_or_else(|| invalid("Appender without a kind".to_owned()))?;
+            let built: Box<dyn Appender> = match &kind[..] {
+                "stderr" => Box::new(StderrAppender::with_colour(colour(appender)?)),
+                "console" => Box::new(ConsoleAppender::with_colour(colour(appender)?)),
+                "file" => {
+                    let path = required_string(appender, "path")?;
+                    let max_size = integer(appender, "max_size")?.unwrap_or(10 << 20);
+                    let keep = integer(appender, "keep")?.unwrap_or(5) as usize;
+                    let mut file = FileAppender::new(path, max_size, keep)?;
+                    if boolean(appender, "gzip")? == Some(true) {
+                        file = file.with_gzip();
+                    }
+                    if let Some(max_total_size) = integer(appender, "max_total_size")? {
+                        file = file.with_max_total_size(max_total_size);
+                    }
+                    Box::new(file)
+                }
+                "tcp" => Box::new(TcpAppender::new(&required_string(appender, "address")?[..])?),
+                "udp" => Box::new(UdpAppender::new(&required_string(appender, "address")?[..])?),
+                _ => return Err(invalid(format!("Unknown appender kind \"{}\"", kind))),
+            };
+            let level = string(appender, "level")?.map(|level| parse_level(&level)).transpose()?;
+            let formatter = formatter(appender, time_format)?;
+            let include = string(appender, "include")?;
+            let exclude = string(appender, "exclude")?;
+            if level.is_none() && formatter.is_none() && include.is_none() && exclude.is_none() {
+                builder = builder.appender(built);
+                continue;
+            }
+            let mut filtered = FilteredAppender::new(built);
+            if let Some(level) = level {
+                filtered = filtered.level(level);
+            }
+            if let Some(formatter) = formatter {
+                filtered = filtered.formatter(formatter);
+            }
+            if let Some(include) = include {
+                filtered = filtered.include(&include).map_err(bad_regex)?;
+            }
+            if let Some(exclude) = exclude {
+                filtered = filtered.exclude(&exclude).map_err(bad_regex)?;
+            }
+            builder = builder.appender(filtered);
+        }
+        Ok(builder)
+    }
+}
+
+// Reapply the file's levels to `logger` each time its contents change, checking every
+// `interval`. A file which fails to load is reported on stderr and otherwise ignored.
+pub fn watch(path: PathBuf,
+             logger: Arc<Logger>,
+             interval: Duration)
+             -> io::Result<RaiiThreadJoiner> {
+    let mut last = fs::read_to_string(&path).ok();
+    named_cancellable("LogConfigWatcher", move |token| {
+        while !token.wait_or_cancelled(interval) {
+            let text = fs::read_to_string(&path).ok();
+            if text == last {
+                continue;
+            }
+            match text.as_ref().map(|text| Config::parse(text)) {
+                Some(Ok(config)) => logger.set_filter(unwrap_result!(config.filter())),
+                Some(Err(error)) => eprintln!("Not reloading {}: {}", path.display(), error),
+                None => eprintln!("Not reloading {}: it could not be read", path.display()),
+            }
+            last = text;
+        }
+    })
+}
+
+// Reject any key of `table` not in `keys`, saying where it was found with `context`.
+fn check_keys(table: &Table, keys: &[&str], context: &str) -> Result<(), ConfigError> {
+    match table.keys().find(|key| !keys.contains(&&key[..])) {
+        Some(key) => Err(invalid(format!("Unknown key \"{}\"{}", key, context))),
+        None => Ok(()),
+    }
+}
+
+fn string(table: &Table, key: &str) -> Result<Option<String>, ConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(invalid(format!("\"{}\" must be a string", key))),
+    }
+}
+
+fn required_string(table: &Table, key: &str) -> Result<String, ConfigError> {
+    string(table, key)?.ok_or_else(|| invalid(format!("\"{}\" is required", key)))
+}
+
+fn integer(table: &Table, key: &str) -> Result<Option<u64>, ConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(&Value::Integer(value)) => Ok(Some(value)),
+        Some(_) => Err(invalid(format!("\"{}\" must be an integer", key))),
+    }
+}
+
+fn boolean(table: &Table, key: &str) -> Result<Option<bool>, ConfigError> {
+    match table.get(key) {
+        None => Ok(None),
+        Some(&Value::Boolean(value)) => Ok(Some(value)),
+        Some(_) => Err(invalid(format!("\"{}\" must be true or false", key))),
+    }
+}
+
+fn colour(table: &Table) -> Result<Colour, ConfigError> {
+    match string(table, "colour")?.as_ref().map(|colour| &colour[..]) {
+        None | Some("auto") => Ok(Colour::Auto),
+        Some("always") => Ok(Colour::Always),
+        Some("never") => Ok(Colour::Never),
+        Some(colour) => Err(invalid(format!("Unknown colour setting \"{}\"", colour))),
+    }
+}
+
+// The formatter chosen by the `format` and `pattern` keys of `table`, if either is present.
+fn formatter(table: &Table,
+             time_format: TimeFormat)
+             -> Result<Option<Box<dyn Formatter>>, ConfigError> {
+    match (string(table, "format")?.as_ref().map(|format| &format[..]),
+           string(table, "pattern")?) {
+        (None, None) => Ok(None),
+        (Some("text"), None) => {
+            let show_thread_name = boolean(table, "show_thread_name")?.unwrap_or(true);
+            let formatter = TextFormatter::new(show_thread_name).with_time_format(time_format);
+            Ok(Some(Box::new(formatter)))
+        }
+        (Some("json"), None) => Ok(Some(Box::new(JsonFormatter::new()))),
+        (None, Some(pattern)) | (Some("pattern"), Some(pattern)) => {
+            let formatter = PatternFormatter::new(&pattern)
+                                .map_err(|error| invalid(error.to_string()))?;
+            Ok(Some(Box::new(formatter.with_time_format(time_format))))
+        }
+        (Some(format), _) => Err(invalid(format!("Unknown format \"{}\"", format))),
+    }
+}
+
+fn time_format(table: &Table) -> Result<TimeFormat, ConfigError> {
+    let mut time_format = TimeFormat::new();
+    match string(table, "time_zone")?.as_ref().map(|zone| &zone[..]) {
+        None | Some("local") => (),
+        Some("utc") => time_format = time_format.zone(TimeZone::Utc),
+        Some(zone) => return Err(invalid(format!("Unknown time zone \"{}\"", zone))),
+    }
+    match string(table, "time_precision")?.as_ref().map(|precision| &precision[..]) {
+        None | Some("us") => (),
+        Some("ms") => time_format = time_format.precision(TimePrecision::Millis),
+        Some("s") => time_format = time_format.precision(TimePrecision::Seconds),
+        Some(precision) => {
+            return Err(invalid(format!("Unknown time precision \"{}\"", precision)))
+        }
+    }
+    Ok(time_format.iso8601(boolean(table, "iso8601")?.unwrap_or(false)))
+}
+
+fn invalid(reason: String) -> ConfigError {
+    ConfigError::Invalid(reason)
+}
+
+fn bad_regex(error: ::regex::Error) -> ConfigError {
+    invalid(format!("Invalid regex: {}", error))
+}
+
+fn parse_level(level: &str) -> Result<LogLevelFilter, ConfigError> {
+    level.parse().map_err(|()| invalid(format!("Unknown level \"{}\"", level)))
+}
+
+fn parse_document(text: &str) -> Result<Document, ConfigError> {
+    enum Section {
+        Root,
+        Table(String),
+        Array(String),
+    }
+
+    let mut document = Document::default();
+    let mut section = Section::Root;
+    for (index, line) in text.lines().enumerate() {
+        let syntax = |reason: &str| {
+            ConfigError::Syntax {
+                line  : index + 1,
+                reason: reason.to_owned(),
+            }
+        };
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix("[[") {
+            let name = header.strip_suffix("]]").ok_or_else(|| syntax("Expected ]]"))?;
+            let name = parse_key(name.trim()).ok_or_else(|| syntax("Invalid table name"))?;
+            document.arrays.entry(name.clone()).or_default().push(Table::new());
+            section = Section::Array(name);
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header.strip_suffix(']').ok_or_else(|| syntax("Expected ]"))?;
+            let name = parse_key(name.trim()).ok_or_else(|| syntax("Invalid table name"))?;
+            if document.tables.insert(name.clone(), Table::new()).is_some() {
+                return Err(syntax("Table defined twice"));
+            }
+            section = Section::Table(name);
+            continue;
+        }
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().and_then(|key| parse_key(key.trim()));
+        let value = parts.next().and_then(|value| parse_value(value.trim()));
+        let (key, value) = match (key, value) {
+            (Some(key), Some(value)) => (key, value),
+            (None, _) => return Err(syntax("Expected a key")),
+            (_, None) => return Err(syntax("Expected a string, integer or boolean value")),
+        };
+        let table = match section {
+            Section::Root => &mut document.root,
+            Section::Table(ref name) => {
+                unwrap_option!(document.tables.get_mut(name), "Table should exist")
+            }
+            Section::Array(ref name) => {
+                unwrap_option!(document.arrays.get_mut(name).and_then(|array| array.last_mut()),
+                               "Table should exist")
+            }
+        };
+        if table.insert(key, value).is_some() {
+            return Err(syntax("Key defined twice"));
+        }
+    }
+    Ok(document)
+}
+
+// `line` up to any `#` which is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '\\' if in_string => {
+                escaped = !escaped;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => (),
+        }
+        escaped = false;
+    }
+    line
+}
+
+// A bare key of letters, digits, `_` and `-`, or a quoted key such as `"routing::core"`.
+fn parse_key(key: &str) -> Option<String> {
+    if key.starts_with('"') {
+        return parse_string(key);
+    }
+    let bare = |character: char| character.is_ascii_alphanumeric() || "_-".contains(character);
+    if !key.is_empty() && key.chars().all(bare) {
+        Some(key.to_owned())
+    } else {
+        None
+    }
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    match value {
+        "true" => Some(Value::Boolean(true)),
+        "false" => Some(Value::Boolean(false)),
+        _ if value.starts_with('"') => parse_string(value).map(Value::String),
+        _ => value.replace('_', "").parse().ok().map(Value::Integer),
+    }
+}
+
+// A basic string, which must be the whole of `quoted`.
+fn parse_string(quoted: &str) -> Option<String> {
+    let mut characters = quoted.strip_prefix('"')?.chars();
+    let mut string = String::new();
+    loop {
+        match characters.next()? {
+            '"' => break,
+            '\\' => {
+                string.push(match characters.next()? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    'n' => '\n',
+                    't' => '\t',
+                    _ => return None,
+                })
+            }
+            character => string.push(character),
+        }
+    }
+    if characters.next().is_none() {
+        Some(string)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+    use std::time::Instant;
+
+    const CONFIG: &str = r#"
+# Verbosity, as for RUST_LOG.
+level = "warn"
+show_thread_name = false
+collapse_repeats_ms = 500
+time_zone = "utc"
+time_precision = "ms"
+pattern = "{level} {msg} # not a comment"
+
+[levels]
+routing = "debug"
+"routing::core" = "trace"  # The most specific match wins.
+
+[[appenders]]
+kind = "stderr"
+colour = "never"
+exclude = "^(Sent|Received) packet"
+level = "info"
+format = "json"
+"#;
+
+    #[test]
+    fn parse() {
+        let config = unwrap_result!(Config::parse(CONFIG));
+        let filter = unwrap_result!(config.filter());
+        assert!(filter.enabled(LogLevel::Warn, "crust"));
+        assert!(!filter.enabled(LogLevel::Info, "crust"));
+        assert!(filter.enabled(LogLevel::Debug, "routing::messages"));
+        assert_eq!(string(&config.document.root, "pattern").ok(),
+                   Some(Some("{level} {msg} # not a comment".to_owned())));
+        assert_eq!(config.document.arrays["appenders"].len(), 1);
+        assert!(config.builder().is_ok());
+    }
+
+    #[test]
+    fn parse_errors() {
+        match Config::parse("level = \"warn\"\nlevel = \"info\"") {
+            Err(ConfigError::Syntax { line: 2, .. }) => (),
+            result => panic!("Unexpected {:?}", result.map(|_| ())),
+        }
+        match Config::parse("[levels]\nrouting = \"loud\"") {
+            Err(ConfigError::Invalid(_)) => (),
+            result => panic!("Unexpected {:?}", result.map(|_| ())),
+        }
+        match Config::parse("time_zone = \"mars\"").map(|config| config.builder().map(|_| ())) {
+            Ok(Err(ConfigError::Invalid(_))) => (),
+            result => panic!("Unexpected {:?}", result.map(|_| ())),
+        }
+        match Config::parse("[[appenders]]\nkind = \"stderr\"\ninclude = \"(\"").map(|config| {
+            config.builder().map(|_| ())
+        }) {
+            Ok(Err(ConfigError::Invalid(_))) => (),
+            result => panic!("Unexpected {:?}", result.map(|_| ())),
+        }
+        assert!(Config::parse("level = \"warn").is_err());
+        assert!(Config::parse("[levels").is_err());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let reason = |text: &str| {
+            match Config::parse(text) {
+                Err(ConfigError::Invalid(reason)) => reason,
+                result => panic!("Unexpected {:?}", result.map(|_| ())),
+            }
+        };
+        assert_eq!(reason("levle = \"warn\""), "Unknown key \"levle\"");
+        assert_eq!(reason("[crash_buffer]\npath = \"crash.log\"\nkeep = 5"),
+                   "Unknown key \"keep\" in [crash_buffer]");
+        assert_eq!(reason("[[appenders]]\nkind = \"tcp\"\naddress = \"10.0.0.1:1\"\npath = \"\""),
+                   "Unknown key \"path\" for a \"tcp\" appender");
+        assert_eq!(reason("[[appenders]]\nkind = \"carrier-pigeon\""),
+                   "Unknown appender kind \"carrier-pigeon\"");
+        assert_eq!(reason("[level]\nrouting = \"debug\""), "Unknown table [level]");
+        assert_eq!(reason("[[appender]]\nkind = \"stderr\""),
+                   "Unknown array of tables [[appender]]");
+    }
+
+    #[test]
+    fn reloads_levels() {
+        let path = ::std::env::temp_dir().join(format!("maidsafe_utilities_log_config_{}.toml",
+                                                       ::std::process::id()));
+        unwrap_result!(fs::write(&path, "level = \"warn\""));
+        let config = unwrap_result!(Config::load(&path));
+        let logger = Arc::new(Logger::new(unwrap_result!(config.filter()),
+                                          Box::new(TextFormatter::default()),
+                                          Vec::new()));
+        let _watcher = unwrap_result!(watch(path.clone(), logger.clone(),
+                                            Duration::from_millis(10)));
+        assert_eq!(logger.max_level(), LogLevelFilter::Warn);
+
+        // Replace rather than rewrite the file, so the watcher never sees it empty.
+        let replace = |text: &str| {
+            let temporary = path.with_extension("tmp");
+            unwrap_result!(fs::write(&temporary, text));
+            unwrap_result!(fs::rename(&temporary, &path));
+        };
+        replace("level = \"debug\"");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while logger.max_level() != LogLevelFilter::Debug && Instant::now() < deadline {
+            ::std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(logger.max_level(), LogLevelFilter::Debug);
+
+        // An invalid file leaves the levels as they were.
+        replace("level = \"loud\"");
+        ::std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(logger.max_level(), LogLevelFilter::Debug);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// The ring's slots hold raw pointers to the records they own.
+#![allow(unsafe_code)]
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use super::{Formatter, Record};
+
+// The last records logged at every level, for `Builder::crash_buffer`. This is a lock-free ring:
+// each record claims the next slot via an atomic counter and is published by swapping a pointer
+// to it into the slot, so logging threads never block one another or a dump. Whoever swaps a
+// record out of a slot, overwriting it or dumping it, owns it from then on.
+pub struct CrashBuffer {
+    slots: Vec<AtomicPtr<Record>>,
+    next : AtomicUsize,
+    path : PathBuf,
+}
+
+impl CrashBuffer {
+    pub fn new(capacity: usize, path: PathBuf) -> CrashBuffer {
+        assert!(capacity > 0, "A crash buffer needs a capacity of at least one record");
+        CrashBuffer {
+            slots: (0..capacity).map(|_| AtomicPtr::new(ptr::null_mut())).collect(),
+            next : AtomicUsize::new(0),
+            path,
+        }
+    }
+
+    pub fn push(&self, record: Record) {
+        let index = self.next.fetch_add(1, Ordering::SeqCst) % self.slots.len();
+        drop(swap(&self.slots[index], Box::into_raw(Box::new(record))));
+    }
+
+    // Take the buffered records, oldest first, leaving the buffer empty.
+    pub fn take(&self) -> Vec<Record> {
+        let start = self.next.load(Ordering::SeqCst);
+        (0..self.slots.len())
+            .filter_map(|offset| {
+                swap(&self.slots[(start + offset) % self.slots.len()], ptr::null_mut())
+            })
+            .collect()
+    }
+
+    // Append the buffered records to the file, formatted by `formatter`, emptying the buffer.
+    pub fn dump(&self, formatter: &dyn Formatter) -> io::Result<()> {
+        let mut text = String::new();
+        for record in self.take() {
+            text.push_str(&formatter.format(&record));
+            text.push('\n');
+        }
+        OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(text.as_bytes())
+    }
+}
+
+impl Drop for CrashBuffer {
+    fn drop(&mut self) {
+        let _ = self.take();
+    }
+}
+
+// Publish `record` (which is null or came from `Box::into_raw`) in `slot`, taking ownership of
+// the record it replaces, if any.
+fn swap(slot: &AtomicPtr<Record>, record: *mut Record) -> Option<Record> {
+    let previous = slot.swap(record, Ordering::AcqRel);
+    if previous.is_null() {
+        None
+    } else {
+        // Safe as the pointer came from `Box::into_raw` and, being swapped out, is no longer
+        // reachable by any other thread.
+        Some(*unsafe { Box::from_raw(previous) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use logger::LogLevel;
+    use super::*;
+
+    #[test]
+    fn keeps_and_dumps_the_latest_records() {
+        let path = ::std::env::temp_dir().join(format!("crash_buffer_{}.log",
+                                                       ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        let buffer = CrashBuffer::new(3, path.clone());
+        for index in 0..5 {
+            buffer.push(Record::for_test(LogLevel::Trace, &index.to_string()));
+        }
+        let format = |record: &Record| record.message.clone();
+        unwrap_result!(buffer.dump(&format));
+        assert!(buffer.take().is_empty());
+        buffer.push(Record::for_test(LogLevel::Trace, "5"));
+        unwrap_result!(buffer.dump(&format));
+        assert_eq!(unwrap_result!(::std::fs::read_to_string(&path)), "2\n3\n4\n5\n");
+        let _ = ::std::fs::remove_file(&path);
+    }
+}
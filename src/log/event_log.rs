@@ -0,0 +1,134 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Writing to the Event Log requires FFI.
+#![allow(unsafe_code)]
+
+use logger::LogLevel;
+use std::ffi::OsStr;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use super::{Appender, Record};
+use winapi::{BOOL, DWORD, FALSE, HANDLE, LPCWSTR, LPVOID, PSID, WORD};
+
+const EVENTLOG_ERROR_TYPE: WORD = 0x0001;
+const EVENTLOG_WARNING_TYPE: WORD = 0x0002;
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegisterEventSourceW(server_name: LPCWSTR, source_name: LPCWSTR) -> HANDLE;
+    fn ReportEventW(event_log: HANDLE,
+                    event_type: WORD,
+                    category: WORD,
+                    event_id: DWORD,
+                    user_sid: PSID,
+                    string_count: WORD,
+                    data_size: DWORD,
+                    strings: *const LPCWSTR,
+                    raw_data: LPVOID)
+                    -> BOOL;
+    fn DeregisterEventSource(event_log: HANDLE) -> BOOL;
+}
+
+/// Writes warnings and errors to the Windows Event Log's Application log under a registered
+/// source name, as Windows service deployments require. Only available on Windows with the
+/// `event-log` feature. Records below `Warn` are ignored, so add another appender for those.
+///
+/// The source is normally registered by the installer, under
+/// `HKLM\SYSTEM\CurrentControlSet\Services\EventLog\Application\<source>`; records are still
+/// written if it is not, but the Event Viewer then prefixes them with a note that the source's
+/// description is missing. As the Event Log records its own timestamp, the logger's format is
+/// not used; each message is written as `<thread> [<target>] <message>`, with event id 0.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, EventLogAppender, StderrAppender};
+///
+/// let builder = Builder::new()
+///     .appender(StderrAppender::new())
+///     .appender(unwrap_result!(EventLogAppender::new("SAFE Vault")));
+/// unwrap_result!(builder.init());
+/// # }
+/// ```
+pub struct EventLogAppender {
+    handle: HANDLE,
+}
+
+// Event source handles may be used from any thread.
+unsafe impl Send for EventLogAppender {}
+unsafe impl Sync for EventLogAppender {}
+
+impl EventLogAppender {
+    /// Write to the Event Log as `source`.
+    pub fn new(source: &str) -> io::Result<EventLogAppender> {
+        let source = wide(source);
+        let handle = unsafe { RegisterEventSourceW(::std::ptr::null(), source.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventLogAppender { handle })
+    }
+}
+
+impl Appender for EventLogAppender {
+    fn append(&self, record: &Record, _line: &str) -> io::Result<()> {
+        let event_type = match record.level {
+            LogLevel::Error => EVENTLOG_ERROR_TYPE,
+            LogLevel::Warn => EVENTLOG_WARNING_TYPE,
+            LogLevel::Info | LogLevel::Debug | LogLevel::Trace => return Ok(()),
+        };
+        let message = wide(&format!("{} [{}] {}",
+                                    record.thread_name,
+                                    record.target,
+                                    record.message));
+        let strings = [message.as_ptr()];
+        let result = unsafe {
+            ReportEventW(self.handle,
+                         event_type,
+                         0,
+                         0,
+                         ::std::ptr::null_mut(),
+                         1,
+                         0,
+                         strings.as_ptr(),
+                         ::std::ptr::null_mut())
+        };
+        if result == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for EventLogAppender {
+    fn drop(&mut self) {
+        let _ = unsafe { DeregisterEventSource(self.handle) };
+    }
+}
+
+// `text` as a nul-terminated UTF-16 string, with any nuls it contains removed.
+fn wide(text: &str) -> Vec<u16> {
+    OsStr::new(text)
+        .encode_wide()
+        .filter(|&unit| unit != 0)
+        .chain(Some(0))
+        .collect()
+}
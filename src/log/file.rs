@@ -0,0 +1,242 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Appender, Record};
+use super::gzip;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Appends records to a file, rotating it once it reaches a size limit.
+///
+/// On rotation `<path>` is renamed to `<path>.1`, any existing `<path>.1` to `<path>.2` and so on,
+/// keeping at most `keep` rotated files; the oldest is deleted. A fresh `<path>` is then started.
+///
+/// For long-running nodes on small disks, rotated files can also be gzip-compressed (see
+/// `with_gzip`) and the space taken by all the files capped (see `with_max_total_size`).
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, FileAppender};
+///
+/// let path = std::env::temp_dir().join("vault.log");
+/// // Up to 10 MiB per file, with the five most recent rotated files kept.
+/// let appender = unwrap_result!(FileAppender::new(&path, 10 * 1024 * 1024, 5));
+/// unwrap_result!(Builder::new().appender(appender).init());
+///
+/// // Up to 100 rotated files compressed, but no more than 100 MiB in all.
+/// let path = std::env::temp_dir().join("archived.log");
+/// let appender = unwrap_result!(FileAppender::new(&path, 10 * 1024 * 1024, 100))
+///                    .with_gzip()
+///                    .with_max_total_size(100 * 1024 * 1024);
+/// # let _ = appender;
+/// # }
+/// ```
+pub struct FileAppender {
+    path          : PathBuf,
+    max_size      : u64,
+    keep          : usize,
+    gzip          : bool,
+    max_total_size: Option<u64>,
+    state         : Mutex<State>,
+}
+
+struct State {
+    file: File,
+    size: u64,
+}
+
+impl FileAppender {
+    /// Append to the file at `path`, creating it if need be, and rotate it whenever writing
+    /// another record would take it over `max_size` bytes, keeping `keep` rotated files. With a
+    /// `keep` of zero the file is just truncated on rotation.
+    pub fn new<P: AsRef<Path>>(path: P, max_size: u64, keep: usize) -> io::Result<FileAppender> {
+        let path = path.as_ref().to_path_buf();
+        let file = open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(FileAppender {
+            path,
+            max_size,
+            keep,
+            gzip          : false,
+            max_total_size: None,
+            state         : Mutex::new(State { file, size }),
+        })
+    }
+
+    /// Compress each file with gzip as it is rotated, to `<path>.<index>.gz`. Compression happens
+    /// on the logging thread, so wrap the appender in an `AsyncAppender` if that is too slow.
+    pub fn with_gzip(mut self) -> FileAppender {
+        self.gzip = true;
+        self
+    }
+
+    /// On each rotation, delete rotated files, oldest first, until they take no more than
+    /// `max_total_size` bytes together with the `max_size` allowed for `<path>` itself.
+    pub fn with_max_total_size(mut self, max_total_size: u64) -> FileAppender {
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// The path of the rotated file `index`, `<path>.<index>`, or `<path>.<index>.gz` if
+    /// compressing.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        if self.gzip {
+            path.push(".gz");
+        }
+        PathBuf::from(path)
+    }
+
+    fn rotate(&self, state: &mut State) -> io::Result<()> {
+        if self.keep > 0 {
+            let _ = fs::remove_file(self.rotated_path(self.keep));
+            for index in (1..self.keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            if self.gzip {
+                fs::write(self.rotated_path(1), gzip::compress(&fs::read(&self.path)?))?;
+                state.file.set_len(0)?;
+            } else {
+                fs::rename(&self.path, self.rotated_path(1))?;
+                state.file = open(&self.path)?;
+            }
+            if let Some(max_total_size) = self.max_total_size {
+                self.cap_total_size(max_total_size)?;
+            }
+        } else {
+            state.file.set_len(0)?;
+        }
+        state.size = 0;
+        Ok(())
+    }
+
+    fn cap_total_size(&self, max_total_size: u64) -> io::Result<()> {
+        let sizes = (1..self.keep + 1)
+                        .map(|index| {
+                            fs::metadata(self.rotated_path(index)).map_or(0, |metadata| {
+                                metadata.len()
+                            })
+                        })
+                        .collect::<Vec<_>>();
+        let mut total = self.max_size + sizes.iter().sum::<u64>();
+        for (index, size) in sizes.iter().enumerate().rev() {
+            if total <= max_total_size {
+                break;
+            }
+            if *size > 0 {
+                fs::remove_file(self.rotated_path(index + 1))?;
+                total -= size;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Appender for FileAppender {
+    fn append(&self, _record: &Record, line: &str) -> io::Result<()> {
+        let mut state = unwrap_result!(self.state.lock());
+        let length = line.len() as u64 + 1;
+        // A record is never split across files, so one bigger than the limit gets a file to itself.
+        if state.size > 0 && state.size + length > self.max_size {
+            self.rotate(&mut state)?;
+        }
+        writeln!(state.file, "{}", line)?;
+        state.size += length;
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        unwrap_result!(self.state.lock()).file.flush()
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+
+    #[test]
+    fn rotates_by_size_and_count() {
+        let dir = ::std::env::temp_dir().join(format!("file_appender_{}", ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        unwrap_result!(fs::create_dir_all(&dir));
+        let path = dir.join("test.log");
+        let record = Record::for_test(::logger::LogLevel::Info, "");
+        let appender = unwrap_result!(FileAppender::new(&path, 20, 2));
+        // Each line takes 10 bytes with its newline, so two fit in a file.
+        for index in 0..7 {
+            unwrap_result!(appender.append(&record, &format!("line {:04}", index)));
+        }
+        let read = |path: PathBuf| unwrap_result!(fs::read_to_string(path));
+        assert_eq!(read(path.clone()), "line 0006\n");
+        assert_eq!(read(appender.rotated_path(1)), "line 0004\nline 0005\n");
+        assert_eq!(read(appender.rotated_path(2)), "line 0002\nline 0003\n");
+        assert!(!appender.rotated_path(3).exists());
+
+        // Reopening carries on from the existing size.
+        let appender = unwrap_result!(FileAppender::new(&path, 20, 0));
+        unwrap_result!(appender.append(&record, "line 0007"));
+        unwrap_result!(appender.append(&record, "line 0008"));
+        assert_eq!(read(path.clone()), "line 0008\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn gzips_and_caps_total_size() {
+        let dir = ::std::env::temp_dir().join(format!("file_appender_gzip_{}",
+                                                      ::std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        unwrap_result!(fs::create_dir_all(&dir));
+        let path = dir.join("test.log");
+        let record = Record::for_test(::logger::LogLevel::Info, "");
+        let appender = unwrap_result!(FileAppender::new(&path, 20, 10))
+                           .with_gzip()
+                           .with_max_total_size(100);
+        for index in 0..12 {
+            unwrap_result!(appender.append(&record, &format!("line {:04}", index)));
+        }
+        let archive = unwrap_result!(fs::read(appender.rotated_path(1)));
+        assert!(appender.rotated_path(1).to_string_lossy().ends_with(".1.gz"));
+        assert_eq!(&archive[..2], &[0x1f, 0x8b]);
+        assert_eq!(&archive[archive.len() - 4..], &20u32.to_le_bytes());
+        // Each archive takes over 20 bytes, so with 20 allowed for the current file only the
+        // newest few fit.
+        let archived = (1..11).filter(|&index| appender.rotated_path(index).exists()).count();
+        assert!(archived > 0 && archived < 5);
+        let total = (1..11)
+                        .filter_map(|index| fs::metadata(appender.rotated_path(index)).ok())
+                        .map(|metadata| metadata.len())
+                        .sum::<u64>();
+        assert!(total + 20 <= 100);
+        assert_eq!(unwrap_result!(fs::read_to_string(&path)), "line 0010\nline 0011\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::{LogLevel, LogLevelFilter};
+
+/// Per-module log levels, parsed from `RUST_LOG`-style directives.
+///
+/// The directives are comma-separated, each being either a bare level which applies to every
+/// module (e.g. `warn`), a module path with a level (e.g. `routing::core=debug`), or a bare module
+/// path, which enables everything from that module. A record is checked against the directive
+/// with the longest module path prefixing its target, falling back to the bare level, or to
+/// `error` if there is none. Unparsable directives are reported on stderr and ignored.
+///
+/// #Examples
+///
+/// ```
+/// # extern crate log;
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use log::LogLevel;
+/// use maidsafe_utilities::log::Filter;
+///
+/// let filter = Filter::parse("warn,routing=info,routing::core=trace");
+/// assert!(filter.enabled(LogLevel::Warn, "crust::service"));
+/// assert!(!filter.enabled(LogLevel::Info, "crust::service"));
+/// assert!(filter.enabled(LogLevel::Info, "routing::messages"));
+/// assert!(filter.enabled(LogLevel::Trace, "routing::core::state"));
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    default   : LogLevelFilter,
+    // Sorted by ascending length of module path, so that the last match is the most specific.
+    directives: Vec<(String, LogLevelFilter)>,
+}
+
+impl Filter {
+    /// Parse comma-separated directives such as `"info,routing=debug"`.
+    pub fn parse(directives: &str) -> Filter {
+        let mut filter = Filter {
+            default   : LogLevelFilter::Error,
+            directives: Vec::new(),
+        };
+        for directive in directives.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let (name, level) = (parts.next().unwrap_or(""), parts.next());
+            match level {
+                None => {
+                    match name.parse() {
+                        Ok(level) => filter.default = level,
+                        Err(()) => filter.add(name, LogLevelFilter::Trace),
+                    }
+                }
+                Some(level) => {
+                    match level.trim().parse() {
+                        Ok(level) => filter.add(name.trim(), level),
+                        Err(()) => eprintln!("Ignoring invalid log directive \"{}\"", directive),
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    /// Parse the directives held in the environment variable `var_name`, as for `parse`. An unset
+    /// variable gives the default filter, which only enables errors.
+    pub fn from_env(var_name: &str) -> Filter {
+        Filter::parse(&::std::env::var(var_name).unwrap_or_default())
+    }
+
+    /// Whether a record at `level` from the module path `target` is to be logged.
+    pub fn enabled(&self, level: LogLevel, target: &str) -> bool {
+        level <= self.level_for(target)
+    }
+
+    /// The most verbose level enabled for any module.
+    pub fn max_level(&self) -> LogLevelFilter {
+        self.directives
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, ::std::cmp::max)
+    }
+
+    /// Enable records up to `level` from the module path `target` and those nested within it,
+    /// replacing any directive for exactly that path. An empty `target` sets the bare level.
+    pub fn set_level(&mut self, target: &str, level: LogLevelFilter) {
+        if target.is_empty() {
+            self.default = level;
+        } else {
+            self.add(target, level);
+        }
+    }
+
+    /// Remove any directive for exactly the module path `target`, so that its records are again
+    /// checked against the enclosing modules' directives or the bare level.
+    pub fn reset_level(&mut self, target: &str) {
+        self.directives.retain(|(existing, _)| existing != target);
+    }
+
+    fn level_for(&self, target: &str) -> LogLevelFilter {
+        self.directives
+            .iter()
+            .rev()
+            .find(|(name, _)| is_within(target, name))
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    fn add(&mut self, name: &str, level: LogLevelFilter) {
+        self.directives.retain(|(existing, _)| existing != name);
+        let index = self.directives
+                        .iter()
+                        .position(|(existing, _)| existing.len() > name.len())
+                        .unwrap_or(self.directives.len());
+        self.directives.insert(index, (name.to_owned(), level));
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter::parse("")
+    }
+}
+
+// Whether `target` is the module `name` or one nested within it.
+fn is_within(target: &str, name: &str) -> bool {
+    target.starts_with(name) &&
+    (target.len() == name.len() || target[name.len()..].starts_with("::"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn most_specific_directive_wins() {
+        let filter = Filter::parse("routing::core=trace, info ,routing=warn,crust,bogus=loud");
+        assert_eq!(filter.max_level(), LogLevelFilter::Trace);
+        assert!(filter.enabled(LogLevel::Info, "safe_core"));
+        assert!(!filter.enabled(LogLevel::Debug, "safe_core"));
+        assert!(!filter.enabled(LogLevel::Info, "routing::messages"));
+        assert!(filter.enabled(LogLevel::Trace, "routing::core::state"));
+        assert!(filter.enabled(LogLevel::Trace, "crust::service"));
+        // A module path only matches whole path segments.
+        assert!(!filter.enabled(LogLevel::Trace, "crusty"));
+        assert!(!filter.enabled(LogLevel::Trace, "bogus"));
+
+        let filter = Filter::default();
+        assert_eq!(filter.max_level(), LogLevelFilter::Error);
+        assert!(filter.enabled(LogLevel::Error, "anything"));
+        assert!(!filter.enabled(LogLevel::Warn, "anything"));
+        assert_eq!(Filter::parse("off").max_level(), LogLevelFilter::Off);
+    }
+
+    #[test]
+    fn set_and_reset_levels() {
+        let mut filter = Filter::parse("info,routing=warn");
+        filter.set_level("routing::core", LogLevelFilter::Trace);
+        assert!(filter.enabled(LogLevel::Trace, "routing::core::state"));
+        assert!(!filter.enabled(LogLevel::Info, "routing::messages"));
+        filter.set_level("routing", LogLevelFilter::Debug);
+        assert!(filter.enabled(LogLevel::Debug, "routing::messages"));
+        filter.set_level("", LogLevelFilter::Error);
+        assert!(!filter.enabled(LogLevel::Warn, "crust"));
+
+        filter.reset_level("routing::core");
+        assert!(!filter.enabled(LogLevel::Trace, "routing::core::state"));
+        assert!(filter.enabled(LogLevel::Debug, "routing::core::state"));
+        assert_eq!(filter.max_level(), LogLevelFilter::Debug);
+    }
+}
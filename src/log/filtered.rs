@@ -0,0 +1,170 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevelFilter;
+use regex::{self, Regex};
+use std::io;
+use super::{Appender, Formatter, Record};
+
+/// Passes to another appender only some of the records, optionally in a format of its own, so
+/// that each of the logger's appenders can be given its own level and format, e.g. to keep
+/// per-packet traces off the console while still writing them to a file as JSON.
+///
+/// A record is passed on if it is at `level` or more severe, its message matches at least one
+/// `include` regex, or there are none, and it matches no `exclude` regex. Regexes are as for the
+/// `regex` crate and match anywhere in the message unless anchored. As the logger's filter
+/// decides which records reach any appender, it needs to enable the most verbose level any
+/// appender wants.
+///
+/// #Examples
+///
+/// ```
+/// # extern crate log;
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use log::LogLevelFilter;
+/// use maidsafe_utilities::log::{Builder, FileAppender, Filter, FilteredAppender, JsonFormatter,
+///                               StderrAppender};
+///
+/// let path = std::env::temp_dir().join(format!("filtered_doc_{}.log", std::process::id()));
+/// // Everything to the file as JSON, but only info and above to the console, and nothing about
+/// // packets.
+/// let file = FilteredAppender::new(unwrap_result!(FileAppender::new(&path, 1024 * 1024, 3)))
+///                .formatter(JsonFormatter::new());
+/// let console = unwrap_result!(FilteredAppender::new(StderrAppender::new())
+///                                  .level(LogLevelFilter::Info)
+///                                  .exclude("^(Sent|Received) packet"));
+/// let builder = Builder::new()
+///     .filter(Filter::parse("trace"))
+///     .appender(file)
+///     .appender(console);
+/// unwrap_result!(builder.init());
+/// # }
+/// ```
+pub struct FilteredAppender {
+    inner    : Box<dyn Appender>,
+    level    : LogLevelFilter,
+    formatter: Option<Box<dyn Formatter>>,
+    includes : Vec<Regex>,
+    excludes : Vec<Regex>,
+}
+
+impl FilteredAppender {
+    /// Pass every record to `inner`, formatted as the logger formats records for its other
+    /// appenders, until a level, formatter or regexes are set. The level defaults to
+    /// `LogLevelFilter::Trace`.
+    pub fn new<A: Appender + 'static>(inner: A) -> FilteredAppender {
+        FilteredAppender {
+            inner    : Box::new(inner),
+            level    : LogLevelFilter::Trace,
+            formatter: None,
+            includes : Vec::new(),
+            excludes : Vec::new(),
+        }
+    }
+
+    /// Only pass on records at `level` or more severe.
+    pub fn level(mut self, level: LogLevelFilter) -> FilteredAppender {
+        self.level = level;
+        self
+    }
+
+    /// Format the records passed on with `formatter`, rather than as the logger does.
+    pub fn formatter<F: Formatter + 'static>(mut self, formatter: F) -> FilteredAppender {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Only pass on records whose messages match `pattern` or another `include` regex.
+    pub fn include(mut self, pattern: &str) -> Result<FilteredAppender, regex::Error> {
+        self.includes.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Drop records whose messages match `pattern`, even if they match an `include` regex.
+    pub fn exclude(mut self, pattern: &str) -> Result<FilteredAppender, regex::Error> {
+        self.excludes.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    fn passes(&self, record: &Record) -> bool {
+        let message = &record.message[..];
+        record.level <= self.level &&
+        (self.includes.is_empty() || self.includes.iter().any(|regex| regex.is_match(message))) &&
+        !self.excludes.iter().any(|regex| regex.is_match(message))
+    }
+}
+
+impl Appender for FilteredAppender {
+    fn append(&self, record: &Record, line: &str) -> io::Result<()> {
+        if !self.passes(record) {
+            return Ok(());
+        }
+        match self.formatter {
+            Some(ref formatter) => self.inner.append(record, &formatter.format(record)),
+            None => self.inner.append(record, line),
+        }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use logger::LogLevel;
+    use std::sync::{Arc, Mutex};
+    use super::*;
+
+    struct Lines(Arc<Mutex<Vec<String>>>);
+
+    impl Appender for Lines {
+        fn append(&self, _record: &Record, line: &str) -> io::Result<()> {
+            unwrap_result!(self.0.lock()).push(line.to_owned());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn includes_and_excludes() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let appender = unwrap_result!(unwrap_result!(FilteredAppender::new(Lines(lines.clone()))
+                                                         .include("packet|peer"))
+                                          .exclude("^Sent"));
+        for message in &["Sent packet 1", "Received packet 2", "Lost peer", "Started"] {
+            unwrap_result!(appender.append(&Record::for_test(LogLevel::Info, message), message));
+        }
+        assert_eq!(*unwrap_result!(lines.lock()), vec!["Received packet 2", "Lost peer"]);
+        assert!(FilteredAppender::new(Lines(lines)).exclude("(").is_err());
+    }
+
+    #[test]
+    fn level_and_format() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let appender = FilteredAppender::new(Lines(lines.clone()))
+                           .level(LogLevelFilter::Info)
+                           .formatter(|record: &Record| {
+                               format!("{}: {}", record.level, record.message)
+                           });
+        for &level in &[LogLevel::Error, LogLevel::Info, LogLevel::Debug] {
+            unwrap_result!(appender.append(&Record::for_test(level, "Hi"), "Unformatted"));
+        }
+        assert_eq!(*unwrap_result!(lines.lock()), vec!["ERROR: Hi", "INFO: Hi"]);
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use super::{Record, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Turns each record into the line handed to the appenders.
+///
+/// This is implemented for closures taking a `&Record`, so `Builder::formatter` can be given
+/// e.g. `|record: &Record| format!("{} {}", record.level, record.message)`.
+pub trait Formatter: Send + Sync {
+    /// The line for `record`, without a trailing newline.
+    fn format(&self, record: &Record) -> String;
+}
+
+impl<F: Fn(&Record) -> String + Send + Sync> Formatter for F {
+    fn format(&self, record: &Record) -> String {
+        self(record)
+    }
+}
+
+impl Formatter for Box<dyn Formatter> {
+    fn format(&self, record: &Record) -> String {
+        (**self).format(record)
+    }
+}
+
+/// Which clock a `TimeFormat` shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeZone {
+    /// The local time of the machine.
+    #[default]
+    Local,
+    /// UTC, e.g. to line up the logs of nodes in different timezones.
+    Utc,
+}
+
+/// The fractions of a second a `TimeFormat` shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimePrecision {
+    /// Whole seconds.
+    Seconds,
+    /// Milliseconds.
+    Millis,
+    /// Microseconds.
+    #[default]
+    Micros,
+}
+
+/// How the `TextFormatter` and a `PatternFormatter`'s `{time}` show when each record was logged.
+///
+/// The default is the local time of day to the microsecond, e.g. `19:33:49.245434`. With
+/// `iso8601` the date and UTC offset are included too, e.g. `2016-05-04T19:33:49.245Z` in UTC to
+/// the millisecond, or `2016-05-04T21:33:49+02:00` in local time to the second.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, TimeFormat, TimePrecision, TimeZone};
+///
+/// let time_format = TimeFormat::new()
+///                       .zone(TimeZone::Utc)
+///                       .precision(TimePrecision::Millis)
+///                       .iso8601(true);
+/// unwrap_result!(Builder::new().time_format(time_format).init());
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeFormat {
+    zone     : TimeZone,
+    precision: TimePrecision,
+    iso8601  : bool,
+}
+
+impl TimeFormat {
+    /// The local time of day to the microsecond.
+    pub fn new() -> TimeFormat {
+        TimeFormat::default()
+    }
+
+    /// Show the time in `zone`.
+    pub fn zone(mut self, zone: TimeZone) -> TimeFormat {
+        self.zone = zone;
+        self
+    }
+
+    /// Show the time to `precision`.
+    pub fn precision(mut self, precision: TimePrecision) -> TimeFormat {
+        self.precision = precision;
+        self
+    }
+
+    /// Whether to show the date and UTC offset too, as an ISO 8601 timestamp.
+    pub fn iso8601(mut self, iso8601: bool) -> TimeFormat {
+        self.iso8601 = iso8601;
+        self
+    }
+
+    /// `timestamp` in this format.
+    pub fn format(&self, timestamp: SystemTime) -> String {
+        let time = to_tm(timestamp, self.zone);
+        let layout = if self.iso8601 {
+            "%Y-%m-%dT%H:%M:%S"
+        } else {
+            "%T"
+        };
+        let mut text = unwrap_result!(::time::strftime(layout, &time));
+        match self.precision {
+            TimePrecision::Seconds => (),
+            TimePrecision::Millis => text.push_str(&format!(".{:03}", time.tm_nsec / 1_000_000)),
+            TimePrecision::Micros => text.push_str(&format!(".{:06}", time.tm_nsec / 1000)),
+        }
+        if self.iso8601 {
+            match self.zone {
+                TimeZone::Utc => text.push('Z'),
+                TimeZone::Local => {
+                    let offset = time.tm_utcoff.abs() / 60;
+                    text.push_str(&format!("{}{:02}:{:02}",
+                                           if time.tm_utcoff < 0 { '-' } else { '+' },
+                                           offset / 60,
+                                           offset % 60));
+                }
+            }
+        }
+        text
+    }
+
+    /// The date of `timestamp` as `YYYY-MM-DD`, in this format's zone.
+    pub fn date(&self, timestamp: SystemTime) -> String {
+        unwrap_result!(::time::strftime("%F", &to_tm(timestamp, self.zone)))
+    }
+}
+
+// `timestamp` broken down in `zone`.
+fn to_tm(timestamp: SystemTime, zone: TimeZone) -> ::time::Tm {
+    let since_epoch = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let spec = ::time::Timespec::new(since_epoch.as_secs() as i64,
+                                     since_epoch.subsec_nanos() as i32);
+    match zone {
+        TimeZone::Local => ::time::at(spec),
+        TimeZone::Utc => ::time::at_utc(spec),
+    }
+}
+
+/// The default, human-readable format described for `log::init`, e.g.
+/// `W 19:33:49.245434 Worker [example:src/main.rs:50] Warning level message.`, followed by any
+/// key-value pairs attached by e.g. `info_kv!` as `key=value`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextFormatter {
+    show_thread_name: bool,
+    time_format     : TimeFormat,
+}
+
+impl TextFormatter {
+    /// Format records including the logging thread's name if `show_thread_name` is true.
+    pub fn new(show_thread_name: bool) -> TextFormatter {
+        TextFormatter {
+            show_thread_name,
+            time_format: TimeFormat::new(),
+        }
+    }
+
+    /// Show each record's time as `time_format` says, rather than as the local time of day to
+    /// the microsecond.
+    pub fn with_time_format(mut self, time_format: TimeFormat) -> TextFormatter {
+        self.time_format = time_format;
+        self
+    }
+}
+
+impl Default for TextFormatter {
+    fn default() -> TextFormatter {
+        TextFormatter::new(true)
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn format(&self, record: &Record) -> String {
+        let thread_name = if self.show_thread_name {
+            format!("{} ", record.thread_name)
+        } else {
+            String::new()
+        };
+        let mut line = format!("{} {} {}[{}:{}:{}] {}",
+                               match record.level {
+                                   LogLevel::Error => 'E',
+                                   LogLevel::Warn => 'W',
+                                   LogLevel::Info => 'I',
+                                   LogLevel::Debug => 'D',
+                                   LogLevel::Trace => 'T',
+                               },
+                               self.time_format.format(record.timestamp),
+                               thread_name,
+                               record.module_path.split("::").next().unwrap_or(""),
+                               record.file,
+                               record.line,
+                               record.message);
+        if !record.fields.is_empty() {
+            line.push(' ');
+            line.push_str(&format_fields(&record.fields));
+        }
+        line
+    }
+}
+
+// `fields` as space-separated `key=value` pairs, quoting strings which are empty or contain
+// whitespace, quotes or `=`.
+pub fn format_fields(fields: &[(&'static str, Value)]) -> String {
+    let pairs = fields.iter().map(|&(key, ref value)| {
+        match *value {
+            Value::Str(ref value) if value.is_empty() ||
+                                     value.contains(|character: char| {
+                                         character.is_whitespace() || "\"=".contains(character)
+                                     }) => format!("{}={:?}", key, value),
+            ref value => format!("{}={}", key, value),
+        }
+    });
+    pairs.collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_format() {
+        let record = Record::for_test(LogLevel::Warn, "Careful");
+        let line = TextFormatter::new(true).format(&record);
+        assert!(line.starts_with("W "), "{}", line);
+        assert!(line.ends_with(" TestThread [maidsafe_utilities:src/log/test.rs:42] Careful"),
+                "{}",
+                line);
+        let line = TextFormatter::new(false).format(&record);
+        assert!(line.ends_with(" [maidsafe_utilities:src/log/test.rs:42] Careful"), "{}", line);
+        assert!(!line.contains("TestThread"), "{}", line);
+
+        let mut record = record;
+        record.fields = vec![("peer", Value::from("10.0.0.1")),
+                             ("reason", Value::from("timed out")),
+                             ("bytes", Value::from(7))];
+        let line = TextFormatter::new(false).format(&record);
+        assert!(line.ends_with(" Careful peer=10.0.0.1 reason=\"timed out\" bytes=7"), "{}", line);
+
+        let time_format = TimeFormat::new().zone(TimeZone::Utc).iso8601(true);
+        let formatter = TextFormatter::new(false).with_time_format(time_format);
+        record.timestamp = UNIX_EPOCH + ::std::time::Duration::new(1_462_390_429, 245_434_000);
+        assert!(formatter.format(&record).starts_with("W 2016-05-04T19:33:49.245434Z ["));
+
+        let closure = |record: &Record| format!("{}: {}", record.level, record.message);
+        assert_eq!(closure.format(&record), "WARN: Careful");
+    }
+
+    #[test]
+    fn time_formats() {
+        let timestamp = UNIX_EPOCH + ::std::time::Duration::new(1_462_390_429, 245_434_000);
+        let utc = TimeFormat::new().zone(TimeZone::Utc);
+        assert_eq!(utc.format(timestamp), "19:33:49.245434");
+        assert_eq!(utc.precision(TimePrecision::Millis).iso8601(true).format(timestamp),
+                   "2016-05-04T19:33:49.245Z");
+        assert_eq!(utc.precision(TimePrecision::Seconds).format(timestamp), "19:33:49");
+        // The offset depends on where the test runs.
+        let local = TimeFormat::new().precision(TimePrecision::Seconds).iso8601(true);
+        let text = local.format(timestamp);
+        assert_eq!(text.len(), "2016-05-04T19:33:49+00:00".len(), "{}", text);
+        assert!(text[19..].starts_with('+') || text[19..].starts_with('-'), "{}", text);
+    }
+}
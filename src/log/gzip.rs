@@ -0,0 +1,340 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// A minimal gzip encoder for archiving rotated log files: a single DEFLATE block using the fixed
+// Huffman codes, with matches found via hash chains. It compresses log text well, without the
+// complexity of building dynamic codes.
+
+// The DEFLATE window, and so the furthest back a match can start.
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+// How many earlier positions with the same hash to try before settling for the best so far.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASES: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43,
+                                 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3,
+                                     4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASES: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257,
+                                   385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193,
+                                   12289, 16385, 24577];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8,
+                                       9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+// Compress `data` as a complete gzip file.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // Magic, deflate, no flags, no modification time, no extra flags, unknown OS.
+    let mut output = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    let mut writer = BitWriter {
+        output,
+        bits : 0,
+        count: 0,
+    };
+    // A single final block with fixed codes.
+    writer.write(1, 1);
+    writer.write(1, 2);
+    deflate(data, &mut writer);
+    write_literal_or_length(&mut writer, 256);
+    output = writer.finish();
+    output.extend_from_slice(&crc32(data).to_le_bytes());
+    output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    output
+}
+
+// The CRC-32 of `data`, as used by gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (index, entry) in table.iter_mut().enumerate() {
+        *entry = (0..8).fold(index as u32, |crc, _| {
+            if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            }
+        });
+    }
+    !data.iter().fold(!0, |crc, &byte| table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+fn deflate(data: &[u8], writer: &mut BitWriter) {
+    let mut chains = Chains {
+        head    : vec![0; 1 << HASH_BITS],
+        previous: vec![0; WINDOW_SIZE],
+    };
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = chains.longest_match(data, position);
+        if length >= MIN_MATCH {
+            write_match(writer, length, distance);
+            for skipped in position..position + length {
+                chains.insert(data, skipped);
+            }
+            position += length;
+        } else {
+            write_literal_or_length(writer, u16::from(data[position]));
+            chains.insert(data, position);
+            position += 1;
+        }
+    }
+}
+
+// The most recent position with each hash, and for each position in the window the previous one
+// with the same hash, all offset by one so that zero means none.
+struct Chains {
+    head    : Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl Chains {
+    fn insert(&mut self, data: &[u8], position: usize) {
+        if position + MIN_MATCH <= data.len() {
+            let hash = hash(&data[position..]);
+            self.previous[position % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = position + 1;
+        }
+    }
+
+    // The length and distance of the longest earlier match for the bytes at `position`,
+    // following the chain of earlier positions with the same hash.
+    fn longest_match(&self, data: &[u8], position: usize) -> (usize, usize) {
+        if position + MIN_MATCH > data.len() {
+            return (0, 0);
+        }
+        let max_length = ::std::cmp::min(MAX_MATCH, data.len() - position);
+        let (mut best_length, mut best_distance) = (0, 0);
+        let mut candidate = self.head[hash(&data[position..])];
+        for _ in 0..MAX_CHAIN {
+            if candidate == 0 || position - (candidate - 1) > WINDOW_SIZE {
+                break;
+            }
+            let start = candidate - 1;
+            let length = data[start..]
+                             .iter()
+                             .zip(&data[position..position + max_length])
+                             .take_while(|&(a, b)| a == b)
+                             .count();
+            if length > best_length {
+                best_length = length;
+                best_distance = position - start;
+                if length == max_length {
+                    break;
+                }
+            }
+            let next = self.previous[start % WINDOW_SIZE];
+            // The slot may since have been reused for a later position.
+            if next == 0 || next > start {
+                break;
+            }
+            candidate = next;
+        }
+        (best_length, best_distance)
+    }
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASES.iter().rposition(|&base| base as usize <= length).unwrap_or(0);
+    write_literal_or_length(writer, 257 + code as u16);
+    writer.write((length - LENGTH_BASES[code] as usize) as u32,
+                 LENGTH_EXTRA_BITS[code] as u32);
+    let code = DISTANCE_BASES.iter().rposition(|&base| base as usize <= distance).unwrap_or(0);
+    writer.write_code(code as u32, 5);
+    writer.write((distance - DISTANCE_BASES[code] as usize) as u32,
+                 DISTANCE_EXTRA_BITS[code] as u32);
+}
+
+// Write `symbol` from the literal/length alphabet with its fixed Huffman code.
+fn write_literal_or_length(writer: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+// Packs bits into bytes least significant first, as DEFLATE requires.
+struct BitWriter {
+    output: Vec<u8>,
+    bits  : u32,
+    count : u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.output.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Huffman codes are packed most significant bit first.
+    fn write_code(&mut self, code: u32, count: u32) {
+        self.write(code.reverse_bits() >> (32 - count), count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.output.push(self.bits as u8);
+        }
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn compresses_repetitive_text() {
+        let text = (0..1000)
+                       .map(|index| format!("I 12:24:07.065746 Worker [routing:core.rs:{}] Sent\n",
+                                            index % 7))
+                       .collect::<String>();
+        let gzip = compress(text.as_bytes());
+        assert_eq!(&gzip[..3], &[0x1f, 0x8b, 8]);
+        assert!(gzip.len() * 10 < text.len());
+        let trailer = &gzip[gzip.len() - 8..];
+        assert_eq!(&trailer[..4], &crc32(text.as_bytes()).to_le_bytes());
+        assert_eq!(&trailer[4..], &(text.len() as u32).to_le_bytes());
+        assert_eq!(compress(b"").len(), 20);
+    }
+
+    #[test]
+    fn round_trip() {
+        let repetitive = b"I 12:24:07 Worker [routing:core.rs:42] Sent\n".repeat(500);
+        // Pseudo-random text, so that matches are short and scattered over the whole window.
+        let mut seed = 1u32;
+        let large = (0..100 * 1024)
+                        .map(|_| {
+                            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                            b"abcdefgh \n"[(seed >> 16) as usize % 10]
+                        })
+                        .collect::<Vec<_>>();
+        let inputs: [&[u8]; 5] = [b"", b"a", b"Hello, world!", &repetitive, &large];
+        for input in &inputs {
+            assert_eq!(&inflate(&compress(input))[..], *input);
+        }
+    }
+
+    // A decoder for gzip files holding stored or fixed-Huffman blocks, which is all `compress`
+    // produces, checking the trailer as it goes.
+    fn inflate(gzip: &[u8]) -> Vec<u8> {
+        assert_eq!(&gzip[..4], &[0x1f, 0x8b, 8, 0]);
+        let mut reader = BitReader {
+            bytes   : &gzip[10..],
+            position: 0,
+        };
+        let mut output = Vec::new();
+        loop {
+            let is_final = reader.bits(1) == 1;
+            match reader.bits(2) {
+                0 => {
+                    reader.position = reader.position.div_ceil(8) * 8;
+                    let length = reader.bits(16) as usize;
+                    assert_eq!(reader.bits(16) as usize, !length & 0xffff);
+                    let start = reader.position / 8;
+                    output.extend_from_slice(&reader.bytes[start..start + length]);
+                    reader.position += length * 8;
+                }
+                1 => {
+                    loop {
+                        let symbol = reader.literal_or_length() as usize;
+                        if symbol < 256 {
+                            output.push(symbol as u8);
+                            continue;
+                        } else if symbol == 256 {
+                            break;
+                        }
+                        let code = symbol - 257;
+                        let length = LENGTH_BASES[code] as usize +
+                                     reader.bits(LENGTH_EXTRA_BITS[code] as u32) as usize;
+                        let code = reader.code(5) as usize;
+                        let distance = DISTANCE_BASES[code] as usize +
+                                       reader.bits(DISTANCE_EXTRA_BITS[code] as u32) as usize;
+                        assert!(distance <= output.len() && distance <= WINDOW_SIZE);
+                        for _ in 0..length {
+                            let byte = output[output.len() - distance];
+                            output.push(byte);
+                        }
+                    }
+                }
+                block_type => panic!("Unexpected block type {}", block_type),
+            }
+            if is_final {
+                break;
+            }
+        }
+        let trailer = &reader.bytes[reader.position.div_ceil(8)..];
+        assert_eq!(trailer.len(), 8);
+        assert_eq!(&trailer[..4], &crc32(&output).to_le_bytes());
+        assert_eq!(&trailer[4..], &(output.len() as u32).to_le_bytes());
+        output
+    }
+
+    struct BitReader<'a> {
+        bytes   : &'a [u8],
+        position: usize,
+    }
+
+    impl<'a> BitReader<'a> {
+        fn bit(&mut self) -> u32 {
+            let bit = self.bytes[self.position / 8] >> (self.position % 8) & 1;
+            self.position += 1;
+            u32::from(bit)
+        }
+
+        // A value packed least significant bit first.
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, index| value | self.bit() << index)
+        }
+
+        // A Huffman code, packed most significant bit first.
+        fn code(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |code, _| code << 1 | self.bit())
+        }
+
+        fn literal_or_length(&mut self) -> u32 {
+            let code = self.code(7);
+            if code <= 0x17 {
+                return code + 256;
+            }
+            let code = code << 1 | self.bit();
+            match code {
+                0x30..=0xbf => code - 0x30,
+                0xc0..=0xc7 => code - 0xc0 + 280,
+                _ => (code << 1 | self.bit()) - 0x190 + 144,
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Formatter, Record, Value};
+
+/// Formats each record as a single-line JSON object, for ingestion by tools such as ELK or Loki.
+///
+/// The object has the fields `timestamp` (RFC 3339 in UTC, to the microsecond), `level`, `target`,
+/// `thread`, `module`, `file`, `line` and `message`, then `fields` holding any key-value pairs
+/// attached by e.g. `info_kv!` (omitted if there are none), followed by any added via `field`.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, JsonFormatter};
+///
+/// // E.g. {"timestamp":"2016-05-04T19:33:49.245434Z","level":"WARN","target":"example",
+/// //       "thread":"main","module":"example","file":"src/main.rs","line":50,
+/// //       "message":"Warning level message.","service":"vault"}
+/// let formatter = JsonFormatter::new().field("service", "vault");
+/// unwrap_result!(Builder::new().formatter(formatter).init());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JsonFormatter {
+    fields: Vec<(String, String)>,
+}
+
+impl JsonFormatter {
+    /// Format records with just the standard fields.
+    pub fn new() -> JsonFormatter {
+        JsonFormatter { fields: Vec::new() }
+    }
+
+    /// Add the string field `key` with `value` to every record, e.g. to identify the node or
+    /// service. Fields appear in the order added, after the standard ones.
+    pub fn field<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> JsonFormatter {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+}
+
+impl Formatter for JsonFormatter {
+    fn format(&self, record: &Record) -> String {
+        let since_epoch = record.timestamp
+                                .duration_since(::std::time::UNIX_EPOCH)
+                                .unwrap_or_default();
+        let time = ::time::at_utc(::time::Timespec::new(since_epoch.as_secs() as i64,
+                                                        since_epoch.subsec_nanos() as i32));
+        let timestamp = format!("{}.{:06}Z",
+                                unwrap_result!(::time::strftime("%Y-%m-%dT%H:%M:%S", &time)),
+                                time.tm_nsec / 1000);
+        let mut json = String::from("{");
+        let strings = [("timestamp", &timestamp[..]),
+                       ("level", &record.level.to_string()[..]),
+                       ("target", &record.target[..]),
+                       ("thread", &record.thread_name[..]),
+                       ("module", &record.module_path[..]),
+                       ("file", &record.file[..])];
+        for &(key, value) in &strings {
+            push_string(&mut json, key);
+            json.push(':');
+            push_string(&mut json, value);
+            json.push(',');
+        }
+        json.push_str(&format!("\"line\":{},\"message\":", record.line));
+        push_string(&mut json, &record.message);
+        push_fields(&mut json, &record.fields);
+        for (key, value) in &self.fields {
+            json.push(',');
+            push_string(&mut json, key);
+            json.push(':');
+            push_string(&mut json, value);
+        }
+        json.push('}');
+        json
+    }
+}
+
+// Append `,"fields":{...}` to `json`, unless `fields` is empty.
+pub fn push_fields(json: &mut String, fields: &[(&'static str, Value)]) {
+    if fields.is_empty() {
+        return;
+    }
+    json.push_str(",\"fields\":{");
+    for (index, &(key, ref value)) in fields.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        push_string(json, key);
+        json.push(':');
+        match *value {
+            Value::Str(ref value) => push_string(json, value),
+            Value::F64(value) if !value.is_finite() => json.push_str("null"),
+            ref value => json.push_str(&value.to_string()),
+        }
+    }
+    json.push('}');
+}
+
+// Append `value` to `json` as a quoted, escaped JSON string.
+pub fn push_string(json: &mut String, value: &str) {
+    json.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            character if character < ' ' => {
+                json.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => json.push(character),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn json_format() {
+        let mut record = Record::for_test(LogLevel::Info, "Said \"hi\"\n\u{1}");
+        record.timestamp = UNIX_EPOCH + Duration::new(1_462_390_429, 245_434_000);
+        let json = JsonFormatter::new().field("node", "A\\B").format(&record);
+        assert_eq!(json,
+                   "{\"timestamp\":\"2016-05-04T19:33:49.245434Z\",\"level\":\"INFO\",\
+                    \"target\":\"maidsafe_utilities::log::test\",\"thread\":\"TestThread\",\
+                    \"module\":\"maidsafe_utilities::log::test\",\"file\":\"src/log/test.rs\",\
+                    \"line\":42,\"message\":\"Said \\\"hi\\\"\\n\\u0001\",\"node\":\"A\\\\B\"}");
+
+        record.fields = vec![("peer", Value::from("10.0.0.1")),
+                             ("bytes", Value::from(7u32)),
+                             ("ratio", Value::from(0.5)),
+                             ("bad", Value::from(f64::NAN)),
+                             ("ok", Value::from(false))];
+        let json = JsonFormatter::new().format(&record);
+        assert!(json.ends_with(",\"fields\":{\"peer\":\"10.0.0.1\",\"bytes\":7,\"ratio\":0.5,\
+                                \"bad\":null,\"ok\":false}}"),
+                "{}",
+                json);
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    // The fields of the record being logged by `__log_kv` on this thread.
+    static FIELDS: RefCell<Vec<(&'static str, Value)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The value of a key-value pair attached to a record by the `*_kv!` macros, keeping its type so
+/// that the JSON and remote formats can serialise numbers and booleans as such.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A string, or a value given as `%value` (formatted via `Display`) or `?value` (via
+    /// `Debug`).
+    Str(String),
+    /// A signed integer.
+    I64(i64),
+    /// An unsigned integer.
+    U64(u64),
+    /// A floating-point number.
+    F64(f64),
+    /// A boolean.
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Str(ref value) => write!(formatter, "{}", value),
+            Value::I64(value) => write!(formatter, "{}", value),
+            Value::U64(value) => write!(formatter, "{}", value),
+            Value::F64(value) => write!(formatter, "{}", value),
+            Value::Bool(value) => write!(formatter, "{}", value),
+        }
+    }
+}
+
+macro_rules! impl_from {
+    ($variant:ident, $target:ty, $($source:ty),+) => {
+        $(
+            impl From<$source> for Value {
+                #[allow(trivial_numeric_casts)]
+                fn from(value: $source) -> Value {
+                    Value::$variant(value as $target)
+                }
+            }
+        )+
+    }
+}
+
+impl_from!(I64, i64, i8, i16, i32, i64, isize);
+impl_from!(U64, u64, u8, u16, u32, u64, usize);
+impl_from!(F64, f64, f32, f64);
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Value {
+        Value::Str(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Value {
+        Value::Str(value)
+    }
+}
+
+impl<'a> From<&'a String> for Value {
+    fn from(value: &'a String) -> Value {
+        Value::Str(value.clone())
+    }
+}
+
+// Used by `log_kv!`: log a record carrying `fields`.
+#[doc(hidden)]
+pub fn __log_kv(level: LogLevel,
+                target: &str,
+                location: (&'static str, &'static str, u32),
+                args: fmt::Arguments,
+                fields: Vec<(&'static str, Value)>) {
+    if level > ::logger::max_log_level() {
+        return;
+    }
+    FIELDS.with(|pending| *pending.borrow_mut() = fields);
+    super::log_at(level, target, location, args);
+    // In case the record was filtered out before being captured.
+    FIELDS.with(|pending| pending.borrow_mut().clear());
+}
+
+// The fields passed to `__log_kv` for the record being captured on this thread, if any.
+pub fn take_fields() -> Vec<(&'static str, Value)> {
+    FIELDS.with(|pending| ::std::mem::take(&mut *pending.borrow_mut()))
+}
+
+/// Log a record with key-value fields at a given level, as for `log!` but with the fields
+/// following the message after a semicolon. See `info_kv!`.
+#[macro_export]
+macro_rules! log_kv {
+    (target: $target:expr, $level:expr, $format:expr $(, $arg:expr)* ; $($fields:tt)+) => {
+        $crate::log::__log_kv($level,
+                              $target,
+                              (module_path!(), file!(), line!()),
+                              format_args!($format $(, $arg)*),
+                              $crate::__kv_fields!($($fields)+))
+    };
+    ($level:expr, $format:expr $(, $arg:expr)* ; $($fields:tt)+) => {
+        $crate::log_kv!(target: module_path!(), $level, $format $(, $arg)*; $($fields)+)
+    };
+}
+
+// Used by `log_kv!` to collect `key = value`, `key = %value` and `key = ?value` pairs into a
+// `Vec`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __kv_fields {
+    (@collect [$($done:expr,)*]) => { vec![$($done),*] };
+    (@collect [$($done:expr,)*] $key:ident = % $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__kv_fields!(@collect [$($done,)*
+                                       (stringify!($key),
+                                        $crate::log::Value::Str($value.to_string())),]
+                             $($($rest)*)?)
+    };
+    (@collect [$($done:expr,)*] $key:ident = ? $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__kv_fields!(@collect [$($done,)*
+                                       (stringify!($key),
+                                        $crate::log::Value::Str(format!("{:?}", $value))),]
+                             $($($rest)*)?)
+    };
+    (@collect [$($done:expr,)*] $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__kv_fields!(@collect [$($done,)*
+                                       (stringify!($key), $crate::log::Value::from($value)),]
+                             $($($rest)*)?)
+    };
+    ($($fields:tt)*) => { $crate::__kv_fields!(@collect [] $($fields)*) };
+}
+
+/// Log an error with key-value fields. See `info_kv!`.
+#[macro_export]
+macro_rules! error_kv {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_kv, Error, $($arg)+) };
+}
+
+/// Log a warning with key-value fields. See `info_kv!`.
+#[macro_export]
+macro_rules! warn_kv {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_kv, Warn, $($arg)+) };
+}
+
+/// Log at info level with key-value fields, which the JSON and WebSocket formats serialise with
+/// their types under `fields` and the text format appends as `key=value`, so that analysis need
+/// not parse values out of the message.
+///
+/// The fields follow the message and its arguments after a semicolon. A value given as
+/// `key = value` must convert into a `log::Value` (strings, integers, floats and booleans do),
+/// while `key = %value` records it via `Display` and `key = ?value` via `Debug`. As for `info!`,
+/// the target defaults to the module path and can be given as `target: "..."` first.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// let peer: std::net::SocketAddr = unwrap_result!("10.0.0.1:5483".parse());
+/// let bytes = 1024;
+/// unwrap_result!(maidsafe_utilities::log::init_logging());
+/// info_kv!("Received {} bytes", bytes; peer = %peer, bytes = bytes, retried = false);
+/// warn_kv!(target: "routing::core", "Slow response"; peer = %peer, millis = 1500.5);
+/// # }
+/// ```
+///
+/// This and the other `*_kv!` and `*_sampled!` macros can also be invoked by path, without
+/// `#[macro_use]`:
+///
+/// ```
+/// # fn main() {
+/// maidsafe_utilities::info_kv!("Joined"; peers = 3);
+/// maidsafe_utilities::trace_sampled!(target: "routing::core", 10, "Sent packet");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! info_kv {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_kv, Info, $($arg)+) };
+}
+
+/// Log at debug level with key-value fields. See `info_kv!`.
+#[macro_export]
+macro_rules! debug_kv {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_kv, Debug, $($arg)+) };
+}
+
+/// Log at trace level with key-value fields. See `info_kv!`.
+#[macro_export]
+macro_rules! trace_kv {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_kv, Trace, $($arg)+) };
+}
+
+// Used by the level-specific macros to invoke `$log`, e.g. `log_kv!`, at `$level`, moving any
+// `target: ...` ahead of the level.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at_level {
+    ($log:ident, $level:ident, target: $target:expr, $($arg:tt)+) => {
+        $crate::$log!(target: $target, $crate::log::__LogLevel::$level, $($arg)+)
+    };
+    ($log:ident, $level:ident, $($arg:tt)+) => {
+        $crate::$log!($crate::log::__LogLevel::$level, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn collect_fields() {
+        let peer: ::std::net::SocketAddr = unwrap_result!("10.0.0.1:5483".parse());
+        let name = "Vault".to_owned();
+        let fields = __kv_fields!(peer = %peer, kind = ?Some(3), name = &name, bytes = 7usize,
+                                  delta = -2, ratio = 0.5, ok = true,);
+        assert_eq!(fields,
+                   vec![("peer", Value::Str("10.0.0.1:5483".to_owned())),
+                        ("kind", Value::Str("Some(3)".to_owned())),
+                        ("name", Value::Str("Vault".to_owned())),
+                        ("bytes", Value::U64(7)),
+                        ("delta", Value::I64(-2)),
+                        ("ratio", Value::F64(0.5)),
+                        ("ok", Value::Bool(true))]);
+    }
+
+    #[test]
+    fn fields_do_not_leak_into_later_records() {
+        // With no logger installed here, the record is dropped before being captured.
+        info_kv!("Unlogged"; peer = "a");
+        assert!(take_fields().is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::{Log, LogLevelFilter, LogMetadata, LogRecord, MaxLogLevelFilter};
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use super::{Appender, Filter, Formatter, Record};
+use super::capture;
+use super::crash_buffer::CrashBuffer;
+use super::repeats::Repeats;
+use super::stats::{CallSite, Stats};
+
+// The crate's implementation of `log::Log`, writing each record to every appender.
+pub struct Logger {
+    filter   : RwLock<Filter>,
+    // The handle to the `log` crate's global maximum level, once installed.
+    max_level: OnceLock<MaxLogLevelFilter>,
+    formatter: Box<dyn Formatter>,
+    appenders: Vec<Box<dyn Appender>>,
+    repeats  : Option<Repeats>,
+    crash    : Option<CrashBuffer>,
+    stats    : Stats,
+}
+
+impl Logger {
+    pub fn new(filter: Filter,
+               formatter: Box<dyn Formatter>,
+               appenders: Vec<Box<dyn Appender>>)
+               -> Logger {
+        Logger {
+            filter   : RwLock::new(filter),
+            max_level: OnceLock::new(),
+            formatter,
+            appenders,
+            repeats  : None,
+            crash    : None,
+            stats    : Stats::default(),
+        }
+    }
+
+    pub fn collapse_repeats(&mut self, window: Duration) {
+        self.repeats = Some(Repeats::new(window));
+    }
+
+    pub fn crash_buffer(&mut self, capacity: usize, path: PathBuf) {
+        self.crash = Some(CrashBuffer::new(capacity, path));
+    }
+
+    pub fn max_level(&self) -> LogLevelFilter {
+        unwrap_result!(self.filter.read()).max_level()
+    }
+
+    // Keep the `log` crate's maximum level in step with the filter from now on.
+    pub fn attach(&self, max_level: MaxLogLevelFilter) {
+        let _ = self.max_level.set(max_level);
+        self.refresh_max_level();
+    }
+
+    // Update the `log` crate's maximum level, which is the most verbose for any module unless a
+    // thread is capturing records or there is a crash buffer.
+    pub fn refresh_max_level(&self) {
+        if let Some(max_level) = self.max_level.get() {
+            max_level.set(if capture::is_active() || self.crash.is_some() {
+                LogLevelFilter::Trace
+            } else {
+                self.max_level()
+            });
+        }
+    }
+
+    pub fn set_filter(&self, filter: Filter) {
+        self.update_filter(|current| *current = filter)
+    }
+
+    pub fn update_filter<F: FnOnce(&mut Filter)>(&self, update: F) {
+        update(&mut unwrap_result!(self.filter.write()));
+        self.refresh_max_level();
+    }
+
+    pub fn flush(&self) {
+        if let Some(ref repeats) = self.repeats {
+            repeats.flush(|record| self.write(record));
+        }
+        for appender in &self.appenders {
+            if let Err(error) = appender.flush() {
+                eprintln!("Failed to flush log appender: {}", error);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<CallSite> {
+        self.stats.snapshot()
+    }
+
+    pub fn dump_crash_buffer(&self) {
+        if let Some(ref crash) = self.crash {
+            if let Err(error) = crash.dump(&*self.formatter) {
+                eprintln!("Failed to dump log crash buffer: {}", error);
+            }
+        }
+    }
+
+    fn filter_enables(&self, metadata: &LogMetadata) -> bool {
+        unwrap_result!(self.filter.read()).enabled(metadata.level(), metadata.target())
+    }
+
+    fn write(&self, record: &Record) {
+        let line = self.formatter.format(record);
+        for appender in &self.appenders {
+            if let Err(error) = appender.append(record, &line) {
+                eprintln!("Failed to write log record: {}", error);
+            }
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        capture::is_capturing() || self.crash.is_some() || self.filter_enables(metadata)
+    }
+
+    fn log(&self, record: &LogRecord) {
+        let capturing = capture::is_capturing();
+        let enabled = self.filter_enables(record.metadata());
+        if !capturing && !enabled && self.crash.is_none() {
+            return;
+        }
+        let record = Record::new(record);
+        if capturing {
+            capture::record(&record);
+        }
+        if let Some(ref crash) = self.crash {
+            crash.push(record.clone());
+        }
+        if !enabled {
+            return;
+        }
+        self.stats.count(&record);
+        match self.repeats {
+            Some(ref repeats) => repeats.check(record, |record| self.write(record)),
+            None => self.write(&record),
+        }
+    }
+}
+
+
+// The installed logger, shared with `log::flush`.
+pub struct SharedLogger(pub ::std::sync::Arc<Logger>);
+
+impl Log for SharedLogger {
+    fn enabled(&self, metadata: &LogMetadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &LogRecord) {
+        self.0.log(record)
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+pub use self::appender::{Appender, Colour, ConsoleAppender, StderrAppender};
+pub use self::async_appender::AsyncAppender;
+pub use self::builder::{Builder, LogInitError};
+pub use self::capture::{Capture, capture};
+pub use self::config::ConfigError;
+#[cfg(all(windows, feature = "event-log"))]
+pub use self::event_log::EventLogAppender;
+pub use self::file::FileAppender;
+pub use self::filter::Filter;
+pub use self::filtered::FilteredAppender;
+pub use self::format::{Formatter, TextFormatter, TimeFormat, TimePrecision, TimeZone};
+pub use self::json::JsonFormatter;
+pub use self::kv::Value;
+#[doc(hidden)]
+pub use self::kv::__log_kv;
+#[doc(hidden)]
+pub use logger::LogLevel as __LogLevel;
+pub use self::pattern::{PatternError, PatternFormatter};
+pub use self::record::Record;
+#[doc(hidden)]
+pub use self::sample::__log_sampled;
+pub use self::stats::CallSite;
+#[cfg(all(unix, feature = "syslog"))]
+pub use self::syslog::{Facility, SyslogAppender};
+pub use self::tcp::TcpAppender;
+pub use self::udp::{MAX_DATAGRAM_SIZE, UdpAppender};
+pub use self::websocket::WebSocketAppender;
+
+use self::config::Config;
+use self::logger::{Logger, SharedLogger};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+mod appender;
+mod async_appender;
+mod builder;
+mod capture;
+mod config;
+mod crash_buffer;
+#[cfg(all(windows, feature = "event-log"))]
+mod event_log;
+mod file;
+mod filter;
+mod filtered;
+mod format;
+mod gzip;
+mod json;
+mod kv;
+mod logger;
+mod pattern;
+mod reconnect;
+mod record;
+mod repeats;
+mod sample;
+mod stats;
+#[cfg(all(unix, feature = "syslog"))]
+mod syslog;
+mod tcp;
+mod udp;
+mod websocket;
+
+/// The environment variable from which `init_logging` reads its directives.
+pub const DEFAULT_ENV_VAR: &str = "RUST_LOG";
+
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+static LOGGER: OnceLock<Arc<Logger>> = OnceLock::new();
+
+/// Initialise logging in one call, for use at the start of every binary and test: the levels to
+/// log are read as `RUST_LOG`-style directives (see `Filter`) from the `RUST_LOG` environment
+/// variable, and records are written to stderr in the default format shown for `init`, including
+/// the thread name and coloured by level if stderr is a terminal (see `Colour`).
+///
+/// Only the first logger initialised in the process takes effect, whether by this or by any other
+/// of the init functions here: once one has been, later calls do nothing and return `Ok`, so tests
+/// and libraries can each call it freely, even concurrently. An error is returned only if a
+/// logger other than this module's was installed for the `log` crate first. Records are written
+/// via `eprintln!`, so the test harness captures them along with the test's other output.
+///
+/// #Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     // E.g. with `RUST_LOG=warn,my_crate::network=trace`
+///     unwrap_result!(maidsafe_utilities::log::init_logging());
+///     unwrap_result!(maidsafe_utilities::log::init_logging());
+///     warn!("Logged once");
+/// }
+/// ```
+pub fn init_logging() -> Result<(), LogInitError> {
+    init_logging_from_env(DEFAULT_ENV_VAR)
+}
+
+/// As `init_logging`, but reading the directives from the environment variable `var_name`, e.g.
+/// to let each binary in a workspace be configured separately.
+pub fn init_logging_from_env(var_name: &str) -> Result<(), LogInitError> {
+    Builder::new().filter(Filter::from_env(var_name)).init()
+}
+
+/// Initialise logging as configured by the file at `path`, then keep watching the file so that
+/// verbosity can be tuned without restarting, e.g. on a running vault.
+///
+/// The file is in a subset of TOML: comments, `key = value` pairs whose values are basic
+/// strings, integers or booleans, `[table]`s and `[[array]]`s of tables. Every key is optional:
+///
+/// ```text
+/// # The default level; see `[levels]` below for particular modules.
+/// level = "info"
+///
+/// # Either the default text format, with or without thread names...
+/// show_thread_name = true
+/// # ...or "json" for a `JsonFormatter`...
+/// format = "json"
+/// # ...or a `PatternFormatter` layout.
+/// pattern = "{time} {level} [{thread}] {target} - {msg}"
+///
+/// # How the default text format and `{time}` show times, as for `TimeFormat`: "local" (the
+/// # default) or "utc"; "s", "ms" or "us" (the default); and whether to show them as ISO 8601.
+/// time_zone = "utc"
+/// time_precision = "ms"
+/// iso8601 = true
+///
+/// # Log every panic with a backtrace, as for `Builder::log_panics`.
+/// log_panics = true
+///
+/// # Collapse identical records logged within this many milliseconds, as for
+/// # `Builder::collapse_repeats`.
+/// collapse_repeats_ms = 1000
+///
+/// # Whether to colour records on stderr: "auto" (the default, see `Colour`), "always" or "never".
+/// colour = "auto"
+///
+/// # Write warnings and errors to stderr and other records to stdout, as for
+/// # `Builder::split_console`.
+/// split_console = true
+///
+/// # Levels for particular modules, as for `RUST_LOG` directives.
+/// [levels]
+/// routing = "debug"
+/// "routing::core" = "trace"
+///
+/// # Keep the last records at every level in memory, and write them to `path` on an uncaught
+/// # panic, as for `Builder::crash_buffer`.
+/// [crash_buffer]
+/// capacity = 10_000  # Records, defaulting to 10,000.
+/// path = "vault-crash.log"
+///
+/// # Any number of appenders; just stderr (or the console as above) if there are none.
+/// [[appenders]]
+/// kind = "stderr"  # Or "console", splitting records between stdout and stderr.
+/// colour = "never"  # As above, for this appender.
+/// # Any appender can have its own level, format (as above) and regexes, as for
+/// # `FilteredAppender`: only records at `level` or more severe, and whose messages match
+/// # `include` and not `exclude`, are written to it.
+/// level = "warn"
+/// pattern = "{level} {msg}"
+/// exclude = "^(Sent|Received) packet"
+///
+/// [[appenders]]
+/// kind = "file"
+/// path = "vault.log"
+/// max_size = 10_485_760  # Bytes, defaulting to 10 MiB.
+/// keep = 5               # Rotated files, defaulting to 5.
+/// gzip = true            # Compress rotated files, as for `FileAppender::with_gzip`.
+/// max_total_size = 52_428_800  # Bytes for this and the rotated files; unlimited by default.
+///
+/// [[appenders]]
+/// kind = "tcp"  # Or "udp".
+/// address = "10.0.0.1:5514"
+/// ```
+///
+/// The file is checked for changes every two seconds, and the levels reapplied when it changes;
+/// the format and appenders are only read at start-up. An edit which makes the file invalid is
+/// reported on stderr and otherwise ignored.
+///
+/// Keys other than those above are rejected, as are unknown tables and appender kinds.
+///
+/// As for `init_logging`, only the first logger initialised in the process takes effect; if
+/// another was initialised first, this only checks that the file parses and that its keys and
+/// levels are valid, without opening its appenders or watching it.
+pub fn init_logging_from_file<P: AsRef<Path>>(path: P) -> Result<(), LogInitError> {
+    let path = path.as_ref().to_path_buf();
+    let config = Config::load(&path)?;
+    // Building opens the appenders, e.g. creating log files, so is skipped when it would be in
+    // vain. Should another logger be installed meanwhile, the appenders are just dropped.
+    if LOGGER.get().is_some() {
+        return Ok(());
+    }
+    if builder::install(config.builder()?)? {
+        let logger = unwrap_option!(LOGGER.get(), "Logger should be installed").clone();
+        config::watch(path, logger, config::WATCH_INTERVAL).map_err(ConfigError::Io)?.detach();
+    }
+    Ok(())
+}
+
+/// This function initialises the logger, taking the levels to log from the `RUST_LOG` environment
+/// variable as for `init_logging`.
+///
+/// An example of a log message is:
+///
+/// ```
+/// # fn main() { /*
+/// W 19:33:49.245434 <main> [example:src/main.rs:50] Warning level message.
+/// ^        ^          ^        ^          ^                    ^
+/// |    timestamp      | top-level module  |                 message
+/// |                   |                   |
+/// |              thread name       file and line no.
+/// |
+/// level (E, W, I, D, or T for error, warn, info, debug or trace respectively)
+/// # */}
+/// ```
+///
+/// Logging of the thread name is enabled or disabled via the `show_thread_name` parameter.  If
+/// enabled, and the thread executing the log statement is unnamed, it is identified by its id as
+/// for `thread::current_name`.
+///
+/// As for `init_logging`, the function can safely be called multiple times concurrently.
+///
+/// #Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     unwrap_result!(maidsafe_utilities::log::init(true));
+///
+///     warn!("A warning");
+///
+///     let unnamed = ::std::thread::spawn(move || info!("Message in unnamed thread"));
+///     let _ = unnamed.join();
+///
+///     let _named = ::maidsafe_utilities::thread::RaiiThreadJoiner::new(thread!("Worker",
+///                      move || error!("Message in named thread")));
+///
+///     // W 12:24:07.064746 <main> [example:src/main.rs:9] A warning
+///     // I 12:24:07.065746 ThreadId(2) [example:src/main.rs:11] Message in unnamed thread
+///     // E 12:24:07.065746 Worker [example:src/main.rs:14] Message in named thread
+/// }
+/// ```
+pub fn init(show_thread_name: bool) -> Result<(), LogInitError> {
+    Builder::new().show_thread_name(show_thread_name).init()
+}
+
+/// Change the level enabled for the module path `target` and those nested within it, on the
+/// logger already running, e.g. from a debug RPC to turn a single module up to `Trace` on a live
+/// node while investigating. An empty `target` sets the level for every module without a more
+/// specific one, as for a bare `RUST_LOG` directive.
+///
+/// The change lasts until the level is set again, `reset_level` is called, or a configuration
+/// file being watched by `init_logging_from_file` changes. This does nothing if no logger has
+/// been initialised via this module.
+///
+/// #Examples
+///
+/// ```
+/// # extern crate log;
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use log::LogLevelFilter;
+/// use maidsafe_utilities::log;
+///
+/// unwrap_result!(log::init_logging());
+/// log::set_level("routing::core", LogLevelFilter::Trace);
+/// // Investigate, then put things back as they were.
+/// log::reset_level("routing::core");
+/// # }
+/// ```
+pub fn set_level(target: &str, level: ::logger::LogLevelFilter) {
+    if let Some(logger) = LOGGER.get() {
+        logger.update_filter(|filter| filter.set_level(target, level));
+    }
+}
+
+/// Undo `set_level` for exactly the module path `target`, so that its records are again checked
+/// against the levels of the enclosing modules. This does nothing if no logger has been
+/// initialised via this module.
+pub fn reset_level(target: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger.update_filter(|filter| filter.reset_level(target));
+    }
+}
+
+/// How many records each logging statement has logged so far, most first, e.g. to find the
+/// hot spots in a node's logging or spot a warning logged in a loop. Only records the filter
+/// enabled are counted. This is empty if no logger has been initialised via this module.
+///
+/// #Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate log;
+/// #[macro_use]
+/// extern crate maidsafe_utilities;
+///
+/// fn main() {
+///     unwrap_result!(maidsafe_utilities::log::init_logging());
+///     for attempt in 0..3 {
+///         error!("Connection attempt {} failed", attempt);
+///     }
+///     let top = &maidsafe_utilities::log::stats()[0];
+///     assert_eq!(top.count, 3);
+///     println!("{}:{} logged {} records", top.file, top.line, top.count);
+/// }
+/// ```
+pub fn stats() -> Vec<CallSite> {
+    LOGGER.get().map_or_else(Vec::new, |logger| logger.stats())
+}
+
+/// Flush every appender of the installed logger, e.g. before exiting so that records queued by an
+/// `AsyncAppender` are not lost. Failures are reported on stderr. This does nothing if no logger
+/// has been initialised via this module.
+pub fn flush() {
+    if let Some(logger) = LOGGER.get() {
+        logger.flush();
+    }
+}
+
+/// Append the records held by the crash buffer to its file, emptying it, as happens on an
+/// uncaught panic; see `Builder::crash_buffer`. Call this on a fatal error before exiting.
+/// Failures are reported on stderr. This does nothing if no logger with a crash buffer has been
+/// initialised via this module.
+pub fn dump_crash_buffer() {
+    if let Some(logger) = LOGGER.get() {
+        logger.dump_crash_buffer();
+    }
+}
+
+// Log a record on behalf of macros such as `log_kv!`, which pass their call site as a
+// `(module path, file, line)` tuple.
+fn log_at(level: ::logger::LogLevel,
+          target: &str,
+          location: (&'static str, &'static str, u32),
+          args: ::std::fmt::Arguments) {
+    let (module_path, file, line) = location;
+    let location = ::logger::LogLocation {
+        __module_path: module_path,
+        __file       : file,
+        __line       : line,
+    };
+    ::logger::__log(level, target, &location, args);
+}
+
+// Install `logger` unless one already has been via this module, returning whether this call did
+// so. A failure to install leaves later calls free to try again, getting the same error.
+fn install(logger: Logger) -> Result<bool, LogInitError> {
+    let _guard = INSTALL_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    if LOGGER.get().is_some() {
+        return Ok(false);
+    }
+    let logger = Arc::new(logger);
+    let logger_clone = logger.clone();
+    ::logger::set_logger(move |max_log_level| {
+        logger_clone.attach(max_log_level);
+        Box::new(SharedLogger(logger_clone))
+    })?;
+    let _ = LOGGER.set(logger);
+    Ok(true)
+}
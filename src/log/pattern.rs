@@ -0,0 +1,227 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use super::{Formatter, Record, TimeFormat};
+use super::format::format_fields;
+
+/// Why a layout string given to `PatternFormatter::new` is invalid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// A `{...}` placeholder not in the list documented for `PatternFormatter`.
+    UnknownPlaceholder(String),
+    /// A `{` without a closing `}`, or a `}` not closing a placeholder and not doubled.
+    UnmatchedBrace,
+}
+
+impl ::std::fmt::Display for PatternError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            PatternError::UnknownPlaceholder(ref name) => {
+                write!(formatter, "Unknown log layout placeholder {{{}}}", name)
+            }
+            PatternError::UnmatchedBrace => write!(formatter, "Unmatched brace in log layout"),
+        }
+    }
+}
+
+impl ::std::error::Error for PatternError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Time,
+    Date,
+    Level,
+    ShortLevel,
+    Thread,
+    Target,
+    TopModule,
+    Module,
+    File,
+    Line,
+    Message,
+    Fields,
+}
+
+/// Formats records according to a layout string, so that each binary can choose how much
+/// metadata to show.
+///
+/// The placeholders are:
+///
+/// * `{time}`: the local time as `HH:MM:SS.micros`, or as set by `with_time_format`
+/// * `{date}`: the date as `YYYY-MM-DD`, in the time format's zone
+/// * `{level}`: the level, e.g. `WARN`, and `{l}` its initial, e.g. `W`
+/// * `{thread}`: the name of the logging thread, or its id if it is unnamed
+/// * `{target}`: the record's target
+/// * `{top_module}` and `{module}`: the top-level module and full module path of the logging
+///   statement
+/// * `{file}` and `{line}`: the logging statement's location
+/// * `{msg}`: the message
+/// * `{fields}`: any key-value pairs attached by e.g. `info_kv!`, as space-separated `key=value`
+///
+/// `{{` and `}}` stand for literal braces. For anything else, pass `Builder::formatter` a closure
+/// instead.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, PatternFormatter};
+///
+/// let layout = "{time} {level} [{thread}] {target} - {msg}";
+/// let formatter = unwrap_result!(PatternFormatter::new(layout));
+/// unwrap_result!(Builder::new().formatter(formatter).init());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternFormatter {
+    segments   : Vec<Segment>,
+    time_format: TimeFormat,
+}
+
+impl PatternFormatter {
+    /// Parse `layout`, failing if it contains an unknown placeholder or an unmatched brace.
+    pub fn new(layout: &str) -> Result<PatternFormatter, PatternError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut characters = layout.chars().peekable();
+        while let Some(character) = characters.next() {
+            match character {
+                '{' if characters.peek() == Some(&'{') => {
+                    let _ = characters.next();
+                    literal.push('{');
+                }
+                '}' if characters.peek() == Some(&'}') => {
+                    let _ = characters.next();
+                    literal.push('}');
+                }
+                '}' => return Err(PatternError::UnmatchedBrace),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match characters.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(PatternError::UnmatchedBrace),
+                            Some(next) => name.push(next),
+                        }
+                    }
+                    let segment = match &name[..] {
+                        "time" => Segment::Time,
+                        "date" => Segment::Date,
+                        "level" => Segment::Level,
+                        "l" => Segment::ShortLevel,
+                        "thread" => Segment::Thread,
+                        "target" => Segment::Target,
+                        "top_module" => Segment::TopModule,
+                        "module" => Segment::Module,
+                        "file" => Segment::File,
+                        "line" => Segment::Line,
+                        "msg" => Segment::Message,
+                        "fields" => Segment::Fields,
+                        _ => return Err(PatternError::UnknownPlaceholder(name)),
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal));
+                        literal = String::new();
+                    }
+                    segments.push(segment);
+                }
+                character => literal.push(character),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(PatternFormatter {
+            segments,
+            time_format: TimeFormat::new(),
+        })
+    }
+
+    /// Show `{time}` as `time_format` says.
+    pub fn with_time_format(mut self, time_format: TimeFormat) -> PatternFormatter {
+        self.time_format = time_format;
+        self
+    }
+}
+
+impl Formatter for PatternFormatter {
+    fn format(&self, record: &Record) -> String {
+        let mut line = String::new();
+        for segment in &self.segments {
+            match *segment {
+                Segment::Literal(ref literal) => line.push_str(literal),
+                Segment::Time => line.push_str(&self.time_format.format(record.timestamp)),
+                Segment::Date => line.push_str(&self.time_format.date(record.timestamp)),
+                Segment::Level => line.push_str(&record.level.to_string()),
+                Segment::ShortLevel => {
+                    line.push(match record.level {
+                        LogLevel::Error => 'E',
+                        LogLevel::Warn => 'W',
+                        LogLevel::Info => 'I',
+                        LogLevel::Debug => 'D',
+                        LogLevel::Trace => 'T',
+                    })
+                }
+                Segment::Thread => line.push_str(&record.thread_name),
+                Segment::Target => line.push_str(&record.target),
+                Segment::TopModule => {
+                    line.push_str(record.module_path.split("::").next().unwrap_or(""))
+                }
+                Segment::Module => line.push_str(&record.module_path),
+                Segment::File => line.push_str(&record.file),
+                Segment::Line => line.push_str(&record.line.to_string()),
+                Segment::Message => line.push_str(&record.message),
+                Segment::Fields => line.push_str(&format_fields(&record.fields)),
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        let record = Record::for_test(LogLevel::Warn, "Careful");
+        let formatter = unwrap_result!(PatternFormatter::new("{{{l}}} {level} [{thread}] \
+                                                              {target} {top_module} \
+                                                              {file}:{line} - {msg}!"));
+        assert_eq!(formatter.format(&record),
+                   "{W} WARN [TestThread] maidsafe_utilities::log::test maidsafe_utilities \
+                    src/log/test.rs:42 - Careful!");
+
+        let formatter = unwrap_result!(PatternFormatter::new("{date} {time}"));
+        let line = formatter.format(&record);
+        assert_eq!(line.len(), "2016-05-04 19:33:49.245434".len(), "{}", line);
+    }
+
+    #[test]
+    fn invalid_layouts() {
+        assert_eq!(PatternFormatter::new("{lvl}"),
+                   Err(PatternError::UnknownPlaceholder("lvl".to_owned())));
+        assert_eq!(PatternFormatter::new("{msg"), Err(PatternError::UnmatchedBrace));
+        assert_eq!(PatternFormatter::new("{msg {line}"), Err(PatternError::UnmatchedBrace));
+        assert_eq!(PatternFormatter::new("x {"), Err(PatternError::UnmatchedBrace));
+        assert_eq!(PatternFormatter::new("msg}"), Err(PatternError::UnmatchedBrace));
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::io;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const INITIAL_DELAY_MS: u64 = 100;
+const MAX_DELAY_MS: u64 = 30_000;
+
+// The connection behind an appender which streams records to a remote endpoint, shared by
+// `TcpAppender` and `WebSocketAppender`.
+//
+// The connection is made for the first record and remade whenever it fails. After a failure,
+// reconnecting is not attempted again until a backoff delay has passed, which doubles with each
+// consecutive failure from `initial_delay` up to `max_delay`; records sent meanwhile are dropped
+// and counted.
+pub struct Reconnecting<S> {
+    initial_delay: Duration,
+    max_delay    : Duration,
+    state        : Mutex<State<S>>,
+    dropped      : AtomicUsize,
+}
+
+struct State<S> {
+    stream  : Option<S>,
+    retry_at: Instant,
+    delay   : Duration,
+}
+
+impl<S> Reconnecting<S> {
+    // Not yet connected, backing off from 100 milliseconds up to 30 seconds.
+    pub fn new() -> Reconnecting<S> {
+        let initial_delay = Duration::from_millis(INITIAL_DELAY_MS);
+        Reconnecting {
+            initial_delay,
+            max_delay: Duration::from_millis(MAX_DELAY_MS),
+            state    : Mutex::new(State {
+                stream  : None,
+                retry_at: Instant::now(),
+                delay   : initial_delay,
+            }),
+            dropped  : AtomicUsize::new(0),
+        }
+    }
+
+    pub fn set_backoff(&mut self, initial_delay: Duration, max_delay: Duration) {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+        unwrap_result!(self.state.lock()).delay = initial_delay;
+    }
+
+    // The number of records dropped so far because the endpoint could not be reached.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::SeqCst)
+    }
+
+    // Send a record with `send`, first connecting with `connect` if need be. If either fails,
+    // the record is counted as dropped, the connection closed and the error returned.
+    pub fn send<C, F>(&self, connect: C, send: F) -> io::Result<()>
+        where C: FnOnce() -> io::Result<S>,
+              F: FnOnce(&mut S) -> io::Result<()>
+    {
+        let mut state = unwrap_result!(self.state.lock());
+        if state.stream.is_none() {
+            if Instant::now() < state.retry_at {
+                let _ = self.dropped.fetch_add(1, Ordering::SeqCst);
+                return Ok(());
+            }
+            match connect() {
+                Ok(stream) => state.stream = Some(stream),
+                Err(error) => return self.fail(&mut state, error),
+            }
+        }
+        let result = match state.stream {
+            Some(ref mut stream) => send(stream),
+            None => Ok(()),
+        };
+        match result {
+            Ok(()) => {
+                state.delay = self.initial_delay;
+                Ok(())
+            }
+            Err(error) => self.fail(&mut state, error),
+        }
+    }
+
+    // Run `action` on the connection, if there is one.
+    pub fn with_stream<F>(&self, action: F) -> io::Result<()>
+        where F: FnOnce(&mut S) -> io::Result<()>
+    {
+        match unwrap_result!(self.state.lock()).stream {
+            Some(ref mut stream) => action(stream),
+            None => Ok(()),
+        }
+    }
+
+    fn fail(&self, state: &mut State<S>, error: io::Error) -> io::Result<()> {
+        let _ = self.dropped.fetch_add(1, Ordering::SeqCst);
+        state.stream = None;
+        state.retry_at = Instant::now() + state.delay;
+        state.delay = (state.delay * 2).min(self.max_delay);
+        Err(error)
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::{LogLevel, LogRecord};
+use super::Value;
+use super::kv::take_fields;
+use std::time::SystemTime;
+
+/// A log record as handed to each `Appender`, owning its data so that it can be kept or sent to
+/// another thread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    /// The record's level.
+    pub level      : LogLevel,
+    /// The record's target, by default the module path of the logging statement.
+    pub target     : String,
+    /// The module path of the logging statement.
+    pub module_path: String,
+    /// The source file of the logging statement.
+    pub file       : String,
+    /// The line of the logging statement.
+    pub line       : u32,
+    /// The name of the logging thread, or its id if it is unnamed, as for `thread::current_name`.
+    /// For threads spawned via the crate this is the name the crate gave them, including any
+    /// `unique_name` suffix and, for threads spawned from another crate thread, the spawning
+    /// thread's name as a prefix (e.g. `Routing/Connection-7`), so a single worker can be
+    /// followed through interleaved records.
+    pub thread_name: String,
+    /// When the record was logged.
+    pub timestamp  : SystemTime,
+    /// The formatted message.
+    pub message    : String,
+    /// The key-value pairs attached by e.g. `info_kv!`, in the order given.
+    pub fields     : Vec<(&'static str, Value)>,
+}
+
+impl Record {
+    /// Capture `record`, as logged by the current thread just now.
+    pub fn new(record: &LogRecord) -> Record {
+        Record {
+            level      : record.level(),
+            target     : record.target().to_owned(),
+            module_path: record.location().module_path().to_owned(),
+            file       : record.location().file().to_owned(),
+            line       : record.location().line(),
+            thread_name: ::thread::current_name(),
+            timestamp  : SystemTime::now(),
+            fields     : take_fields(),
+            message    : record.args().to_string(),
+        }
+    }
+
+    /// An identifier for the logging statement, the same on every run for as long as it stays on
+    /// the same line of the same file, e.g. for counting a statement's records in a log
+    /// aggregator. This is the id in `log::stats`.
+    pub fn call_site_id(&self) -> u64 {
+        call_site_id(&self.file, self.line)
+    }
+}
+
+// A 64-bit FNV-1a hash of `file` and `line`, which unlike `DefaultHasher` is stable across
+// builds.
+fn call_site_id(file: &str, line: u32) -> u64 {
+    file.bytes()
+        .chain(Some(b':'))
+        .chain(line.to_le_bytes().iter().cloned())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[cfg(test)]
+impl Record {
+    /// A record logged at `level` with `message`, for the tests of the appenders and formatters.
+    pub fn for_test(level: LogLevel, message: &str) -> Record {
+        Record {
+            level,
+            target     : "maidsafe_utilities::log::test".to_owned(),
+            module_path: "maidsafe_utilities::log::test".to_owned(),
+            file       : "src/log/test.rs".to_owned(),
+            line       : 42,
+            thread_name: "TestThread".to_owned(),
+            timestamp  : SystemTime::now(),
+            message    : message.to_owned(),
+            fields     : Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use super::Record;
+
+// Collapses runs of identical records into a single "repeated" summary.
+pub struct Repeats {
+    window: Duration,
+    last  : Mutex<Option<Run>>,
+}
+
+// The most recently written record, and how many identical ones have been held back since.
+struct Run {
+    record  : Record,
+    started : Instant,
+    repeated: usize,
+}
+
+impl Repeats {
+    pub fn new(window: Duration) -> Repeats {
+        Repeats {
+            window,
+            last: Mutex::new(None),
+        }
+    }
+
+    // Pass `record` to `write` unless it repeats the last record within the window, first
+    // writing a summary of any repeats held back.
+    pub fn check<F: FnMut(&Record)>(&self, record: Record, mut write: F) {
+        let mut last = unwrap_result!(self.last.lock());
+        if let Some(ref mut run) = *last {
+            if run.started.elapsed() < self.window && is_repeat(&run.record, &record) {
+                run.repeated += 1;
+                return;
+            }
+        }
+        if let Some(run) = last.take() {
+            run.summarise(&mut write);
+        }
+        write(&record);
+        *last = Some(Run {
+            record,
+            started : Instant::now(),
+            repeated: 0,
+        });
+    }
+
+    // Write a summary of any repeats held back, e.g. before exiting.
+    pub fn flush<F: FnMut(&Record)>(&self, mut write: F) {
+        let mut last = unwrap_result!(self.last.lock());
+        if let Some(ref mut run) = *last {
+            run.summarise(&mut write);
+            run.repeated = 0;
+        }
+    }
+}
+
+impl Run {
+    fn summarise<F: FnMut(&Record)>(&self, write: &mut F) {
+        if self.repeated == 0 {
+            return;
+        }
+        let mut summary = self.record.clone();
+        summary.timestamp = SystemTime::now();
+        summary.message = if self.repeated == 1 {
+            "Last message repeated once".to_owned()
+        } else {
+            format!("Last message repeated {} times", self.repeated)
+        };
+        write(&summary);
+    }
+}
+
+fn is_repeat(last: &Record, record: &Record) -> bool {
+    last.level == record.level && last.target == record.target &&
+    last.message == record.message && last.fields == record.fields
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+
+    #[test]
+    fn collapses_repeats() {
+        let repeats = Repeats::new(Duration::from_secs(3600));
+        let mut written = Vec::new();
+        for message in &["Flap", "Flap", "Flap", "Other", "Other", "Flap"] {
+            repeats.check(Record::for_test(LogLevel::Warn, message),
+                          |record| written.push(record.message.clone()));
+        }
+        repeats.flush(|record| written.push(record.message.clone()));
+        assert_eq!(written,
+                   vec!["Flap",
+                        "Last message repeated 2 times",
+                        "Other",
+                        "Last message repeated once",
+                        "Flap"]);
+    }
+
+    #[test]
+    fn window_ends_the_run() {
+        let repeats = Repeats::new(Duration::from_millis(20));
+        let mut written = Vec::new();
+        repeats.check(Record::for_test(LogLevel::Warn, "Flap"),
+                      |record| written.push(record.message.clone()));
+        repeats.check(Record::for_test(LogLevel::Warn, "Flap"),
+                      |record| written.push(record.message.clone()));
+        ::std::thread::sleep(Duration::from_millis(40));
+        repeats.check(Record::for_test(LogLevel::Warn, "Flap"),
+                      |record| written.push(record.message.clone()));
+        assert_eq!(written, vec!["Flap", "Last message repeated once", "Flap"]);
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+thread_local! {
+    // The state of this thread's xorshift generator, or zero until it is seeded.
+    static STATE: Cell<u64> = const { Cell::new(0) };
+}
+
+// Used by `log_sampled!`: log a record with probability 1 in `rate`, noting the rate in its
+// message.
+#[doc(hidden)]
+pub fn __log_sampled(level: LogLevel,
+                     target: &str,
+                     location: (&'static str, &'static str, u32),
+                     rate: u32,
+                     args: fmt::Arguments) {
+    if level > ::logger::max_log_level() || !sample(rate) {
+        return;
+    }
+    super::log_at(level, target, location, format_args!("{} [sampled 1/{}]", args, rate));
+}
+
+// Whether to take this sample, with probability 1 in `rate`. A `rate` of 0 or 1 takes them all.
+fn sample(rate: u32) -> bool {
+    if rate <= 1 {
+        return true;
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        if x == 0 {
+            x = RandomState::new().build_hasher().finish() | 1;
+        }
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) % u64::from(rate) == 0
+    })
+}
+
+/// Log a record with probability 1 in `rate` at a given level, as for `log!` but with the rate
+/// following the level. See `trace_sampled!`.
+#[macro_export]
+macro_rules! log_sampled {
+    (target: $target:expr, $level:expr, $rate:expr, $($arg:tt)+) => {
+        $crate::log::__log_sampled($level,
+                                   $target,
+                                   (module_path!(), file!(), line!()),
+                                   $rate,
+                                   format_args!($($arg)+))
+    };
+    ($level:expr, $rate:expr, $($arg:tt)+) => {
+        $crate::log_sampled!(target: module_path!(), $level, $rate, $($arg)+)
+    };
+}
+
+/// Log an error with probability 1 in `rate`. See `trace_sampled!`.
+#[macro_export]
+macro_rules! error_sampled {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_sampled, Error, $($arg)+) };
+}
+
+/// Log a warning with probability 1 in `rate`. See `trace_sampled!`.
+#[macro_export]
+macro_rules! warn_sampled {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_sampled, Warn, $($arg)+) };
+}
+
+/// Log at info level with probability 1 in `rate`. See `trace_sampled!`.
+#[macro_export]
+macro_rules! info_sampled {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_sampled, Info, $($arg)+) };
+}
+
+/// Log at debug level with probability 1 in `rate`. See `trace_sampled!`.
+#[macro_export]
+macro_rules! debug_sampled {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_sampled, Debug, $($arg)+) };
+}
+
+/// Log at trace level with probability 1 in `rate`, e.g. for a record per packet, so that hot
+/// paths can be traced during load tests at a fraction of the cost. Each record logged has
+/// ` [sampled 1/<rate>]` appended to its message, so counts can be scaled back up. Which calls
+/// are logged is random, and independent on each thread; a `rate` of 0 or 1 logs every call.
+///
+/// The arguments are only formatted for calls which are logged. As for `trace!`, the target
+/// defaults to the module path and can be given as `target: "..."` first.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// unwrap_result!(maidsafe_utilities::log::init_logging());
+/// for sequence in 0..10_000 {
+///     // E.g. `Sent packet 4283 [sampled 1/1000]`, about ten times.
+///     trace_sampled!(1000, "Sent packet {}", sequence);
+/// }
+/// debug_sampled!(target: "crust::service", 100, "Polled");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! trace_sampled {
+    ($($arg:tt)+) => { $crate::__log_at_level!(log_sampled, Trace, $($arg)+) };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn samples_at_the_rate() {
+        assert!((0..100).all(|_| sample(0) && sample(1)));
+        let taken = (0..10_000).filter(|_| sample(4)).count();
+        assert!(taken > 2000 && taken < 3000, "{}", taken);
+
+        let capture = ::log::capture();
+        for index in 0..100 {
+            trace_sampled!(1, "Packet {}", index);
+            trace_sampled!(target: "sampled", 1_000_000_000, "Rare");
+        }
+        capture.assert_logged(LogLevel::Trace, "Packet 99 [sampled 1/1]");
+        assert_eq!(capture.records().len(), 100);
+    }
+}
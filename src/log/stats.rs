@@ -0,0 +1,109 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use logger::LogLevel;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use super::Record;
+
+/// How many records a single logging statement has logged, as returned by `log::stats`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    /// The statement's id, as for `Record::call_site_id`.
+    pub id         : u64,
+    /// The level of the statement's records.
+    pub level      : LogLevel,
+    /// The module path of the statement.
+    pub module_path: String,
+    /// The source file of the statement.
+    pub file       : String,
+    /// The line of the statement.
+    pub line       : u32,
+    /// How many of the statement's records the logger's filter has enabled so far.
+    pub count      : u64,
+}
+
+// The counts for every call site which has logged, keyed by id. Counting a call site seen before
+// only takes the read lock.
+#[derive(Default)]
+pub struct Stats {
+    call_sites: RwLock<HashMap<u64, (CallSite, AtomicU64)>>,
+}
+
+impl Stats {
+    pub fn count(&self, record: &Record) {
+        let id = record.call_site_id();
+        if let Some((_, count)) = unwrap_result!(self.call_sites.read()).get(&id) {
+            let _ = count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let mut call_sites = unwrap_result!(self.call_sites.write());
+        let (_, count) = call_sites.entry(id).or_insert_with(|| {
+            let call_site = CallSite {
+                id,
+                level      : record.level,
+                module_path: record.module_path.clone(),
+                file       : record.file.clone(),
+                line       : record.line,
+                count      : 0,
+            };
+            (call_site, AtomicU64::new(0))
+        });
+        let _ = count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // The call sites, most frequent first.
+    pub fn snapshot(&self) -> Vec<CallSite> {
+        let mut call_sites = unwrap_result!(self.call_sites.read())
+                                 .values()
+                                 .map(|(call_site, count)| {
+                                     CallSite {
+                                         count: count.load(Ordering::Relaxed),
+                                         ..call_site.clone()
+                                     }
+                                 })
+                                 .collect::<Vec<_>>();
+        call_sites.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.id.cmp(&b.id)));
+        call_sites
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_by_call_site() {
+        let stats = Stats::default();
+        let mut record = Record::for_test(LogLevel::Warn, "Retrying");
+        for _ in 0..3 {
+            stats.count(&record);
+        }
+        record.line += 1;
+        record.message = "Different message, different line".to_owned();
+        stats.count(&record);
+
+        let call_sites = stats.snapshot();
+        assert_eq!(call_sites.len(), 2);
+        assert_eq!(call_sites[0].count, 3);
+        assert_eq!(call_sites[0].line, 42);
+        assert_eq!(call_sites[0].id, Record::for_test(LogLevel::Info, "").call_site_id());
+        assert_eq!((call_sites[1].count, call_sites[1].line), (1, 43));
+        assert_ne!(call_sites[0].id, call_sites[1].id);
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Writing to syslog requires FFI.
+#![allow(unsafe_code)]
+
+use logger::LogLevel;
+use std::ffi::CString;
+use std::io;
+use std::sync::OnceLock;
+use super::{Appender, Record};
+
+// The identity passed to `openlog`, which must outlive every later `syslog` call.
+static IDENTITY: OnceLock<CString> = OnceLock::new();
+
+/// The syslog facility under which `SyslogAppender` logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facility {
+    /// `LOG_USER`, for ordinary programs.
+    User,
+    /// `LOG_DAEMON`, for system daemons such as a packaged vault.
+    Daemon,
+    /// `LOG_LOCAL0` to `LOG_LOCAL7`, reserved for local use; the value must be at most 7.
+    Local(u8),
+}
+
+impl Facility {
+    fn code(self) -> ::libc::c_int {
+        match self {
+            Facility::User => ::libc::LOG_USER,
+            Facility::Daemon => ::libc::LOG_DAEMON,
+            Facility::Local(index) => {
+                assert!(index <= 7, "There is no syslog facility LOG_LOCAL{}", index);
+                ::libc::LOG_LOCAL0 + (index as ::libc::c_int) * 8
+            }
+        }
+    }
+}
+
+/// Sends each record to the system logger via syslog(3), and so to journald or syslogd on Linux
+/// distributions. Only available on Unix with the `syslog` feature.
+///
+/// Errors map to `LOG_ERR`, warnings to `LOG_WARNING`, info to `LOG_INFO` and debug and trace to
+/// `LOG_DEBUG`. As the system logger records its own timestamp, the logger's format is not used;
+/// each message is sent as `<thread> [<target>] <message>`.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, Facility, SyslogAppender};
+///
+/// let appender = SyslogAppender::new("safe_vault", Facility::Daemon);
+/// unwrap_result!(Builder::new().appender(appender).init());
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SyslogAppender {
+    facility: ::libc::c_int,
+}
+
+impl SyslogAppender {
+    /// Log under `facility`, with each message tagged with `identity` (conventionally the
+    /// program's name) and the process id.
+    ///
+    /// The system logger only has one identity per process, so only the first `identity` given
+    /// takes effect. Any nul bytes in it are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `facility` is `Local` with a value over 7.
+    pub fn new(identity: &str, facility: Facility) -> SyslogAppender {
+        let facility = facility.code();
+        let _ = IDENTITY.get_or_init(|| {
+            let identity = without_nuls(identity);
+            unsafe {
+                ::libc::openlog(identity.as_ptr(), ::libc::LOG_PID, facility);
+            }
+            identity
+        });
+        SyslogAppender { facility }
+    }
+}
+
+impl Appender for SyslogAppender {
+    fn append(&self, record: &Record, _line: &str) -> io::Result<()> {
+        let message = without_nuls(&format!("{} [{}] {}",
+                                           record.thread_name,
+                                           record.target,
+                                           record.message));
+        unsafe {
+            ::libc::syslog(self.facility | priority(record.level),
+                         b"%s\0".as_ptr() as *const ::libc::c_char,
+                         message.as_ptr());
+        }
+        Ok(())
+    }
+}
+
+fn priority(level: LogLevel) -> ::libc::c_int {
+    match level {
+        LogLevel::Error => ::libc::LOG_ERR,
+        LogLevel::Warn => ::libc::LOG_WARNING,
+        LogLevel::Info => ::libc::LOG_INFO,
+        LogLevel::Debug | LogLevel::Trace => ::libc::LOG_DEBUG,
+    }
+}
+
+fn without_nuls(text: &str) -> CString {
+    unwrap_result!(CString::new(text.replace('\0', "")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn priorities_and_facilities() {
+        assert_eq!(priority(LogLevel::Warn), ::libc::LOG_WARNING);
+        assert_eq!(priority(LogLevel::Trace), ::libc::LOG_DEBUG);
+        assert_eq!(Facility::Local(3).code(), ::libc::LOG_LOCAL3);
+        assert_eq!(without_nuls("a\0b").as_bytes(), b"ab");
+
+        let appender = SyslogAppender::new("maidsafe_utilities_test", Facility::User);
+        unwrap_result!(appender.append(&Record::for_test(LogLevel::Debug, "Syslog test"), ""));
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use super::{Appender, Record};
+use super::reconnect::Reconnecting;
+
+const CONNECT_TIMEOUT_MS: u64 = 1000;
+const WRITE_TIMEOUT_MS: u64 = 1000;
+
+/// Streams records over TCP to a central collector, one formatted record per line.
+///
+/// The connection is made on the first record and remade whenever it fails. After a failure,
+/// reconnecting is not attempted again until a backoff delay has passed, which doubles with each
+/// consecutive failure from 100 milliseconds up to 30 seconds (see `with_backoff`); records logged
+/// meanwhile are dropped and counted by `dropped`. As connecting and writing block the logging
+/// thread for up to a second, wrap this in an `AsyncAppender` for use on hot paths.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, TcpAppender};
+///
+/// let appender = unwrap_result!(TcpAppender::new("127.0.0.1:5514"));
+/// unwrap_result!(Builder::new().appender(appender).init());
+/// # }
+/// ```
+pub struct TcpAppender {
+    address   : SocketAddr,
+    connection: Reconnecting<TcpStream>,
+}
+
+impl TcpAppender {
+    /// Send records to `address`, which is resolved now; the first address it resolves to is
+    /// used.
+    pub fn new<A: ToSocketAddrs>(address: A) -> io::Result<TcpAppender> {
+        let address = address.to_socket_addrs()?
+                             .next()
+                             .ok_or_else(|| {
+                                 io::Error::new(io::ErrorKind::InvalidInput,
+                                                "Log collector address did not resolve")
+                             })?;
+        Ok(TcpAppender {
+            address,
+            connection: Reconnecting::new(),
+        })
+    }
+
+    /// Wait `initial_delay` after a failure before reconnecting, doubling the delay with each
+    /// consecutive failure up to `max_delay`.
+    pub fn with_backoff(mut self, initial_delay: Duration, max_delay: Duration) -> TcpAppender {
+        self.connection.set_backoff(initial_delay, max_delay);
+        self
+    }
+
+    /// The number of records dropped so far because the collector could not be reached.
+    pub fn dropped(&self) -> usize {
+        self.connection.dropped()
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&self.address,
+                                                Duration::from_millis(CONNECT_TIMEOUT_MS))?;
+        stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS)))?;
+        stream.set_nodelay(true)?;
+        Ok(stream)
+    }
+}
+
+impl Appender for TcpAppender {
+    fn append(&self, _record: &Record, line: &str) -> io::Result<()> {
+        self.connection.send(|| self.connect(), |stream| writeln!(stream, "{}", line))
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.connection.with_stream(|stream| stream.flush())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    #[test]
+    fn reconnects_after_backing_off() {
+        let record = Record::for_test(LogLevel::Info, "");
+        // Find a free port, then leave nothing listening on it.
+        let address = unwrap_result!(unwrap_result!(TcpListener::bind("127.0.0.1:0")).local_addr());
+        let appender = unwrap_result!(TcpAppender::new(address))
+                           .with_backoff(Duration::from_millis(50), Duration::from_millis(50));
+        assert!(appender.append(&record, "Lost").is_err());
+        // Backing off, so dropped without another attempt.
+        assert!(appender.append(&record, "Also lost").is_ok());
+        assert_eq!(appender.dropped(), 2);
+
+        let listener = unwrap_result!(TcpListener::bind(address));
+        ::std::thread::sleep(Duration::from_millis(60));
+        unwrap_result!(appender.append(&record, "First"));
+        unwrap_result!(appender.append(&record, "Second"));
+        let (stream, _) = unwrap_result!(listener.accept());
+        let lines = BufReader::new(stream).lines().take(2).map(|line| unwrap_result!(line));
+        assert_eq!(lines.collect::<Vec<_>>(), vec!["First", "Second"]);
+        assert_eq!(appender.dropped(), 2);
+    }
+}
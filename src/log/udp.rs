@@ -0,0 +1,147 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::{Appender, Record};
+use token_bucket::TokenBucket;
+
+/// Sends each record as a single UDP datagram, for telemetry where losing the occasional record is
+/// acceptable but blocking is not.
+///
+/// The socket is non-blocking, and records are rate-limited to 1000 per second in bursts of up to
+/// 1000 unless set otherwise via `with_rate_limit`. Records over the limit, those the socket
+/// cannot take straight away and those which fail to send are dropped and counted by `dropped`;
+/// none of these is reported as an error. A record longer than `MAX_DATAGRAM_SIZE` bytes is
+/// truncated to the last whole character which fits.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, UdpAppender};
+///
+/// let appender = unwrap_result!(UdpAppender::new("127.0.0.1:5514")).with_rate_limit(100, 500);
+/// unwrap_result!(Builder::new().appender(appender).init());
+/// # }
+/// ```
+pub struct UdpAppender {
+    socket : UdpSocket,
+    address: SocketAddr,
+    bucket : Mutex<TokenBucket>,
+    dropped: AtomicUsize,
+}
+
+/// The largest datagram `UdpAppender` sends, chosen to avoid IP fragmentation on typical links.
+pub const MAX_DATAGRAM_SIZE: usize = 1400;
+
+impl UdpAppender {
+    /// Send records to `address`, which is resolved now; the first address it resolves to is
+    /// used.
+    pub fn new<A: ToSocketAddrs>(address: A) -> io::Result<UdpAppender> {
+        let address = address.to_socket_addrs()?
+                             .next()
+                             .ok_or_else(|| {
+                                 io::Error::new(io::ErrorKind::InvalidInput,
+                                                "Log collector address did not resolve")
+                             })?;
+        let local = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpAppender {
+            socket,
+            address,
+            bucket : Mutex::new(TokenBucket::new(1000, 1000)),
+            dropped: AtomicUsize::new(0),
+        })
+    }
+
+    /// Send on average at most `max_per_sec` records per second, and at most `burst` in one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_per_sec` is zero.
+    pub fn with_rate_limit(self, max_per_sec: u32, burst: u32) -> UdpAppender {
+        UdpAppender { bucket: Mutex::new(TokenBucket::new(max_per_sec, burst)), ..self }
+    }
+
+    /// The number of records dropped so far.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::SeqCst)
+    }
+}
+
+impl Appender for UdpAppender {
+    fn append(&self, _record: &Record, line: &str) -> io::Result<()> {
+        let admitted = unwrap_result!(self.bucket.lock()).take().is_ok();
+        let datagram = truncate(line);
+        if !admitted || self.socket.send_to(datagram.as_bytes(), self.address).is_err() {
+            let _ = self.dropped.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+// `line`, truncated if need be to the last character boundary within `MAX_DATAGRAM_SIZE` bytes.
+fn truncate(line: &str) -> &str {
+    let mut length = line.len().min(MAX_DATAGRAM_SIZE);
+    while !line.is_char_boundary(length) {
+        length -= 1;
+    }
+    &line[..length]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+
+    #[test]
+    fn one_datagram_per_record_within_the_rate_limit() {
+        let record = Record::for_test(LogLevel::Info, "");
+        let receiver = unwrap_result!(UdpSocket::bind("127.0.0.1:0"));
+        let timeout = ::std::time::Duration::from_secs(5);
+        unwrap_result!(receiver.set_read_timeout(Some(timeout)));
+        let appender = unwrap_result!(UdpAppender::new(unwrap_result!(receiver.local_addr())))
+                           .with_rate_limit(1, 2);
+        for index in 0..5 {
+            unwrap_result!(appender.append(&record, &format!("Record {}", index)));
+        }
+        unwrap_result!(appender.append(&record, &"x".repeat(2 * MAX_DATAGRAM_SIZE)));
+        assert_eq!(appender.dropped(), 4);
+
+        let mut buffer = [0u8; 2 * MAX_DATAGRAM_SIZE];
+        for expected in &["Record 0", "Record 1"] {
+            let length = unwrap_result!(receiver.recv(&mut buffer));
+            assert_eq!(&buffer[..length], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn truncates_at_a_character_boundary() {
+        assert_eq!(truncate("Short"), "Short");
+        let ascii = "x".repeat(MAX_DATAGRAM_SIZE + 1);
+        assert_eq!(truncate(&ascii).len(), MAX_DATAGRAM_SIZE);
+        // Two-byte characters, with the limit falling in the middle of one.
+        let two_byte = format!("x{}", "é".repeat(MAX_DATAGRAM_SIZE));
+        assert_eq!(truncate(&two_byte).len(), MAX_DATAGRAM_SIZE - 1);
+    }
+}
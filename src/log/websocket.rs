@@ -0,0 +1,548 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::{Appender, Record};
+use super::json::{push_fields, push_string};
+use super::reconnect::Reconnecting;
+use thread::SeededRng;
+
+const TIMEOUT_MS: u64 = 1000;
+// Appended to the handshake key before hashing, as specified by RFC 6455.
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+// The longest handshake response accepted, to bound reading from a misbehaving peer.
+const MAX_RESPONSE_SIZE: usize = 8192;
+// The most received data held while waiting for the rest of a frame.
+const MAX_INCOMING_SIZE: usize = 64 * 1024;
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// Pushes records as JSON text messages over a `ws://` WebSocket, e.g. to the browser-based
+/// network visualiser used to monitor test networks.
+///
+/// Each record is sent as one object with the fields `node` (the identity given to `new`),
+/// `type` (by default the record's target; see `with_message_type`), `level`, `target`,
+/// `thread`, `file`, `line`, `time` (milliseconds since the Unix epoch), `message` and, if it has
+/// any, `fields` holding the record's key-value pairs as for `JsonFormatter`. `wss://` is not
+/// supported.
+///
+/// Frames from the endpoint are only read when a record is about to be sent: pings are then
+/// answered with pongs, a close frame is acknowledged and ends the connection (dropping the
+/// record), and messages are discarded. An endpoint which expects pongs sooner than records are
+/// logged may therefore time the connection out.
+///
+/// Connecting, and reconnecting after a failure, behave as for `TcpAppender`: a failed attempt
+/// backs off from 100 milliseconds up to 30 seconds (see `with_backoff`), records logged
+/// meanwhile are dropped and counted by `dropped`, and each attempt or send may block for up to a
+/// second.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::log::{Builder, WebSocketAppender};
+///
+/// let appender = unwrap_result!(WebSocketAppender::new("ws://127.0.0.1:8080/logs", "Node 1"))
+///                    .with_message_type(|record| record.message
+///                                                      .split(':')
+///                                                      .next()
+///                                                      .unwrap_or("")
+///                                                      .to_owned());
+/// unwrap_result!(Builder::new().appender(appender).init());
+/// # }
+/// ```
+pub struct WebSocketAppender {
+    address     : SocketAddr,
+    host        : String,
+    path        : String,
+    node        : String,
+    message_type: Box<dyn Fn(&Record) -> String + Send + Sync>,
+    connection  : Reconnecting<Socket>,
+    // Generates handshake keys and frame masks.
+    rng         : Mutex<SeededRng>,
+}
+
+struct Socket {
+    stream  : TcpStream,
+    // Received data not yet forming a whole frame.
+    incoming: Vec<u8>,
+}
+
+impl WebSocketAppender {
+    /// Send records to the endpoint at `url`, of the form `ws://host[:port][/path]`, identifying
+    /// this process as `node`. The host is resolved now; the first address it resolves to is
+    /// used.
+    pub fn new<T: Into<String>>(url: &str, node: T) -> io::Result<WebSocketAppender> {
+        let invalid = |reason| io::Error::new(io::ErrorKind::InvalidInput, reason);
+        let rest = match url.strip_prefix("ws://") {
+            Some(rest) => rest,
+            None => return Err(invalid("Log endpoint URL must start with ws://")),
+        };
+        let (host, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        if host.is_empty() {
+            return Err(invalid("Log endpoint URL has no host"));
+        }
+        let address = if host.ends_with(']') || !host.contains(':') {
+            (host.trim_start_matches('[').trim_end_matches(']'), 80).to_socket_addrs()
+        } else {
+            host.to_socket_addrs()
+        };
+        let address = address?.next().ok_or_else(|| invalid("Log endpoint did not resolve"))?;
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        Ok(WebSocketAppender {
+            address,
+            host        : host.to_owned(),
+            path        : path.to_owned(),
+            node        : node.into(),
+            message_type: Box::new(|record| record.target.clone()),
+            connection  : Reconnecting::new(),
+            rng         : Mutex::new(SeededRng::from_seed(nanos as u64)),
+        })
+    }
+
+    /// Wait `initial_delay` after a failure before reconnecting, doubling the delay with each
+    /// consecutive failure up to `max_delay`.
+    pub fn with_backoff(mut self,
+                        initial_delay: Duration,
+                        max_delay: Duration)
+                        -> WebSocketAppender {
+        self.connection.set_backoff(initial_delay, max_delay);
+        self
+    }
+
+    /// Derive each record's `type` field with `message_type` rather than using its target.
+    pub fn with_message_type<F>(self, message_type: F) -> WebSocketAppender
+        where F: Fn(&Record) -> String + Send + Sync + 'static
+    {
+        WebSocketAppender { message_type: Box::new(message_type), ..self }
+    }
+
+    /// The number of records dropped so far because the endpoint could not be reached.
+    pub fn dropped(&self) -> usize {
+        self.connection.dropped()
+    }
+
+    fn connect(&self) -> io::Result<Socket> {
+        let timeout = Duration::from_millis(TIMEOUT_MS);
+        let mut stream = TcpStream::connect_timeout(&self.address, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+
+        let mut nonce = [0u8; 16];
+        unwrap_result!(self.rng.lock()).fill_bytes(&mut nonce);
+        let key = base64(&nonce);
+        write!(stream,
+               "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+               self.path,
+               self.host,
+               key)?;
+
+        // Read byte by byte so as not to consume anything sent after the headers.
+        let mut response = Vec::new();
+        let mut byte = [0u8];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_RESPONSE_SIZE || stream.read(&mut byte)? == 0 {
+                return Err(handshake_error("Incomplete WebSocket handshake response"));
+            }
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8_lossy(&response);
+        let mut lines = response.split("\r\n");
+        if lines.next().and_then(|status| status.split(' ').nth(1)) != Some("101") {
+            return Err(handshake_error("WebSocket endpoint refused the upgrade"));
+        }
+        let expected = accept_key(&key);
+        let accepted = lines.filter_map(|line| {
+                                let mut parts = line.splitn(2, ':');
+                                match (parts.next(), parts.next()) {
+                                    (Some(name), Some(value)) => Some((name, value.trim())),
+                                    _ => None,
+                                }
+                            })
+                            .any(|(name, value)| {
+                                name.eq_ignore_ascii_case("Sec-WebSocket-Accept") &&
+                                value == expected
+                            });
+        if !accepted {
+            return Err(handshake_error("WebSocket endpoint sent the wrong accept key"));
+        }
+        Ok(Socket {
+            stream,
+            incoming: Vec::new(),
+        })
+    }
+
+    fn send_frame(&self, socket: &mut Socket, opcode: u8, payload: &[u8]) -> io::Result<()> {
+        let mut mask = [0u8; 4];
+        unwrap_result!(self.rng.lock()).fill_bytes(&mut mask);
+        socket.stream.write_all(&frame(opcode, payload, mask))
+    }
+
+    // Handle the frames the endpoint has sent since the last record, without blocking.
+    fn service(&self, socket: &mut Socket) -> io::Result<()> {
+        socket.stream.set_nonblocking(true)?;
+        let mut buffer = [0u8; 4096];
+        let received = loop {
+            match socket.stream.read(&mut buffer) {
+                Ok(0) => break Err(closed_error()),
+                Ok(count) => socket.incoming.extend_from_slice(&buffer[..count]),
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                Err(error) => break Err(error),
+            }
+        };
+        socket.stream.set_nonblocking(false)?;
+        received?;
+        while let Some((opcode, payload, length)) = parse_frame(&socket.incoming) {
+            let _ = socket.incoming.drain(..length);
+            match opcode {
+                OPCODE_PING => self.send_frame(socket, OPCODE_PONG, &payload)?,
+                OPCODE_CLOSE => {
+                    // Echo the status code, as RFC 6455 asks.
+                    let status = &payload[..payload.len().min(2)];
+                    let _ = self.send_frame(socket, OPCODE_CLOSE, status);
+                    return Err(closed_error());
+                }
+                _ => (),
+            }
+        }
+        if socket.incoming.len() > MAX_INCOMING_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "WebSocket endpoint sent an oversized frame"));
+        }
+        Ok(())
+    }
+
+    fn to_json(&self, record: &Record) -> String {
+        let millis = record.timestamp
+                           .duration_since(UNIX_EPOCH)
+                           .map(|elapsed| elapsed.as_millis())
+                           .unwrap_or(0);
+        let mut json = String::from("{\"node\":");
+        push_string(&mut json, &self.node);
+        json.push_str(",\"type\":");
+        push_string(&mut json, &(self.message_type)(record));
+        json.push_str(",\"level\":");
+        push_string(&mut json, &record.level.to_string());
+        json.push_str(",\"target\":");
+        push_string(&mut json, &record.target);
+        json.push_str(",\"thread\":");
+        push_string(&mut json, &record.thread_name);
+        json.push_str(",\"file\":");
+        push_string(&mut json, &record.file);
+        json.push_str(&format!(",\"line\":{},\"time\":{},\"message\":", record.line, millis));
+        push_string(&mut json, &record.message);
+        push_fields(&mut json, &record.fields);
+        json.push('}');
+        json
+    }
+}
+
+impl Appender for WebSocketAppender {
+    fn append(&self, record: &Record, _line: &str) -> io::Result<()> {
+        let json = self.to_json(record);
+        self.connection.send(|| self.connect(), |socket| {
+            self.service(socket)?;
+            self.send_frame(socket, OPCODE_TEXT, json.as_bytes())
+        })
+    }
+}
+
+fn handshake_error(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+fn closed_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted,
+                   "WebSocket endpoint closed the connection")
+}
+
+// The opcode and payload of the first frame in `bytes`, and the frame's length, if it has all
+// arrived.
+fn parse_frame(bytes: &[u8]) -> Option<(u8, Vec<u8>, usize)> {
+    if bytes.len() < 2 {
+        return None;
+    }
+    let (length, mut start) = match bytes[1] & 0x7f {
+        126 if bytes.len() >= 4 => (u64::from(u16::from_be_bytes([bytes[2], bytes[3]])), 4),
+        127 if bytes.len() >= 10 => {
+            let mut length = [0u8; 8];
+            length.copy_from_slice(&bytes[2..10]);
+            (u64::from_be_bytes(length), 10)
+        }
+        126 | 127 => return None,
+        length => (u64::from(length), 2),
+    };
+    // Endpoints should not mask their frames, but unmasking costs nothing.
+    let mut mask = [0u8; 4];
+    if bytes[1] & 0x80 != 0 {
+        if bytes.len() < start + 4 {
+            return None;
+        }
+        mask.copy_from_slice(&bytes[start..start + 4]);
+        start += 4;
+    }
+    if ((bytes.len() - start) as u64) < length {
+        return None;
+    }
+    let end = start + length as usize;
+    let payload = bytes[start..end].iter().zip(mask.iter().cycle()).map(|(byte, mask)| byte ^ mask);
+    Some((bytes[0] & 0x0f, payload.collect(), end))
+}
+
+// A single, final frame carrying `payload`, masked with `mask` as required of clients.
+fn frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    if payload.len() < 126 {
+        frame.push(0x80 | payload.len() as u8);
+    } else if payload.len() <= 0xffff {
+        frame.push(0x80 | 126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    } else {
+        frame.push(0x80 | 127);
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().zip(mask.iter().cycle()).map(|(byte, mask)| byte ^ mask));
+    frame
+}
+
+// The `Sec-WebSocket-Accept` value a conforming endpoint returns for `key`.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key, HANDSHAKE_GUID).as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (index, &byte)| {
+            value | (byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// SHA-1, which the handshake requires despite its weakness; it is not used for anything else.
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    for block in padded.chunks(64) {
+        let mut words = [0u32; 80];
+        for (index, word) in block.chunks(4).enumerate() {
+            words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..80 {
+            words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^
+                            words[index - 16])
+                .rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5)
+                        .wrapping_add(f)
+                        .wrapping_add(e)
+                        .wrapping_add(k)
+                        .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (total, value) in state.iter_mut().zip(&[a, b, c, d, e]) {
+            *total = total.wrapping_add(*value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, value) in digest.chunks_mut(4).zip(&state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use logger::LogLevel;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    #[test]
+    fn handshake_accept_key() {
+        // The example from RFC 6455, section 1.3.
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    // Accept a connection on `listener` and complete the handshake, returning the stream, a
+    // reader over it and the request's lines.
+    fn handshake(listener: &TcpListener) -> (TcpStream, BufReader<TcpStream>, Vec<String>) {
+        let (stream, _) = unwrap_result!(listener.accept());
+        let mut reader = BufReader::new(unwrap_result!(stream.try_clone()));
+        let mut request = Vec::new();
+        loop {
+            let mut line = String::new();
+            let _ = unwrap_result!(reader.read_line(&mut line));
+            if line == "\r\n" {
+                break;
+            }
+            request.push(line.trim_end().to_owned());
+        }
+        let key = unwrap_option!(request.iter().find(|line| line.starts_with("Sec-WebSocket-Key")),
+                                 "No key");
+        let key = unwrap_option!(key.split(": ").nth(1), "No key");
+        unwrap_result!(write!(&stream,
+                              "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                               Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                              accept_key(key)));
+        (stream, reader, request)
+    }
+
+    // Read one frame sent by the appender, returning its opcode and unmasked payload.
+    fn read_frame(reader: &mut BufReader<TcpStream>) -> (u8, Vec<u8>) {
+        let mut header = [0u8; 2];
+        unwrap_result!(reader.read_exact(&mut header));
+        assert!(header[1] & 0x80 != 0 && header[1] & 0x7f < 127);
+        let mut length = (header[1] & 0x7f) as usize;
+        if length == 126 {
+            let mut extended = [0u8; 2];
+            unwrap_result!(reader.read_exact(&mut extended));
+            length = u16::from_be_bytes(extended) as usize;
+        }
+        let mut mask = [0u8; 4];
+        unwrap_result!(reader.read_exact(&mut mask));
+        let mut payload = vec![0u8; length];
+        unwrap_result!(reader.read_exact(&mut payload));
+        for (byte, mask) in payload.iter_mut().zip(mask.iter().cycle()) {
+            *byte ^= mask;
+        }
+        (header[0], payload)
+    }
+
+    #[test]
+    fn sends_masked_json_messages() {
+        let listener = unwrap_result!(TcpListener::bind("127.0.0.1:0"));
+        let url = format!("ws://{}/visualiser", unwrap_result!(listener.local_addr()));
+        let endpoint = thread!("Visualiser", move || {
+            let (_stream, mut reader, request) = handshake(&listener);
+
+            // The message is over 125 bytes, so has a 16-bit length.
+            let mut header = [0u8; 8];
+            unwrap_result!(reader.read_exact(&mut header));
+            assert_eq!(&header[..2], &[0x81, 0x80 | 126]);
+            let mut payload = vec![0u8; u16::from_be_bytes([header[2], header[3]]) as usize];
+            unwrap_result!(reader.read_exact(&mut payload));
+            for (byte, mask) in payload.iter_mut().zip(header[4..].iter().cycle()) {
+                *byte ^= mask;
+            }
+            (request[0].clone(), unwrap_result!(String::from_utf8(payload)))
+        });
+
+        let appender = unwrap_result!(WebSocketAppender::new(&url, "Node \"1\""));
+        unwrap_result!(appender.append(&Record::for_test(LogLevel::Info, "Joined"), ""));
+        let (request_line, message) = unwrap_result!(endpoint.join());
+        assert_eq!(request_line, "GET /visualiser HTTP/1.1");
+        assert!(message.starts_with("{\"node\":\"Node \\\"1\\\"\",\
+                                      \"type\":\"maidsafe_utilities::log::test\","),
+                "{}",
+                message);
+        assert!(message.ends_with(",\"message\":\"Joined\"}"), "{}", message);
+        assert_eq!(appender.dropped(), 0);
+    }
+
+    #[test]
+    fn answers_pings_and_closes() {
+        let listener = unwrap_result!(TcpListener::bind("127.0.0.1:0"));
+        let url = format!("ws://{}/", unwrap_result!(listener.local_addr()));
+        let (sent_tx, sent_rx) = ::std::sync::mpsc::channel();
+        let endpoint = thread!("PingingEndpoint", move || {
+            let (mut stream, mut reader, _) = handshake(&listener);
+            assert_eq!(read_frame(&mut reader).0, 0x81);
+            unwrap_result!(stream.write_all(&[0x89, 4, b'p', b'i', b'n', b'g']));
+            unwrap_result!(sent_tx.send(()));
+            assert_eq!(read_frame(&mut reader), (0x8a, b"ping".to_vec()));
+            assert_eq!(read_frame(&mut reader).0, 0x81);
+            // Close with status 1000, a normal closure.
+            unwrap_result!(stream.write_all(&[0x88, 2, 0x03, 0xe8]));
+            unwrap_result!(sent_tx.send(()));
+            assert_eq!(read_frame(&mut reader), (0x88, vec![0x03, 0xe8]));
+        });
+
+        let appender = unwrap_result!(WebSocketAppender::new(&url, "Node"))
+                           .with_backoff(Duration::from_secs(60), Duration::from_secs(60));
+        let record = Record::for_test(LogLevel::Info, "Ping");
+        unwrap_result!(appender.append(&record, ""));
+        unwrap_result!(sent_rx.recv());
+        unwrap_result!(appender.append(&record, ""));
+        unwrap_result!(sent_rx.recv());
+        assert!(appender.append(&record, "").is_err());
+        assert_eq!(appender.dropped(), 1);
+        unwrap_result!(endpoint.join());
+    }
+
+    #[test]
+    fn parses_frames() {
+        assert_eq!(parse_frame(&[0x89]), None);
+        assert_eq!(parse_frame(&[0x89, 2, b'h']), None);
+        assert_eq!(parse_frame(&[0x89, 2, b'h', b'i', 0x88]), Some((0x9, b"hi".to_vec(), 4)));
+        let mut long = vec![0x81, 126, 0, 200];
+        long.extend_from_slice(&[b'x'; 200]);
+        assert_eq!(parse_frame(&long), Some((0x1, vec![b'x'; 200], 204)));
+        let masked = frame(OPCODE_PONG, b"hi", [1, 2, 3, 4]);
+        assert_eq!(parse_frame(&masked), Some((0xa, b"hi".to_vec(), 8)));
+    }
+
+    #[test]
+    fn rejects_unsupported_urls() {
+        assert!(WebSocketAppender::new("wss://127.0.0.1/", "Node").is_err());
+        assert!(WebSocketAppender::new("ws:///", "Node").is_err());
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+const HEADER_PREFIX: &[u8] = b"#maidsafe-data-version ";
+
+/// Errors that can be returned while loading or storing migratable data.
+#[derive(Debug)]
+pub enum MigrationError {
+    /// Error reading or writing the data, its backup or its history.
+    Io(::std::io::Error),
+    /// The version header of the stored data could not be parsed.
+    CorruptHeader,
+    /// The stored data is newer than the latest version known to this `Migrations`.
+    UnknownVersion(u32),
+    /// A migration step failed. The stored data has been left untouched.
+    StepFailed {
+        /// Version the failing step was migrating from.
+        from_version: u32,
+        /// Reason given by the step.
+        reason: String,
+    },
+}
+
+impl ::std::fmt::Display for MigrationError {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            MigrationError::Io(ref error) => write!(formatter, "I/O error: {}", error),
+            MigrationError::CorruptHeader => write!(formatter, "Corrupt data version header"),
+            MigrationError::UnknownVersion(version) => {
+                write!(formatter, "Stored data has unknown version {}", version)
+            }
+            MigrationError::StepFailed { from_version, ref reason } => {
+                write!(formatter,
+                       "Migration from version {} to {} failed: {}",
+                       from_version,
+                       from_version + 1,
+                       reason)
+            }
+        }
+    }
+}
+
+impl ::std::error::Error for MigrationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            MigrationError::Io(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<::std::io::Error> for MigrationError {
+    fn from(error: ::std::io::Error) -> MigrationError {
+        MigrationError::Io(error)
+    }
+}
+
+/// A record of one migration step having been applied to stored data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// Version the data was migrated from.
+    pub from_version: u32,
+    /// Version the data was migrated to.
+    pub to_version: u32,
+    /// When the step was applied.
+    pub applied_at: ::std::time::SystemTime,
+}
+
+type Step = Box<dyn Fn(Vec<u8>) -> Result<Vec<u8>, String>>;
+
+/// An ordered set of migration steps for serialised on-disk data.
+///
+/// Data is stored with a small version header. When loading, the stored version is detected
+/// (data without a header is treated as version 0) and every step needed to bring it up to the
+/// current version is applied in turn. The steps run in memory, so if any of them fails the stored
+/// data is left untouched. Only once all have succeeded is the original file copied to a backup
+/// (`<path>.v<old version>.bak`), the migrated data atomically written back and the applied steps
+/// appended to the history file (`<path>.history`).
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// # let path = std::env::temp_dir().join(format!("migrations_doc_{}", std::process::id()));
+/// use maidsafe_utilities::migrations::Migrations;
+///
+/// // Data written by an old release, before it was versioned.
+/// std::fs::write(&path, b"name").unwrap();
+///
+/// let mut migrations = Migrations::new();
+/// migrations.register(0, |data| Ok([&data[..], b"=value"].concat()));
+/// migrations.register(1, |data| Ok(data.to_ascii_uppercase()));
+///
+/// assert_eq!(migrations.load(&path).unwrap(), b"NAME=VALUE".to_vec());
+/// assert_eq!(Migrations::history(&path).unwrap().len(), 2);
+/// # for suffix in &["", ".v0.bak", ".history"] {
+/// #     let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+/// # }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Migrations {
+    steps: Vec<Step>,
+}
+
+impl Migrations {
+    /// Create an empty set of migrations, i.e. one where the current version is 0.
+    pub fn new() -> Migrations {
+        Migrations::default()
+    }
+
+    /// Register the step transforming data of version `from_version` into `from_version + 1`.
+    /// Steps must be registered in order, starting from version 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from_version` is not the current version, i.e. if a step is registered out of
+    /// order.
+    pub fn register<F>(&mut self, from_version: u32, step: F)
+        where F: Fn(Vec<u8>) -> Result<Vec<u8>, String> + 'static
+    {
+        assert_eq!(from_version as usize,
+                   self.steps.len(),
+                   "Migration steps must be registered in order");
+        self.steps.push(Box::new(step));
+    }
+
+    /// The version produced by the last registered step.
+    pub fn current_version(&self) -> u32 {
+        self.steps.len() as u32
+    }
+
+    /// Load the data stored at `path`, migrating it to the current version first if required.
+    pub fn load<P: AsRef<::std::path::Path>>(&self, path: P) -> Result<Vec<u8>, MigrationError> {
+        let path = path.as_ref();
+        let stored = ::std::fs::read(path)?;
+        let (stored_version, mut data) = Migrations::parse(&stored)?;
+
+        if stored_version == self.current_version() {
+            return Ok(data);
+        }
+        if stored_version > self.current_version() {
+            return Err(MigrationError::UnknownVersion(stored_version));
+        }
+
+        for (from_version, step) in self.steps.iter().enumerate().skip(stored_version as usize) {
+            data = step(data).map_err(|reason| {
+                MigrationError::StepFailed {
+                    from_version: from_version as u32,
+                    reason,
+                }
+            })?;
+        }
+
+        let backup_path = ::file_utils::with_suffix(path, &format!(".v{}.bak", stored_version));
+        ::file_utils::write_atomically(&backup_path, &stored)?;
+        self.store(path, &data)?;
+        self.record_history(path, stored_version)?;
+
+        Ok(data)
+    }
+
+    /// Atomically store `data` at `path`, tagged with the current version.
+    pub fn store<P: AsRef<::std::path::Path>>(&self, path: P, data: &[u8]) -> ::std::io::Result<()> {
+        let mut contents = HEADER_PREFIX.to_vec();
+        contents.extend_from_slice(format!("{}\n", self.current_version()).as_bytes());
+        contents.extend_from_slice(data);
+        ::file_utils::write_atomically(path.as_ref(), &contents)
+    }
+
+    /// Read the history of migrations applied to the data stored at `path`, oldest first.
+    pub fn history<P: AsRef<::std::path::Path>>(path: P)
+                                                -> Result<Vec<AppliedMigration>, MigrationError> {
+        let history_path = ::file_utils::with_suffix(path.as_ref(), ".history");
+        let contents = match ::std::fs::read_to_string(history_path) {
+            Ok(contents) => contents,
+            Err(ref error) if error.kind() == ::std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(MigrationError::Io(error)),
+        };
+
+        let corrupt = || {
+            MigrationError::Io(::std::io::Error::new(::std::io::ErrorKind::InvalidData,
+                                                     "Corrupt migration history"))
+        };
+
+        contents.lines()
+                .map(|line| {
+                    let fields = line.split(' ').collect::<Vec<_>>();
+                    if fields.len() != 3 {
+                        return Err(corrupt());
+                    }
+                    let from_version = fields[0].parse::<u32>().map_err(|_| corrupt())?;
+                    let to_version = fields[1].parse::<u32>().map_err(|_| corrupt())?;
+                    let applied_at = fields[2].parse::<u64>().map_err(|_| corrupt())?;
+                    Ok(AppliedMigration {
+                        from_version,
+                        to_version,
+                        applied_at: ::std::time::UNIX_EPOCH +
+                                    ::std::time::Duration::from_secs(applied_at),
+                    })
+                })
+                .collect()
+    }
+
+    fn parse(stored: &[u8]) -> Result<(u32, Vec<u8>), MigrationError> {
+        if !stored.starts_with(HEADER_PREFIX) {
+            return Ok((0, stored.to_vec()));
+        }
+
+        let rest = &stored[HEADER_PREFIX.len()..];
+        let end = rest.iter().position(|&byte| byte == b'\n').ok_or(MigrationError::CorruptHeader)?;
+        let version = ::std::str::from_utf8(&rest[..end])
+                          .ok()
+                          .and_then(|version| version.parse::<u32>().ok())
+                          .ok_or(MigrationError::CorruptHeader)?;
+        Ok((version, rest[end + 1..].to_vec()))
+    }
+
+    fn record_history(&self, path: &::std::path::Path, from_version: u32) -> ::std::io::Result<()> {
+        use std::io::Write;
+
+        let now = ::std::time::SystemTime::now()
+                      .duration_since(::std::time::UNIX_EPOCH)
+                      .map(|duration| duration.as_secs())
+                      .unwrap_or(0);
+        let mut history = ::std::fs::OpenOptions::new()
+                              .create(true)
+                              .append(true)
+                              .open(::file_utils::with_suffix(path, ".history"))?;
+        for version in from_version..self.current_version() {
+            writeln!(history, "{} {} {}", version, version + 1, now)?;
+        }
+        history.sync_all()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_path(name: &str) -> ::std::path::PathBuf {
+        let path = ::file_utils::test_utils::temp_path(name);
+        cleanup(&path);
+        path
+    }
+
+    fn cleanup(path: &::std::path::Path) {
+        for suffix in &["", ".v0.bak", ".v1.bak", ".history"] {
+            let _ = ::std::fs::remove_file(::file_utils::with_suffix(path, suffix));
+        }
+    }
+
+    fn migrations() -> Migrations {
+        let mut migrations = Migrations::new();
+        migrations.register(0, |mut data| {
+            data.push(b'1');
+            Ok(data)
+        });
+        migrations.register(1, |mut data| {
+            data.push(b'2');
+            Ok(data)
+        });
+        migrations
+    }
+
+    #[test]
+    fn migrate_and_record_history() {
+        let path = temp_path("migrations_history");
+        let mut old_migrations = Migrations::new();
+        old_migrations.register(0, |mut data| {
+            data.push(b'1');
+            Ok(data)
+        });
+        unwrap_result!(old_migrations.store(&path, b"data1"));
+
+        let migrations = migrations();
+        assert_eq!(unwrap_result!(migrations.load(&path)), b"data12".to_vec());
+        // Already up to date, so nothing more should be applied
+        assert_eq!(unwrap_result!(migrations.load(&path)), b"data12".to_vec());
+
+        let history = unwrap_result!(Migrations::history(&path));
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].from_version, history[0].to_version), (1, 2));
+
+        let backup = unwrap_result!(::std::fs::read(::file_utils::with_suffix(&path, ".v1.bak")));
+        assert!(backup.ends_with(b"data1"));
+
+        match old_migrations.load(&path) {
+            Err(MigrationError::UnknownVersion(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // A version which does not fit in a `u32` is rejected rather than wrapped.
+        let history_path = ::file_utils::with_suffix(&path, ".history");
+        unwrap_result!(::std::fs::write(&history_path, b"4294967296 4294967297 0\n"));
+        match Migrations::history(&path) {
+            Err(MigrationError::Io(ref error)) if error.kind() ==
+                                                   ::std::io::ErrorKind::InvalidData => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        cleanup(&path);
+    }
+
+    #[test]
+    fn failed_step_leaves_data_untouched() {
+        let path = temp_path("migrations_failure");
+        unwrap_result!(::std::fs::write(&path, b"legacy"));
+
+        let mut migrations = migrations();
+        migrations.register(2, |_| Err("Unsupported record".to_string()));
+
+        match migrations.load(&path) {
+            Err(MigrationError::StepFailed { from_version: 2, .. }) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(unwrap_result!(::std::fs::read(&path)), b"legacy".to_vec());
+        assert!(unwrap_result!(Migrations::history(&path)).is_empty());
+        cleanup(&path);
+    }
+
+    #[test]
+    #[should_panic(expected = "registered in order")]
+    fn out_of_order_registration_is_rejected() {
+        let mut migrations = Migrations::new();
+        migrations.register(1, Ok);
+    }
+}
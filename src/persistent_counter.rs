@@ -0,0 +1,148 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+const DEFAULT_BLOCK_SIZE: u64 = 1000;
+
+/// A counter issuing strictly increasing `u64` values which are never reused, even across
+/// restarts or crashes.
+///
+/// Rather than writing to disk for every value issued, the counter reserves a block of values at a
+/// time by atomically persisting the upper bound of the block (write to a temporary file, sync,
+/// then rename over the old one). On restart counting resumes from the last persisted bound, so at
+/// worst the unused remainder of a block is skipped - a value is never handed out twice.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// # let path = std::env::temp_dir().join(format!("persistent_counter_doc_{}",
+/// #                                              std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// use maidsafe_utilities::persistent_counter::PersistentCounter;
+///
+/// let first = {
+///     let mut counter = PersistentCounter::open(&path).unwrap();
+///     counter.next_value().unwrap()
+/// };
+///
+/// let mut counter = PersistentCounter::open(&path).unwrap();
+/// assert!(counter.next_value().unwrap() > first);
+/// # let _ = std::fs::remove_file(&path);
+/// # }
+/// ```
+pub struct PersistentCounter {
+    path      : ::std::path::PathBuf,
+    next      : u64,
+    reserved  : u64,
+    block_size: u64,
+}
+
+impl PersistentCounter {
+    /// Open (or create) the counter persisted at `path`, reserving values in blocks of 1000.
+    pub fn open<P: AsRef<::std::path::Path>>(path: P) -> ::std::io::Result<PersistentCounter> {
+        PersistentCounter::with_block_size(path, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Open (or create) the counter persisted at `path`, reserving `block_size` values per disk
+    /// write. Larger blocks mean fewer writes but bigger gaps in the sequence after a restart.
+    pub fn with_block_size<P>(path: P, block_size: u64) -> ::std::io::Result<PersistentCounter>
+        where P: AsRef<::std::path::Path>
+    {
+        if block_size == 0 {
+            return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidInput,
+                                             "Block size must be non-zero"));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let reserved = match ::std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                contents.trim().parse::<u64>().map_err(|error| {
+                    ::std::io::Error::new(::std::io::ErrorKind::InvalidData,
+                                          format!("Corrupt counter file {}: {}",
+                                                  path.display(),
+                                                  error))
+                })?
+            }
+            Err(ref error) if error.kind() == ::std::io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error),
+        };
+
+        Ok(PersistentCounter {
+            path,
+            next: reserved,
+            reserved,
+            block_size,
+        })
+    }
+
+    /// Issue the next value. This only touches the disk when the current block is used up.
+    pub fn next_value(&mut self) -> ::std::io::Result<u64> {
+        if self.next == self.reserved {
+            let reserved = self.reserved.checked_add(self.block_size).ok_or_else(|| {
+                ::std::io::Error::other("Counter exhausted")
+            })?;
+            ::file_utils::write_atomically(&self.path, format!("{}\n", reserved).as_bytes())?;
+            self.reserved = reserved;
+        }
+
+        let value = self.next;
+        self.next += 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use file_utils::test_utils::temp_path;
+    use super::*;
+
+    #[test]
+    fn values_increase_across_restarts() {
+        const BLOCK_SIZE: u64 = 10;
+        let path = temp_path("counter_restarts");
+
+        let mut last = None;
+        for _ in 0..3 {
+            let mut counter = unwrap_result!(PersistentCounter::with_block_size(&path,
+                                                                                BLOCK_SIZE));
+            for _ in 0..(BLOCK_SIZE + 3) {
+                let value = unwrap_result!(counter.next_value());
+                if let Some(last) = last {
+                    assert!(value > last);
+                }
+                last = Some(value);
+            }
+        }
+
+        let contents = unwrap_result!(::std::fs::read_to_string(&path));
+        assert_eq!(unwrap_result!(contents.trim().parse::<u64>()), 6 * BLOCK_SIZE);
+        let _ = ::std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn corrupt_file_is_rejected() {
+        let path = temp_path("counter_corrupt");
+        unwrap_result!(::std::fs::write(&path, "Not a number"));
+
+        match PersistentCounter::open(&path) {
+            Err(ref error) if error.kind() == ::std::io::ErrorKind::InvalidData => (),
+            Err(error) => panic!("Unexpected error: {:?}", error),
+            Ok(_) => panic!("Corrupt counter file should be rejected."),
+        }
+        let _ = ::std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Setting the affinity mask requires FFI.
+#![allow(unsafe_code)]
+
+/// Pin the current thread to the CPUs whose zero-based indices are listed in `cpus`, so that the
+/// OS scheduler does not migrate it to other cores. This is supported on Linux and Windows (where
+/// only the first 64 CPUs can be named) and does nothing elsewhere.
+///
+/// To pin a thread from the start, spawn it via `Builder::affinity` instead.
+pub fn set_current_affinity(cpus: &[usize]) -> ::std::io::Result<()> {
+    if cpus.is_empty() {
+        return Err(::std::io::Error::new(::std::io::ErrorKind::InvalidInput, "No CPUs given"));
+    }
+    platform::set_current_affinity(cpus)
+}
+
+fn cpu_out_of_range(cpu: usize) -> ::std::io::Error {
+    ::std::io::Error::new(::std::io::ErrorKind::InvalidInput,
+                          format!("CPU index {} is out of range", cpu))
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    pub fn set_current_affinity(cpus: &[usize]) -> ::std::io::Result<()> {
+        unsafe {
+            let mut set: ::libc::cpu_set_t = ::std::mem::zeroed();
+            ::libc::CPU_ZERO(&mut set);
+            for &cpu in cpus {
+                if cpu >= 8 * ::std::mem::size_of::<::libc::cpu_set_t>() {
+                    return Err(super::cpu_out_of_range(cpu));
+                }
+                ::libc::CPU_SET(cpu, &mut set);
+            }
+            if ::libc::sched_setaffinity(0, ::std::mem::size_of::<::libc::cpu_set_t>(), &set) != 0 {
+                return Err(::std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    pub fn set_current_affinity(cpus: &[usize]) -> ::std::io::Result<()> {
+        let mut mask: ::winapi::DWORD_PTR = 0;
+        for &cpu in cpus {
+            if cpu >= 8 * ::std::mem::size_of::<::winapi::DWORD_PTR>() {
+                return Err(super::cpu_out_of_range(cpu));
+            }
+            mask |= 1 << cpu;
+        }
+        if unsafe { ::kernel32::SetThreadAffinityMask(::kernel32::GetCurrentThread(), mask) } == 0 {
+            return Err(::std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    pub fn set_current_affinity(_cpus: &[usize]) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use super::*;
+
+    fn current_affinity() -> Vec<usize> {
+        unsafe {
+            let mut set: ::libc::cpu_set_t = ::std::mem::zeroed();
+            assert_eq!(::libc::sched_getaffinity(0,
+                                                 ::std::mem::size_of::<::libc::cpu_set_t>(),
+                                                 &mut set),
+                       0);
+            (0..8 * ::std::mem::size_of::<::libc::cpu_set_t>())
+                .filter(|cpu| ::libc::CPU_ISSET(*cpu, &set))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn pin_to_one_cpu() {
+        let handle = unwrap_result!(::thread::Builder::new("Pinned")
+                                        .affinity(vec![current_affinity()[0]])
+                                        .spawn_unmanaged(current_affinity));
+        let allowed = current_affinity();
+        assert_eq!(unwrap_result!(handle.join()), vec![allowed[0]]);
+
+        assert!(set_current_affinity(&[]).is_err());
+        assert!(set_current_affinity(&[1 << 20]).is_err());
+    }
+}
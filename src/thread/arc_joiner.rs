@@ -0,0 +1,129 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::RaiiThreadJoiner;
+
+/// A joiner which can be shared: clones refer to the same thread, which is joined only once the
+/// last of them is dropped. This settles who owns a listener thread when several components share
+/// the `EventSender` feeding it.
+///
+/// If the last handle happens to be dropped on the managed thread itself, the thread cannot join
+/// itself, so it is detached instead.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{ArcJoiner, RaiiThreadJoiner};
+///
+/// let (event_tx, event_rx) = std::sync::mpsc::channel::<u32>();
+/// let listener = ArcJoiner::new(RaiiThreadJoiner::new(thread!("Listener", move || {
+///     for event in event_rx.iter() {
+///         println!("Received {}", event);
+///     }
+/// })));
+///
+/// let routing = (event_tx.clone(), listener.clone());
+/// let client = (event_tx, listener);
+/// drop(routing);
+/// // The listener is joined here, once the last of its handles (and its senders) has gone.
+/// drop(client);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ArcJoiner {
+    inner: ::std::sync::Arc<Inner>,
+}
+
+struct Inner(Option<RaiiThreadJoiner>);
+
+impl ArcJoiner {
+    /// Share the thread managed by `raii_joiner`.
+    pub fn new(raii_joiner: RaiiThreadJoiner) -> ArcJoiner {
+        ArcJoiner {
+            inner: ::std::sync::Arc::new(Inner(Some(raii_joiner))),
+        }
+    }
+
+    /// The number of handles to the thread, including this one.
+    pub fn handles(&self) -> usize {
+        ::std::sync::Arc::strong_count(&self.inner)
+    }
+
+    /// Whether the thread has finished running, without blocking.
+    pub fn is_finished(&self) -> bool {
+        self.inner.0.as_ref().is_none_or(RaiiThreadJoiner::is_finished)
+    }
+}
+
+impl From<RaiiThreadJoiner> for ArcJoiner {
+    fn from(raii_joiner: RaiiThreadJoiner) -> ArcJoiner {
+        ArcJoiner::new(raii_joiner)
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(raii_joiner) = self.0.take() {
+            let on_managed_thread = raii_joiner.joiner
+                                               .as_ref()
+                                               .is_some_and(|joiner| {
+                                                   joiner.thread().id() ==
+                                                   ::std::thread::current().id()
+                                               });
+            if on_managed_thread {
+                debug!("Last handle dropped on the managed thread itself; detaching it");
+                raii_joiner.detach();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn joined_when_last_handle_drops() {
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let first = ArcJoiner::new(RaiiThreadJoiner::new(thread!("Shared", move || {
+            let _ = finish_rx.recv();
+        })));
+        let second = first.clone();
+        assert_eq!(second.handles(), 2);
+        // Would block for ever if this joined.
+        drop(first);
+        assert_eq!(second.handles(), 1);
+        assert!(!second.is_finished());
+        drop(finish_tx);
+        drop(second);
+    }
+
+    #[test]
+    fn last_handle_dropped_on_managed_thread() {
+        let (handle_tx, handle_rx) = ::std::sync::mpsc::channel::<ArcJoiner>();
+        let (done_tx, done_rx) = ::std::sync::mpsc::channel();
+        let raii_joiner = RaiiThreadJoiner::new(thread!("SelfOwned", move || {
+            drop(unwrap_result!(handle_rx.recv()));
+            unwrap_result!(done_tx.send(()));
+        }));
+        unwrap_result!(handle_tx.send(ArcJoiner::new(raii_joiner)));
+        unwrap_result!(done_rx.recv());
+    }
+}
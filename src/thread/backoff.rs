@@ -0,0 +1,131 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::time::Duration;
+
+// Up to this step, `snooze` spins.
+const SPIN_LIMIT: u32 = 6;
+// Up to this step, `snooze` yields the thread.
+const YIELD_LIMIT: u32 = 10;
+// The longest `snooze` parks the thread for.
+const MAX_PARK_MICROS: u64 = 1000;
+
+/// Adaptive backoff for retry loops such as polling a lock-free queue or retrying a
+/// compare-and-swap, escalating from busy-spinning to yielding the thread and finally to parking
+/// it, rather than burning a CPU in a naive busy-wait or always paying for `sleep`.
+///
+/// Create one per wait, call `snooze` each time the loop finds nothing to do, and `reset` once it
+/// makes progress. `spin` is for retrying after losing a race, where waiting longer never helps.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread::Backoff;
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// let ready = Arc::new(AtomicBool::new(false));
+/// let ready_clone = ready.clone();
+/// let _ = std::thread::spawn(move || ready_clone.store(true, Ordering::SeqCst));
+///
+/// let mut backoff = Backoff::new();
+/// while !ready.load(Ordering::SeqCst) {
+///     backoff.snooze();
+/// }
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    /// Start a backoff from its shortest wait.
+    pub fn new() -> Backoff {
+        Backoff { step: 0 }
+    }
+
+    /// Start again from the shortest wait, e.g. once the loop has made progress.
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Busy-spin for a short, exponentially growing number of iterations, without ever giving up
+    /// the CPU.
+    pub fn spin(&mut self) {
+        for _ in 0..1u32 << self.step.min(SPIN_LIMIT) {
+            ::std::hint::spin_loop();
+        }
+        if self.step <= SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// Wait for another thread to make progress: spin at first, then yield the thread, then park
+    /// it for exponentially growing periods of up to a millisecond.
+    pub fn snooze(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1u32 << self.step {
+                ::std::hint::spin_loop();
+            }
+        } else if self.step <= YIELD_LIMIT {
+            ::std::thread::yield_now();
+        } else {
+            let micros = 1u64 << (self.step - YIELD_LIMIT).min(10);
+            ::std::thread::park_timeout(Duration::from_micros(micros.min(MAX_PARK_MICROS)));
+        }
+        if self.step <= YIELD_LIMIT + 10 {
+            self.step += 1;
+        }
+    }
+
+    /// Whether `snooze` has escalated to parking, a hint that the caller should switch to a
+    /// blocking wait, such as on a `Condvar`, if it has one.
+    pub fn is_completed(&self) -> bool {
+        self.step > YIELD_LIMIT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escalates_and_resets() {
+        let mut backoff = Backoff::new();
+        for _ in 0..YIELD_LIMIT + 1 {
+            assert!(!backoff.is_completed());
+            backoff.snooze();
+        }
+        assert!(backoff.is_completed());
+        // Parking is bounded however long the wait.
+        for _ in 0..100 {
+            backoff.snooze();
+        }
+        let start = ::std::time::Instant::now();
+        backoff.snooze();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        backoff.reset();
+        assert!(!backoff.is_completed());
+        for _ in 0..100 {
+            backoff.spin();
+        }
+        assert!(!backoff.is_completed());
+    }
+}
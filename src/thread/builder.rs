@@ -0,0 +1,398 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{PanicReport, Priority, RaiiThreadJoiner, set_current_affinity,
+            set_current_priority};
+use super::logged::run_logged;
+use super::registry::registered;
+
+type PanicHandler = ::std::sync::Arc<dyn Fn(PanicReport) + Send + Sync>;
+
+thread_local! {
+    // Whether the current thread was spawned via this crate's helpers.
+    static SPAWNED_BY_CRATE: ::std::cell::Cell<bool> = const { ::std::cell::Cell::new(false) };
+}
+
+/// Configuration for spawning a named thread, for when the defaults used by `thread!` are not
+/// enough. Unlike `thread!`, failing to spawn is reported rather than panicking.
+///
+/// A thread spawned this way (or via any of the helpers built on it) from a thread which was
+/// itself spawned via this crate has its name prefixed with its parent's, e.g. `"Router/Tx-3"`,
+/// unless `inherit_name(false)` is set.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::Builder;
+///
+/// fn depth(n: u64) -> u64 {
+///     if n == 0 { 0 } else { 1 + depth(n - 1) }
+/// }
+///
+/// let _raii_joiner = unwrap_result!(Builder::new("RecursiveWorker")
+///                                       .stack_size(64 * 1024 * 1024)
+///                                       .spawn(move || assert_eq!(depth(100_000), 100_000)));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Builder {
+    name      : String,
+    stack_size: Option<usize>,
+    affinity  : Option<Vec<usize>>,
+    priority  : Option<Priority>,
+    log_panics: bool,
+    on_panic  : Option<PanicHandler>,
+    inherit   : bool,
+}
+
+impl Builder {
+    /// Start configuring a thread named `name`.
+    pub fn new<T: Into<String>>(name: T) -> Builder {
+        Builder {
+            name      : name.into(),
+            stack_size: None,
+            affinity  : None,
+            priority  : None,
+            log_panics: false,
+            on_panic  : None,
+            inherit   : true,
+        }
+    }
+
+    /// Append a unique counter to the name, as for `unique_name`, e.g. to give one worker per
+    /// connection a distinct name for log correlation.
+    pub fn unique_name(mut self) -> Builder {
+        self.name = unique_name(&self.name);
+        self
+    }
+
+    /// Whether to prefix the name with the spawning thread's name, if that was itself spawned via
+    /// this crate. This is on by default; turn it off for threads shared by the whole process,
+    /// whose parent is incidental.
+    pub fn inherit_name(mut self, inherit: bool) -> Builder {
+        self.inherit = inherit;
+        self
+    }
+
+    /// Give the thread a stack of `bytes` bytes rather than the platform default.
+    pub fn stack_size(mut self, bytes: usize) -> Builder {
+        self.stack_size = Some(bytes);
+        self
+    }
+
+    /// Pin the thread to the CPUs whose zero-based indices are listed in `cpus` before it runs
+    /// anything, as for `set_current_affinity`. If that fails, a warning is logged and the thread
+    /// runs unpinned.
+    pub fn affinity(mut self, cpus: Vec<usize>) -> Builder {
+        self.affinity = Some(cpus);
+        self
+    }
+
+    /// Give the thread the OS scheduling priority `priority` before it runs anything, as for
+    /// `set_current_priority`. If that fails, a warning is logged and the thread runs at the
+    /// default priority.
+    pub fn priority(mut self, priority: Priority) -> Builder {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Log a panic in the thread as an error, along with the thread's name and a backtrace from
+    /// where it panicked, before it carries on unwinding (and before any `on_panic` handler runs).
+    /// `thread_logged!` spawns threads with this set.
+    pub fn log_panics(mut self) -> Builder {
+        self.log_panics = true;
+        self
+    }
+
+    /// Catch a panic in the thread and pass a `PanicReport` of it to `handler`, which runs on the
+    /// panicking thread. A thread started via `spawn` then just exits, so the joiner does not
+    /// re-panic when dropped; one started via `spawn_unmanaged` resumes unwinding once the
+    /// handler returns, as it has no value to return from `join`.
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate maidsafe_utilities;
+    /// # fn main() {
+    /// use maidsafe_utilities::thread::Builder;
+    ///
+    /// let (report_tx, report_rx) = std::sync::mpsc::channel();
+    /// let report_tx = std::sync::Mutex::new(report_tx);
+    /// {
+    ///     let _raii_joiner = unwrap_result!(Builder::new("FlakyWorker")
+    ///         .on_panic(move |report| {
+    ///             let _ = unwrap_result!(report_tx.lock()).send(report);
+    ///         })
+    ///         .spawn(|| panic!("Lost connection")));
+    /// }
+    /// let report = unwrap_result!(report_rx.recv());
+    /// assert_eq!(report.thread_name, Some("FlakyWorker".to_owned()));
+    /// assert_eq!(report.message, "Lost connection");
+    /// # }
+    /// ```
+    pub fn on_panic<F>(mut self, handler: F) -> Builder
+        where F: Fn(PanicReport) + Send + Sync + 'static
+    {
+        self.on_panic = Some(::std::sync::Arc::new(handler));
+        self
+    }
+
+    /// Spawn the thread, returning a joiner which joins it when dropped.
+    pub fn spawn<F>(mut self, entry_point: F) -> ::std::io::Result<RaiiThreadJoiner>
+        where F: FnOnce() + Send + 'static
+    {
+        let joiner = match self.on_panic.take() {
+            Some(on_panic) => {
+                self.spawn_raw(move || {
+                    let entry_point = ::std::panic::AssertUnwindSafe(entry_point);
+                    if let Err(payload) = ::std::panic::catch_unwind(entry_point) {
+                        on_panic(PanicReport::new(&*payload));
+                    }
+                })
+            }
+            None => self.spawn_raw(entry_point),
+        };
+        joiner.map(RaiiThreadJoiner::new)
+    }
+
+    /// Spawn the thread, returning the plain `JoinHandle` as `thread!` does.
+    pub fn spawn_unmanaged<F, T>(mut self, entry_point: F)
+                                 -> ::std::io::Result<::std::thread::JoinHandle<T>>
+        where F: FnOnce() -> T + Send + 'static,
+              T: Send + 'static
+    {
+        match self.on_panic.take() {
+            Some(on_panic) => {
+                self.spawn_raw(move || {
+                    match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(entry_point)) {
+                        Ok(value) => value,
+                        Err(payload) => {
+                            on_panic(PanicReport::new(&*payload));
+                            ::std::panic::resume_unwind(payload)
+                        }
+                    }
+                })
+            }
+            None => self.spawn_raw(entry_point),
+        }
+    }
+
+    fn spawn_raw<F, T>(self, entry_point: F) -> ::std::io::Result<::std::thread::JoinHandle<T>>
+        where F: FnOnce() -> T + Send + 'static,
+              T: Send + 'static
+    {
+        let name = if self.inherit {
+            inherited_name(self.name)
+        } else {
+            self.name
+        };
+        let entry_point = spawned_by_crate(registered(&name, entry_point));
+        let mut builder = ::std::thread::Builder::new().name(name);
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let affinity = self.affinity;
+        let priority = self.priority;
+        let log_panics = self.log_panics;
+        builder.spawn(move || {
+            if let Some(cpus) = affinity {
+                if let Err(error) = set_current_affinity(&cpus) {
+                    warn!("Could not pin thread to CPUs {:?}: {}", cpus, error);
+                }
+            }
+            if let Some(priority) = priority {
+                if let Err(error) = set_current_priority(priority) {
+                    warn!("Could not set thread priority to {:?}: {}", priority, error);
+                }
+            }
+            if log_panics {
+                run_logged(entry_point)
+            } else {
+                entry_point()
+            }
+        })
+    }
+}
+
+impl ::std::fmt::Debug for Builder {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.debug_struct("Builder")
+                 .field("name", &self.name)
+                 .field("stack_size", &self.stack_size)
+                 .field("affinity", &self.affinity)
+                 .field("priority", &self.priority)
+                 .field("log_panics", &self.log_panics)
+                 .field("on_panic", &self.on_panic.is_some())
+                 .field("inherit_name", &self.inherit)
+                 .finish()
+    }
+}
+
+// `name` prefixed with the current thread's name if that was spawned via this crate.
+pub fn inherited_name(name: String) -> String {
+    match ::std::thread::current().name() {
+        Some(parent) if SPAWNED_BY_CRATE.with(::std::cell::Cell::get) => {
+            format!("{}/{}", parent, name)
+        }
+        _ => name,
+    }
+}
+
+// Wrap `entry_point` so that threads it spawns inherit its thread's name.
+pub fn spawned_by_crate<F: FnOnce() -> T, T>(entry_point: F) -> impl FnOnce() -> T {
+    move || {
+        SPAWNED_BY_CRATE.with(|spawned_by_crate| spawned_by_crate.set(true));
+        entry_point()
+    }
+}
+
+/// Append a counter to `base` which is unique among the names made from it, giving `"Reader-0"`,
+/// `"Reader-1"` and so on, so that threads spawned repeatedly under the same base name can be
+/// told apart in logs without each caller formatting its own names.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{self, Builder};
+///
+/// let first = thread::unique_name("Reader");
+/// let second = thread::unique_name("Reader");
+/// assert!(first.starts_with("Reader-"));
+/// assert_ne!(first, second);
+///
+/// let _raii_joiner = unwrap_result!(Builder::new("Reader").unique_name().spawn(|| {
+///     assert!(unwrap_option!(std::thread::current().name(), "").starts_with("Reader-"));
+/// }));
+/// # }
+/// ```
+pub fn unique_name(base: &str) -> String {
+    type Counters = ::std::sync::Mutex<::std::collections::HashMap<String, u64>>;
+    static COUNTERS: ::std::sync::OnceLock<Counters> = ::std::sync::OnceLock::new();
+    let mut counters = unwrap_result!(COUNTERS.get_or_init(Default::default).lock());
+    let counter = counters.entry(base.to_owned()).or_insert(0);
+    let name = format!("{}-{}", base, counter);
+    *counter += 1;
+    name
+}
+
+/// Spawn a thread named `name`, returning a joiner which joins it when dropped. This is the
+/// non-panicking counterpart of `RaiiThreadJoiner::new(thread!(name, entry_point))`, for callers
+/// which want to handle the OS refusing to create another thread.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// match thread::named("Worker", || ()) {
+///     Ok(_raii_joiner) => (),
+///     Err(error) => println!("Running without a worker: {}", error),
+/// }
+/// # }
+/// ```
+pub fn named<T, F>(name: T, entry_point: F) -> ::std::io::Result<RaiiThreadJoiner>
+    where T: Into<String>,
+          F: FnOnce() + Send + 'static
+{
+    Builder::new(name).spawn(entry_point)
+}
+
+/// Spawn a thread named `name` with a stack of `bytes` bytes. Shorthand for
+/// `Builder::new(name).stack_size(bytes).spawn(entry_point)`.
+pub fn named_with_stack_size<T, F>(name: T, bytes: usize, entry_point: F)
+                                   -> ::std::io::Result<RaiiThreadJoiner>
+    where T: Into<String>,
+          F: FnOnce() + Send + 'static
+{
+    Builder::new(name).stack_size(bytes).spawn(entry_point)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn name_and_stack_size() {
+        let (name_tx, name_rx) = ::std::sync::mpsc::channel();
+        {
+            let _raii_joiner = unwrap_result!(named_with_stack_size("BigStack", 8 << 20, move || {
+                // Would overflow a typical 2 MiB default stack.
+                let buffer = [1u8; 4 << 20];
+                let sum = ::std::hint::black_box(&buffer).iter().map(|byte| *byte as usize).sum();
+                unwrap_result!(name_tx.send((::std::thread::current().name().map(str::to_owned),
+                                             sum)));
+            }));
+        }
+        assert_eq!(unwrap_result!(name_rx.recv()), (Some("BigStack".to_owned()), 4 << 20));
+
+        let handle = unwrap_result!(Builder::new("Unmanaged").spawn_unmanaged(|| 7));
+        assert_eq!(unwrap_result!(handle.join()), 7);
+    }
+
+    #[test]
+    fn children_inherit_names() {
+        let (name_tx, name_rx) = ::std::sync::mpsc::channel();
+        let _raii_joiner = unwrap_result!(named("Router", move || {
+            let name_tx_clone = name_tx.clone();
+            let _raii_joiner = unwrap_result!(named("Tx-3", move || {
+                let grandchild = unwrap_result!(Builder::new("Encoder").spawn_unmanaged(|| {
+                    ::thread::current_name()
+                }));
+                unwrap_result!(name_tx_clone.send(::thread::current_name()));
+                unwrap_result!(name_tx_clone.send(unwrap_result!(grandchild.join())));
+            }));
+            let shared = unwrap_result!(Builder::new("Shared")
+                                            .inherit_name(false)
+                                            .spawn_unmanaged(::thread::current_name));
+            unwrap_result!(name_tx.send(unwrap_result!(shared.join())));
+        }));
+        let mut names = name_rx.iter().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["Router/Tx-3", "Router/Tx-3/Encoder", "Shared"]);
+        // Threads not spawned via the crate do not pass their names on.
+        let spawn_child = || {
+            unwrap_result!(Builder::new("Child").spawn_unmanaged(::thread::current_name))
+        };
+        let handle = unwrap_result!(::std::thread::Builder::new()
+                                        .name("Foreign".to_owned())
+                                        .spawn(spawn_child));
+        assert_eq!(unwrap_result!(unwrap_result!(handle.join()).join()), "Child");
+    }
+
+    #[test]
+    fn unmanaged_panic_is_reported_and_resumed() {
+        let reports = ::std::sync::Arc::new(::std::sync::Mutex::new(Vec::new()));
+        let reports_clone = reports.clone();
+        let handle = unwrap_result!(Builder::new("UnmanagedPanic")
+                                        .on_panic(move |report| {
+                                            unwrap_result!(reports_clone.lock()).push(report)
+                                        })
+                                        .spawn_unmanaged(|| -> u32 { panic!("Oops") }));
+        assert!(handle.join().is_err());
+        assert_eq!(*unwrap_result!(reports.lock()),
+                   vec![PanicReport {
+                            thread_name: Some("UnmanagedPanic".to_owned()),
+                            message    : "Oops".to_owned(),
+                        }]);
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Builder, RaiiThreadJoiner};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+struct Inner {
+    cancelled: AtomicBool,
+    // Only held while checking or setting `cancelled`, so that a waiter cannot miss the wakeup.
+    // Holds the callbacks to run on cancellation.
+    mutex    : Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    condition: Condvar,
+}
+
+/// A flag telling a worker thread to stop, which the worker can also sleep on.
+///
+/// Clones share the same flag. A worker normally receives its token from `named_cancellable`,
+/// whose joiner cancels it on being dropped, so that the worker's `wait_or_cancelled` returns
+/// promptly rather than the destructor blocking for the rest of a sleep.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+/// use std::time::Duration;
+///
+/// let _raii_joiner = unwrap_result!(thread::named_cancellable("PeriodicFlush", |token| {
+///     while !token.wait_or_cancelled(Duration::from_secs(60)) {
+///         // Flush something.
+///     }
+/// }));
+/// // Dropping the joiner wakes the thread then joins it, without waiting a minute.
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    /// Create a token which has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                mutex    : Mutex::new(Vec::new()),
+                condition: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Cancel the token, waking any threads blocked in `wait_or_cancelled`.
+    pub fn cancel(&self) {
+        // The callbacks are run without the lock held, so that they may use the token themselves.
+        let callbacks = {
+            let mut on_cancel = unwrap_result!(self.inner.mutex.lock());
+            self.inner.cancelled.store(true, Ordering::SeqCst);
+            self.inner.condition.notify_all();
+            on_cancel.drain(..).collect::<Vec<_>>()
+        };
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    /// Run `callback` on the cancelling thread when the token is cancelled, or immediately if it
+    /// already has been, e.g. to wake threads blocked on something other than the token.
+    pub fn on_cancel<F: FnOnce() + Send + 'static>(&self, callback: F) {
+        let mut on_cancel = unwrap_result!(self.inner.mutex.lock());
+        if self.is_cancelled() {
+            drop(on_cancel);
+            callback();
+        } else {
+            on_cancel.push(Box::new(callback));
+        }
+    }
+
+    /// Whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Sleep for `timeout` or until the token is cancelled, whichever comes first. Returns
+    /// whether the token has been cancelled. A `timeout` too long to be represented as an
+    /// `Instant`, e.g. `Duration::MAX`, means sleeping until cancelled.
+    pub fn wait_or_cancelled(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut guard = unwrap_result!(self.inner.mutex.lock());
+        while !self.is_cancelled() {
+            guard = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    unwrap_result!(self.inner.condition.wait_timeout(guard, deadline - now)).0
+                }
+                None => unwrap_result!(self.inner.condition.wait(guard)),
+            };
+        }
+        true
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> CancellationToken {
+        CancellationToken::new()
+    }
+}
+
+impl ::std::fmt::Debug for CancellationToken {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.debug_struct("CancellationToken")
+                 .field("cancelled", &self.is_cancelled())
+                 .finish()
+    }
+}
+
+/// Why `sleep_interruptible` returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WokenReason {
+    /// The full duration elapsed.
+    Elapsed,
+    /// The token was cancelled, possibly before the sleep began.
+    Cancelled,
+}
+
+/// Sleep for `duration`, unless `token` is cancelled first, so that long back-off sleeps in
+/// worker loops end immediately on shutdown.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread::{self, CancellationToken, WokenReason};
+/// use std::time::Duration;
+///
+/// let token = CancellationToken::new();
+/// token.cancel();
+/// assert_eq!(thread::sleep_interruptible(Duration::from_secs(3600), &token),
+///            WokenReason::Cancelled);
+/// # }
+/// ```
+pub fn sleep_interruptible(duration: Duration, token: &CancellationToken) -> WokenReason {
+    if token.wait_or_cancelled(duration) {
+        WokenReason::Cancelled
+    } else {
+        WokenReason::Elapsed
+    }
+}
+
+/// Spawn a thread named `name`, passing `entry_point` a `CancellationToken` which the returned
+/// joiner cancels before joining the thread.
+pub fn named_cancellable<T, F>(name: T, entry_point: F) -> ::std::io::Result<RaiiThreadJoiner>
+    where T: Into<String>,
+          F: FnOnce(CancellationToken) + Send + 'static
+{
+    let token = CancellationToken::new();
+    let token_clone = token.clone();
+    Builder::new(name)
+        .spawn(move || entry_point(token_clone))
+        .map(|raii_joiner| raii_joiner.cancel_on_drop(token))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wait_or_cancelled() {
+        let token = CancellationToken::new();
+        let start = Instant::now();
+        assert!(!token.wait_or_cancelled(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let token_clone = token.clone();
+        let _raii_joiner = RaiiThreadJoiner::new(thread!("Canceller", move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            token_clone.cancel();
+        }));
+        let start = Instant::now();
+        assert!(token.wait_or_cancelled(Duration::from_secs(60)));
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert!(token.is_cancelled());
+        assert!(token.wait_or_cancelled(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn sleep_interruptible_reasons() {
+        let token = CancellationToken::new();
+        assert_eq!(sleep_interruptible(Duration::from_millis(1), &token), WokenReason::Elapsed);
+
+        let token_clone = token.clone();
+        let _raii_joiner = RaiiThreadJoiner::new(thread!("Interrupter", move || {
+            ::std::thread::sleep(Duration::from_millis(10));
+            token_clone.cancel();
+        }));
+        assert_eq!(sleep_interruptible(Duration::from_secs(60), &token), WokenReason::Cancelled);
+    }
+
+    #[test]
+    fn sleep_until_cancelled() {
+        let token = CancellationToken::new();
+        let token_clone = token.clone();
+        let _raii_joiner = RaiiThreadJoiner::new(thread!("Interrupter", move || {
+            ::std::thread::sleep(Duration::from_millis(10));
+            token_clone.cancel();
+        }));
+        assert_eq!(sleep_interruptible(Duration::MAX, &token), WokenReason::Cancelled);
+    }
+
+    #[test]
+    fn callbacks_may_use_the_token() {
+        let (ran_tx, ran_rx) = ::std::sync::mpsc::channel();
+        let token = CancellationToken::new();
+        let token_clone = token.clone();
+        let ran_tx_clone = ran_tx.clone();
+        token.on_cancel(move || {
+            // Registering from within a callback runs the new one straight away.
+            token_clone.on_cancel(move || unwrap_result!(ran_tx_clone.send("Nested")));
+            token_clone.cancel();
+        });
+        token.cancel();
+        token.on_cancel(move || unwrap_result!(ran_tx.send("Late")));
+        assert_eq!(ran_rx.iter().collect::<Vec<_>>(), vec!["Nested", "Late"]);
+    }
+
+    #[test]
+    fn joiner_cancels_before_joining() {
+        let (iterations_tx, iterations_rx) = ::std::sync::mpsc::channel();
+        let start = Instant::now();
+        {
+            let _raii_joiner = unwrap_result!(named_cancellable("Cancellable", move |token| {
+                let mut iterations = 0;
+                while !token.wait_or_cancelled(Duration::from_secs(60)) {
+                    iterations += 1;
+                }
+                unwrap_result!(iterations_tx.send(iterations));
+            }));
+        }
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(unwrap_result!(iterations_rx.recv()), 0);
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Builder, PanicReport};
+use super::join::join;
+
+/// A set of threads joined together, replacing a `Vec` of joiners.
+///
+/// Threads are joined in reverse spawn order, either explicitly via `join_all` or when the group
+/// is dropped. Every thread is joined even if some have panicked; the panics are then reported
+/// together, as a `GroupPanicked` error from `join_all` or as a single panic from the destructor
+/// (only logged if the current thread is already panicking).
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::Group;
+///
+/// let mut group = Group::new();
+/// for index in 0..3 {
+///     unwrap_result!(group.spawn(format!("Worker-{}", index), move || {
+///         if index == 1 {
+///             panic!("Worker {} failed", index);
+///         }
+///     }));
+/// }
+///
+/// let error = unwrap_option!(group.join_all().err(), "A worker should have panicked");
+/// assert_eq!(error.reports.len(), 1);
+/// assert_eq!(error.reports[0].thread_name, Some("Worker-1".to_owned()));
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Group {
+    handles: Vec<::std::thread::JoinHandle<()>>,
+}
+
+/// Returned by `Group::join_all` if any of the threads panicked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupPanicked {
+    /// A report for each thread which panicked, in the order they were joined.
+    pub reports: Vec<PanicReport>,
+}
+
+impl ::std::fmt::Display for GroupPanicked {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "{} thread(s) panicked", self.reports.len())?;
+        for report in &self.reports {
+            write!(formatter, "; {}", report)?;
+        }
+        Ok(())
+    }
+}
+
+impl ::std::error::Error for GroupPanicked {}
+
+impl Group {
+    /// Create an empty group.
+    pub fn new() -> Group {
+        Group::default()
+    }
+
+    /// Spawn a thread named `name` into the group.
+    pub fn spawn<T, F>(&mut self, name: T, entry_point: F) -> ::std::io::Result<()>
+        where T: Into<String>,
+              F: FnOnce() + Send + 'static
+    {
+        let handle = Builder::new(name).spawn_unmanaged(entry_point)?;
+        self.handles.push(handle);
+        Ok(())
+    }
+
+    /// Add an already spawned thread, e.g. one from `thread!`, to the group.
+    pub fn add(&mut self, handle: ::std::thread::JoinHandle<()>) {
+        self.handles.push(handle);
+    }
+
+    /// The number of threads in the group.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Whether the group has no threads.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Join every thread, most recently spawned first.
+    pub fn join_all(mut self) -> Result<(), GroupPanicked> {
+        self.join_handles()
+    }
+
+    fn join_handles(&mut self) -> Result<(), GroupPanicked> {
+        let mut reports = Vec::new();
+        while let Some(handle) = self.handles.pop() {
+            if let Err(report) = join(handle) {
+                reports.push(report);
+            }
+        }
+        if reports.is_empty() {
+            Ok(())
+        } else {
+            Err(GroupPanicked { reports })
+        }
+    }
+}
+
+impl Drop for Group {
+    fn drop(&mut self) {
+        if let Err(error) = self.join_handles() {
+            if ::std::thread::panicking() {
+                error!("{}", error);
+            } else {
+                panic!("{}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn joins_in_reverse_order() {
+        let order = ::std::sync::Arc::new(::std::sync::Mutex::new(Vec::new()));
+        let (release_tx, release_rx) = ::std::sync::mpsc::channel::<()>();
+        let release_rx = ::std::sync::Arc::new(::std::sync::Mutex::new(release_rx));
+        {
+            let mut group = Group::new();
+            for index in 0..3 {
+                let order = order.clone();
+                let release_rx = release_rx.clone();
+                unwrap_result!(group.spawn(format!("GroupTest-{}", index), move || {
+                    let _ = unwrap_result!(release_rx.lock()).recv();
+                    unwrap_result!(order.lock()).push(index);
+                }));
+            }
+            group.add(thread!("GroupTestAdded", || ()));
+            assert_eq!(group.len(), 4);
+            drop(release_tx);
+        }
+        // All threads have been joined by the time the group is gone.
+        assert_eq!(unwrap_result!(order.lock()).len(), 3);
+
+        let mut group = Group::new();
+        for index in 0..3 {
+            unwrap_result!(group.spawn(format!("GroupPanic-{}", index), move || {
+                if index != 1 {
+                    panic!("Failed {}", index);
+                }
+            }));
+        }
+        let error = unwrap_option!(group.join_all().err(), "Two threads should have panicked");
+        assert_eq!(error.to_string(),
+                   "2 thread(s) panicked; Thread \"GroupPanic-2\" panicked: Failed 2; Thread \
+                    \"GroupPanic-0\" panicked: Failed 0");
+        assert!(Group::new().is_empty());
+    }
+}
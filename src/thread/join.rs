@@ -0,0 +1,88 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{PanicReport, panic_message};
+
+/// Join every thread in `handles`, in order, carrying on past any which panicked, and return each
+/// one's result. This lets shutdown code decide what to do from the aggregate rather than bailing
+/// out at the first panic.
+///
+/// `RaiiThreadJoiner`s can be passed too, as they convert into their `JoinHandle`; note that this
+/// does not cancel any `CancellationToken` they were set up with.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// let handles = (0..3)
+///                   .map(|index| thread!(format!("Worker-{}", index), move || {
+///                       if index == 1 {
+///                           panic!("Worker {} failed", index);
+///                       }
+///                       index * 10
+///                   }))
+///                   .collect::<Vec<_>>();
+///
+/// let results = thread::join_all(handles);
+/// assert_eq!(results[0], Ok(0));
+/// assert_eq!(unwrap_option!(results[1].clone().err(), "").message, "Worker 1 failed");
+/// assert_eq!(results[2], Ok(20));
+/// # }
+/// ```
+pub fn join_all<I, T>(handles: I) -> Vec<Result<T, PanicReport>>
+    where I: IntoIterator,
+          I::Item: Into<::std::thread::JoinHandle<T>>
+{
+    handles.into_iter().map(|handle| join(handle.into())).collect()
+}
+
+/// Join the thread, converting a panic into a report naming the thread.
+pub fn join<T>(handle: ::std::thread::JoinHandle<T>) -> Result<T, PanicReport> {
+    let thread_name = handle.thread().name().map(str::to_owned);
+    handle.join().map_err(|payload| {
+        PanicReport {
+            thread_name,
+            message: panic_message(&*payload),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use thread::RaiiThreadJoiner;
+
+    #[test]
+    fn joiners_and_panics() {
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let joiners = vec![RaiiThreadJoiner::new(thread!("Panicking", || panic!("Oops"))),
+                           RaiiThreadJoiner::new(thread!("Waiting", move || {
+                               let _ = finish_rx.recv();
+                           }))];
+        drop(finish_tx);
+        assert_eq!(join_all(joiners),
+                   vec![Err(PanicReport {
+                            thread_name: Some("Panicking".to_owned()),
+                            message    : "Oops".to_owned(),
+                        }),
+                        Ok(())]);
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// A countdown latch: threads wait until it has been counted down `n` times, as when starting `n`
+/// workers and waiting until all report that they are ready.
+///
+/// Clones share the same count. Once the count reaches zero it stays there, and all current and
+/// future waits return immediately.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{Group, Latch};
+/// use std::time::Duration;
+///
+/// let ready = Latch::new(3);
+/// let mut group = Group::new();
+/// for index in 0..3 {
+///     let ready = ready.clone();
+///     unwrap_result!(group.spawn(format!("Node-{}", index), move || {
+///         // Bind sockets, load state, etc.
+///         ready.count_down();
+///     }));
+/// }
+/// assert!(ready.wait(Duration::from_secs(30)));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Latch {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Latch {
+    /// Create a latch which opens after `count` calls to `count_down`.
+    pub fn new(count: usize) -> Latch {
+        Latch {
+            inner: Arc::new((Mutex::new(count), Condvar::new())),
+        }
+    }
+
+    /// Decrement the count, waking the waiting threads if it reaches zero. Has no effect once it
+    /// is zero.
+    pub fn count_down(&self) {
+        let (ref count, ref condition) = *self.inner;
+        let mut count = unwrap_result!(count.lock());
+        if *count > 0 {
+            *count -= 1;
+            if *count == 0 {
+                condition.notify_all();
+            }
+        }
+    }
+
+    /// The number of `count_down` calls still needed.
+    pub fn count(&self) -> usize {
+        *unwrap_result!(self.inner.0.lock())
+    }
+
+    /// Block for at most `timeout` until the count reaches zero. Returns whether it did. A
+    /// `timeout` too long to be represented as an `Instant` means waiting as long as it takes.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        let (ref count, ref condition) = *self.inner;
+        let mut count = unwrap_result!(count.lock());
+        while *count > 0 {
+            count = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    unwrap_result!(condition.wait_timeout(count, deadline - now)).0
+                }
+                None => unwrap_result!(condition.wait(count)),
+            };
+        }
+        true
+    }
+}
+
+impl ::std::fmt::Debug for Latch {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        formatter.debug_struct("Latch").field("count", &self.count()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn opens_after_count_downs() {
+        let latch = Latch::new(2);
+        assert!(!latch.wait(Duration::from_millis(10)));
+
+        let latch_clone = latch.clone();
+        let _raii_joiner = ::thread::RaiiThreadJoiner::new(thread!("CountDown", move || {
+            latch_clone.count_down();
+            ::std::thread::sleep(Duration::from_millis(10));
+            latch_clone.count_down();
+        }));
+        assert!(latch.wait(Duration::MAX));
+        assert_eq!(latch.count(), 0);
+
+        latch.count_down();
+        assert_eq!(latch.count(), 0);
+        assert!(Latch::new(0).wait(Duration::from_secs(0)));
+    }
+}
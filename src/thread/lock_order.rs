@@ -0,0 +1,422 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! `Mutex` and `RwLock` wrappers which, in debug builds, detect inconsistent lock ordering.
+//!
+//! Every acquisition while other instrumented locks are held records the order in which the
+//! thread took them. If a thread acquires locks in an order which contradicts one seen before (on
+//! any thread), it panics with a report naming both threads and where each lock was created,
+//! rather than risk the deadlock only showing up as a hung CI run. Taking a lock the thread
+//! already holds is reported the same way. In release builds the wrappers add no tracking.
+//!
+//! #Examples
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate maidsafe_utilities;
+//! # fn main() {
+//! use maidsafe_utilities::thread::lock_order::Mutex;
+//!
+//! let routing_table = Mutex::new(vec![1u64, 2, 3]);
+//! let cache = Mutex::new(Vec::<u64>::new());
+//! {
+//!     let table = unwrap_result!(routing_table.lock());
+//!     unwrap_result!(cache.lock()).extend(table.iter());
+//! }
+//!
+//! // Taking the locks in the opposite order from here on, as below, would now panic in debug
+//! // builds, since it could deadlock with the code above:
+//! //
+//! //     let _cache = unwrap_result!(cache.lock());
+//! //     let _table = unwrap_result!(routing_table.lock());
+//! # }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+use std::panic::Location;
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+use self::tracking::{Held, Lock};
+
+/// A `std::sync::Mutex` whose acquisitions are checked for consistent ordering in debug builds.
+pub struct Mutex<T: ?Sized> {
+    lock : Lock,
+    inner: ::std::sync::Mutex<T>,
+}
+
+/// The guard returned by `Mutex::lock`.
+pub struct MutexGuard<'a, T: ?Sized + 'a> {
+    _held: Held,
+    inner: ::std::sync::MutexGuard<'a, T>,
+}
+
+impl<T> Mutex<T> {
+    /// Create a mutex, identified in reports by where it was created.
+    #[track_caller]
+    pub fn new(value: T) -> Mutex<T> {
+        Mutex {
+            lock : Lock::new(Location::caller()),
+            inner: ::std::sync::Mutex::new(value),
+        }
+    }
+
+    /// Consume the mutex, returning the value. See `std::sync::Mutex::into_inner`.
+    pub fn into_inner(self) -> LockResult<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquire the mutex, blocking until it is available. In debug builds this panics if doing so
+    /// contradicts the lock order seen before. See `std::sync::Mutex::lock`.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        let held = self.lock.acquire();
+        map_result(self.inner.lock(), |inner| MutexGuard { _held: held, inner })
+    }
+
+    /// Attempt to acquire the mutex without blocking. As this cannot deadlock, it is not checked,
+    /// but while held it counts towards the order of later acquisitions.
+    pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+        match self.inner.try_lock() {
+            Ok(inner) => Ok(MutexGuard { _held: self.lock.hold(), inner }),
+            Err(TryLockError::Poisoned(error)) => {
+                let inner = error.into_inner();
+                Err(TryLockError::Poisoned(PoisonError::new(MutexGuard {
+                    _held: self.lock.hold(),
+                    inner,
+                })))
+            }
+            Err(TryLockError::WouldBlock) => Err(TryLockError::WouldBlock),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for MutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// A `std::sync::RwLock` whose acquisitions are checked for consistent ordering in debug builds.
+///
+/// Read and write acquisitions are treated alike, since a cycle involving a reader can still
+/// deadlock against a waiting writer.
+pub struct RwLock<T: ?Sized> {
+    lock : Lock,
+    inner: ::std::sync::RwLock<T>,
+}
+
+/// The guard returned by `RwLock::read`.
+pub struct RwLockReadGuard<'a, T: ?Sized + 'a> {
+    _held: Held,
+    inner: ::std::sync::RwLockReadGuard<'a, T>,
+}
+
+/// The guard returned by `RwLock::write`.
+pub struct RwLockWriteGuard<'a, T: ?Sized + 'a> {
+    _held: Held,
+    inner: ::std::sync::RwLockWriteGuard<'a, T>,
+}
+
+impl<T> RwLock<T> {
+    /// Create a lock, identified in reports by where it was created.
+    #[track_caller]
+    pub fn new(value: T) -> RwLock<T> {
+        RwLock {
+            lock : Lock::new(Location::caller()),
+            inner: ::std::sync::RwLock::new(value),
+        }
+    }
+
+    /// Consume the lock, returning the value. See `std::sync::RwLock::into_inner`.
+    pub fn into_inner(self) -> LockResult<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Acquire shared access, blocking until it is available. See `std::sync::RwLock::read`.
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        let held = self.lock.acquire();
+        map_result(self.inner.read(), |inner| RwLockReadGuard { _held: held, inner })
+    }
+
+    /// Acquire exclusive access, blocking until it is available. See `std::sync::RwLock::write`.
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        let held = self.lock.acquire();
+        map_result(self.inner.write(), |inner| RwLockWriteGuard { _held: held, inner })
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+fn map_result<G, W, F: FnOnce(G) -> W>(result: LockResult<G>, wrap: F) -> LockResult<W> {
+    match result {
+        Ok(guard) => Ok(wrap(guard)),
+        Err(error) => Err(PoisonError::new(wrap(error.into_inner()))),
+    }
+}
+
+#[cfg(debug_assertions)]
+mod tracking {
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::panic::Location;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    thread_local! {
+        static HELD: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    }
+
+    // An edge `a -> b` means some thread acquired `b` while holding `a`; the value is the name of
+    // the first thread seen doing so.
+    #[derive(Default)]
+    struct Graph {
+        locations: HashMap<u64, &'static Location<'static>>,
+        edges    : HashMap<u64, HashMap<u64, String>>,
+    }
+
+    fn graph() -> &'static ::std::sync::Mutex<Graph> {
+        static GRAPH: ::std::sync::OnceLock<::std::sync::Mutex<Graph>> =
+            ::std::sync::OnceLock::new();
+        GRAPH.get_or_init(|| ::std::sync::Mutex::new(Graph::default()))
+    }
+
+    pub struct Lock {
+        id: u64,
+    }
+
+    impl Lock {
+        pub fn new(location: &'static Location<'static>) -> Lock {
+            static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let _ = unwrap_result!(graph().lock()).locations.insert(id, location);
+            Lock { id }
+        }
+
+        // Check the acquisition against the order seen so far, then record it.
+        pub fn acquire(&self) -> Held {
+            let held = HELD.with(|held| held.borrow().clone());
+            if !held.is_empty() {
+                if let Some(report) = self.record(&held) {
+                    panic!("{}", report);
+                }
+            }
+            self.hold()
+        }
+
+        pub fn hold(&self) -> Held {
+            HELD.with(|held| held.borrow_mut().push(self.id));
+            Held { id: self.id }
+        }
+
+        fn record(&self, held: &[u64]) -> Option<String> {
+            let thread_name = ::thread::current_name();
+            let mut graph = unwrap_result!(graph().lock());
+            if held.contains(&self.id) {
+                return Some(format!("Thread \"{}\" is acquiring the lock created at {} which it \
+                                     already holds",
+                                    thread_name,
+                                    graph.locations[&self.id]));
+            }
+            for &earlier in held {
+                if let Some(path) = graph.path(self.id, earlier) {
+                    return Some(graph.report(&thread_name, earlier, self.id, &path));
+                }
+            }
+            for &earlier in held {
+                let _ = graph.edges
+                             .entry(earlier)
+                             .or_default()
+                             .entry(self.id)
+                             .or_insert_with(|| thread_name.clone());
+            }
+            None
+        }
+    }
+
+    impl Drop for Lock {
+        fn drop(&mut self) {
+            let mut graph = unwrap_result!(graph().lock());
+            let _ = graph.locations.remove(&self.id);
+            let _ = graph.edges.remove(&self.id);
+            for targets in graph.edges.values_mut() {
+                let _ = targets.remove(&self.id);
+            }
+        }
+    }
+
+    impl Graph {
+        // A path of edges from `from` to `to`, if there is one.
+        fn path(&self, from: u64, to: u64) -> Option<Vec<u64>> {
+            let mut visited = HashSet::new();
+            let mut stack = vec![vec![from]];
+            while let Some(path) = stack.pop() {
+                let last = path[path.len() - 1];
+                if last == to {
+                    return Some(path);
+                }
+                if !visited.insert(last) {
+                    continue;
+                }
+                for &next in self.edges.get(&last).into_iter().flat_map(|targets| targets.keys()) {
+                    let mut longer = path.clone();
+                    longer.push(next);
+                    stack.push(longer);
+                }
+            }
+            None
+        }
+
+        fn report(&self, thread_name: &str, held: u64, acquiring: u64, path: &[u64]) -> String {
+            let mut report = format!("Lock order cycle: thread \"{}\" is acquiring the lock \
+                                      created at {} while holding the one created at {}, but",
+                                     thread_name,
+                                     self.locations[&acquiring],
+                                     self.locations[&held]);
+            for pair in path.windows(2) {
+                report.push_str(&format!("\n    thread \"{}\" acquired the lock created at {} \
+                                          while holding the one created at {}",
+                                         self.edges[&pair[0]][&pair[1]],
+                                         self.locations[&pair[1]],
+                                         self.locations[&pair[0]]));
+            }
+            report
+        }
+    }
+
+    pub struct Held {
+        id: u64,
+    }
+
+    impl Drop for Held {
+        fn drop(&mut self) {
+            HELD.with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(index) = held.iter().rposition(|&id| id == self.id) {
+                    let _ = held.remove(index);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod tracking {
+    use std::panic::Location;
+
+    pub struct Lock;
+
+    impl Lock {
+        pub fn new(_location: &'static Location<'static>) -> Lock {
+            Lock
+        }
+
+        pub fn acquire(&self) -> Held {
+            Held
+        }
+
+        pub fn hold(&self) -> Held {
+            Held
+        }
+    }
+
+    pub struct Held;
+}
+
+#[cfg(all(test, debug_assertions))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn consistent_order_is_allowed() {
+        let first = Mutex::new(1);
+        let second = RwLock::new(2);
+        for _ in 0..2 {
+            let first = unwrap_result!(first.lock());
+            let second = unwrap_result!(second.read());
+            assert_eq!(*first + *second, 3);
+        }
+        // Either lock on its own is fine in any order.
+        drop(unwrap_result!(second.write()));
+        drop(unwrap_result!(first.lock()));
+    }
+
+    #[test]
+    fn cycle_across_threads_is_reported() {
+        let first = ::std::sync::Arc::new(Mutex::new(()));
+        let second = ::std::sync::Arc::new(Mutex::new(()));
+        let third = ::std::sync::Arc::new(RwLock::new(()));
+        {
+            let _first = unwrap_result!(first.lock());
+            let _second = unwrap_result!(second.lock());
+        }
+        let (second_clone, third_clone) = (second.clone(), third.clone());
+        unwrap_result!(thread!("LockOrderA", move || {
+            let _second = unwrap_result!(second_clone.lock());
+            let _third = unwrap_result!(third_clone.write());
+        }).join());
+
+        let payload = unwrap_option!(thread!("LockOrderB", move || {
+            let _third = unwrap_result!(third.read());
+            let _first = unwrap_result!(first.lock());
+        }).join().err(), "Cycle not detected");
+        let report = ::thread::panic_message(&*payload);
+        assert!(report.starts_with("Lock order cycle: thread \"LockOrderB\""), "{}", report);
+        assert!(report.contains("thread \"LockOrderA\""), "{}", report);
+        assert!(report.contains("lock_order.rs"), "{}", report);
+    }
+
+    #[test]
+    fn relocking_is_reported() {
+        let mutex = Mutex::new(());
+        let _guard = unwrap_result!(mutex.lock());
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            let _again = mutex.lock();
+        }));
+        assert!(::thread::panic_message(&*unwrap_option!(result.err(), ""))
+                    .contains("which it already holds"));
+        assert!(mutex.try_lock().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{current_name, panic_message};
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Whether `log_all_panics` has been called, so the hook logs every panic itself.
+static LOG_ALL: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static CAPTURE_BACKTRACES: Cell<bool> = const { Cell::new(false) };
+    static LAST_BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+/// Run `entry_point`, logging any panic in it as an error along with the thread's name and a
+/// backtrace from the point of the panic, then dump any crash buffer (see
+/// `log::Builder::crash_buffer`) and resume unwinding.
+pub fn run_logged<F: FnOnce() -> T, T>(entry_point: F) -> T {
+    install_hook();
+    CAPTURE_BACKTRACES.with(|capture| capture.set(true));
+    let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(entry_point));
+    CAPTURE_BACKTRACES.with(|capture| capture.set(false));
+    match result {
+        Ok(value) => value,
+        Err(payload) => {
+            let backtrace = LAST_BACKTRACE.with(|last| last.borrow_mut().take())
+                                          .map_or_else(String::new, |trace| trace.to_string());
+            // Otherwise the hook has logged it already.
+            if !LOG_ALL.load(Ordering::SeqCst) {
+                error!("Thread \"{}\" panicked: {}\n{}",
+                       current_name(),
+                       panic_message(&*payload),
+                       backtrace);
+            }
+            ::log::flush();
+            ::log::dump_crash_buffer();
+            ::std::panic::resume_unwind(payload)
+        }
+    }
+}
+
+/// Log every panic in the process from now on, on whichever thread, as an error with the
+/// thread's name, the panic's location and message, and a backtrace, then flush the logger, so
+/// that crashes in detached threads reach the log files operators read even if the process then
+/// aborts. This chains to the previously installed panic hook, so by default the panic is also
+/// still printed to stderr. `log::Builder::log_panics` calls this on initialising the logger.
+///
+/// As a panic may yet be caught, any crash buffer (see `log::Builder::crash_buffer`) is only
+/// dumped here if panics abort the process; otherwise it is dumped when a panic escapes a thread
+/// spawned via this crate with `Builder::log_panics` (e.g. by `thread_logged!`), or by calling
+/// `log::dump_crash_buffer`.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// thread::log_all_panics();
+/// let handle = std::thread::spawn(|| panic!("Lost connection"));
+/// // Logs e.g. `Thread "ThreadId(2)" panicked at src/main.rs:5:36: Lost connection` and a
+/// // backtrace.
+/// assert!(handle.join().is_err());
+/// # }
+/// ```
+pub fn log_all_panics() {
+    LOG_ALL.store(true, Ordering::SeqCst);
+    install_hook();
+}
+
+// The backtrace has to be captured by the panic hook, while the panicking frames are still on the
+// stack. The hook only does so on threads running `run_logged` or after `log_all_panics`, and
+// chains to the previous hook.
+fn install_hook() {
+    static INSTALL: ::std::sync::Once = ::std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let previous = ::std::panic::take_hook();
+        ::std::panic::set_hook(Box::new(move |info| {
+            let log_all = LOG_ALL.load(Ordering::SeqCst);
+            let capture = CAPTURE_BACKTRACES.with(Cell::get);
+            if log_all || capture {
+                let backtrace = Backtrace::force_capture();
+                if log_all {
+                    let location = info.location().map_or_else(String::new, |location| {
+                        format!(" at {}:{}:{}", location.file(), location.line(), location.column())
+                    });
+                    error!("Thread \"{}\" panicked{}: {}\n{}",
+                           current_name(),
+                           location,
+                           panic_message(info.payload()),
+                           backtrace);
+                    ::log::flush();
+                    if cfg!(panic = "abort") {
+                        ::log::dump_crash_buffer();
+                    }
+                }
+                if capture {
+                    LAST_BACKTRACE.with(|last| *last.borrow_mut() = Some(backtrace));
+                }
+            }
+            previous(info);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn panic_is_resumed_after_logging() {
+        let handle = thread_logged!("LoggedPanic", || -> u8 { panic!("Oops") });
+        let payload = unwrap_option!(handle.join().err(), "The thread should have panicked");
+        assert_eq!(panic_message(&*payload), "Oops");
+        assert!(LAST_BACKTRACE.with(|last| last.borrow().is_none()));
+
+        assert_eq!(unwrap_result!(thread_logged!("LoggedSuccess", || 7).join()), 7);
+    }
+
+    #[test]
+    fn backtrace_is_taken_at_the_panic() {
+        let result = ::std::panic::catch_unwind(|| run_logged(|| panic!("Traced")));
+        assert!(result.is_err());
+        // Consumed by the logging.
+        assert!(LAST_BACKTRACE.with(|last| last.borrow().is_none()));
+        assert!(!CAPTURE_BACKTRACES.with(Cell::get));
+
+        let _ = ::std::panic::catch_unwind(|| {
+            CAPTURE_BACKTRACES.with(|capture| capture.set(true));
+            install_hook();
+            panic!("Captured")
+        });
+        CAPTURE_BACKTRACES.with(|capture| capture.set(false));
+        let backtrace = unwrap_option!(LAST_BACKTRACE.with(|last| last.borrow_mut().take()), "");
+        assert_eq!(backtrace.status(), ::std::backtrace::BacktraceStatus::Captured);
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright 2015 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// This macro is intended to be used in all cases where we want to spawn a new thread of execution
+/// and if that is not possible then panic out. Use `thread::named` or `thread::Builder` instead
+/// where failing to spawn should be handled.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// let _ = thread!("DaemonThread", move || {
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+/// });
+///
+/// let sleep_duration_ms = 500;
+/// let _raii_joiner = maidsafe_utilities::thread
+///                                      ::RaiiThreadJoiner::new(thread!("ManagedThread", move || {
+///     std::thread::sleep(std::time::Duration::from_millis(sleep_duration_ms));
+/// }));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! thread {
+    ($thread_name:expr, $entry_point:expr) => {
+        unwrap_result!($crate::thread::Builder::new($thread_name.to_string())
+                                               .spawn_unmanaged($entry_point))
+    }
+}
+
+/// As `thread!`, except that a panic in the thread is logged as an error, along with the thread's
+/// name and a backtrace, before it unwinds. This ensures panics in detached threads are never
+/// silently lost. See also `Builder::log_panics`.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// let handle = thread_logged!("DetachedWorker", move || panic!("Lost connection"));
+/// assert!(handle.join().is_err());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! thread_logged {
+    ($thread_name:expr, $entry_point:expr) => {
+        unwrap_result!($crate::thread::Builder::new($thread_name.to_string())
+                                               .log_panics()
+                                               .spawn_unmanaged($entry_point))
+    }
+}
+
+/// Run the given statements when the enclosing scope exits, whether normally, by an early return
+/// or by unwinding from a panic. This is shorthand for holding a `thread::ScopeGuard`.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// let path = std::env::temp_dir().join(format!("defer-example-{}", std::process::id()));
+/// {
+///     unwrap_result!(std::fs::write(&path, b"partial"));
+///     defer!(let _ = std::fs::remove_file(&path););
+///     // Work with the temporary file.
+/// }
+/// assert!(!path.exists());
+/// # }
+/// ```
+#[macro_export]
+macro_rules! defer {
+    ($($cleanup:tt)*) => {
+        let _scope_guard = $crate::thread::ScopeGuard::new(|| { $($cleanup)* });
+    }
+}
+
+pub use self::affinity::set_current_affinity;
+pub use self::arc_joiner::ArcJoiner;
+pub use self::backoff::Backoff;
+pub use self::builder::{Builder, named, named_with_stack_size, unique_name};
+pub use self::cancellation::{CancellationToken, WokenReason, named_cancellable,
+                             sleep_interruptible};
+pub use self::group::{Group, GroupPanicked};
+pub use self::join::join_all;
+pub use self::latch::Latch;
+pub use self::logged::log_all_panics;
+pub use self::notifier::{Notifier, Waiter, Wakeup};
+pub use self::panic_report::{PanicReport, panic_message};
+pub use self::parallel::parallel_map;
+pub use self::pool::{Pool, QueueFull, ShutdownReport, TaskHandle, TaskPanicked};
+pub use self::priority::{Priority, set_current_priority};
+pub use self::registry::{ThreadInfo, ThreadState, disable_registry, enable_registry, list};
+pub use self::result_handle::{ResultHandle, spawn_with_result};
+pub use self::rng::{SEED_ENV_VAR, SeededRng, global_seed, seeded_rng};
+pub use self::scheduler::{Cadence, MissedTicks, Scheduler, run_after};
+pub use self::scope_guard::ScopeGuard;
+pub use self::scoped::{Scope, scope};
+pub use self::stats::{Stats, record_iteration};
+pub use self::stoppable::StoppableThread;
+pub use self::supervisor::{Restart, RestartPolicy, supervised, supervised_reporting};
+pub use self::watchdog::{Heartbeat, Stall, Watchdog};
+pub use self::worker::{Job, Worker};
+pub use timer::TimerHandle;
+
+mod affinity;
+mod arc_joiner;
+mod backoff;
+mod builder;
+mod cancellation;
+mod group;
+mod join;
+mod latch;
+pub mod lock_order;
+mod logged;
+mod notifier;
+mod panic_report;
+mod parallel;
+mod pool;
+mod priority;
+mod registry;
+mod result_handle;
+mod rng;
+mod scheduler;
+mod scope_guard;
+mod scoped;
+mod stats;
+mod stoppable;
+mod supervisor;
+mod watchdog;
+mod worker;
+
+const JOIN_POLL_MAX_MS: u64 = 10;
+
+/// Returned by `RaiiThreadJoiner::join_timeout` if the thread was still running at the deadline.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinTimedOut {
+    /// The name of the thread, if it has one.
+    pub name: Option<String>,
+}
+
+impl ::std::fmt::Display for JoinTimedOut {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self.name {
+            Some(ref name) => write!(formatter, "Timed out joining thread \"{}\"", name),
+            None => write!(formatter, "Timed out joining unnamed thread"),
+        }
+    }
+}
+
+impl ::std::error::Error for JoinTimedOut {}
+
+/// The name of the current thread or, if it has none, one derived from its `ThreadId` such as
+/// `ThreadId(7)`, so that log lines and reports can always identify the thread.
+///
+/// The name is the one given to the thread on spawning (e.g. via `thread!`), not whatever the OS
+/// may have truncated it to.
+pub fn current_name() -> String {
+    let current = ::std::thread::current();
+    match current.name() {
+        Some(name) => name.to_owned(),
+        None => format!("{:?}", current.id()),
+    }
+}
+
+/// A RAII style thread joiner. The destruction of an instance of this type will block until
+/// the thread it is managing has joined.
+pub struct RaiiThreadJoiner {
+    joiner: Option<::std::thread::JoinHandle<()>>,
+    token : Option<CancellationToken>,
+}
+
+impl RaiiThreadJoiner {
+    /// Create a new instance of self-managing thread joiner
+    pub fn new(joiner: ::std::thread::JoinHandle<()>) -> RaiiThreadJoiner {
+        RaiiThreadJoiner {
+            joiner: Some(joiner),
+            token : None,
+        }
+    }
+
+    /// Cancel `token` before joining the thread, whether on being dropped or in `join_timeout`,
+    /// so that a worker sleeping in `CancellationToken::wait_or_cancelled` wakes promptly.
+    /// `named_cancellable` sets this up automatically.
+    pub fn cancel_on_drop(mut self, token: CancellationToken) -> RaiiThreadJoiner {
+        self.token = Some(token);
+        self
+    }
+
+    /// The managed thread, e.g. to compare its id with the current thread's.
+    pub fn thread(&self) -> &::std::thread::Thread {
+        unwrap_option!(self.joiner.as_ref(), "Programming error: please report this as a bug.")
+            .thread()
+    }
+
+    /// Whether the thread has finished running, without blocking. A supervisor can use this to
+    /// poll a worker's health without attempting a join.
+    pub fn is_finished(&self) -> bool {
+        self.joiner.as_ref().is_none_or(::std::thread::JoinHandle::is_finished)
+    }
+
+    /// Wait at most `timeout` for the thread to finish. If it does not, it is logged and
+    /// detached, so that shutdown code can give a worker a bounded grace period and then proceed
+    /// rather than hanging in the destructor. As when dropped, a panic in the thread is propagated
+    /// if it did finish. A `timeout` too long to be represented as an `Instant` means joining
+    /// without a deadline.
+    pub fn join_timeout(mut self, timeout: ::std::time::Duration) -> Result<(), JoinTimedOut> {
+        let joiner = unwrap_option!(self.joiner.take(),
+                                    "Programming error: please report this as a bug.");
+        self.cancel();
+        let deadline = match ::std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => {
+                unwrap_result!(joiner.join());
+                return Ok(());
+            }
+        };
+        let mut interval = ::std::time::Duration::from_millis(1);
+        while !joiner.is_finished() {
+            let now = ::std::time::Instant::now();
+            if now >= deadline {
+                let name = joiner.thread().name().map(str::to_owned);
+                warn!("Thread {:?} did not finish within {:?}; detaching it", name, timeout);
+                return Err(JoinTimedOut { name });
+            }
+            ::std::thread::sleep(interval.min(deadline - now));
+            interval = (interval * 2).min(::std::time::Duration::from_millis(JOIN_POLL_MAX_MS));
+        }
+        unwrap_result!(joiner.join());
+        Ok(())
+    }
+
+    /// Let the thread run on independently: the joiner is consumed without joining it. This is
+    /// for threads which are intentionally fire-and-forget but still spawned via the helpers here.
+    pub fn detach(mut self) {
+        let _ = self.joiner.take();
+    }
+
+    /// Give up managing the thread, returning its handle without joining it.
+    pub fn into_inner(mut self) -> ::std::thread::JoinHandle<()> {
+        unwrap_option!(self.joiner.take(), "Programming error: please report this as a bug.")
+    }
+
+    fn cancel(&self) {
+        if let Some(ref token) = self.token {
+            token.cancel();
+        }
+    }
+}
+
+impl From<RaiiThreadJoiner> for ::std::thread::JoinHandle<()> {
+    fn from(raii_joiner: RaiiThreadJoiner) -> ::std::thread::JoinHandle<()> {
+        raii_joiner.into_inner()
+    }
+}
+
+impl Drop for RaiiThreadJoiner {
+    fn drop(&mut self) {
+        if let Some(joiner) = self.joiner.take() {
+            self.cancel();
+            unwrap_result!(joiner.join());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate time;
+
+    use super::*;
+
+    #[test]
+    fn raii_thread_joiner() {
+        const SLEEP_DURATION_DAEMON: u64 = 150;
+        // Factor of 3 should ensure that all threads gracefully exit when the test finishes
+        const SLEEP_DURATION_MANAGED: u64 = SLEEP_DURATION_DAEMON * 3;
+
+        {
+            let time_before = time::SteadyTime::now();
+            {
+                let _ = thread!("JoinerTestDaemon", move || {
+                    ::std::thread::sleep(::std::time::Duration::from_millis(SLEEP_DURATION_DAEMON));
+                });
+            }
+            let time_after = time::SteadyTime::now();
+
+            let diff = time_after - time_before;
+
+            assert!(diff < time::Duration::milliseconds(SLEEP_DURATION_DAEMON as i64));
+        }
+
+        {
+            let time_before = time::SteadyTime::now();
+            {
+                let _raii_joiner = RaiiThreadJoiner::new(thread!("JoinerTestManaged", move || {
+                    ::std::thread::sleep(::std::time::Duration::from_millis(SLEEP_DURATION_MANAGED));
+                }));
+            }
+            let time_after = time::SteadyTime::now();
+
+            let diff = time_after - time_before;
+
+            assert!(diff >= time::Duration::milliseconds(SLEEP_DURATION_MANAGED as i64));
+        }
+    }
+
+    #[test]
+    fn current_name() {
+        let name = unwrap_result!(thread!("NameThatIsLongerThanTheLinuxLimit", super::current_name)
+                                      .join());
+        assert_eq!(name, "NameThatIsLongerThanTheLinuxLimit");
+
+        let name = unwrap_result!(::std::thread::spawn(super::current_name).join());
+        assert!(name.starts_with("ThreadId("), "{}", name);
+        assert_ne!(name, super::current_name());
+    }
+
+    #[test]
+    fn join_timeout() {
+        let timeout = ::std::time::Duration::from_millis(50);
+        let raii_joiner = RaiiThreadJoiner::new(thread!("QuickThread", || ()));
+        assert_eq!(raii_joiner.join_timeout(timeout), Ok(()));
+
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let raii_joiner = RaiiThreadJoiner::new(thread!("StuckThread", move || {
+            let _ = finish_rx.recv();
+        }));
+        let start = ::std::time::Instant::now();
+        assert_eq!(raii_joiner.join_timeout(timeout),
+                   Err(JoinTimedOut { name: Some("StuckThread".to_owned()) }));
+        assert!(start.elapsed() >= timeout);
+        unwrap_result!(finish_tx.send(()));
+
+        let raii_joiner = RaiiThreadJoiner::new(thread!("SlowThread", || {
+            ::std::thread::sleep(::std::time::Duration::from_millis(10));
+        }));
+        assert_eq!(raii_joiner.join_timeout(::std::time::Duration::MAX), Ok(()));
+    }
+
+    #[test]
+    fn is_finished() {
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let raii_joiner = RaiiThreadJoiner::new(thread!("PolledThread", move || {
+            let _ = finish_rx.recv();
+        }));
+        assert!(!raii_joiner.is_finished());
+        drop(finish_tx);
+        let start = ::std::time::Instant::now();
+        while !raii_joiner.is_finished() {
+            assert!(start.elapsed() < ::std::time::Duration::from_secs(30));
+            ::std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn detach() {
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let raii_joiner = RaiiThreadJoiner::new(thread!("DetachedThread", move || {
+            unwrap_result!(started_tx.send(()));
+            let _ = finish_rx.recv();
+        }));
+        unwrap_result!(started_rx.recv());
+
+        // Would block for ever if it joined, as the thread is waiting on `finish_tx`.
+        raii_joiner.detach();
+        unwrap_result!(finish_tx.send(()));
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::CancellationToken;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct State {
+    // Notifications from `notify_one` not yet consumed by a waiter.
+    pending   : usize,
+    // Incremented by each `notify_all`.
+    generation: u64,
+}
+
+struct Shared {
+    state    : Mutex<State>,
+    condition: Condvar,
+    token    : Option<CancellationToken>,
+}
+
+/// Why `Waiter::wait_timeout` returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wakeup {
+    /// The waiter was notified.
+    Notified,
+    /// The timeout elapsed without a notification.
+    TimedOut,
+    /// The pair's cancellation token was cancelled, possibly before the wait began.
+    Cancelled,
+}
+
+/// The sending half of a notification pair created by `Notifier::new`, for waking threads
+/// blocked in `Waiter::wait_timeout` without handling a raw `Condvar` and its mutex.
+///
+/// As with `Thread::unpark`, a `notify_one` sent while no thread is waiting is not lost: the next
+/// wait returns immediately. `notify_all` only wakes the threads waiting at the time.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{self, Notifier, Wakeup};
+/// use std::time::Duration;
+///
+/// let (notifier, waiter) = Notifier::new();
+/// let _raii_joiner = unwrap_result!(thread::named("Loader", move || {
+///     // Load something, then tell the waiter it is ready.
+///     notifier.notify_one();
+/// }));
+/// assert_eq!(waiter.wait_timeout(Duration::from_secs(60)), Wakeup::Notified);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Notifier {
+    shared: Arc<Shared>,
+}
+
+impl Notifier {
+    /// Create a connected `Notifier` and `Waiter`. Both may be cloned to share them between
+    /// threads.
+    pub fn new() -> (Notifier, Waiter) {
+        Notifier::create(None)
+    }
+
+    /// As `new`, but cancelling `token` also wakes every waiter, whose waits then return
+    /// `Wakeup::Cancelled` from then on.
+    pub fn with_cancellation(token: CancellationToken) -> (Notifier, Waiter) {
+        let (notifier, waiter) = Notifier::create(Some(token.clone()));
+        let shared = Arc::downgrade(&notifier.shared);
+        token.on_cancel(move || if let Some(shared) = shared.upgrade() {
+            // Taking the lock ensures a waiter which has just seen the token uncancelled is
+            // already waiting, so cannot miss this.
+            let _guard = unwrap_result!(shared.state.lock());
+            shared.condition.notify_all();
+        });
+        (notifier, waiter)
+    }
+
+    fn create(token: Option<CancellationToken>) -> (Notifier, Waiter) {
+        let shared = Arc::new(Shared {
+            state    : Mutex::new(State::default()),
+            condition: Condvar::new(),
+            token,
+        });
+        (Notifier { shared: shared.clone() }, Waiter { shared })
+    }
+
+    /// Wake one waiting thread, or the next one to wait if none is waiting.
+    pub fn notify_one(&self) {
+        unwrap_result!(self.shared.state.lock()).pending += 1;
+        self.shared.condition.notify_one();
+    }
+
+    /// Wake every thread currently waiting.
+    pub fn notify_all(&self) {
+        unwrap_result!(self.shared.state.lock()).generation += 1;
+        self.shared.condition.notify_all();
+    }
+}
+
+/// The receiving half of a notification pair created by `Notifier::new`.
+#[derive(Clone)]
+pub struct Waiter {
+    shared: Arc<Shared>,
+}
+
+impl Waiter {
+    /// Block until notified, `timeout` elapses or the pair's cancellation token is cancelled,
+    /// whichever comes first. Spurious wakeups of the underlying condvar are not reported. A
+    /// `timeout` too long to be represented as an `Instant` means never timing out.
+    pub fn wait_timeout(&self, timeout: Duration) -> Wakeup {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = unwrap_result!(self.shared.state.lock());
+        let generation = state.generation;
+        loop {
+            if self.shared.token.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                return Wakeup::Cancelled;
+            }
+            if state.generation != generation {
+                return Wakeup::Notified;
+            }
+            if state.pending > 0 {
+                state.pending -= 1;
+                return Wakeup::Notified;
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Wakeup::TimedOut;
+                    }
+                    unwrap_result!(self.shared.condition.wait_timeout(state, deadline - now)).0
+                }
+                None => unwrap_result!(self.shared.condition.wait(state)),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn notify_one_is_not_lost() {
+        let (notifier, waiter) = Notifier::new();
+        assert_eq!(waiter.wait_timeout(Duration::from_millis(10)), Wakeup::TimedOut);
+        notifier.notify_one();
+        assert_eq!(waiter.wait_timeout(Duration::from_secs(60)), Wakeup::Notified);
+        assert_eq!(waiter.wait_timeout(Duration::from_millis(10)), Wakeup::TimedOut);
+        notifier.notify_one();
+        assert_eq!(waiter.wait_timeout(Duration::MAX), Wakeup::Notified);
+    }
+
+    #[test]
+    fn notify_all_wakes_every_waiter() {
+        let (notifier, waiter) = Notifier::new();
+        let (woken_tx, woken_rx) = mpsc::channel();
+        let raii_joiners = (0..3)
+            .map(|index| {
+                let waiter = waiter.clone();
+                let woken_tx = woken_tx.clone();
+                unwrap_result!(::thread::named(format!("Waiter-{}", index), move || {
+                    unwrap_result!(woken_tx.send(waiter.wait_timeout(Duration::from_secs(60))));
+                }))
+            })
+            .collect::<Vec<_>>();
+        // A waiter which had not started waiting misses a notification, so keep notifying until
+        // all have woken.
+        let mut woken = 0;
+        while woken < 3 {
+            notifier.notify_all();
+            if let Ok(wakeup) = woken_rx.recv_timeout(Duration::from_millis(10)) {
+                assert_eq!(wakeup, Wakeup::Notified);
+                woken += 1;
+            }
+        }
+        // Nothing was left pending for later waits.
+        assert_eq!(waiter.wait_timeout(Duration::from_millis(10)), Wakeup::TimedOut);
+        drop(raii_joiners);
+    }
+
+    #[test]
+    fn cancellation_wakes_waiters() {
+        let token = CancellationToken::new();
+        let (_notifier, waiter) = Notifier::with_cancellation(token.clone());
+        let raii_joiner = unwrap_result!(::thread::named("Canceller", move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            token.cancel();
+        }));
+        let start = Instant::now();
+        assert_eq!(waiter.wait_timeout(Duration::MAX), Wakeup::Cancelled);
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(waiter.wait_timeout(Duration::from_secs(60)), Wakeup::Cancelled);
+        drop(raii_joiner);
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// The details of a panic caught in a thread spawned with `Builder::on_panic`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanicReport {
+    /// The name of the thread which panicked, if it has one.
+    pub thread_name: Option<String>,
+    /// The panic message, if the payload was a string, as it is for `panic!` with a message.
+    pub message: String,
+}
+
+impl PanicReport {
+    /// Build a report for a panic with `payload` on the current thread.
+    pub fn new(payload: &(dyn std::any::Any + Send)) -> PanicReport {
+        PanicReport {
+            thread_name: ::std::thread::current().name().map(str::to_owned),
+            message    : panic_message(payload),
+        }
+    }
+}
+
+impl ::std::fmt::Display for PanicReport {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self.thread_name {
+            Some(ref name) => write!(formatter, "Thread \"{}\" panicked: {}", name, self.message),
+            None => write!(formatter, "Unnamed thread panicked: {}", self.message),
+        }
+    }
+}
+
+/// Extract the message from a panic payload, as returned by `JoinHandle::join` or
+/// `panic::catch_unwind`.
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&'static str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<Any>".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn messages() {
+        let payload = unwrap_option!(::std::panic::catch_unwind(|| panic!("static")).err(), "");
+        assert_eq!(panic_message(&*payload), "static");
+        let payload = unwrap_option!(::std::panic::catch_unwind(|| panic!("{}", 42)).err(), "");
+        assert_eq!(panic_message(&*payload), "42");
+        let payload = unwrap_option!(::std::panic::catch_unwind(|| {
+                                         ::std::panic::panic_any(7u8)
+                                     })
+                                     .err(),
+                                     "");
+        assert_eq!(PanicReport::new(&*payload).message, "Box<Any>");
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::scope;
+
+/// Apply `f` to each of `items` across a bounded set of threads named `ParallelMap-<n>`, returning
+/// the results in the same order as the items. This suits simple data-parallel jobs such as
+/// hashing chunks or validating signatures.
+///
+/// At most one thread per available CPU is used, and each takes the next unprocessed item as soon
+/// as it is free, so uneven workloads are balanced. The threads are joined before this returns;
+/// if `f` panics for any item, this panics too once they all have.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// let chunks = vec![vec![1u8; 1024], vec![2; 2048], vec![3; 512]];
+/// let sums = thread::parallel_map(chunks, |chunk| {
+///     chunk.iter().map(|&byte| byte as u64).sum::<u64>()
+/// });
+/// assert_eq!(sums, vec![1024, 4096, 1536]);
+/// # }
+/// ```
+pub fn parallel_map<I, T, R, F>(items: I, f: F) -> Vec<R>
+    where I: IntoIterator<Item = T>,
+          T: Send,
+          R: Send,
+          F: Fn(T) -> R + Sync
+{
+    let items = items.into_iter().collect::<Vec<_>>();
+    let count = items.len();
+    let threads = ::std::thread::available_parallelism()
+                      .map_or(1, ::std::num::NonZeroUsize::get)
+                      .min(count);
+    let queue = ::std::sync::Mutex::new(items.into_iter().enumerate());
+    let mut results = scope(|scope| {
+        let handles = (0..threads)
+                          .map(|index| {
+                              let queue = &queue;
+                              let f = &f;
+                              scope.spawn_named(format!("ParallelMap-{}", index), move || {
+                                  let mut results = Vec::new();
+                                  loop {
+                                      // Release the lock before running `f`.
+                                      let next = unwrap_result!(queue.lock()).next();
+                                      match next {
+                                          Some((index, item)) => results.push((index, f(item))),
+                                          None => return results,
+                                      }
+                                  }
+                              })
+                          })
+                          .collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(count);
+        for handle in handles {
+            match handle.join() {
+                Ok(thread_results) => results.extend(thread_results),
+                Err(payload) => ::std::panic::resume_unwind(payload),
+            }
+        }
+        results
+    });
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn results_keep_input_order() {
+        let items = (0..100u64).collect::<Vec<_>>();
+        let results = parallel_map(items, |item| {
+            // Make the early items the slowest so that they finish last.
+            ::std::thread::sleep(::std::time::Duration::from_micros(100 * (100 - item)));
+            (item * item, ::thread::current_name())
+        });
+        assert_eq!(results.iter().map(|&(square, _)| square).collect::<Vec<_>>(),
+                   (0..100u64).map(|item| item * item).collect::<Vec<_>>());
+        assert!(results.iter().all(|(_, name)| name.starts_with("ParallelMap-")));
+
+        assert!(parallel_map(Vec::<u8>::new(), |item| item).is_empty());
+    }
+
+    #[test]
+    fn panics_are_propagated() {
+        let result = ::std::panic::catch_unwind(|| {
+            parallel_map(0..10, |item| if item == 7 { panic!("Bad item") } else { item })
+        });
+        assert_eq!(::thread::panic_message(&*unwrap_option!(result.err(), "")), "Bad item");
+    }
+}
@@ -0,0 +1,570 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Backoff, Builder, PanicReport, RaiiThreadJoiner};
+use std::time::Instant;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed-size pool of named worker threads running boxed jobs.
+///
+/// Workers are named `"<prefix>-0"` to `"<prefix>-<N-1>"`. Dropping the pool lets the workers
+/// finish the jobs already queued and then joins them all, in the same RAII fashion as
+/// `RaiiThreadJoiner`. To bound how long that may take, use `shutdown` instead.
+///
+/// By default all workers take jobs from one shared queue. A pool created via
+/// `with_work_stealing` instead gives each worker a queue of its own, which jobs are dealt out to
+/// in turn; a worker which runs out of jobs steals from the back of the others' queues. This cuts
+/// contention on the queue when there are many small jobs, while a worker busy with a long job
+/// still has its backlog taken over by the others. See `benches/thread_pool.rs` for a comparison.
+///
+/// A panicking job does not take its worker down with it. Jobs queued via `submit` deliver their
+/// return value, or the panic they raised, through the returned `TaskHandle`.
+///
+/// The queue is unbounded unless the pool is created via `bounded`, in which case `execute`
+/// blocks while the queue is full and `try_execute` and `execute_timeout` hand the job back
+/// instead, so that producers outpacing the workers cannot exhaust memory.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::Pool;
+///
+/// let (result_tx, result_rx) = std::sync::mpsc::channel();
+/// {
+///     let pool = unwrap_result!(Pool::new("Hasher", 4));
+///     for n in 0..100u64 {
+///         let result_tx = result_tx.clone();
+///         pool.execute(move || unwrap_result!(result_tx.send(n * n)));
+///     }
+/// }
+/// drop(result_tx);
+/// assert_eq!(result_rx.iter().sum::<u64>(), (0..100).map(|n| n * n).sum());
+/// # }
+/// ```
+pub struct Pool {
+    shared : ::std::sync::Arc<Shared>,
+    workers: Vec<RaiiThreadJoiner>,
+}
+
+struct Shared {
+    state    : ::std::sync::Mutex<State>,
+    condition: ::std::sync::Condvar,
+    // The maximum length of `State::jobs`, if bounded.
+    capacity : Option<usize>,
+    not_full : ::std::sync::Condvar,
+    // One queue per worker in work-stealing mode, otherwise empty.
+    deques   : Vec<::std::sync::Mutex<::std::collections::VecDeque<Job>>>,
+    next     : ::std::sync::atomic::AtomicUsize,
+    // Jobs waiting in `deques`.
+    stealable: ::std::sync::atomic::AtomicUsize,
+    // Whether each worker is running a job. Set under the lock of the queue the job was taken
+    // from, so that once the queues have been emptied no idle worker can become busy.
+    busy     : Vec<::std::sync::atomic::AtomicBool>,
+}
+
+struct State {
+    jobs         : ::std::collections::VecDeque<Job>,
+    shutting_down: bool,
+}
+
+impl Pool {
+    /// Spawn `threads` workers named after `prefix`. If any worker fails to spawn, those already
+    /// spawned are shut down and the error is returned.
+    pub fn new<T: Into<String>>(prefix: T, threads: usize) -> ::std::io::Result<Pool> {
+        Pool::spawn(prefix.into(), threads, false, None)
+    }
+
+    /// As `new`, but giving each worker its own queue and letting idle workers steal jobs from
+    /// the others.
+    pub fn with_work_stealing<T: Into<String>>(prefix: T, threads: usize)
+                                               -> ::std::io::Result<Pool> {
+        Pool::spawn(prefix.into(), threads, true, None)
+    }
+
+    /// As `new`, but holding at most `capacity` jobs waiting for a free worker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn bounded<T: Into<String>>(prefix: T,
+                                    threads: usize,
+                                    capacity: usize)
+                                    -> ::std::io::Result<Pool> {
+        assert!(capacity > 0, "A bounded pool needs a capacity of at least one");
+        Pool::spawn(prefix.into(), threads, false, Some(capacity))
+    }
+
+    fn spawn(prefix: String,
+             threads: usize,
+             work_stealing: bool,
+             capacity: Option<usize>)
+             -> ::std::io::Result<Pool> {
+        let deques = if work_stealing { threads } else { 0 };
+        let mut pool = Pool {
+            shared : ::std::sync::Arc::new(Shared {
+                state    : ::std::sync::Mutex::new(State {
+                    jobs         : ::std::collections::VecDeque::new(),
+                    shutting_down: false,
+                }),
+                condition: ::std::sync::Condvar::new(),
+                capacity,
+                not_full : ::std::sync::Condvar::new(),
+                deques   : (0..deques).map(|_| Default::default()).collect(),
+                next     : ::std::sync::atomic::AtomicUsize::new(0),
+                stealable: ::std::sync::atomic::AtomicUsize::new(0),
+                busy     : (0..threads).map(|_| Default::default()).collect(),
+            }),
+            workers: Vec::with_capacity(threads),
+        };
+        for index in 0..threads {
+            let shared = pool.shared.clone();
+            let worker = Builder::new(format!("{}-{}", prefix, index))
+                             .spawn(move || run(&shared, index))?;
+            pool.workers.push(worker);
+        }
+        Ok(pool)
+    }
+
+    /// Queue `job` to be run by the next free worker, first waiting for room in the queue if the
+    /// pool is bounded. If `job` panics, the panic is logged as an error.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        if self.push(job, None).is_err() {
+            unreachable!("Programming error: please report this as a bug.");
+        }
+    }
+
+    /// As `execute`, but returning a handle through which the job's return value, or the panic it
+    /// raised, is delivered.
+    pub fn submit<F, T>(&self, job: F) -> TaskHandle<T>
+        where F: FnOnce() -> T + Send + 'static,
+              T: Send + 'static
+    {
+        let (result_tx, result_rx) = ::std::sync::mpsc::channel();
+        self.execute(move || {
+            let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(job))
+                             .map_err(|payload| TaskPanicked(PanicReport::new(&*payload)));
+            let _ = result_tx.send(result);
+        });
+        TaskHandle { result_rx }
+    }
+
+    /// Queue `job` if there is room for it now, otherwise hand it back.
+    pub fn try_execute<F: FnOnce() + Send + 'static>(&self, job: F) -> Result<(), QueueFull<F>> {
+        self.push(job, Some(::std::time::Instant::now()))
+    }
+
+    /// Queue `job`, waiting up to `timeout` for room in the queue, otherwise hand it back. A
+    /// `timeout` too long to be represented as an `Instant` means waiting as `execute` does.
+    pub fn execute_timeout<F>(&self,
+                              job: F,
+                              timeout: ::std::time::Duration)
+                              -> Result<(), QueueFull<F>>
+        where F: FnOnce() + Send + 'static
+    {
+        self.push(job, ::std::time::Instant::now().checked_add(timeout))
+    }
+
+    // Queue `job`, waiting for room until `deadline` or for as long as it takes if that is
+    // `None`.
+    fn push<F>(&self, job: F, deadline: Option<::std::time::Instant>) -> Result<(), QueueFull<F>>
+        where F: FnOnce() + Send + 'static
+    {
+        if self.shared.deques.is_empty() {
+            let mut state = unwrap_result!(self.shared.state.lock());
+            if let Some(capacity) = self.shared.capacity {
+                while state.jobs.len() >= capacity {
+                    state = match deadline {
+                        None => unwrap_result!(self.shared.not_full.wait(state)),
+                        Some(deadline) => {
+                            let now = ::std::time::Instant::now();
+                            if now >= deadline {
+                                return Err(QueueFull(job));
+                            }
+                            unwrap_result!(self.shared.not_full.wait_timeout(state, deadline - now))
+                                .0
+                        }
+                    };
+                }
+            }
+            state.jobs.push_back(Box::new(job));
+        } else {
+            let index = self.shared.next.fetch_add(1, ::std::sync::atomic::Ordering::Relaxed) %
+                        self.shared.deques.len();
+            // Counted first so that `stealable` never underflows.
+            let _ = self.shared.stealable.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst);
+            unwrap_result!(self.shared.deques[index].lock()).push_back(Box::new(job));
+            // Taking the lock ensures a worker cannot miss the job between checking `stealable`
+            // and waiting.
+            drop(unwrap_result!(self.shared.state.lock()));
+        }
+        self.shared.condition.notify_one();
+        Ok(())
+    }
+
+    /// The number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// The number of jobs waiting for a free worker.
+    pub fn queued(&self) -> usize {
+        unwrap_result!(self.shared.state.lock()).jobs.len() +
+        self.shared.stealable.load(::std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Shut the pool down, letting the workers carry on with the queued jobs until `deadline`.
+    /// Jobs still queued then are dropped without being run, and workers still busy with a job are
+    /// detached rather than joined; both are counted in the returned report.
+    pub fn shutdown(mut self, deadline: Instant) -> ShutdownReport {
+        unwrap_result!(self.shared.state.lock()).shutting_down = true;
+        self.shared.condition.notify_all();
+        let mut report = ShutdownReport::default();
+        let mut workers = ::std::mem::take(&mut self.workers).into_iter().enumerate();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == ::std::time::Duration::from_secs(0) {
+                break;
+            }
+            match workers.next() {
+                Some((_, worker)) => {
+                    if worker.join_timeout(remaining).is_err() {
+                        report.detached += 1;
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        report.dropped = self.drop_queued();
+        // No worker can start another job now, so the idle ones are about to exit and can be
+        // joined, but there is no time left to wait for the busy ones.
+        for (index, worker) in workers {
+            let busy = self.shared.busy[index].load(::std::sync::atomic::Ordering::SeqCst);
+            if !busy {
+                drop(worker);
+            } else if worker.join_timeout(::std::time::Duration::from_secs(0)).is_err() {
+                report.detached += 1;
+            }
+        }
+        report
+    }
+
+    // Drop the jobs waiting for a worker, returning how many there were.
+    fn drop_queued(&self) -> usize {
+        let jobs = ::std::mem::take(&mut unwrap_result!(self.shared.state.lock()).jobs);
+        let mut dropped = jobs.len();
+        for deque in &self.shared.deques {
+            let jobs = ::std::mem::take(&mut *unwrap_result!(deque.lock()));
+            let _ = self.shared
+                        .stealable
+                        .fetch_sub(jobs.len(), ::std::sync::atomic::Ordering::SeqCst);
+            dropped += jobs.len();
+        }
+        dropped
+    }
+}
+
+/// What `Pool::shutdown` had to abandon on reaching its deadline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Jobs dropped without being run.
+    pub dropped : usize,
+    /// Workers detached while still running a job.
+    pub detached: usize,
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        unwrap_result!(self.shared.state.lock()).shutting_down = true;
+        self.shared.condition.notify_all();
+        // Dropping the joiners joins the workers.
+        self.workers.clear();
+    }
+}
+
+fn run(shared: &Shared, index: usize) {
+    loop {
+        let job = {
+            let mut state = unwrap_result!(shared.state.lock());
+            let mut backoff = Backoff::new();
+            loop {
+                if let Some(job) = state.jobs.pop_front() {
+                    shared.busy[index].store(true, ::std::sync::atomic::Ordering::SeqCst);
+                    if shared.capacity.is_some() {
+                        shared.not_full.notify_one();
+                    }
+                    break job;
+                }
+                if shared.stealable.load(::std::sync::atomic::Ordering::SeqCst) > 0 {
+                    drop(state);
+                    if let Some(job) = steal(shared, index) {
+                        break job;
+                    }
+                    // Another worker got there first, or the job is still being pushed.
+                    backoff.snooze();
+                    state = unwrap_result!(shared.state.lock());
+                    continue;
+                }
+                if state.shutting_down {
+                    return;
+                }
+                state = unwrap_result!(shared.condition.wait(state));
+            }
+        };
+        // Catching the panic keeps the worker alive for the jobs behind this one.
+        if let Err(payload) = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(job)) {
+            error!("Pool job failed: {}", PanicReport::new(&*payload));
+        }
+        shared.busy[index].store(false, ::std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// Handle to a job queued via `Pool::submit`, through which its return value is delivered.
+pub struct TaskHandle<T> {
+    result_rx: ::std::sync::mpsc::Receiver<Result<T, TaskPanicked>>,
+}
+
+impl<T> TaskHandle<T> {
+    /// Block until the job has run, returning its return value or, if it panicked, a report of
+    /// the panic. A job dropped unrun by `Pool::shutdown` is reported as a panic too.
+    pub fn wait(self) -> Result<T, TaskPanicked> {
+        self.result_rx.recv().unwrap_or_else(|_| {
+            Err(TaskPanicked(PanicReport {
+                thread_name: None,
+                message    : "Dropped by Pool::shutdown before running".to_owned(),
+            }))
+        })
+    }
+}
+
+/// The error returned by `TaskHandle::wait` for a job which panicked. The worker which ran it
+/// carries on with the next job.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskPanicked(pub PanicReport);
+
+impl ::std::fmt::Display for TaskPanicked {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "Pool task failed: {}", self.0)
+    }
+}
+
+impl ::std::error::Error for TaskPanicked {}
+
+/// The error returned by `Pool::try_execute` and `Pool::execute_timeout` when the queue of a
+/// bounded pool stays full, holding the job which was not queued.
+pub struct QueueFull<F>(pub F);
+
+impl<F> ::std::fmt::Debug for QueueFull<F> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "QueueFull(..)")
+    }
+}
+
+impl<F> ::std::fmt::Display for QueueFull<F> {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "Thread pool queue is full")
+    }
+}
+
+impl<F> ::std::error::Error for QueueFull<F> {}
+
+// Take the oldest job from this worker's own queue or, failing that, the newest from another's.
+fn steal(shared: &Shared, index: usize) -> Option<Job> {
+    let count = shared.deques.len();
+    let take = |from: usize, newest: bool| {
+        let mut deque = unwrap_result!(shared.deques[from].lock());
+        let job = if newest { deque.pop_back() } else { deque.pop_front() };
+        if job.is_some() {
+            shared.busy[index].store(true, ::std::sync::atomic::Ordering::SeqCst);
+        }
+        job
+    };
+    let job = take(index, false)
+                  .or_else(|| (1..count).filter_map(|offset| take((index + offset) % count, true))
+                                        .next());
+    if job.is_some() {
+        let _ = shared.stealable.fetch_sub(1, ::std::sync::atomic::Ordering::SeqCst);
+    }
+    job
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn worker_names_and_drain_on_drop() {
+        let (name_tx, name_rx) = ::std::sync::mpsc::channel();
+        let barrier = ::std::sync::Arc::new(::std::sync::Barrier::new(3));
+        {
+            let pool = unwrap_result!(Pool::new("PoolTest", 3));
+            assert_eq!(pool.threads(), 3);
+            // One job per worker, held at the barrier so that each worker takes exactly one.
+            for _ in 0..3 {
+                let name_tx = name_tx.clone();
+                let barrier = barrier.clone();
+                pool.execute(move || {
+                    let _ = barrier.wait();
+                    let name = unwrap_option!(::std::thread::current().name(), "").to_owned();
+                    unwrap_result!(name_tx.send(name));
+                });
+            }
+            for _ in 0..10 {
+                let name_tx = name_tx.clone();
+                pool.execute(move || unwrap_result!(name_tx.send("queued".to_owned())));
+            }
+        }
+        drop(name_tx);
+
+        let mut names: Vec<String> = name_rx.iter().collect();
+        names.sort();
+        let mut expected: Vec<_> = (0..3).map(|index| format!("PoolTest-{}", index)).collect();
+        expected.extend(vec!["queued".to_owned(); 10]);
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn work_stealing_takes_over_a_busy_workers_backlog() {
+        let (result_tx, result_rx) = ::std::sync::mpsc::channel();
+        let (release_tx, release_rx) = ::std::sync::mpsc::channel::<()>();
+        let release_rx = ::std::sync::Mutex::new(release_rx);
+        let pool = unwrap_result!(Pool::with_work_stealing("StealTest", 2));
+        // The first job lands on the first worker's queue and blocks it.
+        pool.execute(move || {
+            let _ = unwrap_result!(release_rx.lock()).recv();
+        });
+        // Half of these land behind it, yet all must complete while it is still blocked.
+        for n in 0..20 {
+            let result_tx = result_tx.clone();
+            pool.execute(move || unwrap_result!(result_tx.send(n)));
+        }
+        let mut results = result_rx.iter().take(20).collect::<Vec<_>>();
+        results.sort();
+        assert_eq!(results, (0..20).collect::<Vec<_>>());
+        assert_eq!(pool.queued(), 0);
+
+        drop(release_tx);
+    }
+
+    #[test]
+    fn bounded_queue_applies_backpressure() {
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        let (release_tx, release_rx) = ::std::sync::mpsc::channel::<()>();
+        let (result_tx, result_rx) = ::std::sync::mpsc::channel();
+        let pool = unwrap_result!(Pool::bounded("BoundedTest", 1, 1));
+        pool.execute(move || {
+            unwrap_result!(started_tx.send(()));
+            let _ = release_rx.recv();
+        });
+        unwrap_result!(started_rx.recv());
+        // The worker is busy, so this fills the queue.
+        let result_tx_clone = result_tx.clone();
+        pool.execute(move || unwrap_result!(result_tx_clone.send(1)));
+        assert_eq!(pool.queued(), 1);
+
+        match pool.try_execute(|| ()) {
+            Err(QueueFull(_)) => (),
+            Ok(()) => panic!("Queued beyond capacity"),
+        }
+        let start = ::std::time::Instant::now();
+        assert!(pool.execute_timeout(|| (), ::std::time::Duration::from_millis(20)).is_err());
+        assert!(start.elapsed() >= ::std::time::Duration::from_millis(20));
+
+        // Unblocked once the worker takes the queued job, however long the timeout.
+        let _raii_joiner = RaiiThreadJoiner::new(thread!("Releaser", move || {
+            ::std::thread::sleep(::std::time::Duration::from_millis(20));
+            drop(release_tx);
+        }));
+        let timeout = ::std::time::Duration::MAX;
+        assert!(pool.execute_timeout(move || unwrap_result!(result_tx.send(2)), timeout).is_ok());
+        assert_eq!(result_rx.iter().take(2).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn panicking_task_leaves_worker_running() {
+        let pool = unwrap_result!(Pool::new("IsolationTest", 1));
+        pool.execute(|| panic!("Unobserved bad job"));
+        let failed = pool.submit(|| -> u32 { panic!("Bad job") });
+        let succeeded = pool.submit(|| 42);
+        match failed.wait() {
+            Err(TaskPanicked(report)) => {
+                assert_eq!(report.thread_name, Some("IsolationTest-0".to_owned()));
+                assert_eq!(report.message, "Bad job");
+            }
+            Ok(_) => panic!("Task should have panicked"),
+        }
+        assert_eq!(unwrap_result!(succeeded.wait()), 42);
+    }
+
+    #[test]
+    fn shutdown_drains_until_the_deadline() {
+        let pool = unwrap_result!(Pool::new("DrainTest", 2));
+        let (done_tx, done_rx) = ::std::sync::mpsc::channel();
+        for _ in 0..4 {
+            let done_tx = done_tx.clone();
+            pool.execute(move || unwrap_result!(done_tx.send(())));
+        }
+        let deadline = Instant::now() + ::std::time::Duration::from_secs(60);
+        assert_eq!(pool.shutdown(deadline), ShutdownReport::default());
+        assert_eq!(done_rx.try_iter().count(), 4);
+
+        let pool = unwrap_result!(Pool::new("DeadlineTest", 1));
+        let (release_tx, release_rx) = ::std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        pool.execute(move || {
+            unwrap_result!(started_tx.send(()));
+            let _ = release_rx.recv();
+        });
+        let never_run = pool.submit(|| ());
+        pool.execute(|| ());
+        unwrap_result!(started_rx.recv());
+        let start = Instant::now();
+        let report = pool.shutdown(start + ::std::time::Duration::from_millis(20));
+        assert!(start.elapsed() >= ::std::time::Duration::from_millis(20));
+        assert_eq!(report,
+                   ShutdownReport {
+                       dropped : 2,
+                       detached: 1,
+                   });
+        assert!(never_run.wait().is_err());
+        drop(release_tx);
+    }
+
+    #[test]
+    fn shutdown_only_detaches_busy_workers() {
+        let pool = unwrap_result!(Pool::with_work_stealing("BusyTest", 4));
+        let (release_tx, release_rx) = ::std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        pool.execute(move || {
+            unwrap_result!(started_tx.send(()));
+            let _ = release_rx.recv();
+        });
+        unwrap_result!(started_rx.recv());
+        // With no time at all, the three idle workers are still joined.
+        let report = pool.shutdown(Instant::now());
+        assert_eq!(report,
+                   ShutdownReport {
+                       dropped : 0,
+                       detached: 1,
+                   });
+        drop(release_tx);
+    }
+}
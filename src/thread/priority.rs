@@ -0,0 +1,124 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Setting scheduling priorities requires FFI.
+#![allow(unsafe_code)]
+
+/// A portable OS scheduling priority for a thread, e.g. to favour network I/O threads over
+/// background churn processing.
+///
+/// On Linux this sets the thread's niceness (19, 10, 0, -10 and -20 respectively), and on Windows
+/// its thread priority (`THREAD_PRIORITY_LOWEST` to `THREAD_PRIORITY_HIGHEST`). Elsewhere it does
+/// nothing. Raising the priority above `Normal` typically needs elevated privileges on Linux.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Only run when nothing else wants the CPU.
+    Lowest,
+    /// Background work.
+    Low,
+    /// The default for new threads.
+    Normal,
+    /// Latency-sensitive work.
+    High,
+    /// Work which should preempt nearly everything else.
+    Highest,
+}
+
+/// Set the scheduling priority of the current thread.
+///
+/// To set the priority of a thread from the start, spawn it via `Builder::priority` instead.
+pub fn set_current_priority(priority: Priority) -> ::std::io::Result<()> {
+    platform::set_current_priority(priority)
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::Priority;
+
+    pub fn niceness(priority: Priority) -> ::libc::c_int {
+        match priority {
+            Priority::Lowest => 19,
+            Priority::Low => 10,
+            Priority::Normal => 0,
+            Priority::High => -10,
+            Priority::Highest => -20,
+        }
+    }
+
+    pub fn set_current_priority(priority: Priority) -> ::std::io::Result<()> {
+        // On Linux niceness is a per-thread attribute, addressed by the thread id.
+        unsafe {
+            let thread_id = ::libc::syscall(::libc::SYS_gettid) as ::libc::id_t;
+            if ::libc::setpriority(::libc::PRIO_PROCESS, thread_id, niceness(priority)) != 0 {
+                return Err(::std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::Priority;
+
+    pub fn set_current_priority(priority: Priority) -> ::std::io::Result<()> {
+        // THREAD_PRIORITY_LOWEST, _BELOW_NORMAL, _NORMAL, _ABOVE_NORMAL and _HIGHEST.
+        let value = match priority {
+            Priority::Lowest => -2,
+            Priority::Low => -1,
+            Priority::Normal => 0,
+            Priority::High => 1,
+            Priority::Highest => 2,
+        };
+        if unsafe { ::kernel32::SetThreadPriority(::kernel32::GetCurrentThread(), value) } ==
+           ::winapi::FALSE {
+            return Err(::std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use super::Priority;
+
+    pub fn set_current_priority(_priority: Priority) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod test {
+    use super::*;
+
+    fn current_niceness() -> ::libc::c_int {
+        unsafe {
+            let thread_id = ::libc::syscall(::libc::SYS_gettid) as ::libc::id_t;
+            ::libc::getpriority(::libc::PRIO_PROCESS, thread_id)
+        }
+    }
+
+    #[test]
+    fn lower_priority() {
+        let handle = unwrap_result!(::thread::Builder::new("Background")
+                                        .priority(Priority::Low)
+                                        .spawn_unmanaged(current_niceness));
+        assert_eq!(unwrap_result!(handle.join()), platform::niceness(Priority::Low));
+        // Only the spawned thread was affected.
+        assert_ne!(current_niceness(), platform::niceness(Priority::Low));
+    }
+}
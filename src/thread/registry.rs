@@ -0,0 +1,234 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::stats::{Counters, Stats};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::ThreadId;
+use std::time::SystemTime;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// What a registered thread is doing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreadState {
+    /// Running normally.
+    Running,
+    /// Unwinding from a panic.
+    Panicking,
+}
+
+/// A thread spawned via this crate's helpers, as listed by `thread::list`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreadInfo {
+    /// The thread's name.
+    pub name: String,
+    /// The thread's id.
+    pub id: ThreadId,
+    /// When the thread was spawned.
+    pub spawned_at: SystemTime,
+    /// What the thread is doing.
+    pub state: ThreadState,
+    /// The thread's performance statistics, as of the call to `list`.
+    pub stats: Stats,
+}
+
+impl ::std::fmt::Display for ThreadInfo {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter,
+               "\"{}\" ({:?}) {:?}: {}",
+               self.name,
+               self.id,
+               self.state,
+               self.stats)
+    }
+}
+
+// Keyed by spawn order so that `list` returns the oldest first.
+#[derive(Default)]
+struct Registry {
+    threads : BTreeMap<u64, (ThreadInfo, Counters)>,
+    next_key: u64,
+}
+
+impl Registry {
+    fn list(&self) -> Vec<ThreadInfo> {
+        self.threads
+            .values()
+            .map(|(info, counters)| ThreadInfo { stats: counters.stats(), ..info.clone() })
+            .collect()
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: ::std::sync::OnceLock<Mutex<Registry>> = ::std::sync::OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Start recording the threads spawned via this crate's helpers (`thread!`, `Builder`, `named`,
+/// `Pool`, `scope` and so on) for as long as they run, so that diagnostics endpoints can show
+/// what the process is running via `list`. Threads spawned before this is called are not
+/// recorded.
+///
+/// This also installs a panic hook which, after running the previously installed hook, logs the
+/// list as an error whenever a recorded thread panics. Panics on other threads are left to the
+/// previous hook alone.
+pub fn enable_registry() {
+    static INSTALL: ::std::sync::Once = ::std::sync::Once::new();
+    INSTALL.call_once(|| {
+        let previous = ::std::panic::take_hook();
+        ::std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if ENABLED.load(Ordering::SeqCst) {
+                log_panicking_thread();
+            }
+        }));
+    });
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop recording newly spawned threads and logging the list on panics, undoing
+/// `enable_registry`. Threads already recorded are still listed until they exit.
+pub fn disable_registry() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+// If the current thread is recorded, mark it as panicking and log the list.
+fn log_panicking_thread() {
+    let current = ::std::thread::current().id();
+    let threads = {
+        let mut registry = unwrap_result!(registry().lock());
+        let mut registered = false;
+        for (thread, _) in registry.threads.values_mut() {
+            if thread.id == current {
+                thread.state = ThreadState::Panicking;
+                registered = true;
+            }
+        }
+        if !registered {
+            return;
+        }
+        registry.list().iter().map(ToString::to_string).collect::<Vec<_>>()
+    };
+    error!("Threads running at panic:\n    {}", threads.join("\n    "));
+}
+
+/// The threads currently running which were spawned via this crate's helpers after
+/// `enable_registry` was called, oldest first.
+pub fn list() -> Vec<ThreadInfo> {
+    unwrap_result!(registry().lock()).list()
+}
+
+/// Wrap `entry_point` so that, if the registry is enabled, the thread running it is recorded as
+/// `name` until it returns.
+pub fn registered<F: FnOnce() -> T, T>(name: &str, entry_point: F) -> impl FnOnce() -> T {
+    let registration = if ENABLED.load(Ordering::SeqCst) {
+        Some((name.to_owned(), SystemTime::now()))
+    } else {
+        None
+    };
+    move || {
+        let _guard = registration.map(|(name, spawned_at)| Registration::new(name, spawned_at));
+        entry_point()
+    }
+}
+
+struct Registration {
+    key: u64,
+}
+
+impl Registration {
+    fn new(name: String, spawned_at: SystemTime) -> Registration {
+        let mut registry = unwrap_result!(registry().lock());
+        let key = registry.next_key;
+        registry.next_key += 1;
+        let info = ThreadInfo {
+            name,
+            id: ::std::thread::current().id(),
+            spawned_at,
+            state: ThreadState::Running,
+            stats: Stats::default(),
+        };
+        let _ = registry.threads.insert(key, (info, Counters::start()));
+        Registration { key }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        Counters::stop();
+        let _ = unwrap_result!(registry().lock()).threads.remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The registry is process-wide, so the tests enabling and disabling it mustn't overlap.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn lists_running_threads() {
+        let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+        enable_registry();
+        defer!(disable_registry(););
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        let handle = thread!("RegisteredThread", move || {
+            for _ in 0..3 {
+                ::thread::record_iteration();
+            }
+            unwrap_result!(started_tx.send(::std::thread::current().id()));
+            let _ = finish_rx.recv();
+        });
+        let id = unwrap_result!(started_rx.recv());
+
+        let registered = list().into_iter().filter(|thread| thread.id == id).collect::<Vec<_>>();
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].name, "RegisteredThread");
+        assert_eq!(registered[0].state, ThreadState::Running);
+        assert_eq!(registered[0].stats.iterations, 3);
+        if cfg!(target_os = "linux") {
+            assert!(registered[0].stats.user_time.is_some());
+            assert!(registered[0].stats.system_time.is_some());
+        }
+
+        drop(finish_tx);
+        unwrap_result!(handle.join());
+        assert!(list().iter().all(|thread| thread.id != id));
+    }
+
+    #[test]
+    fn only_recorded_threads_log_the_list_on_panic() {
+        let _serial = SERIAL.lock().unwrap_or_else(|error| error.into_inner());
+        enable_registry();
+        defer!(disable_registry(););
+        let capture = ::log::capture();
+        let _ = ::std::panic::catch_unwind(|| panic!("Unrecorded"));
+        capture.assert_not_logged(::logger::LogLevel::Error, "Threads running at panic");
+
+        let handle = thread!("RecordedThread", || {
+            let capture = ::log::capture();
+            let _ = ::std::panic::catch_unwind(|| panic!("Recorded"));
+            capture.assert_logged(::logger::LogLevel::Error, "Threads running at panic");
+            capture.assert_logged(::logger::LogLevel::Error, "\"RecordedThread\"");
+        });
+        unwrap_result!(handle.join());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Builder, PanicReport, RaiiThreadJoiner};
+
+/// Handle to a thread spawned by `spawn_with_result`, through which the closure's return value is
+/// delivered. Dropping the handle without waiting still joins the thread.
+pub struct ResultHandle<T> {
+    result_rx   : ::std::sync::mpsc::Receiver<Result<T, PanicReport>>,
+    _raii_joiner: RaiiThreadJoiner,
+}
+
+impl<T> ResultHandle<T> {
+    /// Block until the thread finishes, returning the closure's return value or, if it panicked,
+    /// a report of the panic.
+    pub fn wait(self) -> Result<T, PanicReport> {
+        unwrap_result!(self.result_rx.recv())
+    }
+
+    /// Block for at most `timeout` waiting for the thread to finish. Returns the handle back if
+    /// it has not, so that the caller can wait again.
+    pub fn wait_timeout(self, timeout: ::std::time::Duration)
+                        -> Result<Result<T, PanicReport>, ResultHandle<T>> {
+        match self.result_rx.recv_timeout(timeout) {
+            Ok(result) => Ok(result),
+            Err(::std::sync::mpsc::RecvTimeoutError::Timeout) => Err(self),
+            Err(::std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                unreachable!("Programming error: please report this as a bug.")
+            }
+        }
+    }
+}
+
+/// Spawn a thread named `name`, returning a handle from which the return value of `entry_point`
+/// can be collected without setting up a side channel. A panic in `entry_point` is caught and
+/// returned from `ResultHandle::wait` as a `PanicReport` rather than propagated.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// let handle = unwrap_result!(thread::spawn_with_result("Hasher", || {
+///     (0..1000u64).fold(17u64, |hash, value| hash.wrapping_mul(31).wrapping_add(value))
+/// }));
+/// println!("Hash: {}", unwrap_result!(handle.wait()));
+///
+/// let handle = unwrap_result!(thread::spawn_with_result("Failing", || -> u64 {
+///     panic!("Corrupt chunk")
+/// }));
+/// assert_eq!(unwrap_option!(handle.wait().err(), "").message, "Corrupt chunk");
+/// # }
+/// ```
+pub fn spawn_with_result<N, F, T>(name: N, entry_point: F) -> ::std::io::Result<ResultHandle<T>>
+    where N: Into<String>,
+          F: FnOnce() -> T + Send + 'static,
+          T: Send + 'static
+{
+    let (result_tx, result_rx) = ::std::sync::mpsc::channel();
+    let raii_joiner = Builder::new(name).spawn(move || {
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(entry_point))
+                         .map_err(|payload| PanicReport::new(&*payload));
+        let _ = result_tx.send(result);
+    })?;
+    Ok(ResultHandle {
+        result_rx,
+        _raii_joiner: raii_joiner,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wait_timeout() {
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let handle = unwrap_result!(spawn_with_result("Slow", move || {
+            let _ = finish_rx.recv();
+            ::thread::current_name()
+        }));
+        let handle = unwrap_option!(handle.wait_timeout(::std::time::Duration::from_millis(10))
+                                          .err(),
+                                    "Should have timed out");
+        drop(finish_tx);
+        assert_eq!(unwrap_result!(handle.wait()), "Slow");
+
+        let handle = unwrap_result!(spawn_with_result("Panicking", || panic!("Oops")));
+        assert_eq!(handle.wait(),
+                   Err(PanicReport {
+                       thread_name: Some("Panicking".to_owned()),
+                       message    : "Oops".to_owned(),
+                   }));
+    }
+}
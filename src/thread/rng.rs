@@ -0,0 +1,167 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::current_name;
+use std::cell::Cell;
+
+/// The environment variable from which the process-wide seed used by `seeded_rng` is read.
+pub const SEED_ENV_VAR: &str = "MAIDSAFE_TEST_SEED";
+
+thread_local! {
+    static RNGS_CREATED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A small, fast, deterministic pseudo-random number generator (SplitMix64), as returned by
+/// `seeded_rng`. It is not suitable for cryptography.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator which always produces the same sequence for the same `seed`.
+    pub fn from_seed(seed: u64) -> SeededRng {
+        SeededRng { state: seed }
+    }
+
+    /// The next pseudo-random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+
+    /// The next pseudo-random `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// A pseudo-random value in `range`, which must not be empty.
+    pub fn gen_range(&mut self, range: ::std::ops::Range<u64>) -> u64 {
+        assert!(range.start < range.end, "Empty range {:?}", range);
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+
+    /// Fill `bytes` with pseudo-random data.
+    pub fn fill_bytes(&mut self, bytes: &mut [u8]) {
+        for chunk in bytes.chunks_mut(8) {
+            let random = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&random[..chunk.len()]);
+        }
+    }
+}
+
+/// The process-wide seed from which `seeded_rng` derives each thread's generator.
+///
+/// It is read from the `MAIDSAFE_TEST_SEED` environment variable if that is set, and otherwise
+/// chosen at random. Either way, the seed is printed to stderr if any thread panics, so that a
+/// failing multi-threaded test can be rerun with the same seed.
+pub fn global_seed() -> u64 {
+    static SEED: ::std::sync::OnceLock<u64> = ::std::sync::OnceLock::new();
+    *SEED.get_or_init(|| {
+        let seed = match ::std::env::var(SEED_ENV_VAR) {
+            Ok(value) => {
+                match value.trim().parse() {
+                    Ok(seed) => seed,
+                    Err(_) => {
+                        panic!("{} must be an unsigned integer, not {:?}", SEED_ENV_VAR, value)
+                    }
+                }
+            }
+            Err(_) => random_seed(),
+        };
+        let previous = ::std::panic::take_hook();
+        ::std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            eprintln!("To reproduce, rerun with {}={}", SEED_ENV_VAR, seed);
+        }));
+        seed
+    })
+}
+
+/// A deterministic RNG for the current thread, derived from `global_seed`, the thread's name and
+/// how many generators the thread has already created. Soak tests which name their threads (e.g.
+/// via `thread!`) and draw from these are therefore reproducible given the same seed.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// let first = unwrap_result!(thread!("SoakWorker", || thread::seeded_rng().next_u64()).join());
+/// let again = unwrap_result!(thread!("SoakWorker", || thread::seeded_rng().next_u64()).join());
+/// assert_eq!(first, again);
+/// # }
+/// ```
+pub fn seeded_rng() -> SeededRng {
+    let index = RNGS_CREATED.with(|created| {
+        let index = created.get();
+        created.set(index + 1);
+        index
+    });
+    // FNV-1a, which unlike the standard library's hashers is guaranteed stable.
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in current_name().bytes().chain(index.to_le_bytes().iter().cloned()) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    let mut rng = SeededRng::from_seed(global_seed() ^ hash);
+    let _ = rng.next_u64();
+    rng
+}
+
+fn random_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = ::std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(unwrap_result!(::std::time::SystemTime::now()
+                                         .duration_since(::std::time::UNIX_EPOCH))
+                          .as_nanos());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic_per_thread_and_call() {
+        let draw = || {
+            let mut first = seeded_rng();
+            let mut second = seeded_rng();
+            (first.next_u64(), second.next_u64())
+        };
+        let (first, second) = unwrap_result!(thread!("RngA", draw).join());
+        assert_ne!(first, second);
+        assert_eq!(unwrap_result!(thread!("RngA", draw).join()), (first, second));
+        assert_ne!(unwrap_result!(thread!("RngB", draw).join()).0, first);
+    }
+
+    #[test]
+    fn helpers() {
+        let mut rng = SeededRng::from_seed(42);
+        assert_eq!(rng.clone().next_u64(), SeededRng::from_seed(42).next_u64());
+        assert!((0..1000).all(|_| (10..20).contains(&rng.gen_range(10..20))));
+
+        let mut bytes = [0u8; 13];
+        rng.fill_bytes(&mut bytes);
+        assert!(bytes.iter().any(|&byte| byte != 0));
+    }
+}
@@ -0,0 +1,291 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{Builder, RaiiThreadJoiner, panic_message};
+use timer::{Timer, TimerHandle};
+
+/// How a recurring task registered with a `Scheduler` is rescheduled after each run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cadence {
+    /// Run every period, measured from the previous deadline, regardless of how long the task
+    /// itself takes.
+    FixedRate,
+    /// Wait a whole period after each run has finished before running again.
+    FixedDelay,
+}
+
+/// What a `FixedRate` task does about ticks it missed because a run overran its period, or the
+/// scheduler's thread was otherwise held up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissedTicks {
+    /// Run once for every missed tick, back to back, until caught up with the original schedule.
+    Burst,
+    /// Drop the missed ticks and carry on at the next tick of the original schedule.
+    Skip,
+    /// Run once straight away, then carry on every period from then, shifting the schedule.
+    Delay,
+}
+
+/// Runs periodic tasks such as keep-alives, cache pruning and stats flushing, all multiplexed on
+/// one named timer thread rather than a sleeping thread apiece.
+///
+/// A recurring task runs either at a fixed rate (every period, however long it takes) via
+/// `fixed_rate`, or with a fixed delay (a period after the last run finishes) via `fixed_delay`.
+/// Fixed-rate deadlines are computed from the original schedule, so they do not drift however
+/// late each run starts.
+///
+/// Tasks run one at a time on the scheduler's thread, so a long-running task delays the others.
+/// A task which panics is logged and dropped, without affecting the others.
+/// Dropping the scheduler waits for any task currently running to return, then stops the thread
+/// and drops the remaining tasks.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{Cadence, Scheduler};
+/// use std::time::Duration;
+///
+/// let (tick_tx, tick_rx) = std::sync::mpsc::channel();
+/// let scheduler = unwrap_result!(Scheduler::new("KeepAlive"));
+/// let _ = scheduler.every(Duration::from_millis(10), Cadence::FixedRate, move || {
+///     let _ = tick_tx.send(());
+/// });
+///
+/// for _ in 0..3 {
+///     unwrap_result!(tick_rx.recv());
+/// }
+/// # }
+/// ```
+pub struct Scheduler {
+    timer       : ::std::sync::Arc<Timer>,
+    _raii_joiner: RaiiThreadJoiner,
+}
+
+impl Scheduler {
+    /// Start a scheduler whose tasks run on a new thread named `thread_name`.
+    pub fn new<T: Into<String>>(thread_name: T) -> ::std::io::Result<Scheduler> {
+        let (timer, raii_joiner) = Timer::spawn(Builder::new(thread_name))?;
+        Ok(Scheduler {
+            timer,
+            _raii_joiner: raii_joiner,
+        })
+    }
+
+    /// Run `task` every `period`, the first time one period from now. The returned handle can be
+    /// used to stop it; dropping the handle leaves it running.
+    ///
+    /// A `FixedRate` task catches up on missed ticks with `MissedTicks::Burst`; use `fixed_rate`
+    /// to choose otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero, as is the case for `fixed_rate` and `fixed_delay`.
+    pub fn every<F>(&self, period: ::std::time::Duration, cadence: Cadence, task: F) -> TimerHandle
+        where F: FnMut() + Send + 'static
+    {
+        self.timer.schedule_every(period, cadence, MissedTicks::Burst, task)
+    }
+
+    /// Run `task` every `period` regardless of how long each run takes, the first time one period
+    /// from now, handling ticks missed by overrunning according to `missed`.
+    pub fn fixed_rate<F>(&self,
+                         period: ::std::time::Duration,
+                         missed: MissedTicks,
+                         task: F)
+                         -> TimerHandle
+        where F: FnMut() + Send + 'static
+    {
+        self.timer.schedule_every(period, Cadence::FixedRate, missed, task)
+    }
+
+    /// Run `task` one `period` from now, and thereafter one `period` after each run finishes.
+    pub fn fixed_delay<F>(&self, period: ::std::time::Duration, task: F) -> TimerHandle
+        where F: FnMut() + Send + 'static
+    {
+        self.timer.schedule_every(period, Cadence::FixedDelay, MissedTicks::Burst, task)
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.timer.shut_down();
+    }
+}
+
+/// Run `task` once `delay` has elapsed, on the timer thread shared by the whole crate (the same one
+/// used by `EventSender::send_after`) rather than a dedicated sleeping thread. A panic in `task` is
+/// caught and logged along with `name`, so that it cannot take the shared thread down. The
+/// returned handle can be used to cancel the task; dropping it leaves the task scheduled.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+/// use std::time::Duration;
+///
+/// let (timeout_tx, timeout_rx) = std::sync::mpsc::channel();
+/// let _ = thread::run_after("RequestTimeout", Duration::from_millis(10), move || {
+///     let _ = timeout_tx.send("Timed out");
+/// });
+/// assert_eq!(unwrap_result!(timeout_rx.recv()), "Timed out");
+/// # }
+/// ```
+pub fn run_after<T, F>(name: T, delay: ::std::time::Duration, task: F) -> TimerHandle
+    where T: Into<String>,
+          F: FnOnce() + Send + 'static
+{
+    let name = name.into();
+    ::timer::schedule(delay, move || {
+        if let Err(payload) = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(task)) {
+            error!("Delayed task \"{}\" panicked: {}", name, panic_message(&*payload));
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn fixed_rate_and_fixed_delay() {
+        const RUNS: usize = 6;
+        let period = Duration::from_millis(20);
+        let task_duration = Duration::from_millis(10);
+        let record = |runs_tx: ::std::sync::mpsc::Sender<Instant>| {
+            move || {
+                let _ = runs_tx.send(Instant::now());
+                ::std::thread::sleep(task_duration);
+            }
+        };
+
+        let (fixed_rate_tx, fixed_rate_rx) = ::std::sync::mpsc::channel();
+        let (fixed_delay_tx, fixed_delay_rx) = ::std::sync::mpsc::channel();
+        let start = Instant::now();
+        let fixed_rate = unwrap_result!(Scheduler::new("FixedRate"));
+        let _ = fixed_rate.every(period, Cadence::FixedRate, record(fixed_rate_tx));
+        let fixed_delay = unwrap_result!(Scheduler::new("FixedDelay"));
+        let _ = fixed_delay.every(period, Cadence::FixedDelay, record(fixed_delay_tx));
+        let fixed_rate_runs = fixed_rate_rx.iter().take(RUNS).collect::<Vec<_>>();
+        let fixed_delay_runs = fixed_delay_rx.iter().take(RUNS).collect::<Vec<_>>();
+        drop(fixed_rate);
+        drop(fixed_delay);
+
+        // Neither runs early, however much the threads are delayed.
+        for runs in &[&fixed_rate_runs, &fixed_delay_runs] {
+            for (index, run) in runs.iter().enumerate() {
+                assert!(*run - start >= period * (index as u32 + 1));
+            }
+        }
+        // Each fixed-delay gap includes the task duration, so in total they take about 50% longer.
+        assert!(fixed_delay_runs.windows(2)
+                                .all(|pair| pair[1] - pair[0] >= period + task_duration));
+        let span = |runs: &[Instant]| runs[RUNS - 1] - runs[0];
+        assert!(span(&fixed_rate_runs) < span(&fixed_delay_runs));
+    }
+
+    #[test]
+    fn skipping_missed_ticks_keeps_the_schedule() {
+        let period = Duration::from_millis(20);
+        let (runs_tx, runs_rx) = ::std::sync::mpsc::channel();
+        let start = Instant::now();
+        let scheduler = unwrap_result!(Scheduler::new("SkipTicks"));
+        let mut first = true;
+        let _ = scheduler.fixed_rate(period, MissedTicks::Skip, move || {
+            let _ = runs_tx.send(Instant::now());
+            if first {
+                // Overrun by more than two periods.
+                first = false;
+                ::std::thread::sleep(period * 5 / 2);
+            }
+        });
+        let runs = runs_rx.iter().take(3).collect::<Vec<_>>();
+        drop(scheduler);
+
+        // The ticks at 2 and 3 periods were skipped rather than run late, and the remainder stay
+        // on the original schedule.
+        assert!(runs[1] - start >= period * 4);
+        assert!(runs[2] - start >= period * 5);
+    }
+
+    #[test]
+    fn cancel_and_drop() {
+        let (tick_tx, tick_rx) = ::std::sync::mpsc::channel();
+        let scheduler = unwrap_result!(Scheduler::new("CancelTest"));
+        let handle = scheduler.every(Duration::from_millis(5), Cadence::FixedDelay, move || {
+            let _ = tick_tx.send(());
+        });
+        unwrap_result!(tick_rx.recv());
+        assert!(handle.cancel());
+        assert!(!handle.cancel());
+
+        // Cancelling drops the task and with it the sender, ending the receiver's iteration.
+        let remaining = tick_rx.iter().count();
+        assert!(remaining <= 1, "{} runs after cancelling", remaining);
+
+        let (tick_tx, tick_rx) = ::std::sync::mpsc::channel();
+        let _ = scheduler.every(Duration::from_millis(5), Cadence::FixedRate, move || {
+            let _ = tick_tx.send(());
+        });
+        drop(scheduler);
+        assert_eq!(tick_rx.iter().count(), 0);
+    }
+
+    #[test]
+    fn panicking_task_is_dropped() {
+        let (tick_tx, tick_rx) = ::std::sync::mpsc::channel();
+        let scheduler = unwrap_result!(Scheduler::new("PanicTest"));
+        let _ = scheduler.fixed_delay(Duration::from_millis(5), || panic!("Oops"));
+        let _ = scheduler.fixed_delay(Duration::from_millis(5), move || {
+            let _ = tick_tx.send(());
+        });
+        for _ in 0..3 {
+            unwrap_result!(tick_rx.recv());
+        }
+        // Joining the thread would panic if it had died.
+        drop(scheduler);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn zero_period_is_rejected() {
+        let scheduler = unwrap_result!(Scheduler::new("ZeroPeriod"));
+        let _ = scheduler.every(Duration::from_secs(0), Cadence::FixedRate, || ());
+    }
+
+    #[test]
+    fn run_after_survives_panics() {
+        let (fired_tx, fired_rx) = ::std::sync::mpsc::channel();
+        let fired_tx_clone = fired_tx.clone();
+        let cancelled = run_after("Cancelled", Duration::from_millis(20), move || {
+            unwrap_result!(fired_tx_clone.send("Cancelled"))
+        });
+        let _ = run_after("Panicking", Duration::from_millis(10), || panic!("Oops"));
+        let _ = run_after("Later", Duration::from_millis(30), move || {
+            unwrap_result!(fired_tx.send("Later"))
+        });
+        assert!(cancelled.cancel());
+
+        assert_eq!(fired_rx.iter().collect::<Vec<_>>(), vec!["Later"]);
+    }
+}
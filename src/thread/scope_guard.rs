@@ -0,0 +1,92 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+/// Runs a closure when dropped, including when the scope holding it unwinds from a panic, e.g. to
+/// remove a temporary file or to send a worker's `Terminate` event however it exits. See also
+/// `defer!`.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread::ScopeGuard;
+/// use std::sync::mpsc;
+///
+/// let (terminated_tx, terminated_rx) = mpsc::channel();
+/// let result = std::panic::catch_unwind(move || {
+///     let _guard = ScopeGuard::new(move || { let _ = terminated_tx.send("Terminate"); });
+///     panic!("Worker failed");
+/// });
+/// assert!(result.is_err());
+/// assert_eq!(terminated_rx.try_recv(), Ok("Terminate"));
+/// # }
+/// ```
+pub struct ScopeGuard<F: FnOnce()> {
+    cleanup: Option<F>,
+}
+
+impl<F: FnOnce()> ScopeGuard<F> {
+    /// Create a guard which runs `cleanup` when dropped.
+    pub fn new(cleanup: F) -> ScopeGuard<F> {
+        ScopeGuard { cleanup: Some(cleanup) }
+    }
+
+    /// Drop the guard without running its closure, e.g. once the temporary file it would remove
+    /// has been persisted.
+    pub fn dismiss(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl<F: FnOnce()> Drop for ScopeGuard<F> {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn runs_on_drop_and_unwind_unless_dismissed() {
+        let runs = Cell::new(0);
+        {
+            let _guard = ScopeGuard::new(|| runs.set(runs.get() + 1));
+            assert_eq!(runs.get(), 0);
+        }
+        assert_eq!(runs.get(), 1);
+
+        ScopeGuard::new(|| runs.set(runs.get() + 1)).dismiss();
+        assert_eq!(runs.get(), 1);
+
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            defer!(runs.set(runs.get() + 1));
+            defer! {
+                // Guards run in reverse order.
+                assert_eq!(runs.get(), 1);
+                runs.set(10);
+            }
+            panic!("Unwinding");
+        }));
+        assert!(result.is_err());
+        assert_eq!(runs.get(), 11);
+    }
+}
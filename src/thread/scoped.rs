@@ -0,0 +1,99 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::builder::{inherited_name, spawned_by_crate};
+use super::registry::registered;
+
+/// Run `f` with a `Scope` through which it can spawn named threads borrowing from the enclosing
+/// stack frame, without `Arc` or `'static` bounds.
+///
+/// Every thread spawned in the scope is joined before `scope` returns. If any of them panicked
+/// (and was not joined explicitly via its handle) `scope` panics too, once all have been joined.
+///
+/// #Examples
+///
+/// ```
+/// # fn main() {
+/// use maidsafe_utilities::thread;
+///
+/// let chunks = vec![vec![1, 2, 3], vec![4, 5], vec![6]];
+/// let mut total = 0;
+///
+/// thread::scope(|scope| {
+///     let handles: Vec<_> = chunks.iter()
+///         .enumerate()
+///         .map(|(index, chunk)| {
+///             scope.spawn_named(format!("Summer-{}", index), move || chunk.iter().sum::<i32>())
+///         })
+///         .collect();
+///     total = handles.into_iter().map(|handle| handle.join().unwrap_or(0)).sum();
+/// });
+///
+/// assert_eq!(total, 21);
+/// # }
+/// ```
+pub fn scope<'env, F, T>(f: F) -> T
+    where F: for<'scope> FnOnce(&Scope<'scope, 'env>) -> T
+{
+    ::std::thread::scope(|inner| f(&Scope { inner }))
+}
+
+/// A scope for spawning named threads, provided by `scope`.
+pub struct Scope<'scope, 'env: 'scope> {
+    inner: &'scope ::std::thread::Scope<'scope, 'env>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Spawn a thread named `name` within the scope. As with `thread!`, this panics if the thread
+    /// cannot be created, and the name is prefixed with the parent's as for `Builder`.
+    pub fn spawn_named<N, F, T>(&self, name: N, f: F) -> ::std::thread::ScopedJoinHandle<'scope, T>
+        where N: Into<String>,
+              F: FnOnce() -> T + Send + 'scope,
+              T: Send + 'scope
+    {
+        let name = inherited_name(name.into());
+        let f = spawned_by_crate(registered(&name, f));
+        unwrap_result!(::std::thread::Builder::new().name(name).spawn_scoped(self.inner, f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn borrows_and_propagates_panics() {
+        let mut counters = [0u32; 4];
+        scope(|scope| {
+            for (index, counter) in counters.iter_mut().enumerate() {
+                let _ = scope.spawn_named(format!("ScopedTest-{}", index), move || {
+                    assert_eq!(::std::thread::current().name(),
+                               Some(&*format!("ScopedTest-{}", index)));
+                    *counter = index as u32 + 1;
+                });
+            }
+        });
+        assert_eq!(counters, [1, 2, 3, 4]);
+
+        let result = ::std::panic::catch_unwind(|| {
+            scope(|scope| {
+                let _ = scope.spawn_named("ScopedPanic", || panic!("Scoped thread failed"));
+            })
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+// Reading CPU times requires FFI.
+#![allow(unsafe_code)]
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static ITERATIONS: RefCell<Option<Arc<AtomicU64>>> = const { RefCell::new(None) };
+}
+
+/// Performance statistics for a thread spawned via this crate's helpers, as listed by
+/// `thread::list` in its `ThreadInfo`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// How long the thread has been running.
+    pub wall_time  : Duration,
+    /// CPU time spent by the thread in user mode, where the OS supports reading it (currently
+    /// Linux only).
+    pub user_time  : Option<Duration>,
+    /// CPU time spent by the thread in the kernel, where the OS supports reading it (currently
+    /// Linux only).
+    pub system_time: Option<Duration>,
+    /// The number of times the thread has called `record_iteration`.
+    pub iterations : u64,
+}
+
+impl ::std::fmt::Display for Stats {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter, "wall {:.3}s", self.wall_time.as_secs_f64())?;
+        if let (Some(user), Some(system)) = (self.user_time, self.system_time) {
+            write!(formatter,
+                   ", user {:.3}s, system {:.3}s",
+                   user.as_secs_f64(),
+                   system.as_secs_f64())?;
+        }
+        write!(formatter, ", {} iterations", self.iterations)
+    }
+}
+
+/// Count one iteration of the current thread's busy loop, e.g. once per event handled, to be
+/// reported in its `Stats`. This does nothing if the thread is not recorded in the registry, i.e.
+/// if it was not spawned via this crate's helpers after `enable_registry` was called.
+pub fn record_iteration() {
+    ITERATIONS.with(|iterations| if let Some(ref iterations) = *iterations.borrow() {
+        let _ = iterations.fetch_add(1, Ordering::Relaxed);
+    });
+}
+
+// The part of a registry entry from which the thread's `Stats` are read.
+pub struct Counters {
+    started   : Instant,
+    os_id     : Option<u64>,
+    iterations: Arc<AtomicU64>,
+}
+
+impl Counters {
+    // Start counting for the current thread, until `stop` is called on it.
+    pub fn start() -> Counters {
+        let iterations = Arc::new(AtomicU64::new(0));
+        ITERATIONS.with(|current| *current.borrow_mut() = Some(iterations.clone()));
+        Counters {
+            started: Instant::now(),
+            os_id: platform::current_os_id(),
+            iterations,
+        }
+    }
+
+    pub fn stop() {
+        ITERATIONS.with(|current| *current.borrow_mut() = None);
+    }
+
+    pub fn stats(&self) -> Stats {
+        let cpu_times = self.os_id.and_then(platform::cpu_times);
+        Stats {
+            wall_time: self.started.elapsed(),
+            user_time: cpu_times.map(|(user, _)| user),
+            system_time: cpu_times.map(|(_, system)| system),
+            iterations: self.iterations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::time::Duration;
+
+    pub fn current_os_id() -> Option<u64> {
+        Some(unsafe { ::libc::syscall(::libc::SYS_gettid) } as u64)
+    }
+
+    // The user and system times from `/proc/self/task/<tid>/stat`, in which they are the 14th and
+    // 15th fields, measured in clock ticks.
+    pub fn cpu_times(os_id: u64) -> Option<(Duration, Duration)> {
+        let stat = ::std::fs::read_to_string(format!("/proc/self/task/{}/stat", os_id)).ok()?;
+        // The second field is the parenthesised command name, which may contain spaces.
+        let mut fields = stat[stat.rfind(')')? + 1..].split_whitespace().skip(11);
+        let user = fields.next()?.parse::<u64>().ok()?;
+        let system = fields.next()?.parse::<u64>().ok()?;
+        let ticks_per_second = unsafe { ::libc::sysconf(::libc::_SC_CLK_TCK) };
+        if ticks_per_second <= 0 {
+            return None;
+        }
+        let ticks = |count: u64| {
+            Duration::from_secs_f64(count as f64 / ticks_per_second as f64)
+        };
+        Some((ticks(user), ticks(system)))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn current_os_id() -> Option<u64> {
+        None
+    }
+
+    pub fn cpu_times(_os_id: u64) -> Option<(Duration, Duration)> {
+        None
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{CancellationToken, JoinTimedOut, RaiiThreadJoiner, named};
+
+/// A named thread which is told to stop and then joined, formalising the "send Terminate, then
+/// join" pattern.
+///
+/// The thread's closure receives a `CancellationToken`, which it should poll via `is_cancelled`
+/// or sleep on via `wait_or_cancelled`, returning once it is cancelled. `stop_and_join` cancels it
+/// and waits a bounded time for the thread to finish; dropping the `StoppableThread` does the same
+/// but waits indefinitely.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::StoppableThread;
+/// use std::time::Duration;
+///
+/// let thread = unwrap_result!(StoppableThread::spawn("Pruner", |token| {
+///     while !token.wait_or_cancelled(Duration::from_secs(60)) {
+///         // Prune something.
+///     }
+/// }));
+/// assert_eq!(thread.stop_and_join(Duration::from_secs(5)), Ok(()));
+/// # }
+/// ```
+pub struct StoppableThread {
+    token      : CancellationToken,
+    raii_joiner: RaiiThreadJoiner,
+}
+
+impl StoppableThread {
+    /// Spawn a thread named `name`, passing `entry_point` the token which tells it to stop.
+    pub fn spawn<T, F>(name: T, entry_point: F) -> ::std::io::Result<StoppableThread>
+        where T: Into<String>,
+              F: FnOnce(CancellationToken) + Send + 'static
+    {
+        let token = CancellationToken::new();
+        let token_clone = token.clone();
+        let raii_joiner = named(name, move || entry_point(token_clone))?
+                              .cancel_on_drop(token.clone());
+        Ok(StoppableThread { token, raii_joiner })
+    }
+
+    /// Tell the thread to stop, without waiting for it.
+    pub fn stop(&self) {
+        self.token.cancel();
+    }
+
+    /// Whether the thread has finished running.
+    pub fn is_finished(&self) -> bool {
+        self.raii_joiner.is_finished()
+    }
+
+    /// Tell the thread to stop, then wait at most `timeout` for it to finish. If it does not, it
+    /// is logged and detached, as for `RaiiThreadJoiner::join_timeout`. A panic in the thread is
+    /// propagated if it did finish.
+    pub fn stop_and_join(self, timeout: ::std::time::Duration) -> Result<(), JoinTimedOut> {
+        self.stop();
+        self.raii_joiner.join_timeout(timeout)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn stop_and_join() {
+        let (iterations_tx, iterations_rx) = ::std::sync::mpsc::channel();
+        let thread = unwrap_result!(StoppableThread::spawn("Polling", move |token| {
+            let mut iterations = 0u32;
+            while !token.is_cancelled() {
+                iterations += 1;
+                ::std::thread::sleep(Duration::from_millis(1));
+            }
+            unwrap_result!(iterations_tx.send(iterations));
+        }));
+        assert!(!thread.is_finished());
+        assert_eq!(thread.stop_and_join(Duration::from_secs(30)), Ok(()));
+        let _ = unwrap_result!(iterations_rx.try_recv());
+
+        let (finish_tx, finish_rx) = ::std::sync::mpsc::channel::<()>();
+        let thread = unwrap_result!(StoppableThread::spawn("Unresponsive", move |_token| {
+            let _ = finish_rx.recv();
+        }));
+        assert_eq!(thread.stop_and_join(Duration::from_millis(20)),
+                   Err(JoinTimedOut { name: Some("Unresponsive".to_owned()) }));
+        drop(finish_tx);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{CancellationToken, RaiiThreadJoiner, named_cancellable, panic_message};
+use event_sender::{ChannelSender, EventSender};
+use std::time::Duration;
+
+/// How `supervised` backs off between restarts of a failing worker.
+///
+/// The first restart happens after `initial_delay`, and each subsequent delay is doubled up to
+/// `max_delay`. Once the worker has been restarted `max_restarts` times, the next failure is
+/// logged as an error and the supervisor gives up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Delay before the first restart.
+    pub initial_delay: Duration,
+    /// Cap on the delay between restarts.
+    pub max_delay: Duration,
+    /// Maximum number of restarts, not counting the first run.
+    pub max_restarts: u32,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> RestartPolicy {
+        RestartPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay    : Duration::from_secs(30),
+            max_restarts : 10,
+        }
+    }
+}
+
+/// A report of a supervised worker being restarted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Restart {
+    /// The name of the supervised thread.
+    pub thread_name: String,
+    /// How many times the worker has now been restarted, starting from 1.
+    pub restarts: u32,
+    /// Why the previous run ended: its panic message or error.
+    pub reason: String,
+    /// How long the supervisor waits before restarting the worker.
+    pub delay: Duration,
+}
+
+impl ::std::fmt::Display for Restart {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter,
+               "Thread \"{}\" {}; restart {} in {:?}",
+               self.thread_name,
+               self.reason,
+               self.restarts,
+               self.delay)
+    }
+}
+
+/// Run a worker on a thread named `name`, restarting it according to `policy` whenever it panics
+/// or returns an error. Each restart is logged as a warning.
+///
+/// `factory` is called to create a fresh worker for each run, and is passed a `CancellationToken`
+/// which long-running workers should poll or sleep on. The supervisor stops once a worker returns
+/// `Ok(())`, once the restart budget is used up, or once the returned joiner is dropped: that
+/// cancels the token and then waits for the current run to return.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{self, RestartPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RestartPolicy {
+///     initial_delay: Duration::from_millis(1),
+///     ..RestartPolicy::default()
+/// };
+/// let mut attempt = 0;
+/// let _raii_joiner = unwrap_result!(thread::supervised("FlakyWorker", policy, move |_token| {
+///     attempt += 1;
+///     let this_attempt = attempt;
+///     move || if this_attempt < 3 { Err("Transient failure") } else { Ok(()) }
+/// }));
+/// # }
+/// ```
+pub fn supervised<T, F, W, E>(name: T, policy: RestartPolicy, factory: F)
+                              -> ::std::io::Result<RaiiThreadJoiner>
+    where T: Into<String>,
+          F: FnMut(CancellationToken) -> W + Send + 'static,
+          W: FnOnce() -> Result<(), E>,
+          E: ::std::fmt::Display
+{
+    supervise(name.into(), policy, factory, |_| ())
+}
+
+/// As `supervised`, but also firing each `Restart` to `sender` as an event converted from it.
+pub fn supervised_reporting<T, F, W, E, Category, EventSubset, EventTx, CategoryTx>
+    (name: T,
+     policy: RestartPolicy,
+     factory: F,
+     sender: EventSender<Category, EventSubset, EventTx, CategoryTx>)
+     -> ::std::io::Result<RaiiThreadJoiner>
+    where T: Into<String>,
+          F: FnMut(CancellationToken) -> W + Send + 'static,
+          W: FnOnce() -> Result<(), E>,
+          E: ::std::fmt::Display,
+          Category: ::std::fmt::Debug + Clone + Send + Sync + 'static,
+          EventSubset: From<Restart> + ::std::fmt::Debug + Send + 'static,
+          EventTx: ChannelSender<EventSubset> + Send + Sync + 'static,
+          CategoryTx: ChannelSender<Category> + Send + Sync + 'static
+{
+    supervise(name.into(), policy, factory, move |restart| {
+        if let Err(error) = sender.send(EventSubset::from(restart)) {
+            debug!("Restart could not be reported: {}", error);
+        }
+    })
+}
+
+fn supervise<F, W, E, R>(name: String, policy: RestartPolicy, mut factory: F, report: R)
+                         -> ::std::io::Result<RaiiThreadJoiner>
+    where F: FnMut(CancellationToken) -> W + Send + 'static,
+          W: FnOnce() -> Result<(), E>,
+          E: ::std::fmt::Display,
+          R: Fn(Restart) + Send + 'static
+{
+    let thread_name = name.clone();
+    named_cancellable(name, move |token| {
+        let mut delay = policy.initial_delay;
+        let mut restarts = 0;
+        loop {
+            let worker = factory(token.clone());
+            let reason = match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(worker)) {
+                Ok(Ok(())) => return,
+                Ok(Err(error)) => format!("failed: {}", error),
+                Err(payload) => format!("panicked: {}", panic_message(&*payload)),
+            };
+            if token.is_cancelled() {
+                return;
+            }
+            if restarts == policy.max_restarts {
+                error!("Thread \"{}\" {}; giving up after {} restarts",
+                       thread_name,
+                       reason,
+                       restarts);
+                return;
+            }
+
+            restarts += 1;
+            let restart = Restart {
+                thread_name: thread_name.clone(),
+                restarts,
+                reason,
+                delay,
+            };
+            warn!("{}", restart);
+            report(restart);
+            if token.wait_or_cancelled(delay) {
+                return;
+            }
+            delay = ::std::cmp::min(delay * 2, policy.max_delay);
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy {
+            initial_delay: Duration::from_millis(1),
+            max_delay    : Duration::from_millis(4),
+            max_restarts : 3,
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct SupervisorEvent(Restart);
+
+    impl From<Restart> for SupervisorEvent {
+        fn from(restart: Restart) -> SupervisorEvent {
+            SupervisorEvent(restart)
+        }
+    }
+
+    #[test]
+    fn restarts_with_backoff_until_success() {
+        let (event_tx, event_rx) = ::std::sync::mpsc::channel::<SupervisorEvent>();
+        let (category_tx, _category_rx) = ::std::sync::mpsc::channel();
+        let sender = EventSender::new(event_tx, "Supervisor", category_tx);
+        let (done_tx, done_rx) = ::std::sync::mpsc::channel();
+        let mut runs = 0;
+        {
+            let _raii_joiner = unwrap_result!(supervised_reporting("Flaky", policy(), move |_| {
+                runs += 1;
+                let run = runs;
+                let done_tx = done_tx.clone();
+                move || match run {
+                    1 => panic!("Oops"),
+                    2 => Err("Lost connection"),
+                    _ => {
+                        unwrap_result!(done_tx.send(()));
+                        Ok(())
+                    }
+                }
+            }, sender));
+            // Dropping the joiner would cancel pending restarts, so wait for the final run.
+            unwrap_result!(done_rx.recv());
+        }
+
+        let events = event_rx.iter().collect::<Vec<_>>();
+        assert_eq!(events,
+                   vec![SupervisorEvent(Restart {
+                            thread_name: "Flaky".to_owned(),
+                            restarts   : 1,
+                            reason     : "panicked: Oops".to_owned(),
+                            delay      : Duration::from_millis(1),
+                        }),
+                        SupervisorEvent(Restart {
+                            thread_name: "Flaky".to_owned(),
+                            restarts   : 2,
+                            reason     : "failed: Lost connection".to_owned(),
+                            delay      : Duration::from_millis(2),
+                        })]);
+    }
+
+    #[test]
+    fn gives_up_after_max_restarts() {
+        let (runs_tx, runs_rx) = ::std::sync::mpsc::channel();
+        {
+            let _raii_joiner = unwrap_result!(supervised("Broken", policy(), move |_| {
+                let runs_tx = runs_tx.clone();
+                move || {
+                    unwrap_result!(runs_tx.send(()));
+                    Err("Still broken")
+                }
+            }));
+            assert_eq!(runs_rx.iter().take(4).count(), 4);
+        }
+        assert_eq!(runs_rx.iter().count(), 0);
+    }
+
+    #[test]
+    fn dropping_the_joiner_stops_the_worker() {
+        let (started_tx, started_rx) = ::std::sync::mpsc::channel();
+        let raii_joiner = unwrap_result!(supervised("LongRunning", policy(), move |token| {
+            let started_tx = started_tx.clone();
+            move || -> Result<(), String> {
+                unwrap_result!(started_tx.send(()));
+                while !token.wait_or_cancelled(Duration::from_secs(60)) {}
+                Err("Cancelled".to_owned())
+            }
+        }));
+        unwrap_result!(started_rx.recv());
+        drop(raii_joiner);
+        // Cancelled runs are not restarted, so there is just the one run.
+        assert_eq!(started_rx.iter().count(), 0);
+    }
+}
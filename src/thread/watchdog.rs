@@ -0,0 +1,238 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{RaiiThreadJoiner, named_cancellable};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// A registered thread which has not called `Heartbeat::beat` within its deadline, as reported
+/// by a `Watchdog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stall {
+    /// The name the thread was registered under.
+    pub thread_name: String,
+    /// The activity tag passed to the most recent `Heartbeat::beat_with`, if any.
+    pub activity: Option<String>,
+    /// How long ago the thread last beat (or registered).
+    pub silent_for: Duration,
+    /// The deadline it was registered with.
+    pub deadline: Duration,
+}
+
+impl ::std::fmt::Display for Stall {
+    fn fmt(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(formatter,
+               "Thread \"{}\" has not beaten for {:?} (deadline {:?})",
+               self.thread_name,
+               self.silent_for,
+               self.deadline)?;
+        match self.activity {
+            Some(ref activity) => write!(formatter, "; last activity: {}", activity),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Entry {
+    thread_name: String,
+    deadline   : Duration,
+    last_beat  : Instant,
+    activity   : Option<String>,
+    reported   : bool,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<u64, Entry>,
+    next_id: u64,
+}
+
+/// Catches silently stalled worker threads, such as ones which have deadlocked.
+///
+/// Each worker registers with a deadline and then calls `beat()` on the returned `Heartbeat` at
+/// least that often. A monitor thread checks the registrations periodically and reports each
+/// worker which has missed its deadline, once per stall, logging it as an error or passing it to a
+/// callback. Dropping the heartbeat deregisters the worker; dropping the watchdog stops the
+/// monitor thread.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::{RaiiThreadJoiner, Watchdog};
+/// use std::time::Duration;
+///
+/// let watchdog = unwrap_result!(Watchdog::new(Duration::from_secs(1)));
+/// let heartbeat = watchdog.register("Churn", Duration::from_secs(10));
+/// let _raii_joiner = RaiiThreadJoiner::new(thread!("Churn", move || {
+///     for _ in 0..3 {
+///         heartbeat.beat_with("Handling churn event");
+///         // ...
+///     }
+/// }));
+/// # }
+/// ```
+pub struct Watchdog {
+    state       : Arc<Mutex<State>>,
+    _raii_joiner: RaiiThreadJoiner,
+}
+
+impl Watchdog {
+    /// Start a watchdog which checks every `check_interval` and logs stalls as errors.
+    pub fn new(check_interval: Duration) -> ::std::io::Result<Watchdog> {
+        Watchdog::with_callback(check_interval, |stall| error!("{}", stall))
+    }
+
+    /// Start a watchdog which checks every `check_interval` and passes stalls to `on_stall`, which
+    /// runs on the monitor thread.
+    pub fn with_callback<F>(check_interval: Duration, on_stall: F) -> ::std::io::Result<Watchdog>
+        where F: Fn(Stall) + Send + 'static
+    {
+        let state = Arc::new(Mutex::new(State::default()));
+        let state_clone = state.clone();
+        let raii_joiner = named_cancellable("Watchdog", move |token| {
+            while !token.wait_or_cancelled(check_interval) {
+                for stall in stalls(&state_clone) {
+                    on_stall(stall);
+                }
+            }
+        })?;
+        Ok(Watchdog {
+            state,
+            _raii_joiner: raii_joiner,
+        })
+    }
+
+    /// Register a worker, which must then beat at least every `deadline`, under `thread_name`.
+    pub fn register<T: Into<String>>(&self, thread_name: T, deadline: Duration) -> Heartbeat {
+        let mut state = unwrap_result!(self.state.lock());
+        let id = state.next_id;
+        state.next_id += 1;
+        let _ = state.entries.insert(id,
+                                     Entry {
+                                         thread_name: thread_name.into(),
+                                         deadline,
+                                         last_beat  : Instant::now(),
+                                         activity   : None,
+                                         reported   : false,
+                                     });
+        Heartbeat {
+            state: Arc::downgrade(&self.state),
+            id,
+        }
+    }
+}
+
+fn stalls(state: &Mutex<State>) -> Vec<Stall> {
+    let now = Instant::now();
+    let mut state = unwrap_result!(state.lock());
+    state.entries
+         .values_mut()
+         .filter_map(|entry| {
+             let silent_for = now.saturating_duration_since(entry.last_beat);
+             if entry.reported || silent_for <= entry.deadline {
+                 return None;
+             }
+             entry.reported = true;
+             Some(Stall {
+                 thread_name: entry.thread_name.clone(),
+                 activity   : entry.activity.clone(),
+                 silent_for,
+                 deadline   : entry.deadline,
+             })
+         })
+         .collect()
+}
+
+/// A worker's registration with a `Watchdog`, obtained via `Watchdog::register`. Dropping it
+/// deregisters the worker.
+pub struct Heartbeat {
+    state: Weak<Mutex<State>>,
+    id   : u64,
+}
+
+impl Heartbeat {
+    /// Record that the worker is still making progress, leaving its activity tag unchanged.
+    pub fn beat(&self) {
+        self.update(|entry| entry.last_beat = Instant::now());
+    }
+
+    /// Record that the worker is still making progress, currently on `activity`, which is included
+    /// in the report should it stall.
+    pub fn beat_with<T: Into<String>>(&self, activity: T) {
+        let activity = activity.into();
+        self.update(move |entry| {
+            entry.last_beat = Instant::now();
+            entry.activity = Some(activity);
+        });
+    }
+
+    fn update<F: FnOnce(&mut Entry)>(&self, update: F) {
+        if let Some(state) = self.state.upgrade() {
+            if let Some(entry) = unwrap_result!(state.lock()).entries.get_mut(&self.id) {
+                update(entry);
+                entry.reported = false;
+            }
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            let _ = unwrap_result!(state.lock()).entries.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_each_stall_once() {
+        let (stall_tx, stall_rx) = ::std::sync::mpsc::channel();
+        let stall_tx = Mutex::new(stall_tx);
+        let watchdog = unwrap_result!(Watchdog::with_callback(Duration::from_millis(5),
+                                                              move |stall| {
+            unwrap_result!(unwrap_result!(stall_tx.lock()).send(stall));
+        }));
+
+        let deadline = Duration::from_millis(50);
+        let stuck = watchdog.register("Stuck", deadline);
+        stuck.beat_with("Waiting for lock");
+        let healthy = watchdog.register("Healthy", deadline);
+        let deregistered = watchdog.register("Deregistered", deadline);
+        drop(deregistered);
+
+        for _ in 0..20 {
+            ::std::thread::sleep(Duration::from_millis(10));
+            healthy.beat();
+        }
+        drop(watchdog);
+
+        let stalls = stall_rx.iter().collect::<Vec<_>>();
+        assert_eq!(stalls.len(), 1, "{:?}", stalls);
+        assert_eq!(stalls[0].thread_name, "Stuck");
+        assert_eq!(stalls[0].activity, Some("Waiting for lock".to_owned()));
+        assert!(stalls[0].silent_for > deadline);
+        stuck.beat();
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+use super::{RaiiThreadJoiner, named};
+
+/// A closure queued on a `Worker` which runs arbitrary commands.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// A minimal actor: a named thread which handles the commands sent to it, one at a time and in the
+/// order sent.
+///
+/// Commands can be a user enum handled by a closure given to `spawn`, or plain closures via
+/// `spawn_for_jobs` and `execute`. Stopping the worker, whether via `stop()` or by dropping it,
+/// lets it handle whatever is already queued and then joins its thread.
+///
+/// #Examples
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate maidsafe_utilities;
+/// # fn main() {
+/// use maidsafe_utilities::thread::Worker;
+///
+/// enum Command {
+///     Store(u64),
+///     Report(std::sync::mpsc::Sender<u64>),
+/// }
+///
+/// let mut total = 0;
+/// let worker = unwrap_result!(Worker::spawn("Accumulator", move |command| match command {
+///     Command::Store(value) => total += value,
+///     Command::Report(report_tx) => unwrap_result!(report_tx.send(total)),
+/// }));
+///
+/// let (report_tx, report_rx) = std::sync::mpsc::channel();
+/// unwrap_result!(worker.send(Command::Store(2)));
+/// unwrap_result!(worker.send(Command::Store(3)));
+/// unwrap_result!(worker.send(Command::Report(report_tx)));
+/// worker.stop();
+/// assert_eq!(unwrap_result!(report_rx.recv()), 5);
+/// # }
+/// ```
+pub struct Worker<Cmd> {
+    command_tx : Option<::std::sync::mpsc::Sender<Cmd>>,
+    raii_joiner: Option<RaiiThreadJoiner>,
+}
+
+impl<Cmd: Send + 'static> Worker<Cmd> {
+    /// Spawn a thread named `name` which passes each command sent to it to `handler`.
+    pub fn spawn<T, F>(name: T, mut handler: F) -> ::std::io::Result<Worker<Cmd>>
+        where T: Into<String>,
+              F: FnMut(Cmd) + Send + 'static
+    {
+        let (command_tx, command_rx) = ::std::sync::mpsc::channel();
+        let raii_joiner = named(name, move || {
+            for command in command_rx.iter() {
+                handler(command);
+            }
+        })?;
+        Ok(Worker {
+            command_tx : Some(command_tx),
+            raii_joiner: Some(raii_joiner),
+        })
+    }
+
+    /// Queue `command` for the worker. This only fails if the worker's thread has died, in which
+    /// case the command is handed back inside the error.
+    pub fn send(&self, command: Cmd) -> Result<(), ::std::sync::mpsc::SendError<Cmd>> {
+        unwrap_option!(self.command_tx.as_ref(),
+                       "Programming error: please report this as a bug.")
+            .send(command)
+    }
+
+    /// Let the worker handle the commands already queued, then join its thread.
+    pub fn stop(self) {}
+}
+
+impl Worker<Job> {
+    /// Spawn a thread named `name` which runs each closure passed to `execute`.
+    pub fn spawn_for_jobs<T: Into<String>>(name: T) -> ::std::io::Result<Worker<Job>> {
+        Worker::spawn(name, |job: Job| job())
+    }
+
+    /// Queue `job` to run on the worker. Returns `false` if the worker's thread has died.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) -> bool {
+        self.send(Box::new(job)).is_ok()
+    }
+}
+
+impl<Cmd> Drop for Worker<Cmd> {
+    fn drop(&mut self) {
+        // Closing the channel ends the worker's loop once the queue is drained.
+        let _ = self.command_tx.take();
+        let _ = self.raii_joiner.take();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn jobs_run_in_order_on_the_worker() {
+        let (results_tx, results_rx) = ::std::sync::mpsc::channel();
+        let worker = unwrap_result!(Worker::spawn_for_jobs("JobWorker"));
+        for index in 0..10 {
+            let results_tx = results_tx.clone();
+            assert!(worker.execute(move || {
+                unwrap_result!(results_tx.send((index, ::thread::current_name())));
+            }));
+        }
+        drop(results_tx);
+        drop(worker);
+
+        let results = results_rx.iter().collect::<Vec<_>>();
+        assert_eq!(results,
+                   (0..10).map(|index| (index, "JobWorker".to_owned())).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn send_fails_once_the_worker_has_died() {
+        let worker = unwrap_result!(Worker::spawn("Doomed", |command: u8| {
+            if command == 0 {
+                panic!("Bad command");
+            }
+        }));
+        unwrap_result!(worker.send(0));
+        let start = ::std::time::Instant::now();
+        while worker.send(1).is_ok() {
+            assert!(start.elapsed() < ::std::time::Duration::from_secs(30));
+            ::std::thread::yield_now();
+        }
+        match worker.send(2) {
+            Err(::std::sync::mpsc::SendError(2)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        // As with `RaiiThreadJoiner`, joining propagates the worker's panic.
+        let stop = ::std::panic::AssertUnwindSafe(move || worker.stop());
+        assert!(::std::panic::catch_unwind(stop).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! The timer engine behind the crate's delayed and periodic tasks. A single, lazily started timer
+//! thread is shared by everything in the crate which needs to run something after a delay, while
+//! each `thread::Scheduler` drives an engine of its own.
+
+use std::collections::{BinaryHeap, HashMap};
+use std::cmp::Reverse;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+use thread::{Cadence, MissedTicks, panic_message};
+
+enum Task {
+    Once(Box<dyn FnOnce() + Send>),
+    Every {
+        period : Duration,
+        cadence: Cadence,
+        missed : MissedTicks,
+        task   : Box<dyn FnMut() + Send>,
+    },
+}
+
+#[derive(Default)]
+struct State {
+    deadlines    : BinaryHeap<Reverse<(Instant, u64)>>,
+    tasks        : HashMap<u64, Task>,
+    next_id      : u64,
+    // The recurring task currently running, which is not in `tasks`, and whether it has been
+    // cancelled meanwhile.
+    running      : Option<(u64, bool)>,
+    shutting_down: bool,
+}
+
+pub struct Timer {
+    state    : Mutex<State>,
+    condition: Condvar,
+}
+
+/// Handle to a task scheduled on a timer thread. Dropping the handle does not cancel the task.
+pub struct TimerHandle {
+    timer: Weak<Timer>,
+    id   : u64,
+}
+
+impl TimerHandle {
+    /// Cancel the task. Returns `true` if it was cancelled before it started running or, for a
+    /// recurring task, if any further runs were prevented.
+    pub fn cancel(&self) -> bool {
+        let timer = match self.timer.upgrade() {
+            Some(timer) => timer,
+            None => return false,
+        };
+        let removed = {
+            let mut state = unwrap_result!(timer.state.lock());
+            // Dropped outside the lock, as the task may own something whose destructor schedules.
+            let removed = state.tasks.remove(&self.id);
+            match state.running {
+                Some((id, ref mut cancelled)) if id == self.id && !*cancelled => {
+                    *cancelled = true;
+                    return true;
+                }
+                _ => removed,
+            }
+        };
+        timer.condition.notify_one();
+        removed.is_some()
+    }
+}
+
+impl Timer {
+    /// Create an engine, running it on a new thread spawned by `builder`.
+    pub fn spawn(builder: ::thread::Builder)
+                 -> ::std::io::Result<(Arc<Timer>, ::thread::RaiiThreadJoiner)> {
+        let timer = Arc::new(Timer {
+            state    : Mutex::new(State::default()),
+            condition: Condvar::new(),
+        });
+        let timer_clone = timer.clone();
+        let raii_joiner = builder.spawn(move || run(&timer_clone))?;
+        Ok((timer, raii_joiner))
+    }
+
+    /// Run `task` once `delay` has elapsed. A `delay` too long to be represented as an `Instant`
+    /// means the task never runs, though it can still be cancelled.
+    pub fn schedule<F: FnOnce() + Send + 'static>(self: &Arc<Self>, delay: Duration, task: F)
+                                                  -> TimerHandle {
+        self.add(Instant::now().checked_add(delay), Task::Once(Box::new(task)))
+    }
+
+    /// Run `task` every `period`, starting one period from now. `missed` only applies to the
+    /// `FixedRate` cadence. If a run panics, the panic is logged and the task is dropped rather
+    /// than run again. Once the next run would be too far off to be represented as an `Instant`,
+    /// the task is not run again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn schedule_every<F>(self: &Arc<Self>,
+                             period: Duration,
+                             cadence: Cadence,
+                             missed: MissedTicks,
+                             task: F)
+                             -> TimerHandle
+        where F: FnMut() + Send + 'static
+    {
+        assert!(period > Duration::from_secs(0), "A recurring task's period must be non-zero");
+        self.add(Instant::now().checked_add(period),
+                 Task::Every {
+                     period,
+                     cadence,
+                     missed,
+                     task: Box::new(task),
+                 })
+    }
+
+    /// Stop the engine's thread once any currently running task returns. Pending tasks are
+    /// dropped without being run.
+    pub fn shut_down(&self) {
+        let tasks = {
+            let mut state = unwrap_result!(self.state.lock());
+            state.shutting_down = true;
+            state.deadlines.clear();
+            ::std::mem::take(&mut state.tasks)
+        };
+        self.condition.notify_one();
+        drop(tasks);
+    }
+
+    // A task without a deadline is kept, so that cancelling it still works, but never run.
+    fn add(self: &Arc<Self>, deadline: Option<Instant>, task: Task) -> TimerHandle {
+        let mut state = unwrap_result!(self.state.lock());
+        let id = state.next_id;
+        state.next_id += 1;
+        if let Some(deadline) = deadline {
+            state.deadlines.push(Reverse((deadline, id)));
+        }
+        let _ = state.tasks.insert(id, task);
+        self.condition.notify_one();
+        TimerHandle {
+            timer: Arc::downgrade(self),
+            id,
+        }
+    }
+}
+
+/// Run `task` on the shared timer thread once `delay` has elapsed.
+pub fn schedule<F: FnOnce() + Send + 'static>(delay: Duration, task: F) -> TimerHandle {
+    timer().schedule(delay, task)
+}
+
+fn timer() -> &'static Arc<Timer> {
+    static TIMER: OnceLock<Arc<Timer>> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        // Shared by the whole process, so not named after whichever thread happened to start it.
+        let builder = ::thread::Builder::new("MaidSafeTimer").inherit_name(false);
+        let (timer, raii_joiner) = unwrap_result!(Timer::spawn(builder));
+        raii_joiner.detach();
+        timer
+    })
+}
+
+fn run(timer: &Timer) {
+    let mut state = unwrap_result!(timer.state.lock());
+    while !state.shutting_down {
+        let now = Instant::now();
+        let (deadline, id) = match state.deadlines.peek() {
+            Some(&Reverse(next)) => next,
+            None => {
+                state = unwrap_result!(timer.condition.wait(state));
+                continue;
+            }
+        };
+
+        if !state.tasks.contains_key(&id) {
+            // Cancelled
+            let _ = state.deadlines.pop();
+        } else if deadline > now {
+            state = unwrap_result!(timer.condition.wait_timeout(state, deadline - now)).0;
+        } else {
+            let _ = state.deadlines.pop();
+            match state.tasks.remove(&id) {
+                Some(Task::Once(task)) => {
+                    drop(state);
+                    let _ = run_task(task);
+                    state = unwrap_result!(timer.state.lock());
+                }
+                Some(Task::Every { period, cadence, missed, mut task }) => {
+                    state.running = Some((id, false));
+                    drop(state);
+                    let succeeded = run_task(&mut task);
+                    state = unwrap_result!(timer.state.lock());
+                    let reschedule = state.running.take() == Some((id, false)) && succeeded;
+                    if reschedule && !state.shutting_down {
+                        let next = match cadence {
+                            Cadence::FixedRate => next_tick(deadline, period, missed),
+                            Cadence::FixedDelay => Instant::now().checked_add(period),
+                        };
+                        if let Some(next) = next {
+                            state.deadlines.push(Reverse((next, id)));
+                        }
+                        let task = Task::Every {
+                            period,
+                            cadence,
+                            missed,
+                            task,
+                        };
+                        let _ = state.tasks.insert(id, task);
+                    }
+                }
+                None => (),
+            }
+        }
+    }
+}
+
+// Run `task`, catching and logging any panic so that it cannot take the timer thread down.
+// Returns whether it returned normally.
+fn run_task<F: FnOnce()>(task: F) -> bool {
+    match panic::catch_unwind(AssertUnwindSafe(task)) {
+        Ok(()) => true,
+        Err(payload) => {
+            error!("Timer task panicked: {}", panic_message(&*payload));
+            false
+        }
+    }
+}
+
+// The deadline following `deadline` for a fixed-rate task. Each is derived from the last ideal
+// deadline rather than from when the task actually ran, so lateness never accumulates into drift.
+// `None` if the next deadline cannot be represented.
+fn next_tick(deadline: Instant, period: Duration, missed: MissedTicks) -> Option<Instant> {
+    let next = deadline.checked_add(period)?;
+    let now = Instant::now();
+    if next > now {
+        return Some(next);
+    }
+    match missed {
+        MissedTicks::Burst => Some(next),
+        MissedTicks::Delay => Some(now),
+        MissedTicks::Skip => {
+            if period == Duration::from_secs(0) {
+                return Some(now);
+            }
+            let missed_periods = (now - next).as_nanos() / period.as_nanos() + 1;
+            period.checked_mul(missed_periods.min(u32::MAX as u128) as u32)
+                  .and_then(|skipped| next.checked_add(skipped))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tasks_run_in_deadline_order_unless_cancelled() {
+        let (tx, rx) = ::std::sync::mpsc::channel();
+
+        let tx_clone = tx.clone();
+        let _ = schedule(Duration::from_millis(200), move || unwrap_result!(tx_clone.send(2)));
+        let tx_clone = tx.clone();
+        let cancelled = schedule(Duration::from_millis(100), move || unwrap_result!(tx_clone.send(0)));
+        let _ = schedule(Duration::from_millis(50), move || unwrap_result!(tx.send(1)));
+
+        assert!(cancelled.cancel());
+        assert!(!cancelled.cancel());
+
+        assert_eq!(rx.iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn missed_ticks() {
+        let period = Duration::from_millis(100);
+        let now = Instant::now();
+        // On schedule.
+        for &missed in &[MissedTicks::Burst, MissedTicks::Skip, MissedTicks::Delay] {
+            assert_eq!(next_tick(now, period, missed), Some(now + period));
+        }
+        // Three and a half periods late.
+        let deadline = now - period * 7 / 2;
+        assert_eq!(next_tick(deadline, period, MissedTicks::Burst), Some(deadline + period));
+        let skipped = unwrap_option!(next_tick(deadline, period, MissedTicks::Skip), "");
+        assert_eq!(skipped, deadline + period * 4);
+        assert!(skipped > Instant::now());
+        let delayed = unwrap_option!(next_tick(deadline, period, MissedTicks::Delay), "");
+        assert!(delayed >= now && delayed <= Instant::now());
+        // Too far off to represent.
+        assert_eq!(next_tick(now, Duration::MAX, MissedTicks::Burst), None);
+    }
+
+    #[test]
+    fn unrepresentable_deadlines_never_fire() {
+        let builder = ::thread::Builder::new("UnrepresentableDeadlines");
+        let (timer, _raii_joiner) = unwrap_result!(Timer::spawn(builder));
+        let (tx, rx) = ::std::sync::mpsc::channel();
+
+        let tx_clone = tx.clone();
+        let never = timer.schedule(Duration::MAX, move || unwrap_result!(tx_clone.send("Never")));
+        let tx_clone = tx.clone();
+        let never_every = timer.schedule_every(Duration::MAX,
+                                               Cadence::FixedRate,
+                                               MissedTicks::Burst,
+                                               move || unwrap_result!(tx_clone.send("Never")));
+        let _ = timer.schedule(Duration::from_millis(10), move || unwrap_result!(tx.send("Soon")));
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(unwrap_result!(rx.recv_timeout(timeout)), "Soon");
+        assert!(never.cancel());
+        assert!(never_every.cancel());
+        // Both cancelled tasks dropped their senders.
+        assert_eq!(rx.recv_timeout(timeout),
+                   Err(::std::sync::mpsc::RecvTimeoutError::Disconnected));
+        timer.shut_down();
+    }
+}
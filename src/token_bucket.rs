@@ -0,0 +1,62 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! The token-bucket rate limit shared by `RateLimitedSender` and the rate-limited log appenders.
+
+use std::time::{Duration, Instant};
+
+pub struct TokenBucket {
+    tokens_per_sec: f64,
+    capacity      : f64,
+    tokens        : f64,
+    last_refill   : Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket admitting on average `max_per_sec` takes per second and at most `burst` in
+    /// one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_per_sec` is zero, so the public constructors built on this must document
+    /// that they do too.
+    pub fn new(max_per_sec: u32, burst: u32) -> TokenBucket {
+        assert!(max_per_sec > 0, "Rate limit must be non-zero");
+        let capacity = burst.max(1) as f64;
+        TokenBucket {
+            tokens_per_sec: max_per_sec as f64,
+            capacity,
+            tokens        : capacity,
+            last_refill   : Instant::now(),
+        }
+    }
+
+    /// Take a token if one is available, otherwise return how long until one will be.
+    pub fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.tokens_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.tokens_per_sec))
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2016 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement, version 1.0.  This, along with the
+// Licenses can be found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+
+// `thread::log_all_panics` installs a process-wide panic hook which can't be undone, so it's
+// exercised in its own test binary rather than alongside the unit tests.
+
+#[macro_use]
+extern crate maidsafe_utilities;
+
+use maidsafe_utilities::log::{Appender, Builder, Filter, Record};
+use std::sync::{Arc, Mutex};
+
+struct Collector(Arc<Mutex<Vec<String>>>);
+
+impl Appender for Collector {
+    fn append(&self, record: &Record, _line: &str) -> ::std::io::Result<()> {
+        unwrap_result!(self.0.lock()).push(record.message.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn all_panics_are_logged() {
+    let crash_path = ::std::env::temp_dir().join(format!("log_all_panics_{}.log",
+                                                         ::std::process::id()));
+    let _ = ::std::fs::remove_file(&crash_path);
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    unwrap_result!(Builder::new()
+                       .filter(Filter::parse("error"))
+                       .appender(Collector(messages.clone()))
+                       .log_panics(true)
+                       .crash_buffer(10, &crash_path)
+                       .init());
+
+    let _ = ::std::panic::catch_unwind(|| panic!("Unmanaged"));
+    maidsafe_utilities::log::flush();
+    {
+        let messages = unwrap_result!(messages.lock());
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("panicked at tests/log_all_panics.rs:"));
+        assert!(messages[0].contains(": Unmanaged\n"));
+    }
+    // The panic was caught, so the crash buffer is kept rather than dumped.
+    assert!(!crash_path.exists());
+
+    // Logged once, by the hook rather than by the managed thread as well.
+    unwrap_result!(messages.lock()).clear();
+    let _ = thread_logged!("Managed", || panic!("Managed")).join();
+    maidsafe_utilities::log::flush();
+    let messages = unwrap_result!(messages.lock());
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].contains(": Managed\n"));
+
+    // But the panic escaping the thread dumps it.
+    let dumped = unwrap_result!(::std::fs::read_to_string(&crash_path));
+    let _ = ::std::fs::remove_file(&crash_path);
+    assert!(dumped.contains(": Unmanaged\n"));
+    assert!(dumped.contains(": Managed\n"));
+}